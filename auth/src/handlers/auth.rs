@@ -15,8 +15,12 @@ use crate::services::{
     security::SecurityService,
     dev_user::get_dev_user,
 };
-use reqwest::{Client, Url};
+use reqwest::Client;
+use crate::services::repo_access::{
+    check_repo_access, connect_repos, RepoConnectStatus, RepoInfo, RepoRef, BULK_CONNECT_CONCURRENCY, MAX_BULK_CONNECT_REPOS,
+};
 use conhub_middleware::auth::extract_claims_from_http_request;
+use conhub_middleware::ApiError;
 
 // Disabled-mode handler: responds consistently when auth is turned off
 pub async fn disabled() -> Result<HttpResponse> {
@@ -67,17 +71,12 @@ pub async fn login(
         Some(p) => p,
         None => {
             tracing::error!("Database pool not available for login");
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable. Please ensure the database is connected."
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable. Please ensure the database is connected.").into_response());
         }
     };
 
     if let Err(validation_errors) = request.validate() {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        })));
+        return Ok(ApiError::validation(validation_errors).into_response());
     }
 
     // Initialize SecurityService for rate limiting
@@ -85,7 +84,7 @@ pub async fn login(
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize security service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
 
@@ -98,16 +97,14 @@ pub async fn login(
     if !security_service.check_rate_limit(&client_ip, "login", 5, 1).await
         .unwrap_or(false) {
         tracing::warn!("Rate limit exceeded for login attempt from IP: {}", client_ip);
-        return Ok(HttpResponse::TooManyRequests().json(json!({
-            "error": "Too many login attempts. Please try again later."
-        })));
+        return Ok(ApiError::too_many_requests("Too many login attempts. Please try again later.").into_response());
     }
 
     let user_service = match UserService::new(pool.clone()).await {
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize user service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
     
@@ -115,9 +112,7 @@ pub async fn login(
     let user = match user_service.verify_password(&request.email, &request.password).await {
         Ok(user) => user,
         Err(_) => {
-            return Ok(HttpResponse::Unauthorized().json(json!({
-                "error": "Invalid credentials"
-            })));
+            return Ok(ApiError::unauthorized("Invalid credentials").into_response());
         }
     };
 
@@ -133,9 +128,7 @@ pub async fn login(
         Ok(tokens) => tokens,
         Err(e) => {
             tracing::error!("Failed to generate JWT tokens: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to generate authentication tokens"
-            })));
+            return Ok(ApiError::internal("Failed to generate authentication tokens").into_response());
         }
     };
 
@@ -159,16 +152,11 @@ pub async fn forgot_password(
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     if let Err(validation_errors) = request.validate() {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        })));
+        return Ok(ApiError::validation(validation_errors).into_response());
     }
 
     let email = &request.email;
@@ -178,7 +166,7 @@ pub async fn forgot_password(
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize user service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
     let password_reset_service = crate::services::password_reset::PasswordResetService::new(pool.clone());
@@ -215,16 +203,11 @@ pub async fn reset_password(
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     if let Err(validation_errors) = request.validate() {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        })));
+        return Ok(ApiError::validation(validation_errors).into_response());
     }
 
     let token = &request.token;
@@ -239,10 +222,7 @@ pub async fn reset_password(
         Ok(email) => email,
         Err(e) => {
             tracing::warn!("Invalid password reset token: {}", e);
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Invalid or expired reset token",
-                "details": format!("{}", e)
-            })));
+            return Ok(ApiError::bad_request("Invalid or expired reset token").with_details(json!(format!("{}", e))).into_response());
         }
     };
     
@@ -251,18 +231,13 @@ pub async fn reset_password(
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize security service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Service initialization failed"
-            })));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
 
     // Validate password strength
     if let Err(validation_error) = security_service.validate_password_strength(new_password) {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Password validation failed",
-            "details": validation_error
-        })));
+        return Ok(ApiError::bad_request("Password validation failed").with_details(json!(validation_error)).into_response());
     }
 
     // Hash the new password using Argon2
@@ -270,9 +245,7 @@ pub async fn reset_password(
         Ok(hash) => hash,
         Err(e) => {
             tracing::error!("Failed to hash password: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to process password reset"
-            })));
+            return Ok(ApiError::internal("Failed to process password reset").into_response());
         }
     };
 
@@ -280,7 +253,7 @@ pub async fn reset_password(
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize user service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
     
@@ -288,18 +261,14 @@ pub async fn reset_password(
     let user = match user_service.find_by_email(&email).await {
         Ok(user) => user,
         Err(_) => {
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "User not found"
-            })));
+            return Ok(ApiError::bad_request("User not found").into_response());
         }
     };
     
     // Update the user's password
     if let Err(e) = user_service.update_password(user.id, &new_password_hash).await {
         tracing::error!("Failed to update password for user {}: {}", user.id, e);
-        return Ok(HttpResponse::InternalServerError().json(json!({
-            "error": "Failed to update password"
-        })));
+        return Ok(ApiError::internal("Failed to update password").into_response());
     }
     
     tracing::info!("Password successfully reset for email: {}", email);
@@ -325,18 +294,13 @@ pub async fn register(
         },
         None => {
             tracing::error!("❌ [Register] Database pool not available for registration");
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable. Please ensure the database is connected."
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable. Please ensure the database is connected.").into_response());
         }
     };
 
     if let Err(validation_errors) = request.validate() {
         tracing::warn!("⚠️  [Register] Validation failed: {:?}", validation_errors);
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        })));
+        return Ok(ApiError::validation(validation_errors).into_response());
     }
 
     tracing::info!("✅ [Register] Validation passed");
@@ -350,7 +314,7 @@ pub async fn register(
         },
         Err(e) => {
             tracing::error!("❌ [Register] Failed to initialize security service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
 
@@ -366,9 +330,7 @@ pub async fn register(
     if !security_service.check_rate_limit(&client_ip, "register", 3, 1).await
         .unwrap_or(false) {
         tracing::warn!("⚠️  [Register] Rate limit exceeded for registration attempt from IP: {}", client_ip);
-        return Ok(HttpResponse::TooManyRequests().json(json!({
-            "error": "Too many registration attempts. Please try again later."
-        })));
+        return Ok(ApiError::too_many_requests("Too many registration attempts. Please try again later.").into_response());
     }
 
     tracing::info!("✅ [Register] Rate limit check passed");
@@ -381,7 +343,7 @@ pub async fn register(
         },
         Err(e) => {
             tracing::error!("❌ [Register] Failed to initialize user service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
     
@@ -395,15 +357,9 @@ pub async fn register(
             let msg = e.to_string();
             tracing::error!("❌ [Register] Failed to create user: {}", msg);
             if msg.contains("already exists") {
-                return Ok(HttpResponse::Conflict().json(json!({
-                    "error": "User already exists",
-                    "details": msg
-                })));
+                return Ok(ApiError::conflict("User already exists").with_details(json!(msg)).into_response());
             }
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Failed to create user",
-                "details": msg
-            })));
+            return Ok(ApiError::bad_request("Failed to create user").with_details(json!(msg)).into_response());
         }
     };
 
@@ -418,9 +374,7 @@ pub async fn register(
         },
         Err(e) => {
             tracing::error!("❌ [Register] Failed to generate JWT tokens for user {}: {}", new_user.id, e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to generate authentication tokens"
-            })));
+            return Ok(ApiError::internal("Failed to generate authentication tokens").into_response());
         }
     };
 
@@ -459,25 +413,18 @@ pub async fn dev_reset(pool_opt: web::Data<Option<PgPool>>) -> Result<HttpRespon
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
 
     let env = std::env::var("NODE_ENV").unwrap_or_default();
     if env != "development" {
-        return Ok(HttpResponse::Forbidden().json(json!({
-            "error": "Reset is only allowed in development"
-        })));
+        return Ok(ApiError::forbidden("Reset is only allowed in development").into_response());
     }
 
     if let Err(e) = sqlx::query("TRUNCATE TABLE users RESTART IDENTITY CASCADE").execute(pool).await {
         tracing::error!("Failed to truncate users table: {}", e);
-        return Ok(HttpResponse::InternalServerError().json(json!({
-            "error": "Failed to reset database",
-            "details": format!("{}", e)
-        })));
+        return Ok(ApiError::internal("Failed to reset database").with_details(json!(format!("{}", e))).into_response());
     }
 
     Ok(HttpResponse::Ok().json(json!({
@@ -493,9 +440,7 @@ pub async fn logout(
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     use crate::services::middleware::extract_claims_from_request;
@@ -504,27 +449,21 @@ pub async fn logout(
     let claims = match extract_claims_from_request(&req) {
         Some(claims) => claims,
         None => {
-            return Ok(HttpResponse::Unauthorized().json(json!({
-                "error": "Authentication required"
-            })));
+            return Ok(ApiError::unauthorized("Authentication required").into_response());
         }
     };
 
     let user_id: uuid::Uuid = match claims.sub.parse() {
         Ok(id) => id,
         Err(_) => {
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Invalid user ID in token"
-            })));
+            return Ok(ApiError::bad_request("Invalid user ID in token").into_response());
         }
     };
 
     let session_id: uuid::Uuid = match claims.session_id.parse() {
         Ok(id) => id,
         Err(_) => {
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Invalid session ID in token"
-            })));
+            return Ok(ApiError::bad_request("Invalid session ID in token").into_response());
         }
     };
 
@@ -533,9 +472,7 @@ pub async fn logout(
         Some(client) => client.get_ref().clone(),
         None => {
             tracing::error!("Redis client not found in app data");
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Session service unavailable"
-            })));
+            return Ok(ApiError::internal("Session service unavailable").into_response());
         }
     };
 
@@ -543,9 +480,7 @@ pub async fn logout(
         Ok(service) => std::sync::Arc::new(service),
         Err(e) => {
             tracing::error!("Failed to initialize security service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Service initialization failed"
-            })));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
 
@@ -560,9 +495,7 @@ pub async fn logout(
         // Logout from all sessions
         if let Err(e) = session_service.invalidate_all_user_sessions(user_id).await {
             tracing::error!("Failed to invalidate all sessions for user {}: {}", user_id, e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to logout from all sessions"
-            })));
+            return Ok(ApiError::internal("Failed to logout from all sessions").into_response());
         }
         
         tracing::info!("User {} logged out from all sessions", user_id);
@@ -575,9 +508,7 @@ pub async fn logout(
         
         if let Err(e) = session_service.invalidate_session(target_session_id).await {
             tracing::error!("Failed to invalidate session {} for user {}: {}", target_session_id, user_id, e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to logout"
-            })));
+            return Ok(ApiError::internal("Failed to logout").into_response());
         }
         
         tracing::info!("User {} logged out from session {}", user_id, target_session_id);
@@ -594,9 +525,7 @@ pub async fn get_current_user(
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     use crate::services::middleware::extract_claims_from_request;
@@ -605,18 +534,14 @@ pub async fn get_current_user(
     let claims = match extract_claims_from_request(&req) {
         Some(claims) => claims,
         None => {
-            return Ok(HttpResponse::Unauthorized().json(json!({
-                "error": "Authentication required"
-            })));
+            return Ok(ApiError::unauthorized("Authentication required").into_response());
         }
     };
 
     let user_id: uuid::Uuid = match claims.sub.parse() {
         Ok(id) => id,
         Err(_) => {
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Invalid user ID in token"
-            })));
+            return Ok(ApiError::bad_request("Invalid user ID in token").into_response());
         }
     };
 
@@ -625,9 +550,7 @@ pub async fn get_current_user(
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize user service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Service initialization failed"
-            })));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
 
@@ -638,15 +561,11 @@ pub async fn get_current_user(
             Ok(HttpResponse::Ok().json(user_profile))
         }
         Ok(None) => {
-            Ok(HttpResponse::NotFound().json(json!({
-                "error": "User not found"
-            })))
+            Ok(ApiError::not_found("User not found").into_response())
         }
         Err(e) => {
             tracing::error!("Failed to get user {}: {}", user_id, e);
-            Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to retrieve user"
-            })))
+            Ok(ApiError::internal("Failed to retrieve user").into_response())
         }
     }
 }
@@ -659,9 +578,7 @@ pub async fn refresh_token(
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     // Initialize services
@@ -669,9 +586,7 @@ pub async fn refresh_token(
         Ok(service) => std::sync::Arc::new(service),
         Err(e) => {
             tracing::error!("Failed to initialize security service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Service initialization failed"
-            })));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
 
@@ -683,9 +598,10 @@ pub async fn refresh_token(
 
     // Refresh the token
     match session_service.refresh_token(&request.refresh_token).await {
-        Ok((new_access_token, expires_at)) => {
+        Ok((new_access_token, new_refresh_token, expires_at)) => {
             let response = RefreshTokenResponse {
                 token: new_access_token,
+                refresh_token: new_refresh_token,
                 expires_at,
             };
             
@@ -693,9 +609,7 @@ pub async fn refresh_token(
         }
         Err(e) => {
             tracing::warn!("Token refresh failed: {}", e);
-            Ok(HttpResponse::Unauthorized().json(json!({
-                "error": "Invalid or expired refresh token"
-            })))
+            Ok(ApiError::unauthorized("Invalid or expired refresh token").into_response())
         }
     }
 }
@@ -704,9 +618,7 @@ pub async fn get_profile(pool_opt: web::Data<Option<PgPool>>) -> Result<HttpResp
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     
@@ -714,7 +626,7 @@ pub async fn get_profile(pool_opt: web::Data<Option<PgPool>>) -> Result<HttpResp
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize user service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
     
@@ -725,16 +637,12 @@ pub async fn get_profile(pool_opt: web::Data<Option<PgPool>>) -> Result<HttpResp
                 let user_profile = UserProfile::from(user.clone());
                 Ok(HttpResponse::Ok().json(user_profile))
             } else {
-                Ok(HttpResponse::NotFound().json(json!({
-                    "error": "No users found"
-                })))
+                Ok(ApiError::not_found("No users found").into_response())
             }
         }
         Err(e) => {
             tracing::error!("Failed to get user profile: {}", e);
-            Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to get profile"
-            })))
+            Ok(ApiError::internal("Failed to get profile").into_response())
         }
     }
 }
@@ -743,16 +651,14 @@ pub async fn list_users(pool_opt: web::Data<Option<PgPool>>) -> Result<HttpRespo
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     let user_service = match UserService::new(pool.clone()).await {
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize user service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
     
@@ -770,9 +676,7 @@ pub async fn list_users(pool_opt: web::Data<Option<PgPool>>) -> Result<HttpRespo
         }
         Err(e) => {
             tracing::error!("Failed to list users: {}", e);
-            Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to list users"
-            })))
+            Ok(ApiError::internal("Failed to list users").into_response())
         }
     }
 }
@@ -784,23 +688,18 @@ pub async fn oauth_callback(
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
         None => {
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     if let Err(validation_errors) = request.validate() {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        })));
+        return Ok(ApiError::validation(validation_errors).into_response());
     }
 
     let user_service = match UserService::new(pool.clone()).await {
         Ok(service) => service,
         Err(e) => {
             tracing::error!("Failed to initialize user service: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Service initialization failed"})));
+            return Ok(ApiError::internal("Service initialization failed").into_response());
         }
     };
     
@@ -826,10 +725,7 @@ pub async fn oauth_callback(
                 },
                 Err(e) => {
                     tracing::error!("Failed to create OAuth user: {}", e);
-                    return Ok(HttpResponse::InternalServerError().json(json!({
-                        "error": "Failed to create user",
-                        "details": format!("{}", e)
-                    })));
+                    return Ok(ApiError::internal("Failed to create user").with_details(json!(format!("{}", e))).into_response());
                 }
             }
         }
@@ -889,9 +785,7 @@ pub async fn oauth_callback(
         Ok(row) => row.get("id"),
         Err(e) => {
             tracing::error!("Failed to create/update social connection: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to store social connection"
-            })));
+            return Ok(ApiError::internal("Failed to store social connection").into_response());
         }
     };
 
@@ -924,6 +818,7 @@ pub fn configure_auth_routes(cfg: &mut web::ServiceConfig) {
             .route("/repos/bitbucket", web::get().to(list_bitbucket_repos))
             .route("/repos/bitbucket/branches", web::get().to(list_bitbucket_branches))
             .route("/repos/check", web::post().to(check_repo))
+            .route("/repos/connect/bulk", web::post().to(bulk_connect_repos))
             .route("/dev/reset", web::post().to(dev_reset))
     );
 }
@@ -942,11 +837,11 @@ pub async fn list_auth_connections(
     req: HttpRequest,
     pool_opt: web::Data<Option<PgPool>>,
 ) -> Result<HttpResponse> {
-    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(HttpResponse::ServiceUnavailable().json(json!({"error": "Database service unavailable"}))) };
+    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()) };
     use crate::services::middleware::extract_user_id_from_request_async;
     let user_id = match extract_user_id_from_request_async(&req, pool).await {
         Some(id) => id,
-        None => return Ok(HttpResponse::Unauthorized().json(json!({"error": "Authentication required or user not found"})))
+        None => return Ok(ApiError::unauthorized("Authentication required or user not found").into_response())
     };
 
     let rows = sqlx::query(
@@ -973,7 +868,7 @@ pub async fn list_auth_connections(
             }).collect();
             Ok(HttpResponse::Ok().json(json!({"success": true, "data": data})))
         },
-        Err(e) => Ok(HttpResponse::InternalServerError().json(json!({"error": format!("Failed to list connections: {}", e)})))
+        Err(e) => Ok(ApiError::internal(format!("Failed to list connections: {}", e)).into_response())
     }
 }
 
@@ -982,11 +877,11 @@ pub async fn disconnect_auth_connection(
     path: web::Path<String>,
     pool_opt: web::Data<Option<PgPool>>,
 ) -> Result<HttpResponse> {
-    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(HttpResponse::ServiceUnavailable().json(json!({"error": "Database service unavailable"}))) };
+    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()) };
     use crate::services::middleware::extract_user_id_from_request_async;
     let user_id = match extract_user_id_from_request_async(&req, pool).await {
         Some(id) => id,
-        None => return Ok(HttpResponse::Unauthorized().json(json!({"error": "Authentication required or user not found"})))
+        None => return Ok(ApiError::unauthorized("Authentication required or user not found").into_response())
     };
     let conn_id_str = path.into_inner();
     let conn_id = uuid::Uuid::parse_str(&conn_id_str).map_err(|_| actix_web::error::ErrorBadRequest("Invalid connection id"))?;
@@ -1004,7 +899,7 @@ pub async fn disconnect_auth_connection(
 
     match res {
         Ok(_) => Ok(HttpResponse::Ok().json(json!({"success": true}))),
-        Err(e) => Ok(HttpResponse::InternalServerError().json(json!({"error": format!("Failed to disconnect: {}", e)})))
+        Err(e) => Ok(ApiError::internal(format!("Failed to disconnect: {}", e)).into_response())
     }
 }
 
@@ -1017,10 +912,9 @@ pub async fn oauth_url(
     let provider = provider_raw.to_lowercase();
 
     if provider.is_empty() {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Missing provider",
-            "message": "Query parameter 'provider' is required",
-        })));
+        return Ok(ApiError::bad_request("Query parameter 'provider' is required")
+            .with_code("missing_provider")
+            .into_response());
     }
 
     let state = Uuid::new_v4().to_string();
@@ -1084,10 +978,10 @@ pub async fn oauth_url(
             )
         }
         _ => {
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Unsupported provider",
-                "provider": provider_raw,
-            })));
+            return Ok(ApiError::bad_request("Unsupported provider")
+                .with_code("unsupported_provider")
+                .with_details(json!({ "provider": provider_raw }))
+                .into_response());
         }
     };
 
@@ -1114,14 +1008,14 @@ pub async fn list_github_repos(
     req: HttpRequest,
     pool_opt: web::Data<Option<PgPool>>,
 ) -> Result<HttpResponse> {
-    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(HttpResponse::ServiceUnavailable().json(json!({"error": "Database service unavailable"}))) };
+    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()) };
     use crate::services::middleware::extract_claims_from_request;
-    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(HttpResponse::Unauthorized().json(json!({"error": "Authentication required"}))) };
+    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(ApiError::unauthorized("Authentication required").into_response()) };
     let user_id: uuid::Uuid = claims.sub.parse().map_err(|_| actix_web::error::ErrorBadRequest("Invalid user id"))?;
 
     let token = match get_bearer_token_for_provider(pool, user_id, "github").await {
         Ok(t) => t,
-        Err(e) => return Ok(HttpResponse::BadRequest().json(json!({"error": e.to_string()}))),
+        Err(e) => return Ok(ApiError::bad_request(e.to_string()).into_response()),
     };
 
     let client = Client::new();
@@ -1142,8 +1036,8 @@ pub async fn list_github_repos(
             })).collect();
             Ok(HttpResponse::Ok().json(json!({"repos": simplified})))
         }
-        Ok(r) => Ok(HttpResponse::BadRequest().json(json!({"error": format!("GitHub API error: {}", r.status())}))),
-        Err(e) => Ok(HttpResponse::BadRequest().json(json!({"error": format!("GitHub request failed: {}", e)}))),
+        Ok(r) => Ok(ApiError::bad_request(format!("GitHub API error: {}", r.status())).into_response()),
+        Err(e) => Ok(ApiError::bad_request(format!("GitHub request failed: {}", e)).into_response()),
     }
 }
 
@@ -1152,16 +1046,16 @@ pub async fn list_github_branches(
     pool_opt: web::Data<Option<PgPool>>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> Result<HttpResponse> {
-    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(HttpResponse::ServiceUnavailable().json(json!({"error": "Database service unavailable"}))) };
+    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()) };
     use crate::services::middleware::extract_claims_from_request;
-    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(HttpResponse::Unauthorized().json(json!({"error": "Authentication required"}))) };
+    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(ApiError::unauthorized("Authentication required").into_response()) };
     let user_id: uuid::Uuid = claims.sub.parse().map_err(|_| actix_web::error::ErrorBadRequest("Invalid user id"))?;
     let repo_full_name = query.get("repo").cloned().unwrap_or_default();
-    if repo_full_name.is_empty() { return Ok(HttpResponse::BadRequest().json(json!({"error": "Missing repo query parameter"}))); }
+    if repo_full_name.is_empty() { return Ok(ApiError::bad_request("Missing repo query parameter").into_response()); }
 
     let token = match get_bearer_token_for_provider(pool, user_id, "github").await {
         Ok(t) => t,
-        Err(e) => return Ok(HttpResponse::BadRequest().json(json!({"error": e.to_string()}))),
+        Err(e) => return Ok(ApiError::bad_request(e.to_string()).into_response()),
     };
 
     let client = Client::new();
@@ -1179,8 +1073,8 @@ pub async fn list_github_branches(
             let names: Vec<String> = branches.as_array().unwrap_or(&vec![]).iter().map(|b| b["name"].as_str().unwrap_or("").to_string()).collect();
             Ok(HttpResponse::Ok().json(json!({"branches": names})))
         }
-        Ok(r) => Ok(HttpResponse::BadRequest().json(json!({"error": format!("GitHub API error: {}", r.status())}))),
-        Err(e) => Ok(HttpResponse::BadRequest().json(json!({"error": format!("GitHub request failed: {}", e)}))),
+        Ok(r) => Ok(ApiError::bad_request(format!("GitHub API error: {}", r.status())).into_response()),
+        Err(e) => Ok(ApiError::bad_request(format!("GitHub request failed: {}", e)).into_response()),
     }
 }
 
@@ -1188,11 +1082,11 @@ pub async fn list_bitbucket_repos(
     req: HttpRequest,
     pool_opt: web::Data<Option<PgPool>>,
 ) -> Result<HttpResponse> {
-    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(HttpResponse::ServiceUnavailable().json(json!({"error": "Database service unavailable"}))) };
+    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()) };
     use crate::services::middleware::extract_claims_from_request;
-    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(HttpResponse::Unauthorized().json(json!({"error": "Authentication required"}))) };
+    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(ApiError::unauthorized("Authentication required").into_response()) };
     let user_id: uuid::Uuid = claims.sub.parse().map_err(|_| actix_web::error::ErrorBadRequest("Invalid user id"))?;
-    let token = match get_bearer_token_for_provider(pool, user_id, "bitbucket").await { Ok(t) => t, Err(e) => return Ok(HttpResponse::BadRequest().json(json!({"error": e.to_string()}))) };
+    let token = match get_bearer_token_for_provider(pool, user_id, "bitbucket").await { Ok(t) => t, Err(e) => return Ok(ApiError::bad_request(e.to_string()).into_response()) };
 
     let client = Client::new();
     let resp = client
@@ -1211,8 +1105,8 @@ pub async fn list_bitbucket_repos(
             })).collect();
             Ok(HttpResponse::Ok().json(json!({"repos": simplified})))
         }
-        Ok(r) => Ok(HttpResponse::BadRequest().json(json!({"error": format!("Bitbucket API error: {}", r.status())}))),
-        Err(e) => Ok(HttpResponse::BadRequest().json(json!({"error": format!("Bitbucket request failed: {}", e)}))),
+        Ok(r) => Ok(ApiError::bad_request(format!("Bitbucket API error: {}", r.status())).into_response()),
+        Err(e) => Ok(ApiError::bad_request(format!("Bitbucket request failed: {}", e)).into_response()),
     }
 }
 
@@ -1221,13 +1115,13 @@ pub async fn list_bitbucket_branches(
     pool_opt: web::Data<Option<PgPool>>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> Result<HttpResponse> {
-    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(HttpResponse::ServiceUnavailable().json(json!({"error": "Database service unavailable"}))) };
+    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()) };
     use crate::services::middleware::extract_claims_from_request;
-    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(HttpResponse::Unauthorized().json(json!({"error": "Authentication required"}))) };
+    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(ApiError::unauthorized("Authentication required").into_response()) };
     let user_id: uuid::Uuid = claims.sub.parse().map_err(|_| actix_web::error::ErrorBadRequest("Invalid user id"))?;
     let full_name = query.get("repo").cloned().unwrap_or_default();
-    if full_name.is_empty() { return Ok(HttpResponse::BadRequest().json(json!({"error": "Missing repo query parameter"}))); }
-    let token = match get_bearer_token_for_provider(pool, user_id, "bitbucket").await { Ok(t) => t, Err(e) => return Ok(HttpResponse::BadRequest().json(json!({"error": e.to_string()}))) };
+    if full_name.is_empty() { return Ok(ApiError::bad_request("Missing repo query parameter").into_response()); }
+    let token = match get_bearer_token_for_provider(pool, user_id, "bitbucket").await { Ok(t) => t, Err(e) => return Ok(ApiError::bad_request(e.to_string()).into_response()) };
 
     let client = Client::new();
     let url = format!("https://api.bitbucket.org/2.0/repositories/{}/refs/branches?pagelen=100", full_name);
@@ -1239,8 +1133,8 @@ pub async fn list_bitbucket_branches(
             let names: Vec<String> = data["values"].as_array().unwrap_or(&vec![]).iter().map(|b| b["name"].as_str().unwrap_or("").to_string()).collect();
             Ok(HttpResponse::Ok().json(json!({"branches": names})))
         }
-        Ok(r) => Ok(HttpResponse::BadRequest().json(json!({"error": format!("Bitbucket API error: {}", r.status())}))),
-        Err(e) => Ok(HttpResponse::BadRequest().json(json!({"error": format!("Bitbucket request failed: {}", e)}))),
+        Ok(r) => Ok(ApiError::bad_request(format!("Bitbucket API error: {}", r.status())).into_response()),
+        Err(e) => Ok(ApiError::bad_request(format!("Bitbucket request failed: {}", e)).into_response()),
     }
 }
 
@@ -1256,54 +1150,102 @@ pub async fn check_repo(
     payload: web::Json<RepoCheckRequest>,
     pool_opt: web::Data<Option<PgPool>>,
 ) -> Result<HttpResponse> {
-    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(HttpResponse::ServiceUnavailable().json(json!({"error": "Database service unavailable"}))) };
+    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()) };
     use crate::services::middleware::extract_claims_from_request;
-    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(HttpResponse::Unauthorized().json(json!({"error": "Authentication required"}))) };
+    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(ApiError::unauthorized("Authentication required").into_response()) };
     let user_id: uuid::Uuid = claims.sub.parse().map_err(|_| actix_web::error::ErrorBadRequest("Invalid user id"))?;
 
-    let url = &payload.repo_url;
-    let host = Url::parse(url).map(|u| u.host_str().unwrap_or("").to_string()).unwrap_or_default();
-    let provider = payload.provider.clone().unwrap_or_else(|| {
-        if host.contains("github.com") { "github".to_string() } else if host.contains("bitbucket.org") { "bitbucket".to_string() } else { "".to_string() }
-    });
-    if provider.is_empty() { return Ok(HttpResponse::BadRequest().json(json!({"error": "Unsupported repo URL"}))); }
+    let repo = match RepoRef::parse(&payload.repo_url, payload.provider.as_deref()) { Ok(r) => r, Err(e) => return Ok(ApiError::bad_request(e).into_response()) };
 
     let token = if let Some(t) = &payload.access_token { t.clone() } else {
-        match get_bearer_token_for_provider(pool, user_id, &provider).await { Ok(t) => t, Err(e) => return Ok(HttpResponse::BadRequest().json(json!({"error": e.to_string()}))) }
+        match get_bearer_token_for_provider(pool, user_id, &repo.provider).await { Ok(t) => t, Err(e) => return Ok(ApiError::bad_request(e.to_string()).into_response()) }
     };
 
-    let client = Client::new();
-    if provider == "github" {
-        let parts: Vec<&str> = url.trim_end_matches('/').split('/').collect();
-        let (owner, repo) = (parts.get(parts.len()-2).unwrap_or(&""), parts.get(parts.len()-1).unwrap_or(&""));
-        let api_url = format!("https://api.github.com/repos/{}/{}", owner, repo);
-        let resp = client.get(&api_url).header("User-Agent", "ConHub").bearer_auth(&token).send().await;
-        match resp {
-            Ok(r) if r.status().is_success() => {
-                let v: serde_json::Value = r.json().await.unwrap_or(json!({}));
-                let name = v["name"].as_str().unwrap_or("");
-                let full_name = v["full_name"].as_str().unwrap_or("");
-                return Ok(HttpResponse::Ok().json(json!({"provider": "github", "name": name, "full_name": full_name})));
-            }
-            Ok(r) => return Ok(HttpResponse::BadRequest().json(json!({"error": format!("GitHub API error: {}", r.status())}))),
-            Err(e) => return Ok(HttpResponse::BadRequest().json(json!({"error": format!("GitHub request failed: {}", e)}))),
-        }
-    } else {
-        let parts: Vec<&str> = url.trim_end_matches('/').split('/').collect();
-        let (workspace, repo) = (parts.get(parts.len()-2).unwrap_or(&""), parts.get(parts.len()-1).unwrap_or(&""));
-        let api_url = format!("https://api.bitbucket.org/2.0/repositories/{}/{}", workspace, repo);
-        let resp = client.get(&api_url).bearer_auth(&token).send().await;
-        match resp {
-            Ok(r) if r.status().is_success() => {
-                let v: serde_json::Value = r.json().await.unwrap_or(json!({}));
-                let name = v["name"].as_str().unwrap_or("");
-                let full_name = v["full_name"].as_str().unwrap_or("");
-                return Ok(HttpResponse::Ok().json(json!({"provider": "bitbucket", "name": name, "full_name": full_name})));
-            }
-            Ok(r) => return Ok(HttpResponse::BadRequest().json(json!({"error": format!("Bitbucket API error: {}", r.status())}))),
-            Err(e) => return Ok(HttpResponse::BadRequest().json(json!({"error": format!("Bitbucket request failed: {}", e)}))),
-        }
+    match check_repo_access(&Client::new(), &repo, &token).await {
+        Ok(info) => Ok(HttpResponse::Ok().json(info)),
+        Err(e) => Ok(ApiError::bad_request(e).into_response()),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct BulkRepoConnectRequest {
+    provider: Option<String>,
+    repo_urls: Vec<String>,
+    access_token: Option<String>,
+}
+
+/// Record an accessible repository as one of the user's connected sources
+async fn save_connected_repo(pool: &PgPool, user_id: uuid::Uuid, repo_url: &str, info: &RepoInfo) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        INSERT INTO connected_accounts (user_id, connector_type, account_name, account_identifier, metadata)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, connector_type, account_identifier)
+        DO UPDATE SET account_name = EXCLUDED.account_name, metadata = EXCLUDED.metadata,
+                      status = '{"status": "connected"}', updated_at = CURRENT_TIMESTAMP
+        "#
+    )
+    .bind(user_id)
+    .bind(&info.provider)
+    .bind(&info.name)
+    .bind(&info.full_name)
+    .bind(json!({"repo_url": repo_url}))
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| format!("Failed to save repository: {}", e))
+}
+
+/// POST /api/auth/repos/connect/bulk
+/// Check access to each repository with bounded concurrency and connect the
+/// accessible ones. One repository failing doesn't fail the request; every
+/// repository gets its own result.
+pub async fn bulk_connect_repos(
+    req: HttpRequest,
+    payload: web::Json<BulkRepoConnectRequest>,
+    pool_opt: web::Data<Option<PgPool>>,
+) -> Result<HttpResponse> {
+    let pool = match pool_opt.get_ref() { Some(p) => p, None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()) };
+    use crate::services::middleware::extract_claims_from_request;
+    let claims = match extract_claims_from_request(&req) { Some(c) => c, None => return Ok(ApiError::unauthorized("Authentication required").into_response()) };
+    let user_id: uuid::Uuid = claims.sub.parse().map_err(|_| actix_web::error::ErrorBadRequest("Invalid user id"))?;
+
+    let payload = payload.into_inner();
+    if payload.repo_urls.is_empty() { return Ok(ApiError::bad_request("repo_urls must not be empty").into_response()); }
+    if payload.repo_urls.len() > MAX_BULK_CONNECT_REPOS {
+        return Ok(ApiError::bad_request(format!("At most {} repositories can be connected at once", MAX_BULK_CONNECT_REPOS)).into_response());
+    }
+
+    // Look up each provider's token once rather than per repository
+    let mut tokens: std::collections::HashMap<String, Result<String, String>> = std::collections::HashMap::new();
+    for url in &payload.repo_urls {
+        let Ok(repo) = RepoRef::parse(url, payload.provider.as_deref()) else { continue };
+        if tokens.contains_key(&repo.provider) { continue; }
+        let token = match &payload.access_token {
+            Some(t) => Ok(t.clone()),
+            None => get_bearer_token_for_provider(pool, user_id, &repo.provider).await.map_err(|e| e.to_string()),
+        };
+        tokens.insert(repo.provider, token);
     }
+
+    let client = Client::new();
+    let (client, tokens, provider) = (&client, &tokens, payload.provider.as_deref());
+    let results = connect_repos(payload.repo_urls, BULK_CONNECT_CONCURRENCY, |repo_url| async move {
+        let repo = RepoRef::parse(&repo_url, provider)?;
+        let token = tokens.get(&repo.provider).cloned().unwrap_or_else(|| Err(format!("No {} token available", repo.provider)))?;
+        let info = check_repo_access(client, &repo, &token).await?;
+        save_connected_repo(pool, user_id, &repo_url, &info).await?;
+        Ok(info)
+    })
+    .await;
+
+    let connected = results.iter().filter(|r| r.status == RepoConnectStatus::Connected).count();
+    tracing::info!("Bulk repo connect for user {}: {} connected, {} failed", user_id, connected, results.len() - connected);
+    Ok(HttpResponse::Ok().json(json!({
+        "connected": connected,
+        "failed": results.len() - connected,
+        "results": results
+    })))
 }
 
 #[derive(serde::Deserialize)]
@@ -1330,7 +1272,7 @@ pub async fn oauth_exchange(
         Some(p) => p, 
         None => {
             tracing::error!("[OAuth Exchange][{}] Database pool unavailable", correlation_id);
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({"error": "Database service unavailable"})));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
     
@@ -1339,7 +1281,7 @@ pub async fn oauth_exchange(
         Some(c) => c, 
         None => {
             tracing::warn!("[OAuth Exchange][{}] No JWT claims found - authentication required", correlation_id);
-            return Ok(HttpResponse::Unauthorized().json(json!({"error": "Authentication required"})));
+            return Ok(ApiError::unauthorized("Authentication required").into_response());
         }
     };
     let user_id: uuid::Uuid = claims.sub.parse().map_err(|_| actix_web::error::ErrorBadRequest("Invalid user id"))?;
@@ -1359,7 +1301,7 @@ pub async fn oauth_exchange(
         "gitlab" => crate::services::oauth::OAuthProvider::GitLab,
         _ => {
             tracing::warn!("[OAuth Exchange][{}] Unsupported provider: {}", correlation_id, request.provider);
-            return Ok(HttpResponse::BadRequest().json(json!({"error": "Unsupported provider"})));
+            return Ok(ApiError::bad_request("Unsupported provider").into_response());
         }
     };
 
@@ -1376,7 +1318,7 @@ pub async fn oauth_exchange(
                 "[OAuth Exchange][{}] ❌ Token exchange FAILED: provider={}, user_id={}, error={}",
                 correlation_id, request.provider, user_id, e
             );
-            return Ok(HttpResponse::BadRequest().json(json!({"error": format!("Token exchange failed: {}", e)})));
+            return Ok(ApiError::bad_request(format!("Token exchange failed: {}", e)).into_response());
         }
     };
 
@@ -1393,7 +1335,7 @@ pub async fn oauth_exchange(
                 "[OAuth Exchange][{}] ❌ Failed to fetch user info: provider={}, error={}",
                 correlation_id, request.provider, e
             );
-            return Ok(HttpResponse::BadRequest().json(json!({"error": format!("Failed to fetch user info: {}", e)})));
+            return Ok(ApiError::bad_request(format!("Failed to fetch user info: {}", e)).into_response());
         }
     };
 
@@ -1453,7 +1395,7 @@ pub async fn oauth_exchange(
                 "[OAuth Exchange][{}] ❌ DB INSERT FAILED: provider={}, user_id={}, error={}",
                 correlation_id, request.provider, user_id, e
             );
-            return Ok(HttpResponse::InternalServerError().json(json!({"error": "Failed to store social connection"})));
+            return Ok(ApiError::internal("Failed to store social connection").into_response());
         }
     };
 
@@ -1507,9 +1449,7 @@ pub async fn internal_get_oauth_token(
         Some(p) => p,
         None => {
             tracing::error!("[Internal Token][{}] Database pool unavailable", correlation_id);
-            return Ok(HttpResponse::ServiceUnavailable().json(json!({
-                "error": "Database service unavailable"
-            })));
+            return Ok(ApiError::service_unavailable("Database service unavailable").into_response());
         }
     };
 
@@ -1518,9 +1458,7 @@ pub async fn internal_get_oauth_token(
         Some(id) => id,
         None => {
             tracing::warn!("[Internal Token][{}] Missing user_id query parameter", correlation_id);
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Missing user_id query parameter"
-            })));
+            return Ok(ApiError::bad_request("Missing user_id query parameter").into_response());
         }
     };
 
@@ -1528,9 +1466,7 @@ pub async fn internal_get_oauth_token(
         Ok(id) => id,
         Err(_) => {
             tracing::warn!("[Internal Token][{}] Invalid user_id format: {}", correlation_id, user_id_str);
-            return Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Invalid user_id format"
-            })));
+            return Ok(ApiError::bad_request("Invalid user_id format").into_response());
         }
     };
     
@@ -1543,9 +1479,7 @@ pub async fn internal_get_oauth_token(
     let valid_providers = ["github", "bitbucket", "gitlab", "google", "microsoft"];
     if !valid_providers.contains(&provider.as_str()) {
         tracing::warn!("[Internal Token][{}] Unsupported provider: {}", correlation_id, provider);
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": format!("Unsupported provider: {}", provider)
-        })));
+        return Ok(ApiError::bad_request(format!("Unsupported provider: {}", provider)).into_response());
     }
 
     // First, let's see how many connections exist for this user/provider (for debugging)
@@ -1656,17 +1590,11 @@ pub async fn internal_get_oauth_token(
                 }
             };
 
-            Ok(HttpResponse::NotFound().json(json!({
-                "code": error_code,
-                "error": error_message
-            })))
+            Ok(ApiError::not_found(error_message).with_code(error_code).into_response())
         }
         Err(e) => {
             tracing::error!("[Internal Token][{}] ❌ DB QUERY FAILED: provider={}, user_id={}, error={}", correlation_id, provider, user_id, e);
-            Ok(HttpResponse::InternalServerError().json(json!({
-                "code": "internal_error",
-                "error": "Failed to fetch OAuth token"
-            })))
+            Ok(ApiError::internal("Failed to fetch OAuth token").into_response())
         }
     }
 }
@@ -1680,24 +1608,18 @@ pub async fn internal_check_oauth_status(
 ) -> Result<HttpResponse> {
     let pool = match pool_opt.get_ref() {
         Some(p) => p,
-        None => return Ok(HttpResponse::ServiceUnavailable().json(json!({
-            "error": "Database service unavailable"
-        }))),
+        None => return Ok(ApiError::service_unavailable("Database service unavailable").into_response()),
     };
 
     let provider = path.into_inner().to_lowercase();
     let user_id_str = match query.get("user_id") {
         Some(id) => id,
-        None => return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Missing user_id query parameter"
-        }))),
+        None => return Ok(ApiError::bad_request("Missing user_id query parameter").into_response()),
     };
 
     let user_id: uuid::Uuid = match user_id_str.parse() {
         Ok(id) => id,
-        Err(_) => return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "Invalid user_id format"
-        }))),
+        Err(_) => return Ok(ApiError::bad_request("Invalid user_id format").into_response()),
     };
 
     let row = sqlx::query(
@@ -1739,9 +1661,7 @@ pub async fn internal_check_oauth_status(
         }))),
         Err(e) => {
             tracing::error!("Failed to check OAuth status: {}", e);
-            Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to check OAuth status"
-            })))
+            Ok(ApiError::internal("Failed to check OAuth status").into_response())
         }
     }
 }
@@ -1804,6 +1724,6 @@ pub async fn list_auth_connections_current(
             }).collect();
             Ok(HttpResponse::Ok().json(json!({"success": true, "data": data})))
         },
-        Err(e) => Ok(HttpResponse::InternalServerError().json(json!({"error": format!("Failed to list connections: {}", e)})))
+        Err(e) => Ok(ApiError::internal(format!("Failed to list connections: {}", e)).into_response())
     }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 use actix_web::{web, App, HttpServer};
 use actix_cors::Cors;
-use sqlx::{PgPool, postgres::{PgPoolOptions, PgConnectOptions}};
-use std::str::FromStr;
+use sqlx::PgPool;
+use conhub_database::DatabaseConfig;
 use std::env;
 use conhub_observability::{init_tracing, TracingConfig, observability, info, warn, error};
 
@@ -10,6 +10,7 @@ mod handlers;
 
 use services::role_auth_middleware;
 use conhub_middleware::auth::AuthMiddlewareFactory;
+use conhub_middleware::service_auth::ServiceAuthMiddlewareFactory;
 use conhub_config::feature_toggles::FeatureToggles;
 
 #[actix_web::main]
@@ -57,17 +58,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         database_url.split('@').last().unwrap_or("hidden")
     );
 
-    // Disable server-side prepared statements for pgbouncer/Neon transaction pooling
-    let connect_options = PgConnectOptions::from_str(&database_url)?
-        .statement_cache_capacity(0);
-
     tracing::info!("🔌 [Auth Service] Attempting database connection...");
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect_with(connect_options)
-        .await?;
+    let db_config = DatabaseConfig::for_url(database_url);
+    let pool = conhub_database::connect(&db_config).await?;
     tracing::info!("✅ [Auth Service] Database connection established successfully");
-    tracing::info!("📊 [Auth Service] Database pool created with max 10 connections");
+    tracing::info!(
+        "📊 [Auth Service] Database pool created with max {} connections, {}s acquire timeout",
+        db_config.max_connections, db_config.acquire_timeout_seconds
+    );
     let db_pool_opt: Option<PgPool> = Some(pool);
 
     // Redis connection for sessions (gated by Auth and Redis toggles)
@@ -163,6 +161,7 @@ fn configure_routes(cfg: &mut web::ServiceConfig, auth_middleware: AuthMiddlewar
                 .route("/repos/bitbucket", web::get().to(handlers::auth::list_bitbucket_repos))
                 .route("/repos/bitbucket/branches", web::get().to(handlers::auth::list_bitbucket_branches))
                 .route("/repos/check", web::post().to(handlers::auth::check_repo))
+                .route("/repos/connect/bulk", web::post().to(handlers::auth::bulk_connect_repos))
                 .service(
                     web::scope("/admin")
                         .wrap(role_auth_middleware(vec![UserRole::Admin]))
@@ -172,9 +171,10 @@ fn configure_routes(cfg: &mut web::ServiceConfig, auth_middleware: AuthMiddlewar
 
     cfg.service(scope);
 
-    // Internal service-to-service endpoints (no auth middleware - protected by network/service mesh)
+    // Internal service-to-service endpoints, guarded by the shared INTERNAL_SERVICE_TOKEN
     cfg.service(
         web::scope("/internal")
+            .wrap(ServiceAuthMiddlewareFactory::from_env())
             .route("/oauth/{provider}/token", web::get().to(handlers::auth::internal_get_oauth_token))
             .route("/oauth/{provider}/status", web::get().to(handlers::auth::internal_check_oauth_status))
     );
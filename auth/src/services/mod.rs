@@ -8,6 +8,7 @@ pub mod security;
 pub mod middleware;
 pub mod auth_service_orm;
 pub mod dev_user;
+pub mod repo_access;
 
 pub use users::*;
 pub use password_reset::*;
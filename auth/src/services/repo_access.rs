@@ -0,0 +1,204 @@
+//! Repository access checks
+//!
+//! Resolves a repository URL to its provider and verifies that a token can
+//! read it. Backs `/repos/check` and the bulk connect endpoint, which runs the
+//! same check for many repositories with bounded concurrency.
+
+use futures::stream::{self, StreamExt};
+use reqwest::{Client, Url};
+use serde::Serialize;
+use serde_json::json;
+use std::future::Future;
+
+/// Repositories checked at once by a bulk connect
+pub const BULK_CONNECT_CONCURRENCY: usize = 5;
+/// Most repositories accepted by one bulk connect request
+pub const MAX_BULK_CONNECT_REPOS: usize = 100;
+
+/// A repository on a supported provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoRef {
+    pub provider: String,
+    /// GitHub owner or Bitbucket workspace
+    pub owner: String,
+    pub repo: String,
+}
+
+impl RepoRef {
+    /// Parse a repository URL, inferring the provider from the host unless one is given
+    pub fn parse(repo_url: &str, provider: Option<&str>) -> Result<Self, String> {
+        let url = Url::parse(repo_url.trim()).map_err(|_| format!("'{}' is not a valid repo URL", repo_url))?;
+        let host = url.host_str().unwrap_or("");
+        let provider = match provider {
+            Some(p) => p.to_lowercase(),
+            None if host.contains("github.com") => "github".to_string(),
+            None if host.contains("bitbucket.org") => "bitbucket".to_string(),
+            None => return Err("Unsupported repo URL".to_string()),
+        };
+        if provider != "github" && provider != "bitbucket" {
+            return Err(format!("Unsupported provider: {}", provider));
+        }
+
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let [owner, repo, ..] = segments.as_slice() else {
+            return Err(format!("'{}' does not name an owner and repository", repo_url));
+        };
+
+        Ok(Self {
+            provider,
+            owner: owner.to_string(),
+            repo: repo.trim_end_matches(".git").to_string(),
+        })
+    }
+
+    pub fn api_url(&self) -> String {
+        match self.provider.as_str() {
+            "github" => format!("https://api.github.com/repos/{}/{}", self.owner, self.repo),
+            _ => format!("https://api.bitbucket.org/2.0/repositories/{}/{}", self.owner, self.repo),
+        }
+    }
+
+    fn provider_label(&self) -> &'static str {
+        if self.provider == "github" { "GitHub" } else { "Bitbucket" }
+    }
+}
+
+/// What the provider reports about an accessible repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepoInfo {
+    pub provider: String,
+    pub name: String,
+    pub full_name: String,
+}
+
+/// Fetch the repository with `token`; any failure means the token can't read it
+pub async fn check_repo_access(client: &Client, repo: &RepoRef, token: &str) -> Result<RepoInfo, String> {
+    let resp = client
+        .get(repo.api_url())
+        .header("User-Agent", "ConHub")
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| format!("{} request failed: {}", repo.provider_label(), e))?;
+    if !resp.status().is_success() {
+        return Err(format!("{} API error: {}", repo.provider_label(), resp.status()));
+    }
+
+    let v: serde_json::Value = resp.json().await.unwrap_or(json!({}));
+    Ok(RepoInfo {
+        provider: repo.provider.clone(),
+        name: v["name"].as_str().unwrap_or("").to_string(),
+        full_name: v["full_name"].as_str().unwrap_or("").to_string(),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoConnectStatus {
+    Connected,
+    Failed,
+}
+
+/// Outcome of connecting one repository in a bulk request
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoConnectResult {
+    pub repo_url: String,
+    pub status: RepoConnectStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<RepoInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Run `connect` for every URL, at most `concurrency` at a time. One repository
+/// failing doesn't affect the others; results keep the order of `repo_urls`.
+pub async fn connect_repos<F, Fut>(repo_urls: Vec<String>, concurrency: usize, connect: F) -> Vec<RepoConnectResult>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<RepoInfo, String>>,
+{
+    stream::iter(repo_urls)
+        .map(|repo_url| {
+            let outcome = connect(repo_url.clone());
+            async move {
+                match outcome.await {
+                    Ok(repo) => RepoConnectResult { repo_url, status: RepoConnectStatus::Connected, repo: Some(repo), reason: None },
+                    Err(reason) => RepoConnectResult { repo_url, status: RepoConnectStatus::Failed, repo: None, reason: Some(reason) },
+                }
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_repo_url_parsing() {
+        let repo = RepoRef::parse("https://github.com/acme/widgets.git", None).unwrap();
+        assert_eq!((repo.provider.as_str(), repo.owner.as_str(), repo.repo.as_str()), ("github", "acme", "widgets"));
+        assert_eq!(repo.api_url(), "https://api.github.com/repos/acme/widgets");
+
+        let repo = RepoRef::parse("https://bitbucket.org/team/service/src/main/", None).unwrap();
+        assert_eq!(repo.api_url(), "https://api.bitbucket.org/2.0/repositories/team/service");
+
+        assert!(RepoRef::parse("https://gitlab.com/acme/widgets", None).is_err());
+        assert!(RepoRef::parse("https://github.com/acme", None).is_err());
+        assert!(RepoRef::parse("not a url", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_connect_reports_each_repo() {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let urls = vec![
+            "https://github.com/acme/public".to_string(),
+            "https://github.com/acme/secret".to_string(),
+            "https://gitlab.com/acme/elsewhere".to_string(),
+            "https://bitbucket.org/team/service".to_string(),
+            "https://github.com/acme/tools".to_string(),
+        ];
+
+        let results = connect_repos(urls.clone(), 2, |url| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                let repo = RepoRef::parse(&url, None)?;
+                peak.fetch_max(in_flight.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                if repo.repo == "secret" {
+                    return Err("GitHub API error: 404 Not Found".to_string());
+                }
+                Ok(RepoInfo {
+                    provider: repo.provider,
+                    name: repo.repo.clone(),
+                    full_name: format!("{}/{}", repo.owner, repo.repo),
+                })
+            }
+        })
+        .await;
+
+        let statuses: Vec<RepoConnectStatus> = results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, [
+            RepoConnectStatus::Connected,
+            RepoConnectStatus::Failed,
+            RepoConnectStatus::Failed,
+            RepoConnectStatus::Connected,
+            RepoConnectStatus::Connected,
+        ]);
+        assert_eq!(results.iter().map(|r| r.repo_url.clone()).collect::<Vec<_>>(), urls);
+        assert_eq!(results[1].reason.as_deref(), Some("GitHub API error: 404 Not Found"));
+        assert_eq!(results[2].reason.as_deref(), Some("Unsupported repo URL"));
+        assert_eq!(results[3].repo.as_ref().unwrap().full_name, "team/service");
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+}
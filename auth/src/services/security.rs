@@ -168,7 +168,7 @@ impl SecurityService {
         Ok(token_data.claims)
     }
     
-    fn generate_refresh_token(&self) -> Result<String, Box<dyn std::error::Error>> {
+    pub fn generate_refresh_token(&self) -> Result<String, Box<dyn std::error::Error>> {
         let mut bytes = [0u8; 32];
         self.rng.fill(&mut bytes).map_err(|_| "Failed to generate random bytes")?;
         Ok(general_purpose::URL_SAFE_NO_PAD.encode(&bytes))
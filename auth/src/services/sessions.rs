@@ -1,13 +1,71 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use chrono::{DateTime, Utc, Duration};
 use uuid::Uuid;
 use sqlx::PgPool;
 use redis::AsyncCommands;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 
 use conhub_models::auth::*;
 use super::security::SecurityService;
 
+/// Why a refresh token was refused
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RefreshTokenError {
+    #[error("Invalid or expired refresh token")]
+    Invalid,
+
+    #[error("Refresh token was already used; session revoked")]
+    Reused,
+
+    #[error("Session has been revoked")]
+    Revoked,
+}
+
+/// The refresh tokens issued for one session: the current token plus the hashes
+/// of every token rotated out before it
+#[derive(Debug, Clone)]
+pub struct RefreshTokenFamily {
+    pub session_id: Uuid,
+    pub current: String,
+    pub rotated: HashSet<String>,
+    pub revoked: bool,
+}
+
+impl RefreshTokenFamily {
+    pub fn new(session_id: Uuid, current: &str) -> Self {
+        Self {
+            session_id,
+            current: current.to_string(),
+            rotated: HashSet::new(),
+            revoked: false,
+        }
+    }
+
+    /// Swap the presented token for `next`. A previously rotated token revokes the family.
+    pub fn rotate(&mut self, presented: &str, next: &str) -> Result<(), RefreshTokenError> {
+        if self.rotated.contains(&hash_refresh_token(presented)) {
+            self.revoked = true;
+            return Err(RefreshTokenError::Reused);
+        }
+        if self.revoked {
+            return Err(RefreshTokenError::Revoked);
+        }
+        if presented != self.current {
+            return Err(RefreshTokenError::Invalid);
+        }
+
+        self.rotated.insert(hash_refresh_token(presented));
+        self.current = next.to_string();
+        Ok(())
+    }
+}
+
+/// Rotated tokens are only stored as SHA-256 hex digests
+pub fn hash_refresh_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
 pub struct SessionService {
     pool: PgPool,
     redis_client: redis::Client,
@@ -134,17 +192,59 @@ impl SessionService {
         Ok(session)
     }
     
-    pub async fn refresh_token(&self, refresh_token: &str) -> Result<(String, DateTime<Utc>), Box<dyn std::error::Error>> {
-        // Find session by refresh token
+    /// Exchange a refresh token for a new access token and a rotated refresh token.
+    /// Presenting a token that was already rotated out revokes the whole session.
+    pub async fn refresh_token(&self, refresh_token: &str) -> Result<(String, String, DateTime<Utc>), Box<dyn std::error::Error>> {
+        let presented_hash = hash_refresh_token(refresh_token);
+
+        // Find the family the token belongs to, whether it is current or already rotated
         let session = sqlx::query_as::<_, UserSession>(
-            "SELECT * FROM user_sessions WHERE refresh_token = $1 AND status = 'active' AND refresh_expires_at > NOW()"
+            r#"
+            SELECT * FROM user_sessions
+            WHERE refresh_token = $1
+               OR id = (SELECT session_id FROM refresh_token_rotations WHERE token_hash = $2)
+            "#
         )
         .bind(refresh_token)
+        .bind(&presented_hash)
         .fetch_optional(&self.pool)
         .await?;
-        
-        let session = session.ok_or("Invalid or expired refresh token")?;
-        
+
+        let session = session.ok_or(RefreshTokenError::Invalid)?;
+
+        let mut family = RefreshTokenFamily::new(session.id, &session.refresh_token);
+        family.revoked = !matches!(session.status, SessionStatus::Active);
+        if session.refresh_token != refresh_token {
+            family.rotated.insert(presented_hash.clone());
+        }
+
+        let new_refresh_token = self.security_service.generate_refresh_token()?;
+        match family.rotate(refresh_token, &new_refresh_token) {
+            Ok(()) => {}
+            Err(RefreshTokenError::Reused) => {
+                tracing::warn!("Refresh token reuse detected for session {}, revoking", session.id);
+                self.revoke_session(session.id, None).await?;
+                self.security_service.log_security_event(
+                    Some(session.user_id),
+                    AuditEventType::SuspiciousActivity,
+                    session.ip_address.clone(),
+                    session.user_agent.clone(),
+                    Some(json!({
+                        "session_id": session.id,
+                        "reason": "refresh_token_reuse"
+                    })),
+                    Some(100),
+                    Some(session.id)
+                ).await?;
+                return Err(RefreshTokenError::Reused.into());
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        if session.refresh_expires_at <= Utc::now() {
+            return Err(RefreshTokenError::Invalid.into());
+        }
+
         // Get user details
         let user = sqlx::query_as::<_, User>(
             "SELECT * FROM users WHERE id = $1 AND is_active = true"
@@ -160,15 +260,31 @@ impl SessionService {
             .generate_jwt_token(&user, session.id, false)
             .await?;
         
-        // Update session with new token
-        sqlx::query(
-            "UPDATE user_sessions SET session_token = $1, expires_at = $2, last_used_at = NOW(), updated_at = NOW() WHERE id = $3"
+        // Rotate the refresh token, guarding against a concurrent rotation of the same token
+        let mut tx = self.pool.begin().await?;
+        let rotated = sqlx::query(
+            "UPDATE user_sessions SET session_token = $1, refresh_token = $2, expires_at = $3, last_used_at = NOW(), updated_at = NOW() WHERE id = $4 AND refresh_token = $5 AND status = 'active'"
         )
         .bind(&new_access_token[..50])
+        .bind(&new_refresh_token)
         .bind(token_expires)
         .bind(session.id)
-        .execute(&self.pool)
+        .bind(refresh_token)
+        .execute(&mut *tx)
         .await?;
+
+        if rotated.rows_affected() == 0 {
+            return Err(RefreshTokenError::Invalid.into());
+        }
+
+        sqlx::query(
+            "INSERT INTO refresh_token_rotations (token_hash, session_id) VALUES ($1, $2) ON CONFLICT (token_hash) DO NOTHING"
+        )
+        .bind(&presented_hash)
+        .bind(session.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
         
         // Update Redis cache
         let mut redis_conn = self.redis_client.get_async_connection().await?;
@@ -201,7 +317,7 @@ impl SessionService {
             Some(session.id)
         ).await?;
         
-        Ok((new_access_token, token_expires))
+        Ok((new_access_token, new_refresh_token, token_expires))
     }
     
     pub async fn revoke_session(&self, session_id: Uuid, user_id: Option<Uuid>) -> Result<(), Box<dyn std::error::Error>> {
@@ -385,4 +501,33 @@ pub async fn session_cleanup_task(session_service: std::sync::Arc<SessionService
             tracing::error!("Failed to cleanup expired sessions: {}", e);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_rotation() {
+        let mut family = RefreshTokenFamily::new(Uuid::new_v4(), "token-1");
+
+        family.rotate("token-1", "token-2").unwrap();
+        family.rotate("token-2", "token-3").unwrap();
+
+        assert_eq!(family.current, "token-3");
+        assert!(!family.revoked);
+        assert!(family.rotated.contains(&hash_refresh_token("token-1")));
+        assert_eq!(family.rotate("unknown", "token-4"), Err(RefreshTokenError::Invalid));
+    }
+
+    #[test]
+    fn test_reuse_revokes_family() {
+        let mut family = RefreshTokenFamily::new(Uuid::new_v4(), "token-1");
+        family.rotate("token-1", "token-2").unwrap();
+
+        // A replayed token revokes the family, including the legitimate current token
+        assert_eq!(family.rotate("token-1", "token-x"), Err(RefreshTokenError::Reused));
+        assert!(family.revoked);
+        assert_eq!(family.rotate("token-2", "token-3"), Err(RefreshTokenError::Revoked));
+    }
+}
@@ -1,5 +1,73 @@
+use conhub_config::{validate_url, ConfigError};
+use std::collections::HashMap;
 use std::env;
 
+/// Service URL env vars that must be set explicitly outside development mode
+const REQUIRED_URL_VARS: [&str; 1] = ["EMBEDDING_SERVICE_URL"];
+
+/// How embedding inputs longer than the model's limit are handled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlengthStrategy {
+    /// Keep the leading part that fits
+    Truncate,
+    /// Embed each piece that fits and average the vectors
+    SplitAverage,
+}
+
+impl OverlengthStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "truncate" => Some(OverlengthStrategy::Truncate),
+            "split_average" | "split-average" => Some(OverlengthStrategy::SplitAverage),
+            _ => None,
+        }
+    }
+}
+
+/// Which embedding providers have their vectors L2-normalized when they reach the
+/// backend, so stored vectors are unit length whatever the provider returned.
+/// Parsed from `EMBEDDING_L2_NORMALIZE`, e.g. `default=off,openai=on,bge=on`.
+/// Off unless enabled: vectors already stored were not normalized, so turn a
+/// provider on only together with re-embedding its existing data.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EmbeddingNormalization {
+    pub default: bool,
+    pub providers: HashMap<String, bool>,
+}
+
+impl EmbeddingNormalization {
+    pub fn parse(value: &str) -> Self {
+        let mut policy = Self::default();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((provider, flag)) = entry.split_once('=') else { continue };
+            let enabled = match flag.trim().to_ascii_lowercase().as_str() {
+                "on" | "true" | "1" | "yes" => true,
+                "off" | "false" | "0" | "no" => false,
+                _ => continue,
+            };
+            match provider.trim().to_ascii_lowercase().as_str() {
+                "default" | "*" => policy.default = enabled,
+                provider => {
+                    policy.providers.insert(provider.to_string(), enabled);
+                }
+            }
+        }
+        policy
+    }
+
+    /// Whether vectors from `model` are normalized. Looks up the full model name, then
+    /// its provider prefix (`openai` in `openai/text-embedding-3-small`).
+    pub fn applies_to(&self, model: &str) -> bool {
+        let model = model.to_ascii_lowercase();
+        let provider = model.split(['/', ':']).next().unwrap_or_default();
+        self.providers
+            .get(&model)
+            .or_else(|| self.providers.get(provider))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     // Server
@@ -17,10 +85,19 @@ pub struct AppConfig {
     
     // Microservices
     pub embedding_service_url: String,
+    pub graph_service_url: String,
+    pub agentic_service_url: String,
     // Microservice call settings
     pub embedding_request_timeout_ms: u64,
     pub embedding_request_retries: usize,
     pub embedding_max_inflight: usize,
+    /// Distinct texts sent to the embedding service per request
+    pub embedding_batch_size: usize,
+    /// Overall time budget for one embed call; texts not embedded by then are reported back
+    pub embedding_batch_deadline_ms: u64,
+    pub embedding_max_input_tokens: usize,
+    pub embedding_overlength_strategy: OverlengthStrategy,
+    pub embedding_normalization: EmbeddingNormalization,
 
     // Authentication
     pub jwt_secret: String,
@@ -62,91 +139,213 @@ pub struct AppConfig {
 }
 
 impl AppConfig {
+    /// Load from the environment, panicking on invalid values. Startup uses
+    /// `try_from_env` so the error is reported instead.
     pub fn from_env() -> Self {
+        Self::try_from_env().unwrap_or_else(|e| panic!("Invalid backend configuration: {}", e))
+    }
+
+    /// Load from the environment (and `.env`) and validate, failing fast on bad or
+    /// missing values
+    pub fn try_from_env() -> Result<Self, ConfigError> {
         dotenv::dotenv().ok();
+        Self::try_from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Build and validate config from an arbitrary variable source.
+    /// Outside development mode every service URL must be set explicitly.
+    pub fn try_from_lookup<F>(lookup: F) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let backend_port = match lookup("BACKEND_PORT") {
+            Some(value) => value.trim().parse().map_err(|e: std::num::ParseIntError| ConfigError::InvalidValue {
+                var: "BACKEND_PORT".to_string(),
+                value: value.clone(),
+                reason: e.to_string(),
+            })?,
+            None => 8000,
+        };
 
-        Self {
+        let config = Self {
             // Server
-            backend_port: env::var("BACKEND_PORT")
-                .unwrap_or_else(|_| "8000".to_string())
-                .parse()
-                .expect("BACKEND_PORT must be a valid port number"),
-            env_mode: env::var("ENV_MODE").unwrap_or_else(|_| "development".to_string()),
+            backend_port,
+            env_mode: lookup("ENV_MODE").unwrap_or_else(|| "development".to_string()),
 
             // Database
             // Make DB URLs optional to allow full startup when Auth is disabled
-            database_url: env::var("DATABASE_URL").ok(),
-            redis_url: env::var("REDIS_URL").ok(),
+            database_url: lookup("DATABASE_URL"),
+            redis_url: lookup("REDIS_URL"),
             
             // Vector Store (Zilliz Cloud)
-            zilliz_uri: env::var("ZILLIZ_URI").ok(),
-            zilliz_token: env::var("ZILLIZ_TOKEN").ok(),
-            zilliz_db_name: env::var("ZILLIZ_DB_NAME").ok(),
-            embedding_service_url: env::var("EMBEDDING_SERVICE_URL")
-                .unwrap_or_else(|_| "http://localhost:8082".to_string()),
-            embedding_request_timeout_ms: env::var("EMBEDDING_REQUEST_TIMEOUT_MS")
-                .unwrap_or_else(|_| "5000".to_string())
+            zilliz_uri: lookup("ZILLIZ_URI"),
+            zilliz_token: lookup("ZILLIZ_TOKEN"),
+            zilliz_db_name: lookup("ZILLIZ_DB_NAME"),
+            embedding_service_url: lookup("EMBEDDING_SERVICE_URL")
+                .unwrap_or_else(|| "http://localhost:8082".to_string()),
+            graph_service_url: lookup("GRAPH_SERVICE_URL")
+                .unwrap_or_else(|| "http://localhost:8006".to_string()),
+            agentic_service_url: lookup("AGENTIC_SERVICE_URL")
+                .unwrap_or_else(|| "http://localhost:3005".to_string()),
+            embedding_request_timeout_ms: lookup("EMBEDDING_REQUEST_TIMEOUT_MS")
+                .unwrap_or_else(|| "5000".to_string())
                 .parse()
                 .unwrap_or(5000),
-            embedding_request_retries: env::var("EMBEDDING_REQUEST_RETRIES")
-                .unwrap_or_else(|_| "2".to_string())
+            embedding_request_retries: lookup("EMBEDDING_REQUEST_RETRIES")
+                .unwrap_or_else(|| "2".to_string())
                 .parse()
                 .unwrap_or(2),
-            embedding_max_inflight: env::var("EMBEDDING_MAX_INFLIGHT")
-                .unwrap_or_else(|_| "64".to_string())
+            embedding_max_inflight: lookup("EMBEDDING_MAX_INFLIGHT")
+                .unwrap_or_else(|| "64".to_string())
                 .parse()
                 .unwrap_or(64),
+            embedding_batch_size: lookup("EMBEDDING_BATCH_SIZE")
+                .unwrap_or_else(|| "128".to_string())
+                .parse()
+                .unwrap_or(128),
+            embedding_batch_deadline_ms: lookup("EMBEDDING_BATCH_DEADLINE_MS")
+                .unwrap_or_else(|| "30000".to_string())
+                .parse()
+                .unwrap_or(30000),
+            embedding_max_input_tokens: lookup("EMBEDDING_MAX_INPUT_TOKENS")
+                .unwrap_or_else(|| "8192".to_string())
+                .parse()
+                .unwrap_or(8192),
+            embedding_overlength_strategy: lookup("EMBEDDING_OVERLENGTH_STRATEGY")
+                .and_then(|s| OverlengthStrategy::parse(&s))
+                .unwrap_or(OverlengthStrategy::Truncate),
+            embedding_normalization: lookup("EMBEDDING_L2_NORMALIZE")
+                .map(|s| EmbeddingNormalization::parse(&s))
+                .unwrap_or_default(),
 
             // Authentication
             // Require JWT_SECRET only when Auth is enabled; otherwise use a stub to allow startup.
-            jwt_secret: env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
-            google_client_id: env::var("GOOGLE_CLIENT_ID").ok(),
-            google_client_secret: env::var("GOOGLE_CLIENT_SECRET").ok(),
-            github_client_id: env::var("GITHUB_CLIENT_ID").ok(),
-            github_client_secret: env::var("GITHUB_CLIENT_SECRET").ok(),
-            microsoft_client_id: env::var("MICROSOFT_CLIENT_ID").ok(),
-            microsoft_client_secret: env::var("MICROSOFT_CLIENT_SECRET").ok(),
+            jwt_secret: lookup("JWT_SECRET")
+                .filter(|v| !v.trim().is_empty())
+                .ok_or_else(|| ConfigError::MissingVar("JWT_SECRET".to_string()))?,
+            google_client_id: lookup("GOOGLE_CLIENT_ID"),
+            google_client_secret: lookup("GOOGLE_CLIENT_SECRET"),
+            github_client_id: lookup("GITHUB_CLIENT_ID"),
+            github_client_secret: lookup("GITHUB_CLIENT_SECRET"),
+            microsoft_client_id: lookup("MICROSOFT_CLIENT_ID"),
+            microsoft_client_secret: lookup("MICROSOFT_CLIENT_SECRET"),
 
             // Email
-            smtp_host: env::var("SMTP_HOST").ok(),
-            smtp_port: env::var("SMTP_PORT").ok().and_then(|p| p.parse().ok()),
-            smtp_username: env::var("SMTP_USERNAME").ok(),
-            smtp_password: env::var("SMTP_PASSWORD").ok(),
+            smtp_host: lookup("SMTP_HOST"),
+            smtp_port: lookup("SMTP_PORT").and_then(|p| p.parse().ok()),
+            smtp_username: lookup("SMTP_USERNAME"),
+            smtp_password: lookup("SMTP_PASSWORD"),
 
             // Billing
-            stripe_secret_key: env::var("STRIPE_SECRET_KEY").ok(),
-            stripe_webhook_secret: env::var("STRIPE_WEBHOOK_SECRET").ok(),
+            stripe_secret_key: lookup("STRIPE_SECRET_KEY"),
+            stripe_webhook_secret: lookup("STRIPE_WEBHOOK_SECRET"),
 
             // AI
-            openai_api_key: env::var("OPENAI_API_KEY").ok(),
-            anthropic_api_key: env::var("ANTHROPIC_API_KEY").ok(),
+            openai_api_key: lookup("OPENAI_API_KEY"),
+            anthropic_api_key: lookup("ANTHROPIC_API_KEY"),
 
             // Data sources
-            github_token: env::var("GITHUB_TOKEN").ok(),
-            gitlab_token: env::var("GITLAB_TOKEN").ok(),
-            notion_token: env::var("NOTION_TOKEN").ok(),
+            github_token: lookup("GITHUB_TOKEN"),
+            gitlab_token: lookup("GITLAB_TOKEN"),
+            notion_token: lookup("NOTION_TOKEN"),
 
             // Webhooks
-            github_webhook_secret: env::var("GITHUB_WEBHOOK_SECRET").ok(),
-            gitlab_webhook_secret: env::var("GITLAB_WEBHOOK_SECRET").ok(),
+            github_webhook_secret: lookup("GITHUB_WEBHOOK_SECRET"),
+            gitlab_webhook_secret: lookup("GITLAB_WEBHOOK_SECRET"),
 
             // Indexing configuration
-            max_file_size: env::var("MAX_FILE_SIZE")
-                .unwrap_or_else(|_| "10485760".to_string())
+            max_file_size: lookup("MAX_FILE_SIZE")
+                .unwrap_or_else(|| "10485760".to_string())
                 .parse()
                 .unwrap_or(10485760),
-            chunk_size: env::var("CHUNK_SIZE")
-                .unwrap_or_else(|_| "1000".to_string())
+            chunk_size: lookup("CHUNK_SIZE")
+                .unwrap_or_else(|| "1000".to_string())
                 .parse()
                 .unwrap_or(1000),
-            chunk_overlap: env::var("CHUNK_OVERLAP")
-                .unwrap_or_else(|_| "200".to_string())
+            chunk_overlap: lookup("CHUNK_OVERLAP")
+                .unwrap_or_else(|| "200".to_string())
                 .parse()
                 .unwrap_or(200),
-            max_concurrent_indexing: env::var("MAX_CONCURRENT_INDEXING")
-                .unwrap_or_else(|_| "4".to_string())
+            max_concurrent_indexing: lookup("MAX_CONCURRENT_INDEXING")
+                .unwrap_or_else(|| "4".to_string())
                 .parse()
                 .unwrap_or(4),
+        };
+
+        if !config.is_development() {
+            for var in REQUIRED_URL_VARS {
+                if lookup(var).filter(|v| !v.trim().is_empty()).is_none() {
+                    return Err(ConfigError::MissingVar(var.to_string()));
+                }
+            }
         }
+
+        for (var, url) in config.service_urls() {
+            validate_url(var, url)?;
+        }
+        Ok(config)
+    }
+
+    /// Each downstream service URL with the variable it is read from
+    pub fn service_urls(&self) -> [(&'static str, &str); 3] {
+        [
+            ("EMBEDDING_SERVICE_URL", &self.embedding_service_url),
+            ("GRAPH_SERVICE_URL", &self.graph_service_url),
+            ("AGENTIC_SERVICE_URL", &self.agentic_service_url),
+        ]
+    }
+
+    pub fn is_development(&self) -> bool {
+        matches!(self.env_mode.as_str(), "development" | "dev" | "local")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_development_defaults_are_valid() {
+        let config = AppConfig::try_from_lookup(lookup_from(&[("JWT_SECRET", "secret")])).unwrap();
+        assert_eq!(config.backend_port, 8000);
+        assert_eq!(config.embedding_service_url, "http://localhost:8082");
+    }
+
+    #[test]
+    fn test_missing_or_invalid_values_are_config_errors() {
+        assert_eq!(
+            AppConfig::try_from_lookup(lookup_from(&[])).unwrap_err(),
+            ConfigError::MissingVar("JWT_SECRET".to_string())
+        );
+        assert!(matches!(
+            AppConfig::try_from_lookup(lookup_from(&[("JWT_SECRET", "secret"), ("BACKEND_PORT", "80a")])),
+            Err(ConfigError::InvalidValue { var, .. }) if var == "BACKEND_PORT"
+        ));
+        for var in ["EMBEDDING_SERVICE_URL", "GRAPH_SERVICE_URL", "AGENTIC_SERVICE_URL"] {
+            assert!(matches!(
+                AppConfig::try_from_lookup(lookup_from(&[("JWT_SECRET", "secret"), (var, "service:8000")])),
+                Err(ConfigError::InvalidUrl { var: invalid, .. }) if invalid == var
+            ));
+        }
+    }
+
+    #[test]
+    fn test_production_requires_explicit_service_urls() {
+        let vars = [("JWT_SECRET", "secret"), ("ENV_MODE", "production")];
+        assert_eq!(
+            AppConfig::try_from_lookup(lookup_from(&vars)).unwrap_err(),
+            ConfigError::MissingVar("EMBEDDING_SERVICE_URL".to_string())
+        );
+
+        let vars = [("JWT_SECRET", "secret"), ("ENV_MODE", "production"), ("EMBEDDING_SERVICE_URL", "http://embedding:8082")];
+        assert!(AppConfig::try_from_lookup(lookup_from(&vars)).is_ok());
     }
 }
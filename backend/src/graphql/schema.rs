@@ -1,9 +1,12 @@
 use async_graphql::{Context, EmptySubscription, InputObject, Object, Schema};
+use std::future::Future;
+use std::ops::Range;
 use std::time::Duration;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
 use serde::{Deserialize, Serialize};
-use crate::config::AppConfig;
+use crate::config::{AppConfig, OverlengthStrategy};
+use crate::services::embedding_retry_service::{EmbeddingRetryQueue, FailedEmbeddingBatch};
 use conhub_models::auth::Claims;
 use conhub_models::graphql::{EmbeddingResult, RerankResult, RerankDocument as SharedRerankDocument, RerankDocumentOutput};
 use std::collections::HashMap;
@@ -40,7 +43,7 @@ impl QueryRoot {
 
         // Generate cache key from texts and normalization setting
         let normalize_val = normalize.unwrap_or(true);
-        let cache_key = format!("embed:{}:{}", normalize_val, texts.join("|"));
+        let cache_key = embed_cache_key(normalize_val, &texts);
         
         // Try to get from cache first
         let cache = get_cache();
@@ -51,81 +54,29 @@ impl QueryRoot {
         let cfg = ctx.data::<AppConfig>()?;
         let semaphore = ctx.data::<Arc<Semaphore>>()?;
         let _permit = semaphore.acquire().await.map_err(|_| async_graphql::Error::new("Concurrency limiter closed"))?;
-        let url = format!("{}/embed", cfg.embedding_service_url);
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_millis(cfg.embedding_request_timeout_ms))
-            .build()
-            .map_err(|e| async_graphql::Error::new(format!("Failed to build HTTP client: {}", e)))?;
 
-        #[derive(Deserialize)]
-        struct EmbedResponse {
-            embeddings: Vec<Vec<f32>>,
-            dimension: usize,
-            model: String,
-            count: usize,
-        }
-
-        let body = serde_json::json!({
-            "text": texts,
-            "normalize": normalize.unwrap_or(true)
-        });
-
-        let mut last_err: Option<async_graphql::Error> = None;
-        for attempt in 0..=cfg.embedding_request_retries {
-            match client.post(&url).json(&body).send().await {
-                Ok(resp) => {
-                    if resp.status().is_success() {
-                        let parsed: Result<EmbedResponse, _> = resp.json().await;
-                        match parsed {
-                            Ok(parsed) => {
-                                let result = EmbeddingResult {
-                                    embeddings: parsed.embeddings,
-                                    dimension: parsed.dimension,
-                                    model: parsed.model,
-                                    count: parsed.count,
-                                };
-                                
-                                // Cache the result for future requests (1 hour TTL)
-                                let _ = cache.set(&cache_key, &result, Some(Duration::from_secs(3600)));
-                                
-                                return Ok(result);
-                            }
-                            Err(e) => {
-                                last_err = Some(async_graphql::Error::new(format!(
-                                    "Failed to parse embedding response: {}",
-                                    e
-                                )));
-                            }
-                        }
-                    } else {
-                        let status = resp.status();
-                        let text = resp.text().await.unwrap_or_default();
-                        // Retry only on transient errors
-                        if status.is_server_error() || status.as_u16() == 429 || status.as_u16() == 408 {
-                            last_err = Some(async_graphql::Error::new(format!(
-                                "Embedding service transient error {}: {}",
-                                status, text
-                            )));
-                        } else {
-                            return Err(async_graphql::Error::new(format!(
-                                "Embedding service error {}: {}",
-                                status, text
-                            )));
-                        }
-                    }
-                }
-                Err(e) => {
-                    last_err = Some(async_graphql::Error::new(format!(
-                        "Embedding service request failed: {}",
-                        e
-                    )));
+        let result = match embed_texts(cfg, &texts, normalize_val).await {
+            Ok(result) => result,
+            Err(e) => {
+                // Re-embed once the outage passes so the client's retry is answered from the cache
+                let queue = ctx.data::<Arc<EmbeddingRetryQueue>>()?;
+                if let Err(qe) = queue.enqueue_failure(Vec::new(), texts, normalize_val, &e.message).await {
+                    log::error!("Failed to queue embedding batch for retry: {}", qe);
                 }
+                return Err(e);
             }
-            // Exponential backoff between retries
-            let delay_ms = 100u64.saturating_mul(1u64 << attempt);
-            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        };
+
+        if result.unprocessed_indices.is_empty() {
+            // Cache the result for future requests (1 hour TTL)
+            let _ = cache.set(&cache_key, &result, Some(EMBED_CACHE_TTL));
+        } else {
+            log::warn!(
+                "Embedding batch hit its {}ms deadline; returning {} of {} embeddings",
+                cfg.embedding_batch_deadline_ms, result.count, texts.len()
+            );
         }
-        return Err(last_err.unwrap_or_else(|| async_graphql::Error::new("Embedding request failed")));
+        Ok(result)
     }
 
     async fn rerank(&self, ctx: &Context<'_>, query: String, documents: Vec<RerankDocumentInput>, top_k: Option<i32>) -> async_graphql::Result<Vec<RerankResult>> {
@@ -222,6 +173,299 @@ impl QueryRoot {
     }
 }
 
+/// How long a complete `embed` result is served from the cache
+const EMBED_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+fn embed_cache_key(normalize: bool, texts: &[String]) -> String {
+    format!("embed:{}:{}", normalize, texts.join("|"))
+}
+
+/// Embed `texts` through the embedding service. Texts still unembedded when the
+/// batch deadline passes are listed in `unprocessed_indices`.
+async fn embed_texts(cfg: &AppConfig, texts: &[String], normalize: bool) -> async_graphql::Result<EmbeddingResult> {
+    let url = format!("{}/embed", cfg.embedding_service_url);
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(cfg.embedding_request_timeout_ms))
+        .build()
+        .map_err(|e| async_graphql::Error::new(format!("Failed to build HTTP client: {}", e)))?;
+
+    // Keep every input within the model's limit so one huge file can't fail the batch
+    let (pieces, piece_ranges) = fit_to_model(texts, cfg.embedding_max_input_tokens, cfg.embedding_overlength_strategy);
+
+    // Boilerplate-heavy batches repeat the same text; embed each distinct text once
+    let (unique_texts, positions) = dedup_texts(&pieces);
+    if unique_texts.is_empty() {
+        return Ok(EmbeddingResult {
+            embeddings: Vec::new(),
+            dimension: 0,
+            model: String::new(),
+            count: 0,
+            normalized: false,
+            unprocessed_indices: Vec::new(),
+        });
+    }
+
+    // Embed in sub-batches so hitting the deadline keeps the batches that finished
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(cfg.embedding_batch_deadline_ms);
+    let batches = embed_until_deadline(&unique_texts, cfg.embedding_batch_size, deadline, |batch| {
+        request_embeddings(&client, &url, batch, normalize, cfg.embedding_request_retries)
+    })
+    .await?;
+    let embedded_count = batches.len().saturating_mul(cfg.embedding_batch_size.max(1)).min(unique_texts.len());
+    let Some((dimension, model)) = batches.last().map(|b| (b.dimension, b.model.clone())) else {
+        return Err(async_graphql::Error::new(
+            "Embedding batch deadline passed before any embeddings completed",
+        ));
+    };
+    let unique_embeddings: Vec<Vec<f32>> = batches.into_iter().flat_map(|b| b.embeddings).collect();
+
+    // Return the leading texts whose pieces were all embedded
+    let completed = completed_prefix(&piece_ranges, &positions, embedded_count);
+    let pieces_done = piece_ranges[..completed].last().map_or(0, |r| r.end);
+    let mut embeddings = combine_pieces(
+        fan_out(unique_embeddings, embedded_count, &positions[..pieces_done])?,
+        &piece_ranges[..completed],
+        normalize,
+    );
+    // Some providers ignore the flag; enforce unit length here
+    let normalized = normalize && cfg.embedding_normalization.applies_to(&model);
+    if normalized {
+        embeddings.iter_mut().for_each(|e| l2_normalize(e));
+    }
+    Ok(EmbeddingResult {
+        count: embeddings.len(),
+        embeddings,
+        dimension,
+        model,
+        normalized,
+        unprocessed_indices: (completed..texts.len()).collect(),
+    })
+}
+
+/// Retry a batch queued when the `embed` query failed. The result is cached
+/// under that query's key, so the client's next attempt doesn't wait on the
+/// embedding service.
+pub async fn reembed_failed_batch(cfg: &AppConfig, batch: FailedEmbeddingBatch) -> Result<(), String> {
+    let result = embed_texts(cfg, &batch.texts, batch.normalize).await.map_err(|e| e.message)?;
+    if !result.unprocessed_indices.is_empty() {
+        return Err(format!("Embedded {} of {} texts before the deadline", result.count, batch.texts.len()));
+    }
+
+    get_cache()
+        .set(&embed_cache_key(batch.normalize, &batch.texts), &result, Some(EMBED_CACHE_TTL))
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+    dimension: usize,
+    model: String,
+    count: usize,
+}
+
+/// One call to the embedding service, retrying transient failures with backoff
+async fn request_embeddings(
+    client: &reqwest::Client,
+    url: &str,
+    texts: Vec<String>,
+    normalize: bool,
+    retries: usize,
+) -> async_graphql::Result<EmbedResponse> {
+    let body = serde_json::json!({
+        "text": texts,
+        "normalize": normalize
+    });
+
+    let mut last_err: Option<async_graphql::Error> = None;
+    for attempt in 0..=retries {
+        match client.post(url).json(&body).send().await {
+            Ok(resp) => {
+                if resp.status().is_success() {
+                    match resp.json::<EmbedResponse>().await {
+                        Ok(parsed) if parsed.embeddings.len() != texts.len() => {
+                            return Err(async_graphql::Error::new(format!(
+                                "Embedding service returned {} embeddings for {} texts",
+                                parsed.embeddings.len(),
+                                texts.len()
+                            )));
+                        }
+                        Ok(parsed) => return Ok(parsed),
+                        Err(e) => {
+                            last_err = Some(async_graphql::Error::new(format!(
+                                "Failed to parse embedding response: {}",
+                                e
+                            )));
+                        }
+                    }
+                } else {
+                    let status = resp.status();
+                    let text = resp.text().await.unwrap_or_default();
+                    // Retry only on transient errors
+                    if status.is_server_error() || status.as_u16() == 429 || status.as_u16() == 408 {
+                        last_err = Some(async_graphql::Error::new(format!(
+                            "Embedding service transient error {}: {}",
+                            status, text
+                        )));
+                    } else {
+                        return Err(async_graphql::Error::new(format!(
+                            "Embedding service error {}: {}",
+                            status, text
+                        )));
+                    }
+                }
+            }
+            Err(e) => {
+                last_err = Some(async_graphql::Error::new(format!(
+                    "Embedding service request failed: {}",
+                    e
+                )));
+            }
+        }
+        // Exponential backoff between retries
+        let delay_ms = 100u64.saturating_mul(1u64 << attempt);
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+    }
+    Err(last_err.unwrap_or_else(|| async_graphql::Error::new("Embedding request failed")))
+}
+
+/// Embed `texts` in order, `batch_size` at a time, until `deadline`. Returns the
+/// output of every batch that finished; the batch running at the deadline is
+/// dropped and later ones aren't started. Any other failure fails the whole call.
+async fn embed_until_deadline<T, E, F, Fut>(
+    texts: &[String],
+    batch_size: usize,
+    deadline: tokio::time::Instant,
+    mut embed: F,
+) -> Result<Vec<T>, E>
+where
+    F: FnMut(Vec<String>) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut completed = Vec::new();
+    for batch in texts.chunks(batch_size.max(1)) {
+        match tokio::time::timeout_at(deadline, embed(batch.to_vec())).await {
+            Ok(output) => completed.push(output?),
+            Err(_) => break,
+        }
+    }
+    Ok(completed)
+}
+
+/// Number of leading texts whose pieces are all among the first `embedded` distinct texts
+fn completed_prefix(piece_ranges: &[Range<usize>], positions: &[usize], embedded: usize) -> usize {
+    piece_ranges
+        .iter()
+        .take_while(|range| positions[(*range).clone()].iter().all(|&p| p < embedded))
+        .count()
+}
+
+/// Collapse repeated texts, returning the distinct texts in first-seen order and,
+/// for every input position, the index of its text in that distinct list.
+fn dedup_texts(texts: &[String]) -> (Vec<String>, Vec<usize>) {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut unique = Vec::new();
+    let positions = texts
+        .iter()
+        .map(|text| {
+            *seen.entry(text.as_str()).or_insert_with(|| {
+                unique.push(text.clone());
+                unique.len() - 1
+            })
+        })
+        .collect();
+    (unique, positions)
+}
+
+/// Expand per-unique results back to one result per original input position.
+/// `unique_count` is how many distinct texts were sent; any other number of results
+/// would pair texts with the wrong result, so it is an error.
+fn fan_out<T: Clone>(unique_results: Vec<T>, unique_count: usize, positions: &[usize]) -> async_graphql::Result<Vec<T>> {
+    if unique_results.len() != unique_count {
+        return Err(async_graphql::Error::new(format!(
+            "Expected {} embeddings for the distinct texts, got {}",
+            unique_count,
+            unique_results.len()
+        )));
+    }
+    positions
+        .iter()
+        .map(|&i| {
+            unique_results
+                .get(i)
+                .cloned()
+                .ok_or_else(|| async_graphql::Error::new(format!("No embedding for distinct text {}", i)))
+        })
+        .collect()
+}
+
+/// Rough characters-per-token ratio used to estimate input length before tokenizing
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Bring over-length inputs under `max_tokens`. Returns the pieces to embed and, for
+/// each original text, the range of pieces that belong to it.
+fn fit_to_model(texts: &[String], max_tokens: usize, strategy: OverlengthStrategy) -> (Vec<String>, Vec<Range<usize>>) {
+    let max_chars = max_tokens.max(1).saturating_mul(CHARS_PER_TOKEN);
+    let mut pieces = Vec::with_capacity(texts.len());
+    let mut ranges = Vec::with_capacity(texts.len());
+
+    for (i, text) in texts.iter().enumerate() {
+        let start = pieces.len();
+        let char_count = text.chars().count();
+        if char_count <= max_chars {
+            pieces.push(text.clone());
+        } else {
+            log::warn!(
+                "Embedding input {} is {} chars (~{} tokens), over the {} token limit; applying {:?}",
+                i, char_count, char_count / CHARS_PER_TOKEN, max_tokens, strategy
+            );
+            match strategy {
+                OverlengthStrategy::Truncate => pieces.push(text.chars().take(max_chars).collect()),
+                OverlengthStrategy::SplitAverage => {
+                    let chars: Vec<char> = text.chars().collect();
+                    pieces.extend(chars.chunks(max_chars).map(|c| c.iter().collect::<String>()));
+                }
+            }
+        }
+        ranges.push(start..pieces.len());
+    }
+
+    (pieces, ranges)
+}
+
+/// Collapse per-piece embeddings back to one per original text by averaging
+fn combine_pieces(embeddings: Vec<Vec<f32>>, ranges: &[Range<usize>], normalize: bool) -> Vec<Vec<f32>> {
+    ranges
+        .iter()
+        .filter_map(|range| {
+            let group = embeddings.get(range.clone())?;
+            if group.len() == 1 {
+                return Some(group[0].clone());
+            }
+
+            let dimension = group.first()?.len();
+            let mut mean = vec![0.0f32; dimension];
+            for embedding in group {
+                for (m, v) in mean.iter_mut().zip(embedding) {
+                    *m += v / group.len() as f32;
+                }
+            }
+            if normalize {
+                l2_normalize(&mut mean);
+            }
+            Some(mean)
+        })
+        .collect()
+}
+
+/// Scale to unit L2 norm; zero vectors are left as they are
+fn l2_normalize(embedding: &mut [f32]) {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > f32::EPSILON {
+        embedding.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
 #[derive(async_graphql::SimpleObject, Default)]
 pub struct CurrentUser {
     pub user_id: Option<String>,
@@ -242,11 +486,245 @@ pub type ConhubSchema = Schema<QueryRoot, async_graphql::EmptyMutation, EmptySub
 // Local type alias for backward compatibility
 pub type RerankDocumentInput = SharedRerankDocument;
 
-pub fn build_schema(cfg: AppConfig, toggles: FeatureToggles) -> ConhubSchema {
+pub fn build_schema(cfg: AppConfig, toggles: FeatureToggles, retry_queue: Arc<EmbeddingRetryQueue>) -> ConhubSchema {
     let concurrency_limit = cfg.embedding_max_inflight;
     Schema::build(QueryRoot::default(), async_graphql::EmptyMutation, EmptySubscription)
         .data(cfg)
         .data(toggles)
+        .data(retry_queue)
         .data(Arc::new(Semaphore::new(concurrency_limit)))
         .finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::embedding_retry_service::RetryPolicy;
+
+    #[test]
+    fn test_duplicate_texts_are_embedded_once() {
+        let texts: Vec<String> = ["use std::io;", "fn main() {}", "use std::io;", "use std::io;"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+
+        let mut calls = 0;
+        let mut embed = |batch: &[String]| {
+            calls += 1;
+            batch.iter().map(|t| vec![t.len() as f32]).collect::<Vec<_>>()
+        };
+
+        let (unique, positions) = dedup_texts(&texts);
+        let embeddings = fan_out(embed(&unique), unique.len(), &positions).unwrap();
+
+        assert_eq!(calls, 1);
+        assert_eq!(unique, vec!["use std::io;".to_string(), "fn main() {}".to_string()]);
+        assert_eq!(embeddings.len(), texts.len());
+        for (text, embedding) in texts.iter().zip(&embeddings) {
+            assert_eq!(embedding, &vec![text.len() as f32]);
+        }
+    }
+
+    #[test]
+    fn test_overlength_input_is_truncated_not_rejected() {
+        let texts = vec!["short".to_string(), "x".repeat(100)];
+
+        let (pieces, ranges) = fit_to_model(&texts, 10, OverlengthStrategy::Truncate);
+
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0], "short");
+        assert_eq!(pieces[1].len(), 10 * CHARS_PER_TOKEN);
+        assert_eq!(ranges, vec![0..1, 1..2]);
+    }
+
+    #[test]
+    fn test_split_average_yields_one_embedding_per_text() {
+        let texts = vec!["a".repeat(90), "b".to_string()];
+
+        let (pieces, ranges) = fit_to_model(&texts, 10, OverlengthStrategy::SplitAverage);
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(ranges, vec![0..3, 3..4]);
+
+        let embeddings = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0], vec![0.5, 0.5]];
+        let combined = combine_pieces(embeddings, &ranges, false);
+        assert_eq!(combined, vec![vec![2.0 / 3.0, 2.0 / 3.0], vec![0.5, 0.5]]);
+    }
+
+    #[test]
+    fn test_normalization_produces_unit_norm_vectors() {
+        let mut embeddings = vec![vec![3.0, 4.0], vec![0.2, -0.1, 0.7], vec![0.0, 0.0]];
+        embeddings.iter_mut().for_each(|e| l2_normalize(e));
+
+        for embedding in &embeddings[..2] {
+            let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-6);
+        }
+        assert_eq!(embeddings[0], vec![0.6, 0.8]);
+        assert_eq!(embeddings[2], vec![0.0, 0.0]);
+
+        let policy = crate::config::EmbeddingNormalization::parse("default=off, openai=on, bge/large=off");
+        assert!(policy.applies_to("openai/text-embedding-3-small"));
+        assert!(!policy.applies_to("cohere:embed-v3"));
+        assert!(!policy.applies_to("BGE/large"));
+
+        // Unset, nothing is normalized
+        assert!(!crate::config::EmbeddingNormalization::default().applies_to("openai/text-embedding-3-small"));
+        assert!(crate::config::EmbeddingNormalization::parse("default=on").applies_to("cohere:embed-v3"));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_mid_batch_returns_completed_prefix() {
+        let texts: Vec<String> = ["a", "b", "c", "a", "d", "e"].iter().map(|t| t.to_string()).collect();
+        let (pieces, piece_ranges) = fit_to_model(&texts, 10, OverlengthStrategy::Truncate);
+        let (unique, positions) = dedup_texts(&pieces);
+
+        // Batches are [a, b], [c, d], [e]; the last one stalls past the deadline
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(200);
+        let batches = embed_until_deadline(&unique, 2, deadline, |batch| async move {
+            if batch.contains(&"e".to_string()) {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+            Ok::<_, ()>(batch.iter().map(|t| vec![t.as_bytes()[0] as f32]).collect::<Vec<_>>())
+        })
+        .await
+        .unwrap();
+        assert_eq!(batches.len(), 2);
+
+        let embedded: Vec<Vec<f32>> = batches.into_iter().flatten().collect();
+        let completed = completed_prefix(&piece_ranges, &positions, embedded.len());
+        assert_eq!(completed, 5);
+
+        let pieces_done = piece_ranges[..completed].last().map_or(0, |r| r.end);
+        let embeddings = combine_pieces(fan_out(embedded, 4, &positions[..pieces_done]).unwrap(), &piece_ranges[..completed], false);
+        let expected: Vec<Vec<f32>> = texts[..5].iter().map(|t| vec![t.as_bytes()[0] as f32]).collect();
+        assert_eq!(embeddings, expected);
+        assert_eq!((completed..texts.len()).collect::<Vec<_>>(), vec![5]);
+    }
+
+    #[test]
+    fn test_fan_out_rejects_wrong_result_count() {
+        let positions = [0, 1, 0];
+        assert!(fan_out(vec![vec![1.0]], 2, &positions).is_err());
+        assert!(fan_out(vec![vec![1.0], vec![2.0], vec![3.0]], 2, &positions).is_err());
+        assert_eq!(fan_out(vec![vec![1.0], vec![2.0]], 2, &positions).unwrap(), vec![vec![1.0], vec![2.0], vec![1.0]]);
+    }
+
+    /// Embedding service stub: embeds each posted text as `[len]`, dropping the last
+    /// embedding when `short` is set
+    async fn mock_embedding_service(short: bool) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                let body = loop {
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head.lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length || n == 0 {
+                            break body.to_string();
+                        }
+                    } else if n == 0 {
+                        break String::new();
+                    }
+                };
+                let texts: Vec<String> = serde_json::from_str::<serde_json::Value>(&body)
+                    .ok()
+                    .and_then(|v| serde_json::from_value(v["text"].clone()).ok())
+                    .unwrap_or_default();
+                let mut embeddings: Vec<Vec<f32>> = texts.iter().map(|t| vec![t.len() as f32]).collect();
+                if short {
+                    embeddings.pop();
+                }
+                let response = serde_json::json!({
+                    "embeddings": embeddings,
+                    "dimension": 1,
+                    "model": "stub",
+                    "count": embeddings.len(),
+                })
+                .to_string();
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                );
+                let _ = socket.write_all(reply.as_bytes()).await;
+            }
+        });
+        url
+    }
+
+    fn config_for(embedding_url: &str) -> AppConfig {
+        let vars: HashMap<&str, &str> = HashMap::from([
+            ("JWT_SECRET", "secret"),
+            ("EMBEDDING_SERVICE_URL", embedding_url),
+            ("EMBEDDING_REQUEST_RETRIES", "0"),
+            ("EMBEDDING_L2_NORMALIZE", "default=off"),
+        ]);
+        AppConfig::try_from_lookup(|key| vars.get(key).map(|v| v.to_string())).unwrap()
+    }
+
+    fn schema_for(embedding_url: &str) -> ConhubSchema {
+        let queue = EmbeddingRetryQueue::in_memory(RetryPolicy::default());
+        build_schema(config_for(embedding_url), FeatureToggles::default(), Arc::new(queue))
+    }
+
+    #[tokio::test]
+    async fn test_embed_resolver_fans_duplicates_out_to_every_position() {
+        let schema = schema_for(&mock_embedding_service(false).await);
+        let query = r#"{ embed(texts: ["dup-a", "other text", "dup-a", "dup-a"], normalize: false) { embeddings count } }"#;
+
+        let response = schema.execute(query).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["embed"]["count"], 4);
+        assert_eq!(data["embed"]["embeddings"], serde_json::json!([[5.0], [10.0], [5.0], [5.0]]));
+    }
+
+    #[tokio::test]
+    async fn test_embed_resolver_errors_when_service_returns_too_few_embeddings() {
+        let schema = schema_for(&mock_embedding_service(true).await);
+        let query = r#"{ embed(texts: ["short-a", "short-bb", "short-a"], normalize: false) { embeddings count } }"#;
+
+        let response = schema.execute(query).await;
+        assert_eq!(response.errors.len(), 1);
+        assert!(response.errors[0].message.contains("returned 1 embeddings for 2 texts"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_embed_is_queued_and_retry_fills_the_cache() {
+        let queue = Arc::new(EmbeddingRetryQueue::in_memory(RetryPolicy {
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            max_attempts: 3,
+        }));
+        let schema = build_schema(
+            config_for(&mock_embedding_service(true).await),
+            FeatureToggles::default(),
+            queue.clone(),
+        );
+        let texts = vec!["retry-a".to_string(), "retry-bb".to_string()];
+        let query = r#"{ embed(texts: ["retry-a", "retry-bb"], normalize: false) { embeddings count } }"#;
+
+        let response = schema.execute(query).await;
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(queue.depth().await.unwrap().pending, 1);
+
+        // The service has recovered by the time the worker runs
+        let cfg = config_for(&mock_embedding_service(false).await);
+        let stats = queue.process_due(10, |batch| reembed_failed_batch(&cfg, batch)).await.unwrap();
+        assert_eq!(stats.succeeded, 1);
+        assert_eq!(queue.depth().await.unwrap().pending, 0);
+
+        let cached = get_cache().get::<EmbeddingResult>(&embed_cache_key(false, &texts)).unwrap();
+        assert_eq!(cached.embeddings, vec![vec![7.0], vec![8.0]]);
+    }
+}
@@ -2,6 +2,6 @@ pub mod rag;
 pub mod context;
 pub mod dashboard;
 
-pub use rag::{rag_query, rag_vector, rag_hybrid, rag_agentic};
+pub use rag::{rag_query, rag_vector, rag_hybrid, rag_agentic, rag_agentic_stream, rag_search_rerank, rag_analyze};
 pub use context::{query_context, get_stats as get_context_stats, simple_query};
 pub use dashboard::get_dashboard_stats;
@@ -1,21 +1,38 @@
 use actix_web::{web, HttpResponse};
-use crate::services::rag_service::{RagService, RagQueryRequest};
+use conhub_middleware::ApiError;
+use crate::services::rag_service::{analyze_query, AgenticEvent, RagQueryResponse, RagService, RagQueryRequest, ResponseFormat};
+use serde::Deserialize;
 use std::sync::Arc;
 
+#[derive(Debug, Deserialize)]
+pub struct QueryAnalysisRequest {
+    pub query: String,
+}
+
+/// The normal JSON response, or the ranked results as JSONL when requested
+fn respond(response: RagQueryResponse, format: ResponseFormat) -> HttpResponse {
+    match format {
+        ResponseFormat::Json => HttpResponse::Ok().json(response),
+        ResponseFormat::Jsonl => HttpResponse::Ok()
+            .content_type("application/x-ndjson")
+            .body(response.to_jsonl()),
+    }
+}
+
 pub async fn rag_query(
     req: web::Json<RagQueryRequest>,
     rag_service: web::Data<Arc<RagService>>,
 ) -> HttpResponse {
     log::info!("RAG query request: {} (mode: {:?})", req.query, req.mode);
     
+    let format = req.format;
     match rag_service.query(req.into_inner()).await {
-        Ok(response) => HttpResponse::Ok().json(response),
+        Ok(response) => respond(response, format),
         Err(e) => {
             log::error!("RAG query failed: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Query failed",
-                "details": e.to_string()
-            }))
+            ApiError::internal("Query failed")
+                .with_details(serde_json::json!({ "reason": e.to_string() }))
+                .into_response()
         }
     }
 }
@@ -29,14 +46,14 @@ pub async fn rag_vector(
     let mut request = req.into_inner();
     request.mode = Some(crate::services::rag_service::RagMode::Vector);
     
+    let format = request.format;
     match rag_service.query(request).await {
-        Ok(response) => HttpResponse::Ok().json(response),
+        Ok(response) => respond(response, format),
         Err(e) => {
             log::error!("Vector RAG query failed: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Query failed",
-                "details": e.to_string()
-            }))
+            ApiError::internal("Query failed")
+                .with_details(serde_json::json!({ "reason": e.to_string() }))
+                .into_response()
         }
     }
 }
@@ -50,14 +67,14 @@ pub async fn rag_hybrid(
     let mut request = req.into_inner();
     request.mode = Some(crate::services::rag_service::RagMode::Hybrid);
     
+    let format = request.format;
     match rag_service.query(request).await {
-        Ok(response) => HttpResponse::Ok().json(response),
+        Ok(response) => respond(response, format),
         Err(e) => {
             log::error!("Hybrid RAG query failed: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Query failed",
-                "details": e.to_string()
-            }))
+            ApiError::internal("Query failed")
+                .with_details(serde_json::json!({ "reason": e.to_string() }))
+                .into_response()
         }
     }
 }
@@ -71,14 +88,91 @@ pub async fn rag_agentic(
     let mut request = req.into_inner();
     request.mode = Some(crate::services::rag_service::RagMode::Agentic);
     
+    let format = request.format;
     match rag_service.query(request).await {
-        Ok(response) => HttpResponse::Ok().json(response),
+        Ok(response) => respond(response, format),
         Err(e) => {
             log::error!("Agentic RAG query failed: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Query failed",
-                "details": e.to_string()
-            }))
+            ApiError::internal("Query failed")
+                .with_details(serde_json::json!({ "reason": e.to_string() }))
+                .into_response()
+        }
+    }
+}
+
+/// Embed the query, run vector search and rerank the candidates in one request
+pub async fn rag_search_rerank(
+    req: web::Json<RagQueryRequest>,
+    rag_service: web::Data<Arc<RagService>>,
+) -> HttpResponse {
+    log::info!("Search + rerank query: {}", req.query);
+    
+    let mut request = req.into_inner();
+    request.mode = Some(crate::services::rag_service::RagMode::Rerank);
+    
+    let format = request.format;
+    match rag_service.query(request).await {
+        Ok(response) => respond(response, format),
+        Err(e) => {
+            log::error!("Search + rerank query failed: {}", e);
+            ApiError::internal("Query failed")
+                .with_details(serde_json::json!({ "reason": e.to_string() }))
+                .into_response()
         }
     }
 }
+
+/// Intent, detected entities and the strategy `auto` mode would pick for a query,
+/// without running retrieval. For debugging and tuning strategy selection.
+pub async fn rag_analyze(req: web::Json<QueryAnalysisRequest>) -> HttpResponse {
+    if req.query.trim().is_empty() {
+        return ApiError::bad_request("Query must not be empty").into_response();
+    }
+    HttpResponse::Ok().json(analyze_query(&req.query))
+}
+
+/// Streaming variant of `rag_agentic`: emits retrieval, reasoning and answer-token
+/// events as SSE, ending with a `final` or `error` event.
+pub async fn rag_agentic_stream(
+    req: web::Json<RagQueryRequest>,
+    rag_service: web::Data<Arc<RagService>>,
+) -> HttpResponse {
+    log::info!("Streaming agentic RAG query: {}", req.query);
+    
+    let mut request = req.into_inner();
+    request.mode = Some(crate::services::rag_service::RagMode::Agentic);
+    
+    let events = rag_service.get_ref().clone().agentic_stream(request);
+    let body = futures::stream::unfold(events, |mut events| async move {
+        let event: AgenticEvent = events.recv().await?;
+        let frame = web::Bytes::from(event.to_sse());
+        Some((Ok::<_, actix_web::Error>(frame), events))
+    });
+    
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use conhub_models::ErrorEnvelope;
+
+    #[actix_web::test]
+    async fn test_empty_query_is_rejected_with_the_error_envelope() {
+        let app = test::init_service(App::new().route("/rag/analyze", web::post().to(rag_analyze))).await;
+        let req = test::TestRequest::post()
+            .uri("/rag/analyze")
+            .set_json(serde_json::json!({ "query": "  " }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body: ErrorEnvelope = test::read_body_json(resp).await;
+        assert_eq!(body.code, "bad_request");
+        assert_eq!(body.message, "Query must not be empty");
+    }
+}
@@ -9,16 +9,20 @@ mod handlers;
 
 use actix_web::{web, App, HttpServer};
 use actix_cors::Cors;
-use sqlx::postgres::{PgPoolOptions, PgConnectOptions};
+use conhub_database::DatabaseConfig;
 use std::io;
-use std::str::FromStr;
 use conhub_middleware::auth::AuthMiddlewareFactory;
 use conhub_config::feature_toggles::FeatureToggles;
 use conhub_observability::{init_tracing, TracingConfig, observability, info, warn, error};
 
 use config::AppConfig;
 use state::AppState;
-use crate::graphql::schema::build_schema;
+use crate::graphql::schema::{build_schema, reembed_failed_batch};
+
+/// How often queued embedding batches are checked for a due retry
+const EMBEDDING_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+/// Batches retried per pass, so a long outage's backlog drains gradually
+const EMBEDDING_RETRY_BATCH_LIMIT: usize = 20;
 
 #[actix_web::main]
 async fn main() -> io::Result<()> {
@@ -27,8 +31,11 @@ async fn main() -> io::Result<()> {
 
     info!("Starting ConHub Backend Service...");
 
-    // Load configuration from environment
-    let config = AppConfig::from_env();
+    // Load configuration from environment, refusing to start on invalid values
+    let config = AppConfig::try_from_env().map_err(|e| {
+        log::error!("Invalid configuration: {}", e);
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string())
+    })?;
     let port = config.backend_port;
 
     log::info!("Environment mode: {}", config.env_mode);
@@ -57,14 +64,7 @@ async fn main() -> io::Result<()> {
         log::info!("Connecting to PostgreSQL database...");
     }
 
-    // Disable server-side prepared statements for pgbouncer/Neon transaction pooling
-    let connect_options = PgConnectOptions::from_str(&database_url)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?
-        .statement_cache_capacity(0);
-
-    let db_pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect_with(connect_options)
+    let db_pool = conhub_database::connect(&DatabaseConfig::for_url(database_url))
         .await
         .expect("Failed to connect to Postgres");
     log::info!("✅ [Backend Service] Database connection established");
@@ -106,31 +106,145 @@ async fn main() -> io::Result<()> {
     };
 
     // Initialize RAG service
-    let embedding_url = std::env::var("EMBEDDING_SERVICE_URL")
-        .unwrap_or_else(|_| "http://localhost:8082".to_string());
-    let graph_url = std::env::var("GRAPH_SERVICE_URL")
-        .unwrap_or_else(|_| "http://localhost:8006".to_string());
-    let agentic_url = std::env::var("AGENTIC_SERVICE_URL")
-        .unwrap_or_else(|_| "http://localhost:3005".to_string());
+    let embedding_url = config.embedding_service_url.clone();
+    let graph_url = config.graph_service_url.clone();
+    let agentic_url = config.agentic_service_url.clone();
+
+    // Malformed URLs were already rejected with the rest of the config
+    for (var, url) in config.service_urls() {
+        if std::env::var(var).is_err() {
+            log::warn!("⚠️  [Backend Service] {} not set, defaulting to {}", var, url);
+        }
+    }
     
-    let rag_service = std::sync::Arc::new(services::rag_service::RagService::new(
-        embedding_url.clone(),
-        graph_url.clone(),
-        agentic_url.clone(),
-    ));
+    let rag_timeouts = services::rag_service::PipelineTimeouts {
+        step: std::time::Duration::from_millis(
+            std::env::var("RAG_STEP_TIMEOUT_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(10_000),
+        ),
+        overall: std::time::Duration::from_millis(
+            std::env::var("RAG_QUERY_DEADLINE_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(30_000),
+        ),
+    };
+    
+    let rag_service = std::sync::Arc::new(
+        services::rag_service::RagService::new(
+            embedding_url.clone(),
+            graph_url.clone(),
+            agentic_url.clone(),
+        )
+        .with_timeouts(rag_timeouts)
+        .with_endpoint_allow_list(
+            std::env::var("RAG_ENDPOINT_ALLOWLIST")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty()),
+        ),
+    );
     log::info!("🤖 [Backend Service] RAG service initialized");
     log::info!("   Embedding: {}", embedding_url);
     log::info!("   Graph: {}", graph_url);
     log::info!("   Agentic: {}", agentic_url);
 
+    let embedding_dimension = std::env::var("EMBEDDING_DIMENSION")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(384);
+    let embedding_policy = conhub_models::chunking::EmbeddingModelPolicy::from_env();
+    if embedding_policy.default.dimension != embedding_dimension {
+        log::warn!(
+            "⚠️  [Backend Service] EMBEDDING_DIMENSION is {} but the default embedding model {} produces {}-dimensional vectors",
+            embedding_dimension,
+            embedding_policy.default.model,
+            embedding_policy.default.dimension
+        );
+    }
+    let vector_index_service = std::sync::Arc::new(
+        services::vector_index_service::VectorIndexService::new(
+            conhub_models::SpatialIndex::new(embedding_dimension, conhub_models::IndexType::HNSW),
+        )
+        .with_vector_store(embedding_url.clone()),
+    );
+    // Documents that source syncs find deleted upstream leave the vector index
+    let plugin_registry = web::Data::new(conhub_plugins::registry::PluginRegistry::new().with_deletion_sink(
+        std::sync::Arc::new(services::vector_index_service::VectorIndexDeletionSink::new(vector_index_service.clone())),
+    ));
+
+    // Direct graph access for admin operations such as manual entity merges
+    let graph_db: Option<std::sync::Arc<dyn conhub_database::graph::GraphDb>> = match std::env::var("NEO4J_URI") {
+        Ok(uri) => {
+            let user = std::env::var("NEO4J_USER").unwrap_or_else(|_| "neo4j".to_string());
+            let password = std::env::var("NEO4J_PASSWORD").unwrap_or_default();
+            match conhub_database::graph::Neo4jGraphDb::new(&uri, &user, &password).await {
+                Ok(graph) => Some(std::sync::Arc::new(graph)),
+                Err(e) => {
+                    log::warn!("⚠️  [Backend Service] Graph admin endpoints disabled, Neo4j unavailable: {}", e);
+                    None
+                }
+            }
+        }
+        Err(_) => None,
+    };
+
+    // Readiness probes for /readyz; only the database is critical
+    let probe_client = reqwest::Client::new();
+    let mut probes = vec![
+        services::readiness_service::DependencyProbe::http("embedding", &embedding_url, probe_client.clone()),
+        services::readiness_service::DependencyProbe::http("graph", &graph_url, probe_client.clone()),
+        services::readiness_service::DependencyProbe::http("agentic", &agentic_url, probe_client),
+    ];
+    if let Some(pool) = db_pool_opt.clone() {
+        probes.push(services::readiness_service::DependencyProbe::postgres(pool));
+    }
+    if let Some(client) = redis_client.clone() {
+        probes.push(services::readiness_service::DependencyProbe::redis(client));
+    }
+    let readiness_service = std::sync::Arc::new(
+        services::readiness_service::ReadinessService::new("conhub-backend", probes),
+    );
+    if std::env::var("STARTUP_PROBE_DOWNSTREAMS").map(|v| v == "true").unwrap_or(false) {
+        let report = readiness_service.check().await;
+        for (name, dependency) in &report.dependencies {
+            if let Some(error) = &dependency.error {
+                log::warn!("⚠️  [Backend Service] {} unreachable at startup: {}", name, error);
+            }
+        }
+    }
+
     // Initialize application state
     log::info!("Initializing application state...");
     let app_state = AppState::new(db_pool_opt, redis_client, config.clone())
         .await
         .expect("Failed to initialize application state");
 
+    // Retry embedding batches that failed, once their backoff has passed
+    let embedding_retry_queue = app_state.embedding_retry_queue.clone();
+    let retry_config = config.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EMBEDDING_RETRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            match embedding_retry_queue
+                .process_due(EMBEDDING_RETRY_BATCH_LIMIT, |batch| reembed_failed_batch(&retry_config, batch))
+                .await
+            {
+                Ok(stats) if stats.succeeded + stats.requeued + stats.dead_lettered > 0 => {
+                    log::info!("Embedding retry pass: {:?}", stats);
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("Embedding retry pass failed: {}", e),
+            }
+        }
+    });
+    let embedding_retry_queue = app_state.embedding_retry_queue.clone();
+
     let state_data = web::Data::new(app_state);
     let rag_data = web::Data::new(rag_service);
+    let vector_index_data = web::Data::new(vector_index_service);
+    let readiness_data = web::Data::new(readiness_service);
+    let graph_data = graph_db.map(web::Data::new);
+    let embedding_policy_data = web::Data::new(embedding_policy);
+    let embedding_pricing_data = web::Data::new(conhub_models::chunking::EmbeddingPricing::from_env());
 
     log::info!("Application state initialized");
 
@@ -138,11 +252,21 @@ async fn main() -> io::Result<()> {
     log::info!("Starting HTTP server on 0.0.0.0:{}", port);
 
     HttpServer::new(move || {
-        let schema = build_schema(config.clone(), toggles.clone());
+        let schema = build_schema(config.clone(), toggles.clone(), embedding_retry_queue.clone());
 
         App::new()
             .app_data(state_data.clone())
             .app_data(rag_data.clone())
+            .app_data(vector_index_data.clone())
+            .app_data(plugin_registry.clone())
+            .app_data(readiness_data.clone())
+            .configure(|cfg| {
+                if let Some(graph) = &graph_data {
+                    cfg.app_data(graph.clone());
+                }
+            })
+            .app_data(embedding_policy_data.clone())
+            .app_data(embedding_pricing_data.clone())
             .app_data(web::Data::new(toggles.clone()))
             .app_data(web::Data::new(schema))
             // Middleware execution order is REVERSE of registration order.
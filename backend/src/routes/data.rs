@@ -1,6 +1,15 @@
 use actix_web::{web, HttpResponse, Result, HttpRequest};
+use actix_web::http::StatusCode;
 use crate::state::AppState;
+use crate::services::data_service::{DataError, DataService, RepoSyncTarget, SyncIngestSummary};
+use crate::services::sync_lock_service::SyncLockError;
+use serde::Deserialize;
 use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use uuid::Uuid;
+use conhub_middleware::ApiError;
+use conhub_middleware::auth::extract_user_id_from_request;
+use conhub_observability::{get_trace_context, DomainEvent, SyncJobLifecycle};
 
 const DATA_SERVICE_URL: &str = "http://localhost:3013";
 
@@ -21,50 +30,268 @@ pub async fn connect_source(
             let status = response.status();
             match response.json::<Value>().await {
                 Ok(json_body) => Ok(HttpResponse::build(status).json(json_body)),
-                Err(_) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse response from data service"
-                })))
+                Err(_) => Ok(ApiError::internal("Failed to parse response from data service").into_response())
             }
         }
         Err(e) => {
             log::error!("Failed to connect to data service: {}", e);
-            Ok(HttpResponse::BadGateway().json(serde_json::json!({
-                "error": "Data service unavailable"
-            })))
+            Ok(ApiError::bad_gateway("Data service unavailable").into_response())
         }
     }
 }
 
 pub async fn sync_source(
+    req: HttpRequest,
     path: web::Path<String>,
-    _state: web::Data<AppState>,
+    state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     let source_id = path.into_inner();
+
+    // Overlapping syncs of one source would ingest it twice
+    match state.sync_locks.run_exclusive(&source_id, || forward_sync(&req, &state, &source_id)).await {
+        Ok(response) => response,
+        Err(SyncLockError::AlreadySyncing) => Ok(ApiError::conflict("Source is already syncing")
+            .with_code("already_syncing")
+            .with_details(serde_json::json!({ "source_id": source_id }))
+            .into_response()),
+        Err(e) => {
+            log::error!("Failed to take sync lock for {}: {}", source_id, e);
+            Ok(ApiError::service_unavailable("Sync lock unavailable").into_response())
+        }
+    }
+}
+
+async fn forward_sync(req: &HttpRequest, state: &AppState, source_id: &str) -> Result<HttpResponse> {
+    let trace = get_trace_context(req);
+
+    // Connected GitHub repositories are addressed by their repo config id
+    let target = match Uuid::parse_str(source_id) {
+        Ok(repo_config_id) => match state.data_service.get_repo_sync_target(repo_config_id).await {
+            Ok(target) => target,
+            Err(e) => {
+                log::error!("Failed to look up repository {}: {}", source_id, e);
+                return Ok(ApiError::service_unavailable("Sync job storage unavailable").into_response());
+            }
+        },
+        Err(_) => None,
+    };
+    if let Some(target) = target {
+        return Ok(sync_repository(&state.data_service, DATA_SERVICE_URL, &target, Some(&trace.trace_id)).await);
+    }
+
+    let job = SyncJobLifecycle::start("backend-service", Uuid::new_v4(), "data_source", Some(&trace.trace_id));
+    let (outcome, _event) = forward_sync_job(DATA_SERVICE_URL, source_id, job).await;
+    Ok(match outcome {
+        Ok((status, body)) => HttpResponse::build(status).json(body),
+        Err(e) => e.into_response(),
+    })
+}
+
+/// Files to sync, as listed by the data service: those changed since the
+/// repository's last synced commit, or the whole branch on a first sync
+#[derive(Debug, Deserialize)]
+struct SyncPlan {
+    /// Commit the files were listed at; every file is ingested at this commit
+    head_sha: String,
+    files: Vec<SyncPlanFile>,
+    /// Deleted or renamed away since the last synced commit
+    #[serde(default)]
+    removed: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SyncPlanFile {
+    path: String,
+    /// Blob sha, so a resumed job only skips files whose content is unchanged
+    sha: String,
+}
+
+/// Running totals for one pass over a sync job's files
+#[derive(Default)]
+struct SyncProgress {
+    ingested: AtomicUsize,
+    chunks_created: AtomicUsize,
+}
+
+/// Sync a connected repository as a job persisted in `github_sync_jobs`. A job
+/// that never completed is resumed, skipping the files it already ingested.
+/// The response carries `sync_job_id` for polling `/api/github/sync/{id}`.
+async fn sync_repository(
+    data_service: &DataService,
+    base_url: &str,
+    target: &RepoSyncTarget,
+    trace_id: Option<&str>,
+) -> HttpResponse {
+    let sync_job_id = match resume_or_create_sync_job(data_service, target).await {
+        Ok(id) => id,
+        Err(e) => {
+            log::error!("Failed to create sync job for repository {}: {}", target.repo_config_id, e);
+            return ApiError::service_unavailable("Sync job storage unavailable").into_response();
+        }
+    };
+
+    let job = SyncJobLifecycle::start("backend-service", sync_job_id, "github", trace_id);
+    let progress = SyncProgress::default();
+    let outcome = ingest_repository(data_service, base_url, target, sync_job_id, &progress).await;
+    let chunks_created = progress.chunks_created.load(Ordering::Relaxed) as i32;
+
+    match outcome {
+        Ok((plan, summary, chunks_deleted)) => {
+            job.completed(summary.ingested);
+            let processed = (summary.ingested + summary.skipped) as i32;
+            if let Err(e) = data_service.update_sync_job_progress(sync_job_id, processed, 0, chunks_created).await {
+                log::error!("Failed to record progress of sync job {}: {}", sync_job_id, e);
+            }
+            if let Err(e) = data_service.finish_sync_job(sync_job_id, None).await {
+                log::error!("Failed to record the outcome of sync job {}: {}", sync_job_id, e);
+            }
+            HttpResponse::Ok().json(serde_json::json!({
+                "sync_job_id": sync_job_id,
+                "head_sha": plan.head_sha,
+                "files_total": plan.files.len(),
+                "documents_processed": summary.ingested,
+                "files_skipped": summary.skipped,
+                "files_removed": plan.removed.len(),
+                "chunks_created": chunks_created,
+                "chunks_deleted": chunks_deleted,
+            }))
+        }
+        Err(e) => {
+            let error = e.to_string();
+            job.failed(&error);
+            let processed = progress.ingested.load(Ordering::Relaxed) as i32;
+            if let Err(e) = data_service.update_sync_job_progress(sync_job_id, processed, 1, chunks_created).await {
+                log::error!("Failed to record progress of sync job {}: {}", sync_job_id, e);
+            }
+            if let Err(e) = data_service.finish_sync_job(sync_job_id, Some(&error)).await {
+                log::error!("Failed to record the outcome of sync job {}: {}", sync_job_id, e);
+            }
+            ApiError::bad_gateway("Repository sync failed; syncing again resumes it")
+                .with_code("sync_failed")
+                .with_details(serde_json::json!({ "sync_job_id": sync_job_id }))
+                .into_response()
+        }
+    }
+}
+
+async fn resume_or_create_sync_job(data_service: &DataService, target: &RepoSyncTarget) -> Result<Uuid, DataError> {
+    if let Some(sync_job_id) = data_service.find_resumable_sync_job(target.repo_config_id).await? {
+        log::info!("Resuming unfinished sync job {} for repository {}", sync_job_id, target.repo_config_id);
+        return Ok(sync_job_id);
+    }
+    data_service
+        .create_sync_job(target.tenant_id, target.installation_id, target.repo_config_id, "code", Some(&target.default_branch))
+        .await
+}
+
+/// List what changed since the last synced commit through the data service,
+/// ingest it one file at a time through the job's resume token, and drop the
+/// chunks of removed files. The head is recorded as synced only once all of
+/// that succeeded. Returns the plan, the ingest summary and the chunks deleted.
+async fn ingest_repository(
+    data_service: &DataService,
+    base_url: &str,
+    target: &RepoSyncTarget,
+    sync_job_id: Uuid,
+    progress: &SyncProgress,
+) -> Result<(SyncPlan, SyncIngestSummary, u64), DataError> {
+    let client = reqwest::Client::new();
+    let source_url = format!("{}/api/data/sources/{}/sync", base_url, target.repo_config_id);
+
+    let since_commit = data_service.get_last_synced_commit(target.repo_config_id).await?;
+    let plan: SyncPlan = post_json(&client, &format!("{}/plan", source_url), serde_json::json!({
+        "branch": target.default_branch,
+        "since_commit": since_commit,
+    }))
+    .await?;
+    data_service.start_sync_job(sync_job_id, plan.files.len() as i32).await?;
+
+    let files: Vec<(String, String)> = plan.files.iter().map(|f| (f.path.clone(), f.sha.clone())).collect();
+    let files_url = format!("{}/files", source_url);
+    let head_sha = plan.head_sha.as_str();
+    let summary = data_service
+        .ingest_files_resumable(sync_job_id, &files, |path| {
+            let body = serde_json::json!({ "head_sha": head_sha, "path": path });
+            let (client, files_url) = (&client, &files_url);
+            async move {
+                let ingested: Value = post_json(client, files_url, body).await?;
+                let chunks = ingested.get("chunks_created").and_then(|v| v.as_u64()).unwrap_or(0);
+                progress.ingested.fetch_add(1, Ordering::Relaxed);
+                progress.chunks_created.fetch_add(chunks as usize, Ordering::Relaxed);
+                Ok(())
+            }
+        })
+        .await?;
+
+    let chunks_deleted = data_service.delete_chunks_for_paths(target.repo_config_id, &plan.removed).await?;
+    data_service.record_synced_commit(target.repo_config_id, &plan.head_sha).await?;
+
+    Ok((plan, summary, chunks_deleted))
+}
+
+async fn post_json<T: serde::de::DeserializeOwned>(client: &reqwest::Client, url: &str, body: Value) -> Result<T, DataError> {
+    let response = client
+        .post(url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| DataError::ConnectionError(format!("Data service unavailable: {}", e)))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(DataError::ConnectionError(format!("Data service returned {} for {}", status, url)));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| DataError::ConnectionError(format!("Failed to parse response from data service: {}", e)))
+}
+
+/// Ask the data service to sync `source_id` and record the job's terminal event
+async fn forward_sync_job(
+    base_url: &str,
+    source_id: &str,
+    job: SyncJobLifecycle,
+) -> (std::result::Result<(StatusCode, Value), ApiError>, DomainEvent) {
     let client = reqwest::Client::new();
     
     match client
-        .post(&format!("{}/api/data/sources/{}/sync", DATA_SERVICE_URL, source_id))
+        .post(&format!("{}/api/data/sources/{}/sync", base_url, source_id))
         .send()
         .await
     {
         Ok(response) => {
             let status = response.status();
+            if !status.is_success() {
+                let event = job.failed(&format!("Data service rejected sync with status {}", status));
+                return (Err(ApiError::new(status, "sync_failed", "Sync request failed")), event);
+            }
             match response.json::<Value>().await {
-                Ok(json_body) => Ok(HttpResponse::build(status).json(json_body)),
-                Err(_) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse response from data service"
-                })))
+                Ok(json_body) => {
+                    let event = job.completed(synced_document_count(&json_body));
+                    (Ok((status, json_body)), event)
+                }
+                Err(e) => {
+                    let event = job.failed(&format!("Failed to parse response from data service: {}", e));
+                    (Err(ApiError::internal("Failed to parse response from data service")), event)
+                }
             }
         }
         Err(e) => {
             log::error!("Failed to connect to data service: {}", e);
-            Ok(HttpResponse::BadGateway().json(serde_json::json!({
-                "error": "Data service unavailable"
-            })))
+            let event = job.failed(&format!("Data service unavailable: {}", e));
+            (Err(ApiError::bad_gateway("Data service unavailable")), event)
         }
     }
 }
 
+/// Documents the data service reports for a sync, or 0 when it doesn't say
+fn synced_document_count(body: &Value) -> usize {
+    ["documents_processed", "total_documents", "documents_synced"]
+        .iter()
+        .find_map(|key| body.get(*key).and_then(|v| v.as_u64()))
+        .unwrap_or(0) as usize
+}
+
 pub async fn list_sources(
     _state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
@@ -79,16 +306,12 @@ pub async fn list_sources(
             let status = response.status();
             match response.json::<Value>().await {
                 Ok(json_body) => Ok(HttpResponse::build(status).json(json_body)),
-                Err(_) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse response from data service"
-                })))
+                Err(_) => Ok(ApiError::internal("Failed to parse response from data service").into_response())
             }
         }
         Err(e) => {
             log::error!("Failed to connect to data service: {}", e);
-            Ok(HttpResponse::BadGateway().json(serde_json::json!({
-                "error": "Data service unavailable"
-            })))
+            Ok(ApiError::bad_gateway("Data service unavailable").into_response())
         }
     }
 }
@@ -109,16 +332,12 @@ pub async fn connect_source_endpoint(
             let status = response.status();
             match response.json::<Value>().await {
                 Ok(json_body) => Ok(HttpResponse::build(status).json(json_body)),
-                Err(_) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse response from data service"
-                })))
+                Err(_) => Ok(ApiError::internal("Failed to parse response from data service").into_response())
             }
         }
         Err(e) => {
             log::error!("Failed to connect to data service: {}", e);
-            Ok(HttpResponse::BadGateway().json(serde_json::json!({
-                "error": "Data service unavailable"
-            })))
+            Ok(ApiError::bad_gateway("Data service unavailable").into_response())
         }
     }
 }
@@ -139,16 +358,52 @@ pub async fn delete_source(
             let status = response.status();
             match response.json::<Value>().await {
                 Ok(json_body) => Ok(HttpResponse::build(status).json(json_body)),
-                Err(_) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse response from data service"
-                })))
+                Err(_) => Ok(ApiError::internal("Failed to parse response from data service").into_response())
             }
         }
         Err(e) => {
             log::error!("Failed to connect to data service: {}", e);
-            Ok(HttpResponse::BadGateway().json(serde_json::json!({
-                "error": "Data service unavailable"
-            })))
+            Ok(ApiError::bad_gateway("Data service unavailable").into_response())
+        }
+    }
+}
+
+/// GET /api/github/sync/{sync_job_id}
+/// Persisted state of a repository sync job, polled by the UI during long syncs
+pub async fn get_sync_job_status(
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let sync_job_id = path.into_inner();
+
+    match state.data_service.get_sync_job(sync_job_id).await {
+        Ok(Some(job)) => Ok(HttpResponse::Ok().json(job)),
+        Ok(None) => Ok(ApiError::not_found("Sync job not found")
+            .with_details(serde_json::json!({ "sync_job_id": sync_job_id }))
+            .into_response()),
+        Err(e) => {
+            log::error!("Failed to load sync job {}: {}", sync_job_id, e);
+            Ok(ApiError::service_unavailable("Sync job status unavailable").into_response())
+        }
+    }
+}
+
+/// GET /api/github/repos
+/// The caller's connected repositories with when each last synced, its current
+/// sync status and how many documents it holds
+pub async fn list_repositories(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let Some(tenant_id) = extract_user_id_from_request(&req) else {
+        return Ok(ApiError::unauthorized("Authentication required").into_response());
+    };
+
+    match state.data_service.list_repositories(tenant_id).await {
+        Ok(repositories) => Ok(HttpResponse::Ok().json(repositories)),
+        Err(e) => {
+            log::error!("Failed to list repositories for {}: {}", tenant_id, e);
+            Ok(ApiError::service_unavailable("Repository listing unavailable").into_response())
         }
     }
 }
@@ -166,5 +421,373 @@ pub fn configure_data_routes(cfg: &mut web::ServiceConfig) {
             .route("/connect", web::post().to(connect_source_endpoint))
             .route("/{id}/sync", web::post().to(sync_source))
             .route("/{id}", web::delete().to(delete_source))
+    )
+    .service(
+        web::scope("/github")
+            .route("/repos", web::get().to(list_repositories))
+            .route("/sync/{sync_job_id}", web::get().to(get_sync_job_status))
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use actix_web::{test, App};
+    use conhub_models::chunking::{ChunkJobStatus, SyncJobStatusResponse};
+    use sqlx::PgPool;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    async fn seed_repo_config(pool: &PgPool) -> (Uuid, Uuid, Uuid) {
+        let tenant_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO users (email, password_hash, name) VALUES ($1, 'x', 'Sync Test') RETURNING id"
+        )
+        .bind(format!("sync-{}@example.com", Uuid::new_v4()))
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        let installation_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO github_app_installations (tenant_id, installation_id, account_login, app_id)
+             VALUES ($1, $2, 'acme', 1) RETURNING id"
+        )
+        .bind(tenant_id)
+        .bind((Uuid::new_v4().as_u128() % i64::MAX as u128) as i64)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        let repo_config_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO github_repo_configs (tenant_id, installation_id, github_repo_id, full_name, name, owner)
+             VALUES ($1, $2, 42, 'acme/api', 'api', 'acme') RETURNING id"
+        )
+        .bind(tenant_id)
+        .bind(installation_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        (tenant_id, installation_id, repo_config_id)
+    }
+
+    #[actix_web::test]
+    async fn test_successful_sync_completes_job() {
+        use actix_web::HttpServer;
+        use conhub_observability::OperationResult;
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let data_service = HttpServer::new(|| {
+            App::new().route(
+                "/api/data/sources/{id}/sync",
+                web::post().to(|| async { HttpResponse::Ok().json(serde_json::json!({ "documents_processed": 3 })) }),
+            )
+        })
+        .listen(listener)
+        .unwrap()
+        .run();
+        drop(actix_web::rt::spawn(data_service));
+
+        let job_id = Uuid::new_v4();
+        let job = SyncJobLifecycle::start("backend-service", job_id, "data_source", Some("trace-1"));
+        let (outcome, event) = forward_sync_job(&base_url, "source-1", job).await;
+
+        assert_eq!(outcome.unwrap().0, StatusCode::OK);
+        assert_eq!(event.event_type, "job_completed");
+        assert_eq!(event.result, OperationResult::Success);
+        assert_eq!(event.entity_id, Some(job_id.to_string()));
+        assert_eq!(event.metadata.unwrap()["documents_processed"], 3);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_create_then_poll_sync_job_status() {
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let state = AppState::new(Some(pool.clone()), None, AppConfig::from_env()).await.unwrap();
+        let data_service = state.data_service.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .service(web::scope("/api").configure(configure_data_routes))
+        ).await;
+
+        let (tenant_id, installation_id, repo_config_id) = seed_repo_config(&pool).await;
+        let job_id = data_service
+            .create_sync_job(tenant_id, installation_id, repo_config_id, "full", Some("main"))
+            .await
+            .unwrap();
+
+        data_service.start_sync_job(job_id, 10).await.unwrap();
+        data_service.update_sync_job_progress(job_id, 4, 1, 12).await.unwrap();
+
+        let req = test::TestRequest::get().uri(&format!("/api/github/sync/{}", job_id)).to_request();
+        let running: SyncJobStatusResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(running.status, ChunkJobStatus::Running);
+        assert_eq!(running.items_total, 10);
+        assert_eq!(running.items_processed, 4);
+        assert_eq!(running.items_failed, 1);
+        assert_eq!(running.chunks_created, 12);
+        assert!(running.started_at.is_some());
+
+        data_service.finish_sync_job(job_id, Some("rate limited")).await.unwrap();
+
+        let req = test::TestRequest::get().uri(&format!("/api/github/sync/{}", job_id)).to_request();
+        let failed: SyncJobStatusResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(failed.status, ChunkJobStatus::Failed);
+        assert_eq!(failed.error_message.as_deref(), Some("rate limited"));
+        assert!(failed.duration_ms.is_some());
+
+        let req = test::TestRequest::get().uri(&format!("/api/github/sync/{}", Uuid::new_v4())).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    /// Data service stub listing three files at c3, or one change and one
+    /// deletion since c3. Ingesting README.md fails while `readme_fails` is set;
+    /// every ingest request's path is recorded.
+    fn mock_repository_data_service(readme_fails: Arc<AtomicBool>, requested: Arc<Mutex<Vec<String>>>) -> String {
+        use actix_web::HttpServer;
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = HttpServer::new(move || {
+            let (readme_fails, requested) = (readme_fails.clone(), requested.clone());
+            App::new()
+                .route(
+                    "/api/data/sources/{id}/sync/plan",
+                    web::post().to(|body: web::Json<Value>| async move {
+                        // After a sync of c3, only src/lib.rs changed and README.md was deleted
+                        let plan = if body["since_commit"] == "c3" {
+                            serde_json::json!({
+                                "head_sha": "d4",
+                                "files": [{ "path": "src/lib.rs", "sha": "b4" }],
+                                "removed": ["README.md"]
+                            })
+                        } else {
+                            serde_json::json!({
+                                "head_sha": "c3",
+                                "files": [
+                                    { "path": "src/lib.rs", "sha": "b1" },
+                                    { "path": "src/main.rs", "sha": "b2" },
+                                    { "path": "README.md", "sha": "b3" }
+                                ]
+                            })
+                        };
+                        HttpResponse::Ok().json(plan)
+                    }),
+                )
+                .route(
+                    "/api/data/sources/{id}/sync/files",
+                    web::post().to(move |body: web::Json<Value>| {
+                        let path = body["path"].as_str().unwrap_or_default().to_string();
+                        requested.lock().unwrap().push(path.clone());
+                        let fail = path == "README.md" && readme_fails.load(Ordering::SeqCst);
+                        async move {
+                            if fail {
+                                HttpResponse::ServiceUnavailable().finish()
+                            } else {
+                                HttpResponse::Ok().json(serde_json::json!({ "chunks_created": 2 }))
+                            }
+                        }
+                    }),
+                )
+        })
+        .listen(listener)
+        .unwrap()
+        .run();
+        drop(actix_web::rt::spawn(server));
+        base_url
+    }
+
+    async fn response_json(response: HttpResponse) -> Value {
+        serde_json::from_slice(&actix_web::body::to_bytes(response.into_body()).await.unwrap()).unwrap()
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_failed_repository_sync_is_resumed_by_the_next_sync() {
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let state = AppState::new(Some(pool.clone()), None, AppConfig::from_env()).await.unwrap();
+
+        let readme_fails = Arc::new(AtomicBool::new(true));
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let base_url = mock_repository_data_service(readme_fails.clone(), requested.clone());
+
+        let (_, _, repo_config_id) = seed_repo_config(&pool).await;
+        let target = state.data_service.get_repo_sync_target(repo_config_id).await.unwrap().unwrap();
+
+        let response = sync_repository(&state.data_service, &base_url, &target, Some("trace-1")).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let body = response_json(response).await;
+        let sync_job_id: Uuid = serde_json::from_value(body["details"]["sync_job_id"].clone()).unwrap();
+        let failed = state.data_service.get_sync_job(sync_job_id).await.unwrap().unwrap();
+        assert_eq!(failed.status, ChunkJobStatus::Failed);
+        assert_eq!(failed.items_processed, 2);
+
+        // The next sync picks the failed job back up and only ingests README.md
+        readme_fails.store(false, Ordering::SeqCst);
+        requested.lock().unwrap().clear();
+        let response = sync_repository(&state.data_service, &base_url, &target, Some("trace-2")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_json(response).await;
+        assert_eq!(body["sync_job_id"], serde_json::json!(sync_job_id));
+        assert_eq!(body["documents_processed"], 1);
+        assert_eq!(body["files_skipped"], 2);
+        assert_eq!(*requested.lock().unwrap(), vec!["README.md".to_string()]);
+
+        let completed = state.data_service.get_sync_job(sync_job_id).await.unwrap().unwrap();
+        assert_eq!(completed.status, ChunkJobStatus::Completed);
+        assert_eq!(completed.items_total, 3);
+        assert_eq!(completed.items_processed, 3);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_incremental_sync_starts_from_the_stored_commit() {
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let state = AppState::new(Some(pool.clone()), None, AppConfig::from_env()).await.unwrap();
+
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let base_url = mock_repository_data_service(Arc::new(AtomicBool::new(false)), requested.clone());
+
+        let (tenant_id, _, repo_config_id) = seed_repo_config(&pool).await;
+        let target = state.data_service.get_repo_sync_target(repo_config_id).await.unwrap().unwrap();
+
+        let response = sync_repository(&state.data_service, &base_url, &target, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.data_service.get_last_synced_commit(repo_config_id).await.unwrap().as_deref(), Some("c3"));
+
+        sqlx::query(
+            "INSERT INTO chunks (chunk_id, tenant_id, source_item_id, source_id, chunk_index, content, content_hash, source_kind, metadata)
+             VALUES ($1, $2, $3, $4, 0, '# Readme', 'h', 'code_repo', '{\"path\": \"README.md\"}')"
+        )
+        .bind(Uuid::new_v4())
+        .bind(tenant_id)
+        .bind(Uuid::new_v4())
+        .bind(repo_config_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // The second sync diffs from c3: one file fetched, README.md's chunks dropped
+        requested.lock().unwrap().clear();
+        let response = sync_repository(&state.data_service, &base_url, &target, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_json(response).await;
+        assert_eq!(body["head_sha"], "d4");
+        assert_eq!(body["chunks_deleted"], 1);
+        assert_eq!(*requested.lock().unwrap(), vec!["src/lib.rs".to_string()]);
+        assert_eq!(state.data_service.get_last_synced_commit(repo_config_id).await.unwrap().as_deref(), Some("d4"));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_repository_listing_includes_sync_status() {
+        use actix_web::HttpMessage;
+        use conhub_models::{RepositoryListing, RepositorySyncStatus};
+
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let state = AppState::new(Some(pool.clone()), None, AppConfig::from_env()).await.unwrap();
+        let data_service = state.data_service.clone();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .service(web::scope("/api").configure(configure_data_routes))
+        ).await;
+
+        let (tenant_id, installation_id, repo_config_id) = seed_repo_config(&pool).await;
+        let job_id = data_service
+            .create_sync_job(tenant_id, installation_id, repo_config_id, "code", Some("main"))
+            .await
+            .unwrap();
+        data_service.start_sync_job(job_id, 2).await.unwrap();
+        for path in ["src/lib.rs", "README.md"] {
+            sqlx::query(
+                "INSERT INTO github_documents (tenant_id, repo_config_id, doc_type, external_id, file_path)
+                 VALUES ($1, $2, 'code_file', $3, $3)"
+            )
+            .bind(tenant_id)
+            .bind(repo_config_id)
+            .bind(path)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        data_service.finish_sync_job(job_id, None).await.unwrap();
+
+        let mut claims = conhub_models::auth::default_dev_claims();
+        claims.sub = tenant_id.to_string();
+        let req = test::TestRequest::get().uri("/api/github/repos").to_request();
+        req.extensions_mut().insert(claims);
+        let repositories: Vec<RepositoryListing> = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(repositories.len(), 1);
+        assert_eq!(repositories[0].full_name, "acme/api");
+        assert_eq!(repositories[0].sync_status, RepositorySyncStatus::SyncCompleted);
+        assert!(repositories[0].last_synced.is_some());
+        assert_eq!(repositories[0].document_count, 2);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn test_resumed_sync_skips_already_ingested_files() {
+        use crate::services::data_service::{DataError, SyncIngestSummary};
+
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let state = AppState::new(Some(pool.clone()), None, AppConfig::from_env()).await.unwrap();
+        let data_service = state.data_service.clone();
+
+        let (tenant_id, installation_id, repo_config_id) = seed_repo_config(&pool).await;
+        let job_id = data_service
+            .create_sync_job(tenant_id, installation_id, repo_config_id, "code", Some("main"))
+            .await
+            .unwrap();
+
+        let files: Vec<(String, String)> = ["src/lib.rs", "src/main.rs", "README.md"]
+            .iter()
+            .map(|path| (path.to_string(), format!("sha-{}", path)))
+            .collect();
+
+        // First attempt dies on the third file
+        let seen = Mutex::new(Vec::new());
+        let result = data_service
+            .ingest_files_resumable(job_id, &files, |path| {
+                seen.lock().unwrap().push(path.to_string());
+                let fail = path == "README.md";
+                async move {
+                    if fail {
+                        Err(DataError::ConnectionError("embedding service unavailable".to_string()))
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(seen.lock().unwrap().len(), 3);
+
+        let token = data_service.get_resume_token(job_id).await.unwrap();
+        assert!(token.is_ingested("src/lib.rs", "sha-src/lib.rs"));
+        assert!(!token.is_ingested("README.md", "sha-README.md"));
+
+        // The retry only ingests what the first attempt didn't finish
+        let seen = Mutex::new(Vec::new());
+        let summary = data_service
+            .ingest_files_resumable(job_id, &files, |path| {
+                seen.lock().unwrap().push(path.to_string());
+                async { Ok(()) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(summary, SyncIngestSummary { ingested: 1, skipped: 2 });
+        assert_eq!(*seen.lock().unwrap(), vec!["README.md".to_string()]);
+    }
+}
@@ -0,0 +1,58 @@
+use actix_web::{web, HttpResponse, Result};
+use conhub_models::chunking::{BatchEmbedChunksRequest, EmbeddingModelPolicy, EmbeddingPricing};
+
+/// POST /api/embed/estimate
+/// Projected tokens and cost of a batch without embedding it, so callers can
+/// enforce a budget before running it. Uses the batch's model, or the default.
+pub async fn estimate_embedding_cost(
+    body: web::Json<BatchEmbedChunksRequest>,
+    policy: web::Data<EmbeddingModelPolicy>,
+    pricing: web::Data<EmbeddingPricing>,
+) -> Result<HttpResponse> {
+    let model = body.model.as_deref().unwrap_or(&policy.default.model);
+    Ok(HttpResponse::Ok().json(pricing.estimate(model, &body.chunks)))
+}
+
+pub fn configure_embedding_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/embed")
+            .route("/estimate", web::post().to(estimate_embedding_cost))
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use conhub_models::chunking::EmbeddingCostEstimate;
+    use serde_json::json;
+
+    #[actix_web::test]
+    async fn test_estimate_uses_active_model_pricing() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(EmbeddingModelPolicy::parse("default=voyage-code-3:1024")))
+                .app_data(web::Data::new(EmbeddingPricing::parse("voyage-code-3=0.18")))
+                .service(web::scope("/api").configure(configure_embedding_routes)),
+        )
+        .await;
+
+        // 40 characters over two chunks: 10 tokens
+        let req = test::TestRequest::post()
+            .uri("/api/embed/estimate")
+            .set_json(json!({
+                "chunks": [
+                    { "chunk_id": uuid::Uuid::new_v4(), "content": "a".repeat(32), "metadata": {} },
+                    { "chunk_id": uuid::Uuid::new_v4(), "content": "b".repeat(8), "metadata": {} }
+                ],
+                "normalize": true,
+                "store_in_vector_db": false
+            }))
+            .to_request();
+        let estimate: EmbeddingCostEstimate = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(estimate.model, "voyage-code-3");
+        assert_eq!(estimate.tokens, 10);
+        assert!((estimate.estimated_cost_usd.unwrap() - 10.0 * 0.18 / 1_000_000.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,127 @@
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use conhub_database::graph::GraphDb;
+use conhub_middleware::auth::{extract_user_id_from_request, RoleAuthMiddlewareFactory};
+use conhub_middleware::ApiError;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct MergeEntitiesRequest {
+    /// Entity that survives the merge
+    pub kept_id: Uuid,
+    /// Entity folded into `kept_id` and removed
+    pub merged_id: Uuid,
+}
+
+fn graph_unavailable() -> HttpResponse {
+    ApiError::service_unavailable("Graph database is not configured").into_response()
+}
+
+/// POST /api/admin/graph/entities/merge
+/// Manually merge two entities, e.g. when resolution missed a duplicate. The
+/// response carries the merge id needed to undo it.
+pub async fn merge_entities(
+    req: HttpRequest,
+    body: web::Json<MergeEntitiesRequest>,
+    graph: Option<web::Data<Arc<dyn GraphDb>>>,
+) -> Result<HttpResponse> {
+    let Some(graph) = graph else { return Ok(graph_unavailable()) };
+    let merged_by = extract_user_id_from_request(&req)
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    match graph.merge_entities(body.kept_id, body.merged_id, &merged_by).await {
+        Ok(merge) => Ok(HttpResponse::Ok().json(merge)),
+        Err(e) => {
+            log::warn!("Entity merge {} -> {} failed: {}", body.merged_id, body.kept_id, e);
+            Ok(ApiError::bad_request("Merge failed")
+                .with_details(serde_json::json!({ "reason": e.to_string() }))
+                .into_response())
+        }
+    }
+}
+
+/// POST /api/admin/graph/merges/{merge_id}/undo
+/// Split a merged entity back out, restoring its original edges
+pub async fn unmerge_entities(
+    merge_id: web::Path<Uuid>,
+    graph: Option<web::Data<Arc<dyn GraphDb>>>,
+) -> Result<HttpResponse> {
+    let Some(graph) = graph else { return Ok(graph_unavailable()) };
+
+    let merge_id = merge_id.into_inner();
+    match graph.unmerge_entities(merge_id).await {
+        Ok(Some(merge)) => Ok(HttpResponse::Ok().json(merge)),
+        Ok(None) => Ok(ApiError::not_found("Merge not found or already undone").into_response()),
+        Err(e) => {
+            log::error!("Undoing merge {} failed: {}", merge_id, e);
+            Ok(ApiError::internal("Unmerge failed").into_response())
+        }
+    }
+}
+
+pub fn configure_graph_admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin/graph")
+            .wrap(RoleAuthMiddlewareFactory::new(vec!["admin".to_string()]))
+            .route("/entities/merge", web::post().to(merge_entities))
+            .route("/merges/{merge_id}/undo", web::post().to(unmerge_entities))
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use async_trait::async_trait;
+    use conhub_database::graph::*;
+
+    /// Graph whose only working call is undoing a merge, which either finds nothing or fails
+    struct UnmergeGraph {
+        fails: bool,
+    }
+
+    #[async_trait]
+    impl GraphDb for UnmergeGraph {
+        async fn initialize(&self) -> anyhow::Result<()> { unreachable!() }
+        async fn insert_entity(&self, _: &GraphEntity) -> anyhow::Result<()> { unreachable!() }
+        async fn update_entity(&self, _: &GraphEntity) -> anyhow::Result<()> { unreachable!() }
+        async fn find_entity(&self, _: Uuid) -> anyhow::Result<Option<GraphEntity>> { unreachable!() }
+        async fn insert_relationship(&self, _: &GraphRelationship) -> anyhow::Result<()> { unreachable!() }
+        async fn insert_canonical_entity(&self, _: &CanonicalEntity) -> anyhow::Result<()> { unreachable!() }
+        async fn batch_insert_entities(&self, _: &[GraphEntity]) -> anyhow::Result<usize> { unreachable!() }
+        async fn batch_upsert_entities(&self, _: &[GraphEntity]) -> anyhow::Result<Vec<EntityUpsertResult>> { unreachable!() }
+        async fn batch_insert_relationships(&self, _: &[GraphRelationship]) -> anyhow::Result<usize> { unreachable!() }
+        async fn get_neighbors(&self, _: Uuid, _: &NeighborQuery) -> anyhow::Result<Vec<Neighbor>> { unreachable!() }
+        async fn traverse_graph(&self, _: Uuid, _: &TraversalQuery) -> anyhow::Result<Traversal> { unreachable!() }
+        async fn merge_entities(&self, _: Uuid, _: Uuid, _: &str) -> anyhow::Result<EntityMerge> { unreachable!() }
+        async fn find_paths(&self, _: Uuid, _: Uuid, _: usize) -> anyhow::Result<Vec<EntityPath>> { unreachable!() }
+        async fn get_statistics(&self) -> anyhow::Result<GraphStatistics> { unreachable!() }
+
+        async fn unmerge_entities(&self, _: Uuid) -> anyhow::Result<Option<EntityMerge>> {
+            if self.fails {
+                anyhow::bail!("connection reset");
+            }
+            Ok(None)
+        }
+    }
+
+    async fn undo_status(graph: UnmergeGraph) -> StatusCode {
+        let graph: Arc<dyn GraphDb> = Arc::new(graph);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(graph))
+                .route("/merges/{merge_id}/undo", web::post().to(unmerge_entities)),
+        )
+        .await;
+        let req = test::TestRequest::post().uri(&format!("/merges/{}/undo", Uuid::new_v4())).to_request();
+        test::call_service(&app, req).await.status()
+    }
+
+    #[actix_web::test]
+    async fn test_undo_is_not_found_only_when_no_merge_record_exists() {
+        assert_eq!(undo_status(UnmergeGraph { fails: false }).await, StatusCode::NOT_FOUND);
+        assert_eq!(undo_status(UnmergeGraph { fails: true }).await, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -1,16 +1,97 @@
-use actix_web::{HttpResponse, Result};
+use actix_web::{web, HttpResponse, Result};
+use conhub_models::chunking::{EmbeddingInfoResponse, EmbeddingModelPolicy};
+use std::sync::Arc;
+
+use crate::services::readiness_service::{ReadinessService, ReadinessState};
+
+/// Liveness, plus whether each dependency was reachable on the last probe.
+/// Always 200: an unreachable downstream shows as "degraded" but doesn't mean
+/// the process should be restarted.
+pub async fn health_check(readiness: web::Data<Arc<ReadinessService>>) -> Result<HttpResponse> {
+    let report = readiness.check().await;
+    let dependencies: serde_json::Map<String, serde_json::Value> = report
+        .dependencies
+        .iter()
+        .map(|(name, dependency)| (name.clone(), serde_json::json!(dependency.status)))
+        .collect();
 
-pub async fn health_check() -> Result<HttpResponse> {
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "healthy",
-        "service": "conhub-backend"
+        "status": if report.status == ReadinessState::Ready { "healthy" } else { "degraded" },
+        "service": "conhub-backend",
+        "dependencies": dependencies
     })))
 }
 
-pub async fn readiness_check() -> Result<HttpResponse> {
-    // TODO: Check database connectivity, Redis, Zilliz, etc.
+/// Per-dependency status with an overall rollup. Degraded still answers 200 so
+/// the instance stays in rotation; only a critical dependency failing gives 503.
+pub async fn readiness_check(readiness: web::Data<Arc<ReadinessService>>) -> Result<HttpResponse> {
+    let report = readiness.check().await;
+    let mut response = if report.is_serving() {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+    Ok(response.json(report))
+}
+
+/// Embedding models in use and the dimension each produces, so consumers can
+/// validate their index space against it at startup
+pub async fn service_info(policy: web::Data<EmbeddingModelPolicy>) -> Result<HttpResponse> {
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "ready",
-        "service": "conhub-backend"
+        "service": "conhub-backend",
+        "embedding": EmbeddingInfoResponse::from(policy.get_ref())
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::readiness_service::DependencyProbe;
+    use actix_web::{http::StatusCode, test, App};
+    use conhub_models::chunking::SourceKind;
+
+    #[actix_web::test]
+    async fn test_health_reports_unreachable_downstream() {
+        let readiness = ReadinessService::new(
+            "conhub-backend",
+            vec![
+                DependencyProbe::new("database", true, || Box::pin(async { Ok(()) })),
+                DependencyProbe::new("embedding", false, || Box::pin(async { Err("connection refused".to_string()) })),
+            ],
+        );
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(readiness)))
+                .route("/health", web::get().to(health_check)),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/health").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["dependencies"]["database"], "up");
+        assert_eq!(body["dependencies"]["embedding"], "down");
+    }
+
+    #[actix_web::test]
+    async fn test_info_reports_configured_model_dimension() {
+        let policy = EmbeddingModelPolicy::parse("default=text-embedding-3-large:3072,code_repo=voyage-code-3:1024");
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(policy.clone()))
+                .route("/info", web::get().to(service_info)),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/info").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        let info: EmbeddingInfoResponse = serde_json::from_value(body["embedding"].clone()).unwrap();
+
+        assert_eq!(info.default.model, "text-embedding-3-large");
+        assert_eq!(info.dimension_for(None), policy.model_for(None).dimension);
+        assert_eq!(info.dimension_for(Some(&SourceKind::CodeRepo)), 1024);
+        assert_eq!(info.dimension_for(Some(&SourceKind::Chat)), 3072);
+    }
+}
@@ -127,11 +127,27 @@ pub async fn get_status(
     })))
 }
 
+/// Pending and dead-lettered embedding batches
+pub async fn embedding_retry_depth(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    match state.embedding_retry_queue.depth().await {
+        Ok(depth) => Ok(HttpResponse::Ok().json(depth)),
+        Err(e) => {
+            log::error!("Failed to read embedding retry queue depth: {}", e);
+            Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": format!("{}", e)
+            })))
+        }
+    }
+}
+
 pub fn configure_indexing_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/index")
             .route("/repository", web::post().to(index_repository))
             .route("/documentation", web::post().to(index_documentation))
+            .route("/embedding-retry", web::get().to(embedding_retry_depth))
     )
     .service(
         web::scope("/")
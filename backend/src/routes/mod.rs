@@ -1,11 +1,14 @@
 pub mod auth;
 pub mod billing;
 pub mod data;
+pub mod embedding;
+pub mod graph_admin;
 pub mod health;
 pub mod indexing;
 pub mod security;
 pub mod webhooks;
 pub mod rag;
+pub mod vector_index;
 
 use actix_web::web;
 use conhub_config::feature_toggles::get_cached_toggles;
@@ -20,6 +23,9 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         .configure(security::configure_security_routes)
         .configure(webhooks::configure_webhook_routes)
         .configure(rag::configure_rag_routes)
+        .configure(vector_index::configure_vector_index_routes)
+        .configure(graph_admin::configure_graph_admin_routes)
+        .configure(embedding::configure_embedding_routes)
         .configure(crate::graphql::configure_graphql_routes)
         .route("/dashboard/stats", web::get().to(get_dashboard_stats));
 
@@ -27,5 +33,7 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
 
     cfg.service(api_scope)
         .route("/health", web::get().to(health::health_check))
-        .route("/ready", web::get().to(health::readiness_check));
+        .route("/info", web::get().to(health::service_info))
+        .route("/ready", web::get().to(health::readiness_check))
+        .route("/readyz", web::get().to(health::readiness_check));
 }
@@ -8,5 +8,8 @@ pub fn configure_rag_routes(cfg: &mut web::ServiceConfig) {
             .route("/vector", web::post().to(handlers::rag_vector))
             .route("/hybrid", web::post().to(handlers::rag_hybrid))
             .route("/agentic", web::post().to(handlers::rag_agentic))
+            .route("/agentic/stream", web::post().to(handlers::rag_agentic_stream))
+            .route("/search/rerank", web::post().to(handlers::rag_search_rerank))
+            .route("/analyze", web::post().to(handlers::rag_analyze))
     );
 }
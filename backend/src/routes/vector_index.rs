@@ -0,0 +1,95 @@
+use actix_web::{web, HttpResponse, Result};
+use conhub_middleware::auth::RoleAuthMiddlewareFactory;
+use conhub_middleware::ApiError;
+use conhub_models::chunking::{IndexEmbeddedChunksRequest, IngestChunksRequest};
+use std::sync::Arc;
+use crate::services::vector_index_service::VectorIndexService;
+
+pub async fn start_rebuild(
+    service: web::Data<Arc<VectorIndexService>>,
+) -> Result<HttpResponse> {
+    match service.start_rebuild() {
+        Ok(status) => {
+            log::info!("Vector index rebuild started");
+            Ok(HttpResponse::Accepted().json(status))
+        }
+        Err(status) => Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "A rebuild is already running",
+            "status": status,
+        }))),
+    }
+}
+
+pub async fn rebuild_status(
+    service: web::Data<Arc<VectorIndexService>>,
+) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(service.status()))
+}
+
+/// POST /api/admin/vector-index/chunks
+/// Add chunks the embedding pipeline has embedded, so searches and rebuilds
+/// see the source's current content
+pub async fn index_chunks(
+    body: web::Json<IndexEmbeddedChunksRequest>,
+    service: web::Data<Arc<VectorIndexService>>,
+) -> Result<HttpResponse> {
+    let body = body.into_inner();
+    if body.embeddings.len() != body.chunks.len() {
+        return Ok(ApiError::bad_request(format!(
+            "Expected one embedding per chunk, got {} embeddings for {} chunks",
+            body.embeddings.len(),
+            body.chunks.len()
+        ))
+        .into_response());
+    }
+
+    let source_id = body.source_id;
+    let request = IngestChunksRequest::new(body.source_id, body.source_kind, body.chunks);
+    let response = service.index_chunks(request, body.embeddings);
+    if !response.skipped_chunks.is_empty() {
+        log::warn!(
+            "Skipped {} chunks of source {} with zero or wrong-dimension embeddings",
+            response.skipped_chunks.len(),
+            source_id
+        );
+    }
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// DELETE /api/admin/vector-index/sources/{source_id}
+/// Drop the vectors of a source deleted upstream, from the index and the
+/// embedding service's vector store. Deleting a source that has no vectors
+/// succeeds with nothing removed.
+pub async fn delete_source_vectors(
+    source_id: web::Path<String>,
+    service: web::Data<Arc<VectorIndexService>>,
+) -> Result<HttpResponse> {
+    match service.delete_source(&source_id).await {
+        Ok(deletion) => {
+            log::info!(
+                "Removed vectors of deleted source {}: {} from the index, {:?} from the store",
+                source_id, deletion.index_removed, deletion.store_removed
+            );
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "source_id": source_id.into_inner(),
+                "removed": deletion.index_removed,
+                "store_removed": deletion.store_removed,
+            })))
+        }
+        Err(e) => {
+            log::error!("Failed to delete vectors of source {}: {}", source_id, e);
+            Ok(ApiError::bad_gateway(e).into_response())
+        }
+    }
+}
+
+pub fn configure_vector_index_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin/vector-index")
+            .wrap(RoleAuthMiddlewareFactory::new(vec!["admin".to_string()]))
+            .route("/rebuild", web::post().to(start_rebuild))
+            .route("/rebuild", web::get().to(rebuild_status))
+            .route("/chunks", web::post().to(index_chunks))
+            .route("/sources/{source_id}", web::delete().to(delete_source_vectors))
+    );
+}
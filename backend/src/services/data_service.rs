@@ -1,6 +1,14 @@
 use sqlx::PgPool;
 use crate::config::AppConfig;
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use conhub_models::chunking::{ChunkJobStatus, SyncJobStatusResponse, SyncResumeToken};
+use conhub_models::{RepositoryListing, RepositorySyncStatus};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize)]
 pub struct DataSource {
@@ -29,14 +37,19 @@ impl std::fmt::Display for DataError {
 
 impl std::error::Error for DataError {}
 
+/// How long per-repository document counts are reused before recounting
+const DOCUMENT_COUNT_TTL: Duration = Duration::from_secs(60);
+
 pub struct DataService {
     db_pool: Option<PgPool>,
     config: AppConfig,
+    /// Document counts per repository, keyed by tenant
+    document_counts: Mutex<HashMap<Uuid, (Instant, HashMap<Uuid, i64>)>>,
 }
 
 impl DataService {
     pub fn new(db_pool: Option<PgPool>, config: AppConfig) -> Self {
-        Self { db_pool, config }
+        Self { db_pool, config, document_counts: Mutex::new(HashMap::new()) }
     }
 
     pub async fn connect_data_source(&self, user_id: &str, source_type: &str, config: &str) -> Result<DataSource, DataError> {
@@ -59,4 +72,425 @@ impl DataService {
 
         Ok(Vec::new())
     }
+
+    fn pool(&self) -> Result<&PgPool, DataError> {
+        self.db_pool
+            .as_ref()
+            .ok_or_else(|| DataError::DatabaseError("Database not configured".to_string()))
+    }
+
+    /// The connected GitHub repository `repo_config_id` names, if any
+    pub async fn get_repo_sync_target(&self, repo_config_id: Uuid) -> Result<Option<RepoSyncTarget>, DataError> {
+        sqlx::query_as::<_, RepoSyncTarget>(
+            r#"
+            SELECT id AS repo_config_id, tenant_id, installation_id, default_branch
+            FROM github_repo_configs
+            WHERE id = $1 AND is_active
+            "#
+        )
+        .bind(repo_config_id)
+        .fetch_optional(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))
+    }
+
+    /// The repository's latest code sync job if it never completed, so a new
+    /// sync can resume it instead of ingesting every file again
+    pub async fn find_resumable_sync_job(&self, repo_config_id: Uuid) -> Result<Option<Uuid>, DataError> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM (
+                SELECT id, status FROM github_sync_jobs
+                WHERE repo_config_id = $1 AND job_type = 'code'
+                ORDER BY created_at DESC
+                LIMIT 1
+            ) latest
+            WHERE status IN ('pending', 'running', 'failed')
+            "#
+        )
+        .bind(repo_config_id)
+        .fetch_optional(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))
+    }
+
+    /// Persist a new GitHub sync job in `pending` state and return its id
+    pub async fn create_sync_job(
+        &self,
+        tenant_id: Uuid,
+        installation_id: Uuid,
+        repo_config_id: Uuid,
+        job_type: &str,
+        target_branch: Option<&str>,
+    ) -> Result<Uuid, DataError> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO github_sync_jobs (tenant_id, installation_id, repo_config_id, job_type, target_branch)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            "#
+        )
+        .bind(tenant_id)
+        .bind(installation_id)
+        .bind(repo_config_id)
+        .bind(job_type)
+        .bind(target_branch)
+        .fetch_one(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))
+    }
+
+    pub async fn start_sync_job(&self, sync_job_id: Uuid, items_total: i32) -> Result<(), DataError> {
+        sqlx::query(
+            r#"
+            UPDATE github_sync_jobs
+            SET status = 'running', started_at = CURRENT_TIMESTAMP, items_total = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $2
+            "#
+        )
+        .bind(items_total)
+        .bind(sync_job_id)
+        .execute(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub async fn update_sync_job_progress(
+        &self,
+        sync_job_id: Uuid,
+        items_processed: i32,
+        items_failed: i32,
+        chunks_created: i32,
+    ) -> Result<(), DataError> {
+        sqlx::query(
+            r#"
+            UPDATE github_sync_jobs
+            SET items_processed = $1, items_failed = $2, chunks_created = $3, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $4
+            "#
+        )
+        .bind(items_processed)
+        .bind(items_failed)
+        .bind(chunks_created)
+        .bind(sync_job_id)
+        .execute(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Mark a sync job finished; `error` set means it failed
+    pub async fn finish_sync_job(&self, sync_job_id: Uuid, error: Option<&str>) -> Result<(), DataError> {
+        let status = if error.is_some() { "failed" } else { "completed" };
+
+        sqlx::query(
+            r#"
+            UPDATE github_sync_jobs
+            SET status = $1, error_message = $2, completed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $3
+            "#
+        )
+        .bind(status)
+        .bind(error)
+        .bind(sync_job_id)
+        .execute(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        // A finished sync may have added or removed documents
+        self.document_counts.lock().unwrap_or_else(|e| e.into_inner()).clear();
+
+        Ok(())
+    }
+
+    /// Files the job has already ingested; empty for a job that never ran
+    pub async fn get_resume_token(&self, sync_job_id: Uuid) -> Result<SyncResumeToken, DataError> {
+        let token = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT resume_token FROM github_sync_jobs WHERE id = $1"
+        )
+        .bind(sync_job_id)
+        .fetch_optional(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        Ok(token
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default())
+    }
+
+    /// Add one file to the job's resume token. Written per file so progress
+    /// survives the sync failing part way through.
+    pub async fn record_ingested_file(&self, sync_job_id: Uuid, path: &str, blob_sha: &str) -> Result<(), DataError> {
+        sqlx::query(
+            r#"
+            UPDATE github_sync_jobs
+            SET resume_token = jsonb_set(
+                    resume_token,
+                    '{ingested}',
+                    COALESCE(resume_token->'ingested', '{}'::jsonb) || jsonb_build_object($1::text, $2::text)
+                ),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $3
+            "#
+        )
+        .bind(path)
+        .bind(blob_sha)
+        .bind(sync_job_id)
+        .execute(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Ingest `(path, blob_sha)` files for a sync job, skipping those its resume
+    /// token already covers. Stops at the first failure; every file ingested
+    /// before it stays recorded, so retrying the same job picks up from there.
+    pub async fn ingest_files_resumable<F, Fut>(
+        &self,
+        sync_job_id: Uuid,
+        files: &[(String, String)],
+        mut ingest: F,
+    ) -> Result<SyncIngestSummary, DataError>
+    where
+        F: FnMut(&str) -> Fut,
+        Fut: Future<Output = Result<(), DataError>>,
+    {
+        let token = self.get_resume_token(sync_job_id).await?;
+        let pending = token.pending(files.iter().map(|(path, sha)| (path.as_str(), sha.as_str())));
+        let mut summary = SyncIngestSummary {
+            ingested: 0,
+            skipped: files.len() - pending.len(),
+        };
+        if summary.skipped > 0 {
+            log::info!("Resuming sync job {}: skipping {} already ingested files", sync_job_id, summary.skipped);
+        }
+
+        for (path, blob_sha) in pending {
+            ingest(path).await?;
+            self.record_ingested_file(sync_job_id, path, blob_sha).await?;
+            summary.ingested += 1;
+        }
+
+        Ok(summary)
+    }
+
+    /// Head commit of the last completed code sync, the base for the next tree diff
+    pub async fn get_last_synced_commit(&self, repo_config_id: Uuid) -> Result<Option<String>, DataError> {
+        let commit = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT last_code_sync_commit FROM github_repo_configs WHERE id = $1"
+        )
+        .bind(repo_config_id)
+        .fetch_optional(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        Ok(commit.flatten())
+    }
+
+    /// Record `commit_sha` as synced. Only call once every changed file has been
+    /// indexed, otherwise the next diff would skip the files that were missed.
+    pub async fn record_synced_commit(&self, repo_config_id: Uuid, commit_sha: &str) -> Result<(), DataError> {
+        sqlx::query(
+            r#"
+            UPDATE github_repo_configs
+            SET last_code_sync_commit = $1, last_code_sync_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $2
+            "#
+        )
+        .bind(commit_sha)
+        .bind(repo_config_id)
+        .execute(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Drop the chunks of files deleted from the repo since the last sync
+    pub async fn delete_chunks_for_paths(&self, source_id: Uuid, paths: &[String]) -> Result<u64, DataError> {
+        if paths.is_empty() {
+            return Ok(0);
+        }
+
+        let result = sqlx::query(
+            "DELETE FROM chunks WHERE source_id = $1 AND metadata->>'path' = ANY($2)"
+        )
+        .bind(source_id)
+        .bind(paths)
+        .execute(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_sync_job(&self, sync_job_id: Uuid) -> Result<Option<SyncJobStatusResponse>, DataError> {
+        let row = sqlx::query_as::<_, GithubSyncJobRow>(
+            r#"
+            SELECT id, job_type, status, items_total, items_processed, items_failed, chunks_created,
+                   error_message, started_at, completed_at
+            FROM github_sync_jobs
+            WHERE id = $1
+            "#
+        )
+        .bind(sync_job_id)
+        .fetch_optional(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        Ok(row.map(GithubSyncJobRow::into_status))
+    }
+
+    /// A tenant's active repositories with their latest sync state and
+    /// document counts
+    pub async fn list_repositories(&self, tenant_id: Uuid) -> Result<Vec<RepositoryListing>, DataError> {
+        let rows = sqlx::query_as::<_, RepositoryRow>(
+            r#"
+            SELECT r.id, r.name, r.full_name, r.owner, r.html_url, r.private, r.default_branch,
+                   GREATEST(r.last_code_sync_at, done.completed_at) AS last_synced,
+                   latest.status AS last_job_status
+            FROM github_repo_configs r
+            LEFT JOIN LATERAL (
+                SELECT status FROM github_sync_jobs
+                WHERE repo_config_id = r.id
+                ORDER BY created_at DESC
+                LIMIT 1
+            ) latest ON true
+            LEFT JOIN LATERAL (
+                SELECT MAX(completed_at) AS completed_at FROM github_sync_jobs
+                WHERE repo_config_id = r.id AND status = 'completed'
+            ) done ON true
+            WHERE r.tenant_id = $1 AND r.is_active
+            ORDER BY r.full_name
+            "#
+        )
+        .bind(tenant_id)
+        .fetch_all(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?;
+
+        let counts = self.document_counts(tenant_id).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let document_count = counts.get(&row.id).copied().unwrap_or(0);
+                row.into_listing(document_count)
+            })
+            .collect())
+    }
+
+    /// Documents per repository for a tenant, counted in one grouped query and
+    /// cached for `DOCUMENT_COUNT_TTL`
+    async fn document_counts(&self, tenant_id: Uuid) -> Result<HashMap<Uuid, i64>, DataError> {
+        if let Some((at, counts)) = self.document_counts.lock().unwrap_or_else(|e| e.into_inner()).get(&tenant_id) {
+            if at.elapsed() < DOCUMENT_COUNT_TTL {
+                return Ok(counts.clone());
+            }
+        }
+
+        let counts: HashMap<Uuid, i64> = sqlx::query_as::<_, (Uuid, i64)>(
+            "SELECT repo_config_id, COUNT(*) FROM github_documents WHERE tenant_id = $1 GROUP BY repo_config_id"
+        )
+        .bind(tenant_id)
+        .fetch_all(self.pool()?)
+        .await
+        .map_err(|e| DataError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .collect();
+
+        self.document_counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(tenant_id, (Instant::now(), counts.clone()));
+        Ok(counts)
+    }
+}
+
+/// A connected repository, with what a sync job for it is recorded against
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RepoSyncTarget {
+    pub repo_config_id: Uuid,
+    pub tenant_id: Uuid,
+    pub installation_id: Uuid,
+    pub default_branch: String,
+}
+
+/// Outcome of one (possibly resumed) pass over a sync job's files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncIngestSummary {
+    pub ingested: usize,
+    /// Already ingested by an earlier attempt
+    pub skipped: usize,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct GithubSyncJobRow {
+    id: Uuid,
+    job_type: String,
+    status: String,
+    items_total: Option<i32>,
+    items_processed: Option<i32>,
+    items_failed: Option<i32>,
+    chunks_created: Option<i32>,
+    error_message: Option<String>,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl GithubSyncJobRow {
+    fn into_status(self) -> SyncJobStatusResponse {
+        let count = |v: Option<i32>| v.unwrap_or(0).max(0) as usize;
+        let duration_ms = self.started_at.map(|started| {
+            let end = self.completed_at.unwrap_or_else(Utc::now);
+            (end - started).num_milliseconds().max(0) as u64
+        });
+
+        SyncJobStatusResponse {
+            sync_job_id: self.id,
+            job_type: self.job_type,
+            status: ChunkJobStatus::parse(&self.status).unwrap_or(ChunkJobStatus::Pending),
+            items_total: count(self.items_total),
+            items_processed: count(self.items_processed),
+            items_failed: count(self.items_failed),
+            chunks_created: count(self.chunks_created),
+            error_message: self.error_message,
+            started_at: self.started_at,
+            completed_at: self.completed_at,
+            duration_ms,
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RepositoryRow {
+    id: Uuid,
+    name: String,
+    full_name: String,
+    owner: String,
+    html_url: Option<String>,
+    private: bool,
+    default_branch: String,
+    last_synced: Option<DateTime<Utc>>,
+    last_job_status: Option<String>,
+}
+
+impl RepositoryRow {
+    fn into_listing(self, document_count: i64) -> RepositoryListing {
+        RepositoryListing {
+            id: self.id,
+            name: self.name,
+            full_name: self.full_name,
+            owner: self.owner,
+            url: self.html_url,
+            is_private: self.private,
+            default_branch: self.default_branch,
+            last_synced: self.last_synced,
+            sync_status: RepositorySyncStatus::from_job_status(self.last_job_status.as_deref()),
+            document_count,
+        }
+    }
 }
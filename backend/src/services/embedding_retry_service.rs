@@ -0,0 +1,318 @@
+use chrono::Utc;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Sorted set of pending batches, scored by the epoch millis they are next due
+const RETRY_KEY: &str = "embedding:retry";
+/// Batches that exhausted their attempts, kept for inspection and manual replay
+const DEAD_LETTER_KEY: &str = "embedding:deadletter";
+
+#[derive(Debug)]
+pub enum RetryQueueError {
+    Store(String),
+}
+
+impl std::fmt::Display for RetryQueueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryQueueError::Store(msg) => write!(f, "Retry queue store error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RetryQueueError {}
+
+impl From<redis::RedisError> for RetryQueueError {
+    fn from(e: redis::RedisError) -> Self {
+        RetryQueueError::Store(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for RetryQueueError {
+    fn from(e: serde_json::Error) -> Self {
+        RetryQueueError::Store(e.to_string())
+    }
+}
+
+/// Documents whose embedding call failed, waiting to be embedded again
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FailedEmbeddingBatch {
+    pub id: Uuid,
+    pub document_ids: Vec<String>,
+    pub texts: Vec<String>,
+    /// Whether the failed call asked for unit-length vectors; retries ask the same
+    #[serde(default = "default_normalize")]
+    pub normalize: bool,
+    /// Attempts made so far, including the one that first failed
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+fn default_normalize() -> bool {
+    true
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub max_attempts: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(30),
+            max_delay: Duration::from_secs(30 * 60),
+            max_attempts: 8,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff after `attempts` failures, capped at `max_delay`
+    pub fn delay_for(&self, attempts: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempts.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct QueueDepth {
+    pub pending: usize,
+    pub dead_lettered: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct RetryRunStats {
+    pub succeeded: usize,
+    pub requeued: usize,
+    pub dead_lettered: usize,
+}
+
+enum RetryStore {
+    Redis(redis::Client),
+    Memory(Mutex<MemoryStore>),
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    pending: Vec<(i64, FailedEmbeddingBatch)>,
+    dead: Vec<FailedEmbeddingBatch>,
+}
+
+/// Holds embedding batches that failed so a transient embedding outage delays
+/// documents instead of dropping them. Backed by Redis when available.
+pub struct EmbeddingRetryQueue {
+    store: RetryStore,
+    policy: RetryPolicy,
+}
+
+impl EmbeddingRetryQueue {
+    pub fn redis(client: redis::Client, policy: RetryPolicy) -> Self {
+        Self { store: RetryStore::Redis(client), policy }
+    }
+
+    /// Process-local queue, used when Redis is disabled
+    pub fn in_memory(policy: RetryPolicy) -> Self {
+        Self { store: RetryStore::Memory(Mutex::new(MemoryStore::default())), policy }
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Record a batch whose first embedding attempt just failed
+    pub async fn enqueue_failure(
+        &self,
+        document_ids: Vec<String>,
+        texts: Vec<String>,
+        normalize: bool,
+        error: &str,
+    ) -> Result<Uuid, RetryQueueError> {
+        let batch = FailedEmbeddingBatch {
+            id: Uuid::new_v4(),
+            document_ids,
+            texts,
+            normalize,
+            attempts: 1,
+            last_error: error.to_string(),
+        };
+        let id = batch.id;
+        log::warn!("Embedding batch {} failed ({}); queued for retry", id, error);
+        self.schedule(batch).await?;
+        Ok(id)
+    }
+
+    pub async fn depth(&self) -> Result<QueueDepth, RetryQueueError> {
+        match &self.store {
+            RetryStore::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                let pending: usize = conn.zcard(RETRY_KEY).await?;
+                let dead_lettered: usize = conn.llen(DEAD_LETTER_KEY).await?;
+                Ok(QueueDepth { pending, dead_lettered })
+            }
+            RetryStore::Memory(store) => {
+                let store = store.lock().unwrap_or_else(|e| e.into_inner());
+                Ok(QueueDepth { pending: store.pending.len(), dead_lettered: store.dead.len() })
+            }
+        }
+    }
+
+    /// Re-attempt up to `limit` due batches with `embed`. Failures are rescheduled
+    /// with backoff, or dead-lettered once `max_attempts` is reached.
+    pub async fn process_due<F, Fut>(&self, limit: usize, mut embed: F) -> Result<RetryRunStats, RetryQueueError>
+    where
+        F: FnMut(FailedEmbeddingBatch) -> Fut,
+        Fut: Future<Output = Result<(), String>>,
+    {
+        let mut stats = RetryRunStats::default();
+
+        for mut batch in self.take_due(limit).await? {
+            match embed(batch.clone()).await {
+                Ok(()) => {
+                    log::info!("Embedding batch {} succeeded on attempt {}", batch.id, batch.attempts + 1);
+                    stats.succeeded += 1;
+                }
+                Err(e) => {
+                    batch.attempts += 1;
+                    batch.last_error = e;
+                    if batch.attempts >= self.policy.max_attempts {
+                        log::error!(
+                            "Embedding batch {} failed {} times, dead-lettering: {}",
+                            batch.id, batch.attempts, batch.last_error
+                        );
+                        self.dead_letter(batch).await?;
+                        stats.dead_lettered += 1;
+                    } else {
+                        self.schedule(batch).await?;
+                        stats.requeued += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    async fn schedule(&self, batch: FailedEmbeddingBatch) -> Result<(), RetryQueueError> {
+        let due = Utc::now().timestamp_millis() + self.policy.delay_for(batch.attempts).as_millis() as i64;
+        match &self.store {
+            RetryStore::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                conn.zadd::<_, _, _, ()>(RETRY_KEY, serde_json::to_string(&batch)?, due).await?;
+            }
+            RetryStore::Memory(store) => {
+                store.lock().unwrap_or_else(|e| e.into_inner()).pending.push((due, batch));
+            }
+        }
+        Ok(())
+    }
+
+    async fn dead_letter(&self, batch: FailedEmbeddingBatch) -> Result<(), RetryQueueError> {
+        match &self.store {
+            RetryStore::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                conn.rpush::<_, _, ()>(DEAD_LETTER_KEY, serde_json::to_string(&batch)?).await?;
+            }
+            RetryStore::Memory(store) => {
+                store.lock().unwrap_or_else(|e| e.into_inner()).dead.push(batch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove and return batches whose retry time has passed
+    async fn take_due(&self, limit: usize) -> Result<Vec<FailedEmbeddingBatch>, RetryQueueError> {
+        let now = Utc::now().timestamp_millis();
+        match &self.store {
+            RetryStore::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                let members: Vec<String> = conn
+                    .zrangebyscore_limit(RETRY_KEY, "-inf", now, 0, limit as isize)
+                    .await?;
+
+                let mut due = Vec::with_capacity(members.len());
+                for member in members {
+                    // Only the worker whose ZREM succeeds owns the batch
+                    let removed: usize = conn.zrem(RETRY_KEY, &member).await?;
+                    if removed == 1 {
+                        due.push(serde_json::from_str(&member)?);
+                    }
+                }
+                Ok(due)
+            }
+            RetryStore::Memory(store) => {
+                let mut store = store.lock().unwrap_or_else(|e| e.into_inner());
+                store.pending.sort_by_key(|(at, _)| *at);
+                let count = store.pending.iter().take(limit).take_while(|(at, _)| *at <= now).count();
+                Ok(store.pending.drain(..count).map(|(_, batch)| batch).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn immediate(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy { base_delay: Duration::ZERO, max_delay: Duration::ZERO, max_attempts }
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_is_enqueued_and_reattempted() {
+        let queue = EmbeddingRetryQueue::in_memory(immediate(5));
+        queue
+            .enqueue_failure(vec!["doc-1".to_string()], vec!["fn main() {}".to_string()], true, "503 from embedding service")
+            .await
+            .unwrap();
+        assert_eq!(queue.depth().await.unwrap(), QueueDepth { pending: 1, dead_lettered: 0 });
+
+        // Still down: the batch goes back on the queue with its attempt counted
+        let stats = queue.process_due(10, |_| async { Err("timeout".to_string()) }).await.unwrap();
+        assert_eq!(stats.requeued, 1);
+        assert_eq!(queue.depth().await.unwrap().pending, 1);
+
+        let mut seen = Vec::new();
+        let stats = queue
+            .process_due(10, |batch| {
+                seen.push(batch.clone());
+                async { Ok(()) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(stats.succeeded, 1);
+        assert_eq!(seen[0].texts, vec!["fn main() {}".to_string()]);
+        assert_eq!(seen[0].attempts, 2);
+        assert_eq!(seen[0].last_error, "timeout");
+        assert_eq!(queue.depth().await.unwrap(), QueueDepth::default());
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_dead_lettered_after_max_attempts() {
+        let queue = EmbeddingRetryQueue::in_memory(immediate(2));
+        queue.enqueue_failure(vec!["doc-1".to_string()], vec!["text".to_string()], true, "down").await.unwrap();
+
+        let stats = queue.process_due(10, |_| async { Err("still down".to_string()) }).await.unwrap();
+        assert_eq!(stats.dead_lettered, 1);
+        assert_eq!(queue.depth().await.unwrap(), QueueDepth { pending: 0, dead_lettered: 1 });
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_is_capped() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_secs(10),
+            max_delay: Duration::from_secs(60),
+            max_attempts: 10,
+        };
+        assert_eq!(policy.delay_for(1), Duration::from_secs(10));
+        assert_eq!(policy.delay_for(3), Duration::from_secs(40));
+        assert_eq!(policy.delay_for(4), Duration::from_secs(60));
+        assert_eq!(policy.delay_for(40), Duration::from_secs(60));
+    }
+}
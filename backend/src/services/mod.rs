@@ -5,5 +5,9 @@ pub mod indexing_service;
 pub mod security_service;
 pub mod rag_service;
 pub mod decision_engine_client;
+pub mod vector_index_service;
+pub mod embedding_retry_service;
+pub mod readiness_service;
+pub mod sync_lock_service;
 
 pub use decision_engine_client::DecisionEngineClient;
@@ -1,8 +1,22 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use reqwest::Client;
+use tokio::sync::mpsc;
 use anyhow::{Result, Context};
 
+use crate::services::readiness_service::validate_service_url;
+
+/// Extra attempts made against the embedding and graph services before giving up
+const DOWNSTREAM_RETRIES: u32 = 2;
+
+/// Rerank mode retrieves this many candidates per requested result
+const RERANK_CANDIDATE_FACTOR: usize = 4;
+/// Upper bound on the candidates sent to the reranker in one request
+const MAX_RERANK_CANDIDATES: usize = 100;
+
 #[derive(Debug, Deserialize)]
 pub struct RagQueryRequest {
     pub query: String,
@@ -10,14 +24,43 @@ pub struct RagQueryRequest {
     pub mode: Option<RagMode>,
     pub filters: Option<RagFilters>,
     pub top_k: Option<usize>,
+    /// Drop vector matches with similarity below this, even if fewer than `top_k` remain
+    pub min_score: Option<f32>,
+    /// Drop results whose fused confidence is below this, in `[0, 1]`
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
+    /// `jsonl` returns one result per line for offline evaluation instead of the JSON response
+    #[serde(default)]
+    pub format: ResponseFormat,
+    /// Per-tenant embedding/graph backends; unset fields use the service defaults
+    #[serde(default)]
+    pub endpoints: Option<EndpointOverrides>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Downstream URLs a query may route to instead of the global ones. Each must
+/// be on the service's allow-list.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct EndpointOverrides {
+    pub embedding_url: Option<String>,
+    pub graph_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    Jsonl,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum RagMode {
     Vector,
     Hybrid,
     Agentic,
+    /// Vector search over a wider candidate set, reordered by the reranker
+    Rerank,
     Auto, // Automatically choose based on query
 }
 
@@ -49,9 +92,269 @@ pub struct RagQueryResponse {
 pub struct Source {
     pub source_type: String, // "vector", "graph", "hybrid"
     pub content: String,
-    pub score: f32,
+    pub score: f32,     // Normalized to [0, 1] per source before fusion
+    pub raw_score: f32, // Score as reported by the originating system
+    /// How well supported the result is in `[0, 1]`; rises when several sources agree
+    pub confidence: f32,
     pub metadata: serde_json::Value,
     pub citation: Option<String>,
+    pub provenance: Vec<String>, // Every source this content was found in, after dedup
+}
+
+/// Longest snippet written per result in JSONL exports
+const JSONL_SNIPPET_CHARS: usize = 500;
+
+impl RagQueryResponse {
+    /// One JSON object per ranked result (`rank`, `id`, `score`, `raw_score`, `source`,
+    /// `snippet`), newline-terminated, for offline scoring pipelines
+    pub fn to_jsonl(&self) -> String {
+        let mut out = String::new();
+        for (rank, source) in self.sources.iter().enumerate() {
+            let line = serde_json::json!({
+                "rank": rank + 1,
+                "id": result_id(source),
+                "score": source.score,
+                "raw_score": source.raw_score,
+                "confidence": source.confidence,
+                "source": source.citation.as_deref().unwrap_or(&source.source_type),
+                "source_type": source.source_type,
+                "snippet": source.content.chars().take(JSONL_SNIPPET_CHARS).collect::<String>(),
+            });
+            out.push_str(&line.to_string());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Upstream id of a result when the index reports one, otherwise its content identity
+fn result_id(source: &Source) -> String {
+    ["id", "chunk_id", "document_id"]
+        .iter()
+        .find_map(|key| match source.metadata.get(*key) {
+            Some(serde_json::Value::String(id)) => Some(id.clone()),
+            Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+            _ => None,
+        })
+        .unwrap_or_else(|| dedup_key(source))
+}
+
+/// Progress events emitted while an agentic query is answered in streaming mode
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AgenticEvent {
+    RetrievalDone { sources: Vec<Source> },
+    Reasoning { step: usize, thought: String },
+    Token { text: String },
+    Final { answer: String, confidence: f32, query_time_ms: u64 },
+    Error { message: String },
+}
+
+impl AgenticEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AgenticEvent::RetrievalDone { .. } => "retrieval_done",
+            AgenticEvent::Reasoning { .. } => "reasoning",
+            AgenticEvent::Token { .. } => "token",
+            AgenticEvent::Final { .. } => "final",
+            AgenticEvent::Error { .. } => "error",
+        }
+    }
+
+    /// `final` and `error` close the stream
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, AgenticEvent::Final { .. } | AgenticEvent::Error { .. })
+    }
+
+    /// Encode as a server-sent event frame
+    pub fn to_sse(&self) -> String {
+        let data = serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string());
+        format!("event: {}\ndata: {}\n\n", self.name(), data)
+    }
+}
+
+/// How the agentic service answered a streaming request
+enum AgenticReply {
+    /// `text/event-stream` body, forwarded frame by frame
+    Streaming(reqwest::Response),
+    /// Finished JSON response from a service without streaming support
+    Complete(serde_json::Value),
+}
+
+/// Progress while forwarding the agentic service's event stream
+#[derive(Default)]
+struct AgenticStreamState {
+    /// Bytes received after the last complete SSE frame
+    pending: Vec<u8>,
+    steps: usize,
+    answer: String,
+    sources: Vec<Source>,
+}
+
+impl AgenticStreamState {
+    /// Append a body chunk and return the `(event, data)` of every frame it completes
+    fn push(&mut self, chunk: &[u8]) -> Vec<(String, serde_json::Value)> {
+        self.pending.extend(chunk.iter().filter(|&&b| b != b'\r'));
+        let mut frames = Vec::new();
+        while let Some(end) = self.pending.windows(2).position(|w| w == b"\n\n") {
+            let frame: Vec<u8> = self.pending.drain(..end + 2).collect();
+            if let Some(parsed) = parse_sse_frame(&String::from_utf8_lossy(&frame[..end])) {
+                frames.push(parsed);
+            }
+        }
+        frames
+    }
+}
+
+/// Event name and data of one SSE frame; `None` for comments and keep-alives.
+/// Data that isn't JSON is kept as a string.
+fn parse_sse_frame(frame: &str) -> Option<(String, serde_json::Value)> {
+    let mut event = "message";
+    let mut data = String::new();
+    for line in frame.lines() {
+        if let Some(name) = line.strip_prefix("event:") {
+            event = name.trim();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    if data.is_empty() && event == "message" {
+        return None;
+    }
+    let value = serde_json::from_str(&data).unwrap_or(serde_json::Value::String(data));
+    Some((event.to_string(), value))
+}
+
+/// Text describing an agentic step or tool call, as a string or `{thought}`/`{tool}` object
+fn step_thought(step: &serde_json::Value) -> String {
+    step.as_str()
+        .or_else(|| step.get("thought").and_then(|t| t.as_str()))
+        .map(str::to_string)
+        .or_else(|| step.get("tool").and_then(|t| t.as_str()).map(|tool| format!("calling {}", tool)))
+        .unwrap_or_default()
+}
+
+/// Time budget for the downstream calls made while answering a query
+#[derive(Debug, Clone, Copy)]
+pub struct PipelineTimeouts {
+    /// Maximum time for any single downstream call
+    pub step: Duration,
+    /// Deadline for the whole query, shared across all steps
+    pub overall: Duration,
+}
+
+impl Default for PipelineTimeouts {
+    fn default() -> Self {
+        Self {
+            step: Duration::from_secs(10),
+            overall: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Completed,
+    Failed,
+    TimedOut,
+}
+
+/// Outcome and latency of one pipeline step, reported in the response metadata
+#[derive(Debug, Clone, Serialize)]
+pub struct StepReport {
+    pub step: String,
+    pub status: StepStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Tracks the steps of a single query against its time budget. Steps that exceed
+/// their budget are dropped (cancelling the in-flight request) and whatever was
+/// retrieved before the timeout is kept as partial progress.
+pub struct Pipeline {
+    timeouts: PipelineTimeouts,
+    started: Instant,
+    steps: Vec<StepReport>,
+    partial: Vec<Source>,
+}
+
+impl Pipeline {
+    pub fn new(timeouts: PipelineTimeouts) -> Self {
+        Self {
+            timeouts,
+            started: Instant::now(),
+            steps: Vec::new(),
+            partial: Vec::new(),
+        }
+    }
+
+    /// Run one step under the smaller of the per-step timeout and the remaining overall deadline
+    pub async fn step<T, F>(&mut self, name: &str, fut: F) -> Result<T>
+    where
+        F: std::future::Future<Output = Result<T>>,
+    {
+        let remaining = self.timeouts.overall.saturating_sub(self.started.elapsed());
+        let budget = self.timeouts.step.min(remaining);
+        let step_start = Instant::now();
+
+        let outcome = tokio::time::timeout(budget, fut).await;
+        let latency_ms = step_start.elapsed().as_millis() as u64;
+
+        let (status, error) = match &outcome {
+            Ok(Ok(_)) => (StepStatus::Completed, None),
+            Ok(Err(e)) => (StepStatus::Failed, Some(e.to_string())),
+            Err(_) => (StepStatus::TimedOut, Some(format!("exceeded {}ms budget", budget.as_millis()))),
+        };
+        log::info!("RAG step '{}' {:?} in {}ms", name, status, latency_ms);
+
+        self.steps.push(StepReport {
+            step: name.to_string(),
+            status,
+            latency_ms,
+            error,
+        });
+
+        match outcome {
+            Ok(result) => result,
+            Err(_) => anyhow::bail!("RAG step '{}' timed out after {}ms", name, latency_ms),
+        }
+    }
+
+    /// Keep results from a finished step so they can be returned if a later step times out
+    pub fn record_partial(&mut self, sources: &[Source]) {
+        self.partial.extend_from_slice(sources);
+    }
+
+    pub fn timed_out_step(&self) -> Option<&str> {
+        self.steps
+            .iter()
+            .find(|s| s.status == StepStatus::TimedOut)
+            .map(|s| s.step.as_str())
+    }
+
+    pub fn steps(&self) -> &[StepReport] {
+        &self.steps
+    }
+
+    /// Retrieval systems whose step completed and fed into the answer
+    pub fn contributing_sources(&self) -> Vec<&'static str> {
+        self.steps
+            .iter()
+            .filter(|s| s.status == StepStatus::Completed)
+            .filter_map(|s| match s.step.as_str() {
+                "vector_search" => Some("vector"),
+                "rerank" => Some("rerank"),
+                "graph_search" => Some("graph"),
+                "agentic_query" => Some("agentic"),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 pub struct RagService {
@@ -59,6 +362,16 @@ pub struct RagService {
     graph_url: String,
     agentic_url: String,
     client: Client,
+    timeouts: PipelineTimeouts,
+    /// Origins (`scheme://host[:port]`) that request overrides may target
+    allowed_endpoints: Vec<String>,
+}
+
+/// Embedding and graph base URLs used for one query
+#[derive(Debug, Clone, PartialEq)]
+struct Endpoints {
+    embedding_url: String,
+    graph_url: String,
 }
 
 impl RagService {
@@ -68,26 +381,98 @@ impl RagService {
             graph_url,
             agentic_url,
             client: Client::new(),
+            timeouts: PipelineTimeouts::default(),
+            allowed_endpoints: Vec::new(),
+        }
+    }
+
+    pub fn with_timeouts(mut self, timeouts: PipelineTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Allow request overrides to target these base URLs. Only the origin of
+    /// each entry is compared, so paths are ignored; invalid entries are skipped.
+    pub fn with_endpoint_allow_list<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.allowed_endpoints = urls
+            .into_iter()
+            .filter_map(|url| match validate_service_url(url.as_ref()) {
+                Ok(parsed) => Some(parsed.origin().ascii_serialization()),
+                Err(e) => {
+                    log::warn!("Ignoring endpoint allow-list entry: {}", e);
+                    None
+                }
+            })
+            .collect();
+        self
+    }
+
+    /// Endpoints for `request`: the global ones, replaced by any allowed override
+    fn resolve_endpoints(&self, request: &RagQueryRequest) -> Result<Endpoints> {
+        let overrides = request.endpoints.clone().unwrap_or_default();
+        Ok(Endpoints {
+            embedding_url: self.allowed_override("embedding", overrides.embedding_url)?
+                .unwrap_or_else(|| self.embedding_url.clone()),
+            graph_url: self.allowed_override("graph", overrides.graph_url)?
+                .unwrap_or_else(|| self.graph_url.clone()),
+        })
+    }
+
+    fn allowed_override(&self, service: &str, url: Option<String>) -> Result<Option<String>> {
+        let Some(url) = url else { return Ok(None) };
+        let parsed = validate_service_url(&url).map_err(|e| anyhow::anyhow!("Invalid {} override: {}", service, e))?;
+        if !self.allowed_endpoints.contains(&parsed.origin().ascii_serialization()) {
+            anyhow::bail!("{} override '{}' is not on the endpoint allow-list", service, url);
         }
+        Ok(Some(url.trim().trim_end_matches('/').to_string()))
     }
 
     pub async fn query(&self, request: RagQueryRequest) -> Result<RagQueryResponse> {
         let start = std::time::Instant::now();
         
         // Determine mode
-        let mode = request.mode.clone().unwrap_or(RagMode::Auto);
+        let analysis = analyze_query(&request.query);
+        let mode = request.mode.unwrap_or(RagMode::Auto);
         let actual_mode = match mode {
-            RagMode::Auto => self.classify_query(&request.query),
+            RagMode::Auto => analysis.mode,
             other => other,
         };
 
-        let (answer, sources) = match actual_mode {
-            RagMode::Vector => self.vector_rag(&request).await?,
-            RagMode::Hybrid => self.hybrid_rag(&request).await?,
-            RagMode::Agentic => self.agentic_rag(&request).await?,
+        let endpoints = self.resolve_endpoints(&request)?;
+        let mut pipeline = Pipeline::new(self.timeouts);
+        let result = match actual_mode {
+            RagMode::Vector => self.vector_rag(&request, &endpoints, &mut pipeline).await,
+            RagMode::Hybrid => self.hybrid_rag(&request, &endpoints, analysis.intent.hybrid_weights(), &mut pipeline).await,
+            RagMode::Agentic => self.agentic_rag(&request, &mut pipeline).await,
+            RagMode::Rerank => self.rerank_rag(&request, &endpoints, &mut pipeline).await,
             RagMode::Auto => unreachable!(),
         };
 
+        let (answer, sources) = match result {
+            Ok(answered) => answered,
+            Err(e) => match pipeline.timed_out_step() {
+                Some(step) => {
+                    log::warn!("RAG query returning partial results, step '{}' timed out", step);
+                    let partial = std::mem::take(&mut pipeline.partial);
+                    (self.generate_answer_from_sources(&request.query, &partial), partial)
+                }
+                None => return Err(e),
+            },
+        };
+
+        // Only the confident results inform the answer when a threshold is set
+        let (answer, sources) = match request.min_confidence {
+            Some(threshold) if sources.iter().any(|s| s.confidence < threshold) => {
+                let kept = apply_min_confidence(sources, Some(threshold));
+                (self.generate_answer_from_sources(&request.query, &kept), kept)
+            }
+            _ => (answer, sources),
+        };
+
         let query_time_ms = start.elapsed().as_millis() as u64;
         let confidence = self.calculate_confidence(&sources);
 
@@ -100,78 +485,151 @@ impl RagService {
             metadata: serde_json::json!({
                 "query": request.query,
                 "tenant_id": request.tenant_id,
+                "steps": pipeline.steps(),
+                "timed_out_step": pipeline.timed_out_step(),
+                "contributing_sources": pipeline.contributing_sources(),
             }),
         })
     }
 
-    fn classify_query(&self, query: &str) -> RagMode {
-        let query_lower = query.to_lowercase();
+    async fn vector_rag(
+        &self,
+        request: &RagQueryRequest,
+        endpoints: &Endpoints,
+        pipeline: &mut Pipeline,
+    ) -> Result<(String, Vec<Source>)> {
+        log::info!("Executing Vector RAG for query: {}", request.query);
         
-        // Ownership/structure questions → Hybrid (graph + vector)
-        if query_lower.contains("who owns") 
-            || query_lower.contains("who wrote")
-            || query_lower.contains("who maintains")
-            || query_lower.contains("related to")
-            || query_lower.contains("connected to") {
-            return RagMode::Hybrid;
-        }
+        let sources = self.vector_search(request, endpoints, request.top_k.unwrap_or(10), pipeline).await?;
         
-        // Multi-step/investigation questions → Agentic
-        if query_lower.contains("trace")
-            || query_lower.contains("investigate")
-            || query_lower.contains("how did")
-            || query_lower.contains("timeline") {
-            return RagMode::Agentic;
-        }
+        // Generate answer from sources
+        let answer = self.generate_answer_from_sources(&request.query, &sources);
         
-        // Default to vector for content questions
-        RagMode::Vector
+        Ok((answer, sources))
     }
 
-    async fn vector_rag(&self, request: &RagQueryRequest) -> Result<(String, Vec<Source>)> {
-        log::info!("Executing Vector RAG for query: {}", request.query);
-        
+    /// Run the `vector_search` step for `top_k` results
+    async fn vector_search(
+        &self,
+        request: &RagQueryRequest,
+        endpoints: &Endpoints,
+        top_k: usize,
+        pipeline: &mut Pipeline,
+    ) -> Result<Vec<Source>> {
         // Call embedding service for vector search
         let search_req = serde_json::json!({
             "query_text": request.query,
             "tenant_id": request.tenant_id,
-            "top_k": request.top_k.unwrap_or(10),
+            "top_k": top_k,
+            "min_score": request.min_score,
             "filters": request.filters,
         });
 
-        let response = self.client
-            .post(format!("{}/vector/search", self.embedding_url))
-            .json(&search_req)
-            .send()
-            .await
-            .context("Failed to call embedding service")?;
+        let search_results: serde_json::Value = pipeline.step("vector_search", with_retries("embedding", || async {
+            let response = self.client
+                .post(format!("{}/vector/search", endpoints.embedding_url))
+                .json(&search_req)
+                .send()
+                .await
+                .context("Failed to call embedding service")?
+                .error_for_status()
+                .context("Embedding service returned an error")?;
 
-        let search_results: serde_json::Value = response.json().await?;
-        
-        // Convert to sources
-        let sources = self.parse_vector_results(&search_results);
+            Ok(response.json().await?)
+        })).await?;
         
-        // Generate answer from sources
+        // Convert to sources, collapsing the same document synced from several connectors
+        Ok(dedup_sources(apply_min_score(
+            self.parse_vector_results(&search_results),
+            request.min_score,
+        )))
+    }
+
+    /// Embed, search and rerank in one pass: retrieve a wider candidate set, have
+    /// the embedding service's reranker score it against the query, and keep the
+    /// best `top_k`. If the reranker fails the vector ranking is returned instead.
+    async fn rerank_rag(
+        &self,
+        request: &RagQueryRequest,
+        endpoints: &Endpoints,
+        pipeline: &mut Pipeline,
+    ) -> Result<(String, Vec<Source>)> {
+        log::info!("Executing Vector RAG with reranking for query: {}", request.query);
+
+        let top_k = request.top_k.unwrap_or(10);
+        let candidate_count = top_k.saturating_mul(RERANK_CANDIDATE_FACTOR).min(MAX_RERANK_CANDIDATES).max(top_k);
+        let candidates = self.vector_search(request, endpoints, candidate_count, pipeline).await?;
+        pipeline.record_partial(&candidates[..candidates.len().min(top_k)]);
+
+        let sources = match pipeline.step("rerank", self.rerank(&endpoints.embedding_url, &request.query, &candidates, top_k)).await {
+            Ok(reranked) => apply_rerank(candidates, &reranked, top_k),
+            Err(e) if pipeline.timed_out_step().is_some() => return Err(e),
+            Err(e) => {
+                log::warn!("Reranking failed, returning vector ranking: {}", e);
+                candidates.into_iter().take(top_k).collect()
+            }
+        };
+
         let answer = self.generate_answer_from_sources(&request.query, &sources);
-        
         Ok((answer, sources))
     }
 
-    async fn hybrid_rag(&self, request: &RagQueryRequest) -> Result<(String, Vec<Source>)> {
+    /// POST the candidates to `{embedding_url}/rerank`, identified by their position
+    async fn rerank(&self, embedding_url: &str, query: &str, candidates: &[Source], top_k: usize) -> Result<serde_json::Value> {
+        let documents: Vec<serde_json::Value> = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, source)| serde_json::json!({
+                "id": i.to_string(),
+                "text": source.content,
+                "metadata": source.metadata,
+            }))
+            .collect();
+        let rerank_req = serde_json::json!({
+            "query": query,
+            "documents": documents,
+            "top_k": top_k,
+        });
+
+        with_retries("embedding", || async {
+            let response = self.client
+                .post(format!("{}/rerank", embedding_url))
+                .json(&rerank_req)
+                .send()
+                .await
+                .context("Failed to call rerank endpoint")?
+                .error_for_status()
+                .context("Rerank endpoint returned an error")?;
+
+            Ok(response.json().await?)
+        }).await
+    }
+
+    async fn hybrid_rag(
+        &self,
+        request: &RagQueryRequest,
+        endpoints: &Endpoints,
+        weights: RetrievalWeights,
+        pipeline: &mut Pipeline,
+    ) -> Result<(String, Vec<Source>)> {
         log::info!("Executing Hybrid RAG (Graph + Vector) for query: {}", request.query);
         
         // Step 1: Graph search for entities
-        let graph_results = self.graph_search(&request.query, &request.tenant_id).await?;
+        let graph_results = pipeline
+            .step("graph_search", self.graph_search(&endpoints.graph_url, &request.query, &request.tenant_id))
+            .await;
+        if let Ok(graph_sources) = &graph_results {
+            pipeline.record_partial(graph_sources);
+        }
         
         // Step 2: Vector search with entity context
-        let vector_results = self.vector_rag(request).await?;
+        let vector_results = self.vector_rag(request, endpoints, pipeline).await.map(|(_, sources)| sources);
         
-        // Step 3: Fuse results
-        let mut all_sources = graph_results.clone();
-        all_sources.extend(vector_results.1);
+        // Step 3: Fuse whichever results came back
+        let all_sources = fuse_hybrid(graph_results, vector_results)?;
         
         // Rerank based on graph proximity + vector similarity
-        let reranked_sources = self.rerank_sources(all_sources);
+        let reranked_sources = self.rerank_sources(all_sources, weights);
         
         // Generate answer
         let answer = self.generate_answer_from_sources(&request.query, &reranked_sources);
@@ -179,9 +637,17 @@ impl RagService {
         Ok((answer, reranked_sources))
     }
 
-    async fn agentic_rag(&self, request: &RagQueryRequest) -> Result<(String, Vec<Source>)> {
+    async fn agentic_rag(&self, request: &RagQueryRequest, pipeline: &mut Pipeline) -> Result<(String, Vec<Source>)> {
         log::info!("Executing Agentic RAG for query: {}", request.query);
         
+        let agentic_response = pipeline
+            .step("agentic_query", self.call_agentic_service(request))
+            .await?;
+        
+        Ok(Self::parse_agentic_response(&agentic_response))
+    }
+
+    async fn call_agentic_service(&self, request: &RagQueryRequest) -> Result<serde_json::Value> {
         // Call agentic service for multi-step orchestration
         let agentic_req = serde_json::json!({
             "query": request.query,
@@ -196,8 +662,207 @@ impl RagService {
             .await
             .context("Failed to call agentic service")?;
 
-        let agentic_response: serde_json::Value = response.json().await?;
-        
+        Ok(response.json().await?)
+    }
+
+    /// Ask the agentic service to stream its progress as server-sent events
+    async fn open_agentic_stream(&self, request: &RagQueryRequest) -> Result<AgenticReply> {
+        let agentic_req = serde_json::json!({
+            "query": request.query,
+            "tenant_id": request.tenant_id,
+            "max_steps": 5,
+            "stream": true,
+        });
+
+        let response = self.client
+            .post(format!("{}/api/agentic/query", self.agentic_url))
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&agentic_req)
+            .send()
+            .await
+            .context("Failed to call agentic service")?
+            .error_for_status()
+            .context("Agentic service rejected the query")?;
+
+        let streaming = response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if streaming {
+            Ok(AgenticReply::Streaming(response))
+        } else {
+            Ok(AgenticReply::Complete(response.json().await?))
+        }
+    }
+
+    /// Streaming variant of the agentic query. Retrieval, tool steps and answer tokens
+    /// are forwarded as the agentic service produces them; the receiver always ends
+    /// with a `final` or `error` event.
+    pub fn agentic_stream(self: Arc<Self>, request: RagQueryRequest) -> mpsc::Receiver<AgenticEvent> {
+        let (tx, rx) = mpsc::channel(32);
+
+        tokio::spawn(async move {
+            let start = Instant::now();
+            let mut pipeline = Pipeline::new(self.timeouts);
+            log::info!("Executing streaming Agentic RAG for query: {}", request.query);
+
+            let result = pipeline
+                .step("agentic_query", self.open_agentic_stream(&request))
+                .await;
+
+            match result {
+                Ok(AgenticReply::Streaming(response)) => {
+                    self.forward_agentic_stream(&tx, response.bytes_stream(), start).await
+                }
+                Ok(AgenticReply::Complete(agentic_response)) => {
+                    self.emit_agentic_events(&tx, &agentic_response, start).await
+                }
+                Err(e) => {
+                    log::error!("Streaming agentic RAG query failed: {}", e);
+                    let _ = tx.send(AgenticEvent::Error { message: e.to_string() }).await;
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// Forward the agentic service's SSE body as progress events while it arrives.
+    /// Each chunk must arrive within the step timeout and the stream must finish
+    /// within the overall deadline. Stops early if the client has gone away.
+    async fn forward_agentic_stream<S, B, E>(&self, tx: &mpsc::Sender<AgenticEvent>, mut body: S, start: Instant)
+    where
+        S: futures::Stream<Item = std::result::Result<B, E>> + Unpin,
+        B: AsRef<[u8]>,
+        E: std::fmt::Display,
+    {
+        use futures::StreamExt;
+
+        let deadline = tokio::time::Instant::from_std(start + self.timeouts.overall);
+        let mut state = AgenticStreamState::default();
+
+        loop {
+            let idle = tokio::time::Instant::now() + self.timeouts.step;
+            let chunk = match tokio::time::timeout_at(idle.min(deadline), body.next()).await {
+                Ok(Some(Ok(chunk))) => chunk,
+                Ok(Some(Err(e))) => {
+                    log::error!("Agentic stream failed: {}", e);
+                    let _ = tx.send(AgenticEvent::Error { message: format!("Agentic stream failed: {}", e) }).await;
+                    return;
+                }
+                Ok(None) => {
+                    let message = "Agentic service closed the stream before its final event".to_string();
+                    log::warn!("{}", message);
+                    let _ = tx.send(AgenticEvent::Error { message }).await;
+                    return;
+                }
+                Err(_) => {
+                    let message = format!("Agentic stream timed out after {}ms", start.elapsed().as_millis());
+                    log::warn!("{}", message);
+                    let _ = tx.send(AgenticEvent::Error { message }).await;
+                    return;
+                }
+            };
+
+            for (event, data) in state.push(chunk.as_ref()) {
+                let Some(event) = self.agentic_event(&mut state, &event, &data, start) else {
+                    continue;
+                };
+                let terminal = event.is_terminal();
+                if tx.send(event).await.is_err() || terminal {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Map one upstream SSE frame to a progress event; unknown events are skipped
+    fn agentic_event(
+        &self,
+        state: &mut AgenticStreamState,
+        event: &str,
+        data: &serde_json::Value,
+        start: Instant,
+    ) -> Option<AgenticEvent> {
+        match event {
+            "retrieval_done" | "sources" => {
+                let sources = Self::parse_agentic_sources(data.get("sources").unwrap_or(data));
+                state.sources = sources.clone();
+                Some(AgenticEvent::RetrievalDone { sources })
+            }
+            "reasoning" | "step" | "tool_call" | "tool_result" => {
+                state.steps += 1;
+                Some(AgenticEvent::Reasoning { step: state.steps, thought: step_thought(data) })
+            }
+            "token" => {
+                let text = data.get("text").and_then(|t| t.as_str()).or(data.as_str())?.to_string();
+                state.answer.push_str(&text);
+                Some(AgenticEvent::Token { text })
+            }
+            "final" => {
+                if let Some(sources) = data.get("sources") {
+                    state.sources = Self::parse_agentic_sources(sources);
+                }
+                let answer = data.get("answer")
+                    .and_then(|a| a.as_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| std::mem::take(&mut state.answer));
+                Some(AgenticEvent::Final {
+                    answer,
+                    confidence: self.calculate_confidence(&state.sources),
+                    query_time_ms: start.elapsed().as_millis() as u64,
+                })
+            }
+            "error" => Some(AgenticEvent::Error {
+                message: data.get("message")
+                    .and_then(|m| m.as_str())
+                    .or(data.as_str())
+                    .unwrap_or("Agentic service failed")
+                    .to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Replay a finished agentic service response as progress events. Stops early if
+    /// the client has gone away.
+    async fn emit_agentic_events(
+        &self,
+        tx: &mpsc::Sender<AgenticEvent>,
+        agentic_response: &serde_json::Value,
+        start: Instant,
+    ) {
+        let (answer, sources) = Self::parse_agentic_response(agentic_response);
+        let confidence = self.calculate_confidence(&sources);
+
+        if tx.send(AgenticEvent::RetrievalDone { sources }).await.is_err() {
+            return;
+        }
+
+        let steps = agentic_response.get("steps")
+            .and_then(|s| s.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for (i, step) in steps.iter().enumerate() {
+            if tx.send(AgenticEvent::Reasoning { step: i + 1, thought: step_thought(step) }).await.is_err() {
+                return;
+            }
+        }
+
+        for token in answer.split_inclusive(' ') {
+            if tx.send(AgenticEvent::Token { text: token.to_string() }).await.is_err() {
+                return;
+            }
+        }
+
+        let _ = tx.send(AgenticEvent::Final {
+            answer,
+            confidence,
+            query_time_ms: start.elapsed().as_millis() as u64,
+        }).await;
+    }
+
+    fn parse_agentic_response(agentic_response: &serde_json::Value) -> (String, Vec<Source>) {
         // Extract answer and sources from agentic response
         let answer = agentic_response.get("answer")
             .and_then(|a| a.as_str())
@@ -205,38 +870,54 @@ impl RagService {
             .to_string();
         
         let sources = agentic_response.get("sources")
-            .and_then(|s| s.as_array())
+            .map(Self::parse_agentic_sources)
+            .unwrap_or_default();
+        
+        (answer, sources)
+    }
+
+    fn parse_agentic_sources(sources: &serde_json::Value) -> Vec<Source> {
+        sources.as_array()
             .map(|arr| {
                 arr.iter().filter_map(|s| {
                     Some(Source {
                         source_type: s.get("source_type")?.as_str()?.to_string(),
                         content: s.get("content")?.as_str()?.to_string(),
                         score: s.get("score")?.as_f64()? as f32,
+                        raw_score: s.get("score")?.as_f64()? as f32,
+                        confidence: s.get("confidence")
+                            .or_else(|| s.get("score"))
+                            .and_then(|c| c.as_f64())
+                            .unwrap_or(0.0)
+                            .clamp(0.0, 1.0) as f32,
                         metadata: s.get("metadata")?.clone(),
                         citation: None,
+                        provenance: Vec::new(),
                     })
                 }).collect()
             })
-            .unwrap_or_default();
-        
-        Ok((answer, sources))
+            .unwrap_or_default()
     }
 
-    async fn graph_search(&self, query: &str, tenant_id: &str) -> Result<Vec<Source>> {
+    async fn graph_search(&self, graph_url: &str, query: &str, tenant_id: &str) -> Result<Vec<Source>> {
         // Call graph service for entity/relationship search
         let search_req = serde_json::json!({
             "query": query,
             "tenant_id": tenant_id,
         });
 
-        let response = self.client
-            .post(format!("{}/api/graph/query", self.graph_url))
-            .json(&search_req)
-            .send()
-            .await
-            .context("Failed to call graph service")?;
+        let graph_results: serde_json::Value = with_retries("graph", || async {
+            let response = self.client
+                .post(format!("{}/api/graph/query", graph_url))
+                .json(&search_req)
+                .send()
+                .await
+                .context("Failed to call graph service")?
+                .error_for_status()
+                .context("Graph service returned an error")?;
 
-        let graph_results: serde_json::Value = response.json().await?;
+            Ok(response.json().await?)
+        }).await?;
         
         Ok(self.parse_graph_results(&graph_results))
     }
@@ -250,8 +931,11 @@ impl RagService {
                     source_type: "vector".to_string(),
                     content: r.get("content")?.as_str()?.to_string(),
                     score: r.get("score")?.as_f64()? as f32,
+                    raw_score: r.get("score")?.as_f64()? as f32,
+                    confidence: (r.get("score")?.as_f64()? as f32).clamp(0.0, 1.0),
                     metadata: r.get("metadata")?.clone(),
                     citation: r.get("source").and_then(|s| s.as_str()).map(String::from),
+                    provenance: Vec::new(),
                 })
             }).collect()
         }).unwrap_or_default()
@@ -266,22 +950,27 @@ impl RagService {
                     source_type: "graph".to_string(),
                     content: e.get("name")?.as_str()?.to_string(),
                     score: e.get("relevance_score")?.as_f64().unwrap_or(0.5) as f32,
+                    raw_score: e.get("relevance_score")?.as_f64().unwrap_or(0.5) as f32,
+                    confidence: (e.get("relevance_score")?.as_f64().unwrap_or(0.5) as f32).clamp(0.0, 1.0),
                     metadata: e.clone(),
                     citation: e.get("source_id").and_then(|s| s.as_str()).map(String::from),
+                    provenance: Vec::new(),
                 })
             }).collect()
         }).unwrap_or_default()
     }
 
-    fn rerank_sources(&self, mut sources: Vec<Source>) -> Vec<Source> {
-        // Simple reranking: boost graph results slightly, then sort by score
+    fn rerank_sources(&self, mut sources: Vec<Source>, weights: RetrievalWeights) -> Vec<Source> {
+        // Simple reranking: weight each source's results, then sort by score
         for source in &mut sources {
-            if source.source_type == "graph" {
-                source.score *= 1.1; // 10% boost for graph results
+            match source.source_type.as_str() {
+                "graph" => source.score *= weights.graph,
+                "vector" => source.score *= weights.vector,
+                _ => {}
             }
         }
         
-        sources.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        sources.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
         sources.truncate(20); // Top 20 results
         sources
     }
@@ -313,3 +1002,924 @@ impl RagService {
         avg_score.min(1.0)
     }
 }
+
+/// Drop results whose similarity (as reported upstream) is below `min_score`.
+/// Applied before normalization, which would otherwise stretch poor matches up to 1.0.
+fn apply_min_score(sources: Vec<Source>, min_score: Option<f32>) -> Vec<Source> {
+    match min_score {
+        Some(threshold) => sources.into_iter().filter(|s| s.raw_score >= threshold).collect(),
+        None => sources,
+    }
+}
+
+/// Reorder `candidates` by the reranker's `{"results": [{"id", "score"}]}`, where
+/// ids are candidate positions. The rerank score replaces `score`; `raw_score`
+/// keeps the vector similarity. Candidates the reranker didn't return are dropped.
+fn apply_rerank(candidates: Vec<Source>, reranked: &serde_json::Value, top_k: usize) -> Vec<Source> {
+    let mut candidates: Vec<Option<Source>> = candidates.into_iter().map(Some).collect();
+    let mut results: Vec<(usize, f32)> = reranked
+        .get("results")
+        .and_then(|r| r.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|r| Some((r.get("id")?.as_str()?.parse().ok()?, r.get("score")?.as_f64()? as f32)))
+                .collect()
+        })
+        .unwrap_or_default();
+    results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    results
+        .into_iter()
+        .filter_map(|(i, score)| {
+            let mut source = candidates.get_mut(i)?.take()?;
+            source.score = score;
+            Some(source)
+        })
+        .take(top_k)
+        .collect()
+}
+
+/// Drop results whose fused confidence is below `min_confidence`
+fn apply_min_confidence(sources: Vec<Source>, min_confidence: Option<f32>) -> Vec<Source> {
+    match min_confidence {
+        Some(threshold) => sources.into_iter().filter(|s| s.confidence >= threshold).collect(),
+        None => sources,
+    }
+}
+
+/// Retry a downstream call with exponential backoff (100ms, 200ms, ...)
+async fn with_retries<T, F, Fut>(service: &str, mut call: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match call().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < DOWNSTREAM_RETRIES => {
+                log::warn!("{} service call failed (attempt {}): {}", service, attempt + 1, e);
+                let delay_ms = 100u64.saturating_mul(1u64 << attempt);
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Phrases that mark an ownership/structure question, answered from the graph
+const OWNERSHIP_PHRASES: &[&str] = &["who owns", "who wrote", "who maintains", "related to", "connected to"];
+/// Phrases that mark a multi-step investigation, handed to the agentic service
+const INVESTIGATION_PHRASES: &[&str] = &["trace", "investigate", "how did", "timeline"];
+/// Extensions that make a token a file path rather than prose
+const CODE_FILE_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "py", "go", "java", "kt", "rb", "c", "h", "cpp", "hpp", "cs", "swift", "php",
+    "sql", "toml", "yaml", "yml", "json", "md", "sh",
+];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryIntent {
+    Ownership,
+    Investigation,
+    /// Names a symbol or file
+    Code,
+    Content,
+}
+
+impl QueryIntent {
+    /// Weights applied to each source's normalized scores when hybrid results are fused
+    pub fn hybrid_weights(self) -> RetrievalWeights {
+        match self {
+            // The graph holds symbols and files, so an exact entity hit should beat a
+            // semantically close chunk
+            QueryIntent::Code => RetrievalWeights { vector: 1.0, graph: 1.3 },
+            _ => RetrievalWeights { vector: 1.0, graph: 1.1 },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct RetrievalWeights {
+    pub vector: f32,
+    pub graph: f32,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    /// camelCase, snake_case, `path::to::item` or `call()`
+    Identifier,
+    FilePath,
+    /// `owner/repo`
+    Repository,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct DetectedEntity {
+    pub kind: EntityKind,
+    pub text: String,
+}
+
+/// How `Auto` mode would handle a query, without running it
+#[derive(Debug, Serialize, Clone)]
+pub struct QueryAnalysis {
+    pub query: String,
+    pub intent: QueryIntent,
+    pub entities: Vec<DetectedEntity>,
+    pub mode: RagMode,
+    /// Fusion weights; only set for hybrid retrieval
+    pub weights: Option<RetrievalWeights>,
+    /// What decided the intent
+    pub reason: String,
+}
+
+/// Classify a query and pick the retrieval strategy `Auto` mode uses for it
+pub fn analyze_query(query: &str) -> QueryAnalysis {
+    let query_lower = query.to_lowercase();
+    let entities = detect_entities(query);
+
+    let (intent, reason) = if let Some(phrase) = OWNERSHIP_PHRASES.iter().find(|p| query_lower.contains(*p)) {
+        (QueryIntent::Ownership, format!("ownership phrase '{}'", phrase))
+    } else if let Some(phrase) = INVESTIGATION_PHRASES.iter().find(|p| query_lower.contains(*p)) {
+        (QueryIntent::Investigation, format!("investigation phrase '{}'", phrase))
+    } else if let Some(entity) = entities.iter().find(|e| e.kind != EntityKind::Repository) {
+        (QueryIntent::Code, format!("code entity '{}'", entity.text))
+    } else {
+        (QueryIntent::Content, "no ownership, investigation or code markers".to_string())
+    };
+
+    // Ownership and code questions go to the graph as well; content questions to vectors alone
+    let mode = match intent {
+        QueryIntent::Ownership | QueryIntent::Code => RagMode::Hybrid,
+        QueryIntent::Investigation => RagMode::Agentic,
+        QueryIntent::Content => RagMode::Vector,
+    };
+
+    QueryAnalysis {
+        query: query.to_string(),
+        intent,
+        entities,
+        mode,
+        weights: (mode == RagMode::Hybrid).then(|| intent.hybrid_weights()),
+        reason,
+    }
+}
+
+/// Identifiers, file paths and repositories mentioned in a query, in order
+fn detect_entities(query: &str) -> Vec<DetectedEntity> {
+    let mut entities: Vec<DetectedEntity> = Vec::new();
+    for word in query.split_whitespace() {
+        let token = word.trim_matches(|c: char| matches!(c, '`' | '"' | '\'' | ',' | '?' | '!' | ';' | '.' | '(' | ')'));
+        let is_call = word.trim_end_matches(['`', ',', '?', '!', '.']).ends_with("()");
+        if token.is_empty() || token.contains("://") {
+            continue;
+        }
+
+        let kind = if token.contains('/') {
+            let segments: Vec<&str> = token.split('/').filter(|s| !s.is_empty()).collect();
+            if segments.len() == 2 && !token.starts_with('/') && segments.iter().all(|s| !s.contains('.')) {
+                Some(EntityKind::Repository)
+            } else {
+                Some(EntityKind::FilePath)
+            }
+        } else if token
+            .rsplit_once('.')
+            .is_some_and(|(stem, ext)| !stem.is_empty() && CODE_FILE_EXTENSIONS.contains(&ext))
+        {
+            Some(EntityKind::FilePath)
+        } else if is_identifier(token) || (is_call && token.chars().all(|c| c.is_alphanumeric() || c == '_')) {
+            Some(EntityKind::Identifier)
+        } else {
+            None
+        };
+
+        if let Some(kind) = kind {
+            let entity = DetectedEntity { kind, text: token.to_string() };
+            if !entities.contains(&entity) {
+                entities.push(entity);
+            }
+        }
+    }
+    entities
+}
+
+/// camelCase, snake_case or `::`-qualified names; plain words don't count
+fn is_identifier(token: &str) -> bool {
+    if !token.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':') || !token.chars().any(char::is_alphabetic) {
+        return false;
+    }
+    let chars: Vec<char> = token.chars().collect();
+    token.contains("::")
+        || token.trim_matches('_').contains('_')
+        || chars.windows(2).any(|w| w[0].is_lowercase() && w[1].is_uppercase())
+}
+
+/// Combine graph and vector results for hybrid retrieval. If one side failed the
+/// other is used on its own; only when both fail is the query an error.
+fn fuse_hybrid(graph: Result<Vec<Source>>, vector: Result<Vec<Source>>) -> Result<Vec<Source>> {
+    let all_sources = match (graph, vector) {
+        (Ok(graph_sources), Ok(vector_sources)) => {
+            // Bring each system's scores onto the same scale before merging
+            let mut all = normalize_scores(graph_sources);
+            all.extend(normalize_scores(vector_sources));
+            all
+        }
+        (Ok(graph_sources), Err(e)) => {
+            log::warn!("Vector search unavailable, using graph results only: {}", e);
+            normalize_scores(graph_sources)
+        }
+        (Err(e), Ok(vector_sources)) => {
+            log::warn!("Graph search unavailable, using vector results only: {}", e);
+            normalize_scores(vector_sources)
+        }
+        (Err(graph_err), Err(vector_err)) => {
+            return Err(vector_err.context(format!("graph search also failed: {}", graph_err)));
+        }
+    };
+
+    Ok(dedup_sources(all_sources))
+}
+
+/// Min-max normalize scores within a single source so results from systems with
+/// different score ranges (cosine similarity vs. graph relevance) can be fused.
+/// The original value is preserved in `raw_score`.
+fn normalize_scores(mut sources: Vec<Source>) -> Vec<Source> {
+    let (min, max) = sources.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), s| {
+        (min.min(s.raw_score), max.max(s.raw_score))
+    });
+    let range = max - min;
+
+    for source in &mut sources {
+        source.score = if range > f32::EPSILON {
+            (source.raw_score - min) / range
+        } else {
+            // A single result (or all-equal scores) carries no relative signal
+            1.0
+        };
+    }
+
+    sources
+}
+
+/// Stable identity for a result: an upstream content hash when the indexer provides one,
+/// otherwise a hash of the whitespace-normalized content.
+fn dedup_key(source: &Source) -> String {
+    if let Some(hash) = source.metadata.get("content_hash").and_then(|h| h.as_str()) {
+        return hash.to_string();
+    }
+
+    let normalized: Vec<&str> = source.content.split_whitespace().collect();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    normalized.join(" ").hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+/// Collapse results that refer to the same content (e.g. a file synced from both a
+/// repository and Google Drive), keeping the highest-scoring copy and recording every
+/// place the content was found in `provenance`. Confidence starts at each copy's
+/// (normalized) score and is fused across copies as independent evidence:
+/// `1 - (1 - a)(1 - b)`, so agreement raises it but never past 1.
+fn dedup_sources(sources: Vec<Source>) -> Vec<Source> {
+    let mut merged: Vec<Source> = Vec::with_capacity(sources.len());
+    let mut index_by_key: HashMap<String, usize> = HashMap::new();
+
+    for mut source in sources {
+        let origin = source.citation.clone().unwrap_or_else(|| source.source_type.clone());
+        if source.provenance.is_empty() {
+            source.provenance.push(origin);
+        }
+        source.confidence = source.score.clamp(0.0, 1.0);
+
+        let key = dedup_key(&source);
+        match index_by_key.get(&key) {
+            Some(&idx) => {
+                let existing = &mut merged[idx];
+                let mut provenance = std::mem::take(&mut existing.provenance);
+                for p in source.provenance.drain(..) {
+                    if !provenance.contains(&p) {
+                        provenance.push(p);
+                    }
+                }
+                let confidence = 1.0 - (1.0 - existing.confidence) * (1.0 - source.confidence);
+                if source.score > existing.score {
+                    *existing = source;
+                }
+                existing.provenance = provenance;
+                existing.confidence = confidence;
+            }
+            None => {
+                index_by_key.insert(key, merged.len());
+                merged.push(source);
+            }
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn source(source_type: &str, content: &str, score: f32) -> Source {
+        Source {
+            source_type: source_type.to_string(),
+            content: content.to_string(),
+            score,
+            raw_score: score,
+            confidence: score,
+            metadata: serde_json::json!({}),
+            citation: None,
+            provenance: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_scores_maps_to_unit_range() {
+        let normalized = normalize_scores(vec![
+            source("graph", "a", 10.0),
+            source("graph", "b", 30.0),
+            source("graph", "c", 50.0),
+        ]);
+
+        let scores: Vec<f32> = normalized.iter().map(|s| s.score).collect();
+        assert_eq!(scores, vec![0.0, 0.5, 1.0]);
+        assert_eq!(normalized[2].raw_score, 50.0);
+    }
+
+    #[test]
+    fn test_normalization_makes_cross_source_ranking_comparable() {
+        let service = RagService::new(String::new(), String::new(), String::new());
+
+        // Graph relevance is unbounded while vector similarity sits in a narrow band;
+        // without normalization every graph hit would outrank every vector hit.
+        let graph = vec![source("graph", "graph-top", 40.0), source("graph", "graph-low", 4.0)];
+        let vector = vec![source("vector", "vector-top", 0.92), source("vector", "vector-low", 0.81)];
+
+        let mut fused = normalize_scores(graph);
+        fused.extend(normalize_scores(vector));
+        let ranked = service.rerank_sources(fused, QueryIntent::Content.hybrid_weights());
+
+        let order: Vec<&str> = ranked.iter().map(|s| s.content.as_str()).collect();
+        assert_eq!(order, vec!["graph-top", "vector-top", "graph-low", "vector-low"]);
+        assert!(ranked.iter().all(|s| s.score <= 1.1));
+    }
+
+    #[test]
+    fn test_single_result_normalizes_to_one() {
+        let normalized = normalize_scores(vec![source("vector", "only", 0.3)]);
+        assert_eq!(normalized[0].score, 1.0);
+        assert_eq!(normalized[0].raw_score, 0.3);
+    }
+
+    #[test]
+    fn test_same_content_from_two_sources_is_merged() {
+        let mut from_repo = source("vector", "fn main() {\n    run();\n}", 0.72);
+        from_repo.citation = Some("github:conhub/app/src/main.rs".to_string());
+        let mut from_drive = source("vector", "fn main() {   run(); }", 0.88);
+        from_drive.citation = Some("google_drive:main.rs".to_string());
+        let other = source("vector", "unrelated content", 0.5);
+
+        let merged = dedup_sources(vec![from_repo, from_drive, other]);
+
+        assert_eq!(merged.len(), 2);
+        let main = &merged[0];
+        assert_eq!(main.score, 0.88);
+        assert_eq!(main.citation.as_deref(), Some("google_drive:main.rs"));
+        assert_eq!(
+            main.provenance,
+            vec!["github:conhub/app/src/main.rs".to_string(), "google_drive:main.rs".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_dedup_prefers_upstream_content_hash() {
+        let mut a = source("vector", "version one", 0.4);
+        a.metadata = serde_json::json!({ "content_hash": "abc123" });
+        let mut b = source("graph", "Version One (graph entity)", 0.9);
+        b.metadata = serde_json::json!({ "content_hash": "abc123" });
+
+        let merged = dedup_sources(vec![a, b]);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].source_type, "graph");
+        assert_eq!(merged[0].provenance, vec!["vector".to_string(), "graph".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_slow_step_trips_step_timeout() {
+        let mut pipeline = Pipeline::new(PipelineTimeouts {
+            step: Duration::from_millis(20),
+            overall: Duration::from_secs(5),
+        });
+
+        let fast: Vec<Source> = pipeline
+            .step("graph_search", async { Ok(vec![source("graph", "partial", 1.0)]) })
+            .await
+            .unwrap();
+        pipeline.record_partial(&fast);
+
+        let slow = pipeline
+            .step("vector_search", async {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                Ok(Vec::<Source>::new())
+            })
+            .await;
+
+        assert!(slow.is_err());
+        assert_eq!(pipeline.timed_out_step(), Some("vector_search"));
+        assert_eq!(pipeline.steps()[0].status, StepStatus::Completed);
+        assert_eq!(pipeline.steps()[1].status, StepStatus::TimedOut);
+        assert!(pipeline.steps()[1].latency_ms < 500);
+        assert_eq!(pipeline.partial.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_overall_deadline_caps_later_steps() {
+        let mut pipeline = Pipeline::new(PipelineTimeouts {
+            step: Duration::from_secs(5),
+            overall: Duration::from_millis(50),
+        });
+
+        let result: Result<()> = pipeline
+            .step("agentic_query", async {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                Ok(())
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(pipeline.timed_out_step(), Some("agentic_query"));
+    }
+
+    #[tokio::test]
+    async fn test_agentic_stream_reaches_final_event() {
+        let service = RagService::new(
+            "http://localhost:8082".to_string(),
+            "http://localhost:8006".to_string(),
+            "http://localhost:3005".to_string(),
+        );
+        let agentic_response = serde_json::json!({
+            "answer": "Auth uses JWT tokens",
+            "steps": ["search code", {"thought": "read middleware"}],
+            "sources": [{
+                "source_type": "vector",
+                "content": "fn verify_jwt()",
+                "score": 0.9,
+                "metadata": {}
+            }]
+        });
+
+        let (tx, mut rx) = mpsc::channel(8);
+        tokio::spawn(async move {
+            service.emit_agentic_events(&tx, &agentic_response, Instant::now()).await;
+        });
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            let terminal = event.is_terminal();
+            events.push(event);
+            if terminal {
+                break;
+            }
+        }
+
+        assert!(matches!(&events[0], AgenticEvent::RetrievalDone { sources } if sources.len() == 1));
+        assert!(matches!(&events[2], AgenticEvent::Reasoning { step: 2, thought } if thought == "read middleware"));
+        let streamed: String = events.iter().filter_map(|e| match e {
+            AgenticEvent::Token { text } => Some(text.as_str()),
+            _ => None,
+        }).collect();
+        assert_eq!(streamed, "Auth uses JWT tokens");
+        match events.last() {
+            Some(AgenticEvent::Final { answer, .. }) => assert_eq!(answer, "Auth uses JWT tokens"),
+            other => panic!("expected final event, got {:?}", other),
+        }
+        assert!(events.last().unwrap().to_sse().starts_with("event: final\ndata: {\"event\":\"final\""));
+    }
+
+    #[tokio::test]
+    async fn test_agentic_stream_forwards_upstream_events_as_they_arrive() {
+        let service = RagService::new(
+            "http://localhost:8082".to_string(),
+            "http://localhost:8006".to_string(),
+            "http://localhost:3005".to_string(),
+        );
+        let (upstream, chunks) = mpsc::channel::<std::result::Result<Vec<u8>, String>>(8);
+        let body = Box::pin(futures::stream::unfold(chunks, |mut chunks| async move {
+            chunks.recv().await.map(|chunk| (chunk, chunks))
+        }));
+        let (tx, mut rx) = mpsc::channel(8);
+        tokio::spawn(async move {
+            service.forward_agentic_stream(&tx, body, Instant::now()).await;
+        });
+        let send = |frame: &str| {
+            let upstream = upstream.clone();
+            let frame = frame.as_bytes().to_vec();
+            async move { upstream.send(Ok(frame)).await.unwrap() }
+        };
+
+        // A frame split across chunks is emitted once it is complete
+        send("event: retrieval_done\ndata: {\"sources\": [{\"source_type\": \"vector\", ").await;
+        send("\"content\": \"fn verify_jwt()\", \"score\": 0.8, \"metadata\": {}}]}\n\n").await;
+        assert!(matches!(rx.recv().await, Some(AgenticEvent::RetrievalDone { sources }) if sources.len() == 1));
+
+        // Tool steps and tokens are forwarded before the upstream has finished
+        send(": keep-alive\n\nevent: tool_call\ndata: {\"tool\": \"vector_search\"}\n\n").await;
+        assert!(matches!(rx.recv().await, Some(AgenticEvent::Reasoning { step: 1, thought }) if thought == "calling vector_search"));
+        send("event: token\ndata: {\"text\": \"Auth uses \"}\n\n").await;
+        assert!(matches!(rx.recv().await, Some(AgenticEvent::Token { text }) if text == "Auth uses "));
+        send("event: token\r\ndata: {\"text\": \"JWT\"}\r\n\r\nevent: final\ndata: {}\n\n").await;
+        assert!(matches!(rx.recv().await, Some(AgenticEvent::Token { text }) if text == "JWT"));
+
+        match rx.recv().await {
+            Some(AgenticEvent::Final { answer, confidence, .. }) => {
+                assert_eq!(answer, "Auth uses JWT");
+                assert!((confidence - 0.8).abs() < 1e-6);
+            }
+            other => panic!("expected final event, got {:?}", other),
+        }
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_agentic_stream_reports_truncated_upstream_stream() {
+        let service = RagService::new(
+            "http://localhost:8082".to_string(),
+            "http://localhost:8006".to_string(),
+            "http://localhost:3005".to_string(),
+        );
+        let body = futures::stream::iter(vec![Ok::<_, String>(b"event: token\ndata: {\"text\": \"Au\"}\n\n".to_vec())]);
+        let (tx, mut rx) = mpsc::channel(8);
+        service.forward_agentic_stream(&tx, body, Instant::now()).await;
+
+        assert!(matches!(rx.recv().await, Some(AgenticEvent::Token { .. })));
+        assert!(matches!(rx.recv().await, Some(AgenticEvent::Error { message }) if message.contains("before its final event")));
+    }
+
+    #[tokio::test]
+    async fn test_agentic_stream_reads_event_stream_from_agentic_service() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(b"event: reasoning\ndata: {\"thought\": \"search code\"}\n\n").await.unwrap();
+            // Hold the rest back until the first event has reached the client
+            let _ = released.await;
+            socket.write_all(b"event: final\ndata: {\"answer\": \"Auth uses JWT\"}\n\n").await.unwrap();
+        });
+
+        let service = Arc::new(RagService::new(
+            "http://localhost:8082".to_string(),
+            "http://localhost:8006".to_string(),
+            url,
+        ));
+        let mut request = hybrid_request(None);
+        request.mode = Some(RagMode::Agentic);
+        let mut events = service.agentic_stream(request);
+
+        assert!(matches!(events.recv().await, Some(AgenticEvent::Reasoning { step: 1, thought }) if thought == "search code"));
+        release.send(()).unwrap();
+        assert!(matches!(events.recv().await, Some(AgenticEvent::Final { answer, .. }) if answer == "Auth uses JWT"));
+    }
+
+    #[test]
+    fn test_failing_graph_client_yields_vector_only_results() {
+        let fused = fuse_hybrid(
+            Err(anyhow::anyhow!("graph service unavailable")),
+            Ok(vec![source("vector", "a", 0.8), source("vector", "b", 0.4)]),
+        )
+        .unwrap();
+
+        assert_eq!(fused.len(), 2);
+        assert!(fused.iter().all(|s| s.source_type == "vector"));
+    }
+
+    #[test]
+    fn test_hybrid_fails_only_when_both_clients_fail() {
+        let result = fuse_hybrid(
+            Err(anyhow::anyhow!("graph down")),
+            Err(anyhow::anyhow!("vector down")),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_contributing_sources_skip_failed_steps() {
+        let mut pipeline = Pipeline::new(PipelineTimeouts::default());
+        pipeline.steps.push(StepReport {
+            step: "graph_search".to_string(),
+            status: StepStatus::Failed,
+            latency_ms: 3,
+            error: Some("graph down".to_string()),
+        });
+        pipeline.steps.push(StepReport {
+            step: "vector_search".to_string(),
+            status: StepStatus::Completed,
+            latency_ms: 5,
+            error: None,
+        });
+
+        assert_eq!(pipeline.contributing_sources(), vec!["vector"]);
+    }
+
+    #[tokio::test]
+    async fn test_with_retries_recovers_from_transient_failure() {
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result = with_retries("graph", || async {
+            if attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                anyhow::bail!("connection reset");
+            }
+            Ok(42)
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_min_score_drops_weak_matches() {
+        let results = vec![
+            source("vector", "strong", 0.91),
+            source("vector", "borderline", 0.6),
+            source("vector", "weak", 0.42),
+        ];
+
+        let kept = apply_min_score(results.clone(), Some(0.6));
+        let contents: Vec<&str> = kept.iter().map(|s| s.content.as_str()).collect();
+        assert_eq!(contents, vec!["strong", "borderline"]);
+
+        assert_eq!(apply_min_score(results, None).len(), 3);
+    }
+
+    #[test]
+    fn test_jsonl_export_is_one_valid_object_per_line() {
+        let mut with_id = source("vector", "fn verify_jwt() {}", 0.9);
+        with_id.metadata = serde_json::json!({ "chunk_id": "chunk-42" });
+        with_id.citation = Some("repo/src/auth.rs".to_string());
+        let long = source("graph", &"x\n".repeat(JSONL_SNIPPET_CHARS), 0.4);
+
+        let response = RagQueryResponse {
+            answer: "ignored".to_string(),
+            mode_used: "hybrid".to_string(),
+            sources: vec![with_id, long],
+            confidence: 0.7,
+            query_time_ms: 12,
+            metadata: serde_json::json!({}),
+        };
+
+        let jsonl = response.to_jsonl();
+        assert!(jsonl.ends_with('\n'));
+        let lines: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("each line is a JSON object"))
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["rank"], 1);
+        assert_eq!(lines[0]["id"], "chunk-42");
+        assert_eq!(lines[0]["source"], "repo/src/auth.rs");
+        assert_eq!(lines[1]["source"], "graph");
+        assert_eq!(lines[1]["snippet"].as_str().unwrap().chars().count(), JSONL_SNIPPET_CHARS);
+        assert!(lines[1]["id"].as_str().is_some_and(|id| !id.is_empty()));
+    }
+
+    /// Minimal HTTP server answering every request with `body`; returns its
+    /// base URL and a count of the requests it received
+    async fn mock_service(body: serde_json::Value) -> (String, Arc<AtomicUsize>) {
+        mock_router(vec![("/", body)]).await
+    }
+
+    /// Like `mock_service`, answering with the body of the first route whose
+    /// path prefixes the request path
+    async fn mock_router(routes: Vec<(&'static str, serde_json::Value)>) -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counted = hits.clone();
+        let routes: Vec<(&str, String)> = routes.into_iter().map(|(path, body)| (path, body.to_string())).collect();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counted.fetch_add(1, Ordering::SeqCst);
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                // Read the headers and the JSON body before answering
+                while let Ok(n) = socket.read(&mut buf).await {
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some((head, rest)) = text.split_once("\r\n\r\n") {
+                        let length = head.lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if rest.len() >= length {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                let text = String::from_utf8_lossy(&request);
+                let path = text.split_whitespace().nth(1).unwrap_or("/");
+                let (status, body) = match routes.iter().find(|(prefix, _)| path.starts_with(prefix)) {
+                    Some((_, body)) => ("200 OK", body.as_str()),
+                    None => ("404 Not Found", "{}"),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        (url, hits)
+    }
+
+    fn hybrid_request(endpoints: Option<EndpointOverrides>) -> RagQueryRequest {
+        RagQueryRequest {
+            query: "who owns the billing service".to_string(),
+            tenant_id: "tenant-a".to_string(),
+            mode: Some(RagMode::Hybrid),
+            filters: None,
+            top_k: None,
+            min_score: None,
+            min_confidence: None,
+            format: ResponseFormat::Json,
+            endpoints,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_overrides_route_to_specified_endpoints() {
+        let vector = serde_json::json!({ "results": [{ "content": "billing/README.md", "score": 0.8, "metadata": {} }] });
+        let graph = serde_json::json!({ "entities": [{ "name": "billing-team", "relevance_score": 0.9 }] });
+        let (global_embedding, global_embedding_hits) = mock_service(vector.clone()).await;
+        let (global_graph, global_graph_hits) = mock_service(graph.clone()).await;
+        let (tenant_embedding, tenant_embedding_hits) = mock_service(vector).await;
+        let (tenant_graph, tenant_graph_hits) = mock_service(graph).await;
+
+        let service = RagService::new(global_embedding, global_graph, String::new())
+            .with_endpoint_allow_list([format!("{}/", tenant_embedding), tenant_graph.clone()]);
+
+        let overrides = EndpointOverrides {
+            embedding_url: Some(tenant_embedding),
+            graph_url: Some(tenant_graph),
+        };
+        let response = service.query(hybrid_request(Some(overrides))).await.unwrap();
+        assert_eq!(response.sources.len(), 2);
+        assert_eq!(tenant_embedding_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(tenant_graph_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(global_embedding_hits.load(Ordering::SeqCst), 0);
+        assert_eq!(global_graph_hits.load(Ordering::SeqCst), 0);
+
+        // Without overrides the global clients are used
+        service.query(hybrid_request(None)).await.unwrap();
+        assert_eq!(global_embedding_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(global_graph_hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_overrides_outside_allow_list_are_rejected() {
+        let service = RagService::new(
+            "http://localhost:8082".to_string(),
+            "http://localhost:8006".to_string(),
+            String::new(),
+        )
+        .with_endpoint_allow_list(["https://embedding.tenant-a.internal", "not a url"]);
+
+        let allowed = hybrid_request(Some(EndpointOverrides {
+            embedding_url: Some("https://embedding.tenant-a.internal/".to_string()),
+            graph_url: None,
+        }));
+        assert_eq!(
+            service.resolve_endpoints(&allowed).unwrap(),
+            Endpoints {
+                embedding_url: "https://embedding.tenant-a.internal".to_string(),
+                graph_url: "http://localhost:8006".to_string(),
+            }
+        );
+
+        for url in ["https://embedding.tenant-b.internal", "http://embedding.tenant-a.internal", "embedding.tenant-a.internal"] {
+            let request = hybrid_request(Some(EndpointOverrides {
+                embedding_url: Some(url.to_string()),
+                graph_url: None,
+            }));
+            assert!(service.resolve_endpoints(&request).is_err(), "{} should be rejected", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_rerank_matches_staged_calls() {
+        let vector = serde_json::json!({ "results": [
+            { "content": "billing/README.md", "score": 0.91, "metadata": { "chunk_id": "a" } },
+            { "content": "billing/src/invoice.rs", "score": 0.84, "metadata": { "chunk_id": "b" } },
+            { "content": "billing/CODEOWNERS", "score": 0.80, "metadata": { "chunk_id": "c" } },
+        ] });
+        let reranked = serde_json::json!({ "results": [
+            { "id": "0", "score": 0.40 },
+            { "id": "2", "score": 0.97 },
+            { "id": "1", "score": 0.12 },
+        ] });
+        let (embedding, _) = mock_router(vec![("/vector/search", vector), ("/rerank", reranked)]).await;
+        let service = RagService::new(embedding.clone(), String::new(), String::new());
+
+        let request = |mode: RagMode, top_k: usize| RagQueryRequest {
+            query: "who owns billing".to_string(),
+            tenant_id: "tenant-a".to_string(),
+            mode: Some(mode),
+            filters: None,
+            top_k: Some(top_k),
+            min_score: None,
+            min_confidence: None,
+            format: ResponseFormat::Json,
+            endpoints: None,
+        };
+
+        let combined = service.query(request(RagMode::Rerank, 2)).await.unwrap();
+
+        // The same pipeline as separate calls: vector search over the candidate
+        // set, then the reranker over those candidates
+        let staged = service.query(request(RagMode::Vector, 2 * RERANK_CANDIDATE_FACTOR)).await.unwrap();
+        let staged_rerank = service.rerank(&embedding, "who owns billing", &staged.sources, 2).await.unwrap();
+        let expected = apply_rerank(staged.sources, &staged_rerank, 2);
+
+        let ids = |sources: &[Source]| sources.iter().map(result_id).collect::<Vec<_>>();
+        assert_eq!(ids(&combined.sources), vec!["c", "a"]);
+        assert_eq!(ids(&combined.sources), ids(&expected));
+        let scores: Vec<(f32, f32)> = combined.sources.iter().map(|s| (s.score, s.raw_score)).collect();
+        assert_eq!(scores, vec![(0.97, 0.80), (0.40, 0.91)]);
+        assert_eq!(combined.metadata["contributing_sources"], serde_json::json!(["vector", "rerank"]));
+    }
+
+    #[tokio::test]
+    async fn test_low_confidence_results_filtered_by_threshold() {
+        // The README is found in two connectors, so its evidence is fused
+        let vector = serde_json::json!({ "results": [
+            { "content": "billing/README.md", "score": 0.6, "metadata": {}, "source": "github" },
+            { "content": "billing/README.md", "score": 0.5, "metadata": {}, "source": "gdrive" },
+            { "content": "billing/notes.txt", "score": 0.3, "metadata": {}, "source": "slack" },
+        ] });
+        let (embedding, _) = mock_service(vector).await;
+        let service = RagService::new(embedding, String::new(), String::new());
+
+        let request = |min_confidence: Option<f32>| RagQueryRequest {
+            query: "billing readme".to_string(),
+            tenant_id: "tenant-a".to_string(),
+            mode: Some(RagMode::Vector),
+            filters: None,
+            top_k: None,
+            min_score: None,
+            min_confidence,
+            format: ResponseFormat::Json,
+            endpoints: None,
+        };
+
+        let all = service.query(request(None)).await.unwrap();
+        assert_eq!(all.sources.len(), 2);
+        assert!((all.sources[0].confidence - 0.8).abs() < 1e-6);
+        assert_eq!(all.sources[0].provenance, vec!["github", "gdrive"]);
+        assert!((all.sources[1].confidence - 0.3).abs() < 1e-6);
+
+        let confident = service.query(request(Some(0.7))).await.unwrap();
+        let contents: Vec<&str> = confident.sources.iter().map(|s| s.content.as_str()).collect();
+        assert_eq!(contents, vec!["billing/README.md"]);
+        assert!(!confident.answer.contains("notes.txt"));
+    }
+
+    #[test]
+    fn test_code_like_query_selects_code_biased_strategy() {
+        let analysis = analyze_query("where is getUserById called in src/users/service.rs?");
+        assert_eq!(analysis.intent, QueryIntent::Code);
+        assert_eq!(analysis.mode, RagMode::Hybrid);
+        assert_eq!(analysis.entities, vec![
+            DetectedEntity { kind: EntityKind::Identifier, text: "getUserById".to_string() },
+            DetectedEntity { kind: EntityKind::FilePath, text: "src/users/service.rs".to_string() },
+        ]);
+        let weights = analysis.weights.unwrap();
+        assert!(weights.graph > QueryIntent::Content.hybrid_weights().graph);
+
+        let prose = analyze_query("what is our refund policy for annual plans?");
+        assert_eq!((prose.intent, prose.mode, prose.weights), (QueryIntent::Content, RagMode::Vector, None));
+        assert!(prose.entities.is_empty());
+
+        // Ownership phrasing still wins over the code entity it names
+        let owner = analyze_query("who owns acme/billing and parse_invoice()?");
+        assert_eq!(owner.intent, QueryIntent::Ownership);
+        assert_eq!(owner.entities[0].kind, EntityKind::Repository);
+    }
+}
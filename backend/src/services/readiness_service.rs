@@ -0,0 +1,292 @@
+// Readiness probing: checks each downstream dependency with a short timeout and
+// rolls the results up into one status for /readyz.
+use futures::future::{join_all, BoxFuture};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type ProbeFn = Box<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyState {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyStatus {
+    pub status: DependencyState,
+    /// Whether the service can serve requests without this dependency
+    pub critical: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Overall readiness: `Degraded` means a non-critical dependency is down
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadinessState {
+    Ready,
+    Degraded,
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessReport {
+    pub status: ReadinessState,
+    pub service: String,
+    pub dependencies: BTreeMap<String, DependencyStatus>,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ReadinessReport {
+    /// Only an unavailable service should be taken out of rotation
+    pub fn is_serving(&self) -> bool {
+        self.status != ReadinessState::Unavailable
+    }
+}
+
+/// Check that a downstream base URL is an absolute http(s) URL with a host
+pub fn validate_service_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("'{}' is not a valid URL: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("'{}' must use http or https", url));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("'{}' has no host", url));
+    }
+    Ok(parsed)
+}
+
+/// A named check against one dependency
+pub struct DependencyProbe {
+    name: String,
+    critical: bool,
+    check: ProbeFn,
+}
+
+impl DependencyProbe {
+    pub fn new<F>(name: impl Into<String>, critical: bool, check: F) -> Self
+    where
+        F: Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            critical,
+            check: Box::new(check),
+        }
+    }
+
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::new("database", true, move || {
+            let pool = pool.clone();
+            Box::pin(async move {
+                sqlx::query("SELECT 1")
+                    .execute(&pool)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+        })
+    }
+
+    pub fn redis(client: redis::Client) -> Self {
+        Self::new("redis", false, move || {
+            let client = client.clone();
+            Box::pin(async move {
+                let mut conn = client
+                    .get_multiplexed_async_connection()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                redis::cmd("PING")
+                    .query_async::<_, String>(&mut conn)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+        })
+    }
+
+    /// GET `{base_url}/health` and expect a success status. An invalid base URL
+    /// fails every probe with the validation error.
+    pub fn http(name: impl Into<String>, base_url: &str, client: reqwest::Client) -> Self {
+        let url = validate_service_url(base_url)
+            .map(|_| format!("{}/health", base_url.trim().trim_end_matches('/')));
+        Self::new(name, false, move || {
+            let client = client.clone();
+            let url = url.clone();
+            Box::pin(async move {
+                let url = url?;
+                let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
+                if response.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(format!("{} returned {}", url, response.status()))
+                }
+            })
+        })
+    }
+}
+
+pub struct ReadinessService {
+    service: String,
+    probes: Vec<DependencyProbe>,
+    probe_timeout: Duration,
+    /// How long a report is reused, so frequent probes don't hammer dependencies
+    cache_ttl: Duration,
+    cached: Mutex<Option<(Instant, ReadinessReport)>>,
+}
+
+impl ReadinessService {
+    pub fn new(service: impl Into<String>, probes: Vec<DependencyProbe>) -> Self {
+        Self {
+            service: service.into(),
+            probes,
+            probe_timeout: Duration::from_millis(
+                std::env::var("READINESS_PROBE_TIMEOUT_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(2_000),
+            ),
+            cache_ttl: Duration::from_millis(
+                std::env::var("READINESS_CACHE_TTL_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(5_000),
+            ),
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub fn with_probe_timeout(mut self, timeout: Duration) -> Self {
+        self.probe_timeout = timeout;
+        self
+    }
+
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Latest report, probing again only once the cached one has expired
+    pub async fn check(&self) -> ReadinessReport {
+        if let Some((at, report)) = self.cached.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            if at.elapsed() < self.cache_ttl {
+                return report.clone();
+            }
+        }
+
+        let report = self.probe_all().await;
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    async fn probe_all(&self) -> ReadinessReport {
+        let results = join_all(self.probes.iter().map(|probe| async move {
+            let started = Instant::now();
+            let outcome = match tokio::time::timeout(self.probe_timeout, (probe.check)()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!("timed out after {}ms", self.probe_timeout.as_millis())),
+            };
+            if let Err(e) = &outcome {
+                log::warn!("Readiness probe '{}' failed: {}", probe.name, e);
+            }
+
+            let status = DependencyStatus {
+                status: if outcome.is_ok() { DependencyState::Up } else { DependencyState::Down },
+                critical: probe.critical,
+                latency_ms: started.elapsed().as_millis() as u64,
+                error: outcome.err(),
+            };
+            (probe.name.clone(), status)
+        }))
+        .await;
+
+        let dependencies: BTreeMap<String, DependencyStatus> = results.into_iter().collect();
+        let down = dependencies.values().filter(|d| d.status == DependencyState::Down);
+        let status = match down.map(|d| d.critical).max() {
+            None => ReadinessState::Ready,
+            Some(false) => ReadinessState::Degraded,
+            Some(true) => ReadinessState::Unavailable,
+        };
+
+        ReadinessReport {
+            status,
+            service: self.service.clone(),
+            dependencies,
+            checked_at: chrono::Utc::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn up(name: &str, critical: bool) -> DependencyProbe {
+        DependencyProbe::new(name, critical, || Box::pin(async { Ok(()) }))
+    }
+
+    fn down(name: &str, critical: bool) -> DependencyProbe {
+        DependencyProbe::new(name, critical, || Box::pin(async { Err("connection refused".to_string()) }))
+    }
+
+    #[tokio::test]
+    async fn test_one_down_dependency_degrades_rollup() {
+        let service = ReadinessService::new("backend", vec![up("database", true), down("graph", false), up("redis", false)]);
+
+        let report = service.check().await;
+        assert_eq!(report.status, ReadinessState::Degraded);
+        assert!(report.is_serving());
+        assert_eq!(report.dependencies["database"].status, DependencyState::Up);
+        assert_eq!(report.dependencies["graph"].status, DependencyState::Down);
+        assert_eq!(report.dependencies["graph"].error.as_deref(), Some("connection refused"));
+
+        let service = ReadinessService::new("backend", vec![down("database", true), up("graph", false)]);
+        assert_eq!(service.check().await.status, ReadinessState::Unavailable);
+    }
+
+    #[test]
+    fn test_service_url_validation() {
+        assert!(validate_service_url("http://localhost:8082").is_ok());
+        assert!(validate_service_url(" https://graph.internal/ ").is_ok());
+
+        for invalid in ["", "localhost:8082", "ftp://files.internal", "http://", "embedding-service"] {
+            assert!(validate_service_url(invalid).is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_url_probe_reports_validation_error() {
+        let probe = DependencyProbe::http("graph", "graph-service:8006", reqwest::Client::new());
+        let service = ReadinessService::new("backend", vec![probe]);
+
+        let report = service.check().await;
+        assert_eq!(report.status, ReadinessState::Degraded);
+        assert!(report.dependencies["graph"].error.as_deref().unwrap().contains("must use http or https"));
+    }
+
+    #[tokio::test]
+    async fn test_slow_probe_times_out_and_results_are_cached() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        let slow = DependencyProbe::new("embedding", false, move || {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+        });
+        let service = ReadinessService::new("backend", vec![slow])
+            .with_probe_timeout(Duration::from_millis(20))
+            .with_cache_ttl(Duration::from_secs(60));
+
+        let started = Instant::now();
+        let report = service.check().await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(report.dependencies["embedding"].status, DependencyState::Down);
+        assert!(report.dependencies["embedding"].error.as_deref().unwrap().contains("timed out"));
+
+        service.check().await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
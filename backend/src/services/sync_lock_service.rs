@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Prefix of the per-source lock keys
+const LOCK_KEY_PREFIX: &str = "sync:lock:";
+
+/// Deletes the lock only while it still holds our token, so a holder whose lock
+/// expired can't release the next holder's
+const RELEASE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+#[derive(Debug)]
+pub enum SyncLockError {
+    /// Another sync of the same source holds the lock
+    AlreadySyncing,
+    Store(String),
+}
+
+impl std::fmt::Display for SyncLockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncLockError::AlreadySyncing => write!(f, "Source is already syncing"),
+            SyncLockError::Store(msg) => write!(f, "Sync lock store error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SyncLockError {}
+
+impl From<redis::RedisError> for SyncLockError {
+    fn from(e: redis::RedisError) -> Self {
+        SyncLockError::Store(e.to_string())
+    }
+}
+
+type MemoryLocks = Arc<Mutex<HashMap<String, (Uuid, Instant)>>>;
+
+/// Where a held lock was taken
+enum HeldIn {
+    Redis(redis::Client),
+    Memory,
+}
+
+/// A held sync lock. Dropping it without `release` (the sync's future was
+/// dropped on a client disconnect or timeout) still frees the source: memory
+/// locks are removed right away and Redis locks from a spawned task.
+struct SyncLockGuard {
+    source_id: String,
+    token: Uuid,
+    held_in: HeldIn,
+    memory: MemoryLocks,
+    released: bool,
+}
+
+impl SyncLockGuard {
+    async fn release(mut self) -> Result<(), SyncLockError> {
+        self.released = true;
+        match &self.held_in {
+            HeldIn::Redis(client) => release_redis(client, &self.source_id, self.token).await,
+            HeldIn::Memory => {
+                release_memory(&self.memory, &self.source_id, self.token);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Drop for SyncLockGuard {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        match &self.held_in {
+            HeldIn::Memory => release_memory(&self.memory, &self.source_id, self.token),
+            HeldIn::Redis(client) => {
+                let (client, source_id, token) = (client.clone(), self.source_id.clone(), self.token);
+                match tokio::runtime::Handle::try_current() {
+                    Ok(handle) => {
+                        handle.spawn(async move {
+                            if let Err(e) = release_redis(&client, &source_id, token).await {
+                                log::warn!("Failed to release abandoned sync lock for {}: {}", source_id, e);
+                            }
+                        });
+                    }
+                    Err(_) => log::warn!("No runtime to release sync lock for {}; it expires on its TTL", source_id),
+                }
+            }
+        }
+    }
+}
+
+async fn release_redis(client: &redis::Client, source_id: &str, token: Uuid) -> Result<(), SyncLockError> {
+    let mut conn = client.get_async_connection().await?;
+    redis::Script::new(RELEASE_SCRIPT)
+        .key(format!("{}{}", LOCK_KEY_PREFIX, source_id))
+        .arg(token.to_string())
+        .invoke_async::<_, i64>(&mut conn)
+        .await?;
+    Ok(())
+}
+
+fn release_memory(memory: &MemoryLocks, source_id: &str, token: Uuid) {
+    let mut locks = memory.lock().unwrap_or_else(|e| e.into_inner());
+    if locks.get(source_id).is_some_and(|(held, _)| *held == token) {
+        locks.remove(source_id);
+    }
+}
+
+/// One sync at a time per source, so overlapping requests can't ingest the same
+/// repository twice. Backed by Redis when available so the lock holds across
+/// instances; while Redis is unreachable, locks fall back to this process.
+/// Locks expire after `ttl` in case a holder dies mid-sync.
+pub struct SyncLocks {
+    redis: Option<redis::Client>,
+    /// Lock token and expiry per source, for when Redis is disabled or down
+    memory: MemoryLocks,
+    ttl: Duration,
+}
+
+impl SyncLocks {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(30 * 60);
+
+    pub fn redis(client: redis::Client, ttl: Duration) -> Self {
+        Self { redis: Some(client), memory: MemoryLocks::default(), ttl }
+    }
+
+    /// Process-local locks, used when Redis is disabled
+    pub fn in_memory(ttl: Duration) -> Self {
+        Self { redis: None, memory: MemoryLocks::default(), ttl }
+    }
+
+    /// Run `sync` holding the lock for `source_id`, releasing it whether the sync
+    /// succeeds, fails or is dropped. Fails with `AlreadySyncing` without running
+    /// `sync` if another sync holds the lock.
+    pub async fn run_exclusive<F, Fut, T>(&self, source_id: &str, sync: F) -> Result<T, SyncLockError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let guard = self.acquire(source_id).await?;
+        let result = sync().await;
+        if let Err(e) = guard.release().await {
+            log::warn!("Failed to release sync lock for {}; it expires in {:?}: {}", source_id, self.ttl, e);
+        }
+        Ok(result)
+    }
+
+    async fn acquire(&self, source_id: &str) -> Result<SyncLockGuard, SyncLockError> {
+        let token = Uuid::new_v4();
+        let held_in = match &self.redis {
+            Some(client) => match self.acquire_redis(client, source_id, token).await {
+                Ok(true) => Some(HeldIn::Redis(client.clone())),
+                Ok(false) => None,
+                Err(e) => {
+                    log::warn!("Redis unavailable for sync lock on {}, locking in this process only: {}", source_id, e);
+                    self.acquire_memory(source_id, token).then_some(HeldIn::Memory)
+                }
+            },
+            None => self.acquire_memory(source_id, token).then_some(HeldIn::Memory),
+        };
+
+        match held_in {
+            Some(held_in) => Ok(SyncLockGuard {
+                source_id: source_id.to_string(),
+                token,
+                held_in,
+                memory: self.memory.clone(),
+                released: false,
+            }),
+            None => Err(SyncLockError::AlreadySyncing),
+        }
+    }
+
+    async fn acquire_redis(&self, client: &redis::Client, source_id: &str, token: Uuid) -> Result<bool, SyncLockError> {
+        let mut conn = client.get_async_connection().await?;
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(format!("{}{}", LOCK_KEY_PREFIX, source_id))
+            .arg(token.to_string())
+            .arg("NX")
+            .arg("PX")
+            .arg(self.ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await?;
+        Ok(reply.is_some())
+    }
+
+    fn acquire_memory(&self, source_id: &str, token: Uuid) -> bool {
+        let mut locks = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match locks.get(source_id) {
+            Some((_, expires)) if *expires > now => false,
+            _ => {
+                locks.insert(source_id.to_string(), (token, now + self.ttl));
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn test_second_concurrent_sync_is_rejected() {
+        let locks = Arc::new(SyncLocks::in_memory(SyncLocks::DEFAULT_TTL));
+        let (started_tx, started_rx) = oneshot::channel();
+        let (finish_tx, finish_rx) = oneshot::channel::<()>();
+
+        let first = tokio::spawn({
+            let locks = locks.clone();
+            async move {
+                locks
+                    .run_exclusive("repo-1", || async move {
+                        started_tx.send(()).unwrap();
+                        finish_rx.await.unwrap();
+                        "synced"
+                    })
+                    .await
+            }
+        });
+        started_rx.await.unwrap();
+
+        let second = locks.run_exclusive("repo-1", || async { "synced twice" }).await;
+        assert!(matches!(second, Err(SyncLockError::AlreadySyncing)));
+        // Other sources are unaffected
+        assert_eq!(locks.run_exclusive("repo-2", || async { 2 }).await.unwrap(), 2);
+
+        finish_tx.send(()).unwrap();
+        assert_eq!(first.await.unwrap().unwrap(), "synced");
+
+        // Released on completion, and on failure too
+        let failed: Result<(), &str> = locks.run_exclusive("repo-1", || async { Err("clone failed") }).await.unwrap();
+        assert!(failed.is_err());
+        assert!(locks.run_exclusive("repo-1", || async {}).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_expired_lock_can_be_taken_over() {
+        let locks = SyncLocks::in_memory(Duration::from_millis(10));
+        let stale = locks.acquire("repo-1").await.unwrap();
+
+        assert!(matches!(locks.acquire("repo-1").await, Err(SyncLockError::AlreadySyncing)));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let current = locks.acquire("repo-1").await.unwrap();
+
+        // The stale holder finishing late doesn't free the new holder's lock
+        stale.release().await.unwrap();
+        assert!(matches!(locks.acquire("repo-1").await, Err(SyncLockError::AlreadySyncing)));
+        current.release().await.unwrap();
+        assert!(locks.acquire("repo-1").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_sync_releases_the_lock() {
+        let locks = Arc::new(SyncLocks::in_memory(SyncLocks::DEFAULT_TTL));
+        let (started_tx, started_rx) = oneshot::channel();
+
+        // The handler future is dropped mid-sync, as on a client disconnect
+        let sync = tokio::spawn({
+            let locks = locks.clone();
+            async move {
+                locks
+                    .run_exclusive("repo-1", || async move {
+                        started_tx.send(()).unwrap();
+                        std::future::pending::<()>().await
+                    })
+                    .await
+            }
+        });
+        started_rx.await.unwrap();
+        sync.abort();
+        assert!(sync.await.unwrap_err().is_cancelled());
+
+        assert!(locks.run_exclusive("repo-1", || async {}).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_redis_falls_back_to_process_locks() {
+        let client = redis::Client::open("redis://127.0.0.1:1/").unwrap();
+        let locks = SyncLocks::redis(client, SyncLocks::DEFAULT_TTL);
+
+        let held = locks.acquire("repo-1").await.unwrap();
+        assert!(matches!(held.held_in, HeldIn::Memory));
+        assert!(matches!(locks.acquire("repo-1").await, Err(SyncLockError::AlreadySyncing)));
+        held.release().await.unwrap();
+        assert!(locks.run_exclusive("repo-1", || async { 1 }).await.is_ok());
+    }
+}
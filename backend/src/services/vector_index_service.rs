@@ -0,0 +1,345 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use conhub_plugins::error::PluginError;
+use conhub_plugins::sources::{DeletionSink, DocumentDeletion};
+use conhub_plugins::PluginResult;
+use conhub_models::chunking::{IndexEmbeddedChunksResponse, IngestChunksRequest};
+use conhub_models::{CompactionStats, OptimizedVector, SpatialIndex, VectorMetadata};
+use serde::Serialize;
+use std::sync::{Arc, Mutex, RwLock};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RebuildState {
+    Idle,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Pollable status of the most recent index rebuild
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildStatus {
+    pub state: RebuildState,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub stats: Option<CompactionStats>,
+    pub error: Option<String>,
+}
+
+impl Default for RebuildStatus {
+    fn default() -> Self {
+        Self {
+            state: RebuildState::Idle,
+            started_at: None,
+            finished_at: None,
+            stats: None,
+            error: None,
+        }
+    }
+}
+
+/// What deleting a source removed
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SourceDeletion {
+    /// Vectors removed from the in-memory index
+    pub index_removed: usize,
+    /// Vectors the embedding service's store reported removed, when it says
+    pub store_removed: Option<u64>,
+}
+
+/// Owns the in-memory vector index and runs compaction off the request path
+pub struct VectorIndexService {
+    index: Arc<RwLock<SpatialIndex>>,
+    status: Arc<Mutex<RebuildStatus>>,
+    /// Embedding service whose vector store backs RAG search
+    store_url: Option<String>,
+    http: reqwest::Client,
+}
+
+impl VectorIndexService {
+    pub fn new(index: SpatialIndex) -> Self {
+        Self {
+            index: Arc::new(RwLock::new(index)),
+            status: Arc::new(Mutex::new(RebuildStatus::default())),
+            store_url: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Also delete from the vector store of the embedding service at `url`
+    pub fn with_vector_store(mut self, url: impl Into<String>) -> Self {
+        self.store_url = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    pub fn index(&self) -> Arc<RwLock<SpatialIndex>> {
+        self.index.clone()
+    }
+
+    /// Index embedded chunks as they come out of the embedding pipeline, each
+    /// stamped with its source. A chunk indexed again replaces its old vector.
+    /// `embeddings` must hold one vector per chunk.
+    pub fn index_chunks(&self, request: IngestChunksRequest, embeddings: Vec<Vec<f32>>) -> IndexEmbeddedChunksResponse {
+        let mut index = self.index.write().unwrap_or_else(|e| e.into_inner());
+        let mut response = IndexEmbeddedChunksResponse::default();
+
+        let mut entries = Vec::new();
+        let mut entry_chunks = Vec::new();
+        for (chunk, embedding) in request.chunks.iter().zip(embeddings) {
+            let id = chunk.chunk_id.to_string();
+            let metadata = match VectorMetadata::from_chunk_metadata(&id, &chunk.metadata) {
+                Some(metadata) if embedding.len() == index.dimension => metadata,
+                _ => {
+                    response.skipped_chunks.push(chunk.chunk_id);
+                    continue;
+                }
+            };
+            while index.remove(&id) {}
+            entries.push((OptimizedVector::new(embedding), metadata));
+            entry_chunks.push(chunk.chunk_id);
+        }
+
+        let skipped = index.insert_batch(entries);
+        response.indexed = entry_chunks.len() - skipped.len();
+        response.skipped_chunks.extend(skipped.into_iter().map(|i| entry_chunks[i]));
+        response
+    }
+
+    /// Remove every vector of `source_id`; the space is reclaimed by the next rebuild
+    pub fn remove_source(&self, source_id: &str) -> usize {
+        self.index.write().unwrap_or_else(|e| e.into_inner()).remove_source(source_id)
+    }
+
+    /// Remove `source_id` from the index and from the embedding service's vector
+    /// store, so RAG search stops returning it. The store answering 404 counts
+    /// as already deleted, so a repeated delete succeeds.
+    pub async fn delete_source(&self, source_id: &str) -> Result<SourceDeletion, String> {
+        let index_removed = self.remove_source(source_id);
+        let Some(store_url) = &self.store_url else {
+            return Ok(SourceDeletion { index_removed, store_removed: None });
+        };
+
+        let response = self
+            .http
+            .delete(format!("{}/vector/sources/{}", store_url, source_id))
+            .send()
+            .await
+            .map_err(|e| format!("Vector store unavailable: {}", e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(SourceDeletion { index_removed, store_removed: Some(0) });
+        }
+        if !status.is_success() {
+            return Err(format!("Vector store rejected delete with status {}", status));
+        }
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        Ok(SourceDeletion {
+            index_removed,
+            store_removed: body.get("deleted").and_then(|d| d.as_u64()),
+        })
+    }
+
+    pub fn status(&self) -> RebuildStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Kick off a rebuild on the blocking pool. Returns `Err` with the current
+    /// status if a rebuild is already running.
+    pub fn start_rebuild(&self) -> Result<RebuildStatus, RebuildStatus> {
+        let started = {
+            let mut status = self.status.lock().unwrap_or_else(|e| e.into_inner());
+            if status.state == RebuildState::Running {
+                return Err(status.clone());
+            }
+            *status = RebuildStatus {
+                state: RebuildState::Running,
+                started_at: Some(Utc::now()),
+                ..RebuildStatus::default()
+            };
+            status.clone()
+        };
+
+        let index = self.index.clone();
+        let status = self.status.clone();
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                let mut index = index.write().unwrap_or_else(|e| e.into_inner());
+                index.rebuild()
+            })
+            .await;
+
+            let mut status = status.lock().unwrap_or_else(|e| e.into_inner());
+            status.finished_at = Some(Utc::now());
+            match result {
+                Ok(stats) => {
+                    log::info!(
+                        "Vector index rebuild completed: {} removed, {} live",
+                        stats.removed,
+                        stats.live
+                    );
+                    status.state = RebuildState::Completed;
+                    status.stats = Some(stats);
+                }
+                Err(e) => {
+                    log::error!("Vector index rebuild failed: {}", e);
+                    status.state = RebuildState::Failed;
+                    status.error = Some(e.to_string());
+                }
+            }
+        });
+
+        Ok(started)
+    }
+}
+
+/// Removes documents deleted upstream from the vector index and store; a
+/// document's vectors are stored under its id as the source id
+pub struct VectorIndexDeletionSink {
+    service: Arc<VectorIndexService>,
+}
+
+impl VectorIndexDeletionSink {
+    pub fn new(service: Arc<VectorIndexService>) -> Self {
+        Self { service }
+    }
+}
+
+#[async_trait]
+impl DeletionSink for VectorIndexDeletionSink {
+    fn name(&self) -> &str {
+        "vector-index"
+    }
+
+    async fn document_deleted(&self, deletion: &DocumentDeletion) -> PluginResult<()> {
+        let deleted = self
+            .service
+            .delete_source(&deletion.document_id)
+            .await
+            .map_err(PluginError::NetworkError)?;
+        log::debug!(
+            "Removed {} of {} from the vector index: {:?}",
+            deletion.document_id,
+            deletion.instance_id,
+            deleted
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use conhub_models::chunking::{Chunk, SourceKind};
+    use conhub_models::IndexType;
+    use uuid::Uuid;
+
+    fn chunk(index: u32) -> Chunk {
+        serde_json::from_value(serde_json::json!({
+            "chunk_id": Uuid::new_v4(),
+            "source_item_id": Uuid::new_v4(),
+            "chunk_index": index,
+            "content": format!("fn handler_{}() {{}}", index),
+            "start_offset": null,
+            "end_offset": null,
+            "block_type": "code",
+            "language": "rust",
+            "metadata": {}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_indexed_chunks_are_searchable_by_source() {
+        let service = VectorIndexService::new(SpatialIndex::new(3, IndexType::Flat));
+        let source_id = Uuid::new_v4();
+        let chunks = vec![chunk(0), chunk(1), chunk(2)];
+        let ids: Vec<Uuid> = chunks.iter().map(|c| c.chunk_id).collect();
+        let request = IngestChunksRequest::new(source_id, SourceKind::CodeRepo, chunks);
+
+        let response = service.index_chunks(
+            request,
+            vec![vec![1.0, 0.0, 0.0], vec![0.0, 0.0, 0.0], vec![0.0, 1.0]],
+        );
+        // A zero vector and a wrong-dimension vector are left out
+        assert_eq!(response.indexed, 1);
+        assert_eq!(response.skipped_chunks.len(), 2);
+        assert!(response.skipped_chunks.contains(&ids[1]) && response.skipped_chunks.contains(&ids[2]));
+
+        let index = service.index();
+        let index = index.read().unwrap();
+        let results = index.search(&OptimizedVector::new(vec![1.0, 0.0, 0.0]), 5);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, ids[0].to_string());
+        assert_eq!(results[0].0.source_id, source_id.to_string());
+        assert_eq!(results[0].0.source_kind, Some(SourceKind::CodeRepo));
+    }
+
+    #[actix_web::test]
+    async fn test_deleted_source_is_removed_from_index_and_store() {
+        use actix_web::{web, App, HttpResponse, HttpServer};
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let store_url = format!("http://{}", listener.local_addr().unwrap());
+        let store = HttpServer::new(|| {
+            App::new().route(
+                "/vector/sources/{id}",
+                web::delete().to(|id: web::Path<String>| async move {
+                    // The store already forgot this one
+                    if id.as_str() == "gone" {
+                        HttpResponse::NotFound().finish()
+                    } else {
+                        HttpResponse::Ok().json(serde_json::json!({ "deleted": 2 }))
+                    }
+                }),
+            )
+        })
+        .listen(listener)
+        .unwrap()
+        .run();
+        drop(actix_web::rt::spawn(store));
+
+        let service = VectorIndexService::new(SpatialIndex::new(2, IndexType::Flat)).with_vector_store(store_url);
+        let source_id = Uuid::new_v4();
+        let request = IngestChunksRequest::new(source_id, SourceKind::CodeRepo, vec![chunk(0), chunk(1)]);
+        service.index_chunks(request, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let deleted = service.delete_source(&source_id.to_string()).await.unwrap();
+        assert_eq!(deleted, SourceDeletion { index_removed: 2, store_removed: Some(2) });
+        let index = service.index();
+        assert!(index.read().unwrap().search(&OptimizedVector::new(vec![1.0, 0.0]), 5).is_empty());
+
+        let repeated = service.delete_source("gone").await.unwrap();
+        assert_eq!(repeated, SourceDeletion { index_removed: 0, store_removed: Some(0) });
+    }
+
+    #[actix_web::test]
+    async fn test_deletion_sink_removes_the_document_from_the_index() {
+        let service = Arc::new(VectorIndexService::new(SpatialIndex::new(2, IndexType::Flat)));
+        let source_id = Uuid::new_v4();
+        let request = IngestChunksRequest::new(source_id, SourceKind::CodeRepo, vec![chunk(0)]);
+        service.index_chunks(request, vec![vec![1.0, 0.0]]);
+
+        let sink = VectorIndexDeletionSink::new(service.clone());
+        let deletion = DocumentDeletion { instance_id: "dropbox-1".to_string(), document_id: source_id.to_string() };
+        sink.document_deleted(&deletion).await.unwrap();
+        assert!(service.index().read().unwrap().is_empty());
+
+        // A repeated delivery still succeeds
+        sink.document_deleted(&deletion).await.unwrap();
+    }
+
+    #[test]
+    fn test_reindexed_chunk_replaces_its_vector() {
+        let service = VectorIndexService::new(SpatialIndex::new(2, IndexType::Flat));
+        let source_id = Uuid::new_v4();
+        let chunks = vec![chunk(0)];
+
+        let first = IngestChunksRequest::new(source_id, SourceKind::CodeRepo, chunks.clone());
+        service.index_chunks(first, vec![vec![1.0, 0.0]]);
+        let again = IngestChunksRequest::new(source_id, SourceKind::CodeRepo, chunks);
+        service.index_chunks(again, vec![vec![0.0, 1.0]]);
+
+        assert_eq!(service.index().read().unwrap().len(), 1);
+    }
+}
@@ -3,11 +3,14 @@ use crate::services::{
     auth_service::AuthService,
     billing_service::BillingService,
     data_service::DataService,
+    embedding_retry_service::{EmbeddingRetryQueue, RetryPolicy},
     indexing_service::IndexingService,
     security_service::SecurityService,
+    sync_lock_service::SyncLocks,
 };
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub struct AppState {
     pub db_pool: Option<PgPool>,
@@ -18,8 +21,10 @@ pub struct AppState {
     pub auth_service: Arc<AuthService>,
     pub billing_service: Arc<BillingService>,
     pub data_service: Arc<DataService>,
+    pub embedding_retry_queue: Arc<EmbeddingRetryQueue>,
     pub indexing_service: Arc<IndexingService>,
     pub security_service: Arc<SecurityService>,
+    pub sync_locks: Arc<SyncLocks>,
 }
 
 impl AppState {
@@ -45,6 +50,12 @@ impl AppState {
             config.clone(),
         ));
 
+        // Failed embedding batches survive restarts when Redis is available
+        let embedding_retry_queue = Arc::new(match redis_client.clone() {
+            Some(client) => EmbeddingRetryQueue::redis(client, RetryPolicy::default()),
+            None => EmbeddingRetryQueue::in_memory(RetryPolicy::default()),
+        });
+
         let indexing_service = Arc::new(IndexingService::new(
             config.clone(),
         ));
@@ -53,6 +64,17 @@ impl AppState {
             db_pool.clone(),
         ));
 
+        // Shared through Redis so the lock holds across backend instances
+        let sync_lock_ttl = std::env::var("SYNC_LOCK_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(SyncLocks::DEFAULT_TTL);
+        let sync_locks = Arc::new(match redis_client.clone() {
+            Some(client) => SyncLocks::redis(client, sync_lock_ttl),
+            None => SyncLocks::in_memory(sync_lock_ttl),
+        });
+
         Ok(Self {
             db_pool,
             redis_client,
@@ -60,8 +82,10 @@ impl AppState {
             auth_service,
             billing_service,
             data_service,
+            embedding_retry_queue,
             indexing_service,
             security_service,
+            sync_locks,
         })
     }
 }
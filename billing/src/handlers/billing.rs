@@ -7,6 +7,7 @@ use tracing::error;
 use conhub_models::billing::*;
 use crate::services::billing::BillingService;
 use crate::services::billing_db::BillingServiceDb;
+use crate::services::stripe_sync::parse_subscription_event;
 use crate::errors::ServiceError;
 use sqlx::PgPool;
 
@@ -222,7 +223,11 @@ pub async fn get_invoices(path: web::Path<String>) -> Result<HttpResponse, Servi
 }
 
 
-pub async fn handle_stripe_webhook(req: HttpRequest, body: web::Bytes) -> Result<HttpResponse, ServiceError> {
+pub async fn handle_stripe_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    pool_opt: web::Data<Option<PgPool>>,
+) -> Result<HttpResponse, ServiceError> {
     let signature = req.headers()
         .get("stripe-signature")
         .and_then(|v| v.to_str().ok())
@@ -233,17 +238,25 @@ pub async fn handle_stripe_webhook(req: HttpRequest, body: web::Bytes) -> Result
 
     let billing_service = BillingService::new();
 
-    match billing_service.handle_webhook_event(payload, signature).await {
-        Ok(_) => Ok(HttpResponse::Ok().json(json!({
-            "success": true
-        }))),
-        Err(e) => {
-            tracing::error!("Failed to handle webhook: {}", e);
-            Ok(HttpResponse::BadRequest().json(json!({
-                "error": "Failed to handle webhook"
-            })))
-        }
+    if let Err(e) = billing_service.handle_webhook_event(payload, signature).await {
+        tracing::error!("Failed to handle webhook: {}", e);
+        return Ok(HttpResponse::BadRequest().json(json!({
+            "error": "Failed to handle webhook"
+        })));
+    }
+
+    // Reconcile local subscription state; a database error fails the request so Stripe redelivers
+    let event: serde_json::Value = serde_json::from_str(payload)
+        .map_err(|e| ServiceError::ValidationError(format!("Invalid webhook payload: {}", e)))?;
+    if let (Some(pool), Some(subscription_event)) = (pool_opt.get_ref(), parse_subscription_event(&event)?) {
+        BillingServiceDb::new(pool.clone())
+            .sync_stripe_subscription(&subscription_event)
+            .await?;
     }
+
+    Ok(HttpResponse::Ok().json(json!({
+        "success": true
+    })))
 }
 
 pub async fn get_subscription(path: web::Path<String>) -> Result<HttpResponse, ServiceError> {
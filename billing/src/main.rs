@@ -1,8 +1,8 @@
 use actix_web::{web, App, HttpServer};
 use actix_cors::Cors;
-use sqlx::{PgPool, postgres::{PgPoolOptions, PgConnectOptions}};
+use sqlx::PgPool;
+use conhub_database::DatabaseConfig;
 use redis::Client as RedisClient;
-use std::str::FromStr;
 use std::env;
 use conhub_middleware::auth::AuthMiddlewareFactory;
 use conhub_observability::{init_tracing, TracingConfig, observability, info, warn, error};
@@ -11,6 +11,8 @@ mod handlers;
 mod services;
 mod errors;
 
+use services::usage_flush::{StripeUsageClient, UsageBuffer, UsageFlusher};
+
 #[actix_web::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize observability with structured logging
@@ -47,14 +49,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tracing::info!("📊 [Billing Service] Connecting to database...");
     }
 
-    // Disable server-side prepared statements for pgbouncer/Neon
-    let connect_options = PgConnectOptions::from_str(&database_url)?
-        .statement_cache_capacity(0);
-
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect_with(connect_options)
-        .await?;
+    let pool = conhub_database::connect(&DatabaseConfig::for_url(database_url)).await?;
     tracing::info!("✅ [Billing Service] Database connection established");
     let db_pool_opt = Some(pool);
 
@@ -88,6 +83,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    // Report metered usage to Stripe in the background
+    if let (Some(pool), Some(key)) = (db_pool_opt.clone(), stripe_key_opt.clone()) {
+        let interval = env::var("STRIPE_USAGE_FLUSH_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(UsageFlusher::DEFAULT_INTERVAL);
+        let flusher = std::sync::Arc::new(UsageFlusher::new(UsageBuffer::Postgres(pool), interval));
+        flusher.spawn(StripeUsageClient::new(key));
+        tracing::info!("📈 [Billing Service] Usage reporting to Stripe every {}s", interval.as_secs());
+    }
+
     tracing::info!("🚀 [Billing Service] Starting on port {}", port);
     HttpServer::new(move || {
         let cors = Cors::default()
@@ -1,8 +1,9 @@
 use conhub_models::billing::*;
 use crate::errors::ServiceError;
+use crate::services::stripe_sync::{parse_status_column, status_column, SubscriptionEvent, SyncedSubscription};
 use uuid::Uuid;
-use sqlx::PgPool;
-use tracing::{info, error};
+use sqlx::{PgPool, Row};
+use tracing::{info, warn, error};
 use chrono::{Datelike, Timelike};
 
 pub struct BillingServiceDb {
@@ -390,6 +391,184 @@ impl BillingServiceDb {
         Ok(())
     }
 
+    /// Apply a Stripe subscription event to `user_subscriptions`, creating the
+    /// row the first time a subscription is seen. Returns false when the event
+    /// is older than the last one applied or can't be matched to a user and plan.
+    pub async fn sync_stripe_subscription(&self, event: &SubscriptionEvent) -> Result<bool, ServiceError> {
+        let db_error = |e: sqlx::Error| {
+            error!("Failed to sync Stripe subscription {}: {}", event.stripe_subscription_id, e);
+            ServiceError::DatabaseError(e.to_string())
+        };
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+
+        let plan_id = match &event.stripe_price_id {
+            Some(price_id) => sqlx::query_scalar::<_, Uuid>(
+                "SELECT id FROM subscription_plans WHERE stripe_price_id = $1 LIMIT 1"
+            )
+            .bind(price_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?,
+            None => None,
+        };
+
+        // Lock the row so concurrent deliveries for a known subscription apply in turn
+        let existing = sqlx::query(
+            "SELECT id, user_id, plan_id, status, current_period_start, current_period_end, trial_start, trial_end, cancel_at_period_end, cancelled_at, stripe_subscription_id, stripe_customer_id, stripe_event_created_at, (metadata -> 'stripe_metered_items')::text AS stripe_metered_items FROM user_subscriptions WHERE stripe_subscription_id = $1 ORDER BY created_at DESC LIMIT 1 FOR UPDATE"
+        )
+        .bind(&event.stripe_subscription_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        let Some(row) = existing else {
+            let user_id = match (event.user_id, &event.stripe_customer_id) {
+                (Some(user_id), _) => Some(user_id),
+                (None, Some(customer_id)) => sqlx::query_scalar::<_, Uuid>(
+                    "SELECT user_id FROM user_subscriptions WHERE stripe_customer_id = $1 ORDER BY created_at DESC LIMIT 1"
+                )
+                .bind(customer_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db_error)?,
+                (None, None) => None,
+            };
+            let subscription = match (user_id, plan_id) {
+                (Some(user_id), Some(plan_id)) => SyncedSubscription::from_event(event, user_id, plan_id),
+                _ => None,
+            };
+            let Some(subscription) = subscription else {
+                warn!(
+                    "Ignoring Stripe event {} for unknown subscription {}: no matching user, plan or billing period",
+                    event.event_id, event.stripe_subscription_id
+                );
+                return Ok(false);
+            };
+
+            // A concurrent delivery may have created the row since the lookup
+            // above; keep whichever event is newer
+            let result = sqlx::query(
+                r#"
+                INSERT INTO user_subscriptions (
+                    user_id, plan_id, status, current_period_start, current_period_end, trial_start, trial_end,
+                    cancel_at_period_end, cancelled_at, stripe_subscription_id, stripe_customer_id, stripe_event_created_at,
+                    metadata
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, jsonb_build_object('stripe_metered_items', $13::jsonb))
+                ON CONFLICT (stripe_subscription_id) WHERE stripe_subscription_id IS NOT NULL DO UPDATE
+                SET
+                    plan_id = EXCLUDED.plan_id,
+                    status = EXCLUDED.status,
+                    current_period_start = EXCLUDED.current_period_start,
+                    current_period_end = EXCLUDED.current_period_end,
+                    trial_start = EXCLUDED.trial_start,
+                    trial_end = EXCLUDED.trial_end,
+                    cancel_at_period_end = EXCLUDED.cancel_at_period_end,
+                    cancelled_at = EXCLUDED.cancelled_at,
+                    stripe_customer_id = COALESCE(EXCLUDED.stripe_customer_id, user_subscriptions.stripe_customer_id),
+                    stripe_event_created_at = EXCLUDED.stripe_event_created_at,
+                    metadata = COALESCE(user_subscriptions.metadata, '{}'::jsonb) || EXCLUDED.metadata,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE user_subscriptions.stripe_event_created_at IS NULL
+                    OR user_subscriptions.stripe_event_created_at <= EXCLUDED.stripe_event_created_at
+                "#
+            )
+            .bind(subscription.user_id)
+            .bind(subscription.plan_id)
+            .bind(status_column(subscription.status))
+            .bind(subscription.current_period_start)
+            .bind(subscription.current_period_end)
+            .bind(subscription.trial_start)
+            .bind(subscription.trial_end)
+            .bind(subscription.cancel_at_period_end)
+            .bind(subscription.cancelled_at)
+            .bind(&subscription.stripe_subscription_id)
+            .bind(&subscription.stripe_customer_id)
+            .bind(subscription.stripe_event_created_at)
+            .bind(metered_items_json(&subscription)?)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            if result.rows_affected() == 0 {
+                tx.commit().await.map_err(db_error)?;
+                info!(
+                    "Skipping out-of-order Stripe event {} for subscription {}",
+                    event.event_id, event.stripe_subscription_id
+                );
+                return Ok(false);
+            }
+            tx.commit().await.map_err(db_error)?;
+            info!("Synced subscription for user {} from Stripe event {}", subscription.user_id, event.event_id);
+            return Ok(true);
+        };
+
+        let id: Uuid = row.try_get("id").map_err(db_error)?;
+        let mut subscription = SyncedSubscription {
+            user_id: row.try_get("user_id").map_err(db_error)?,
+            plan_id: row.try_get("plan_id").map_err(db_error)?,
+            status: parse_status_column(row.try_get::<String, _>("status").map_err(db_error)?.as_str()),
+            current_period_start: row.try_get("current_period_start").map_err(db_error)?,
+            current_period_end: row.try_get("current_period_end").map_err(db_error)?,
+            trial_start: row.try_get("trial_start").map_err(db_error)?,
+            trial_end: row.try_get("trial_end").map_err(db_error)?,
+            cancel_at_period_end: row.try_get::<Option<bool>, _>("cancel_at_period_end").map_err(db_error)?.unwrap_or(false),
+            cancelled_at: row.try_get("cancelled_at").map_err(db_error)?,
+            stripe_subscription_id: row.try_get("stripe_subscription_id").map_err(db_error)?,
+            stripe_customer_id: row.try_get("stripe_customer_id").map_err(db_error)?,
+            stripe_event_created_at: row.try_get("stripe_event_created_at").map_err(db_error)?,
+            metered_items: row
+                .try_get::<Option<String>, _>("stripe_metered_items")
+                .map_err(db_error)?
+                .and_then(|items| serde_json::from_str(&items).ok())
+                .unwrap_or_default(),
+        };
+        if !subscription.apply(event, plan_id) {
+            info!(
+                "Skipping out-of-order Stripe event {} for subscription {}",
+                event.event_id, event.stripe_subscription_id
+            );
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE user_subscriptions
+            SET
+                plan_id = $2,
+                status = $3,
+                current_period_start = $4,
+                current_period_end = $5,
+                trial_start = $6,
+                trial_end = $7,
+                cancel_at_period_end = $8,
+                cancelled_at = $9,
+                stripe_customer_id = $10,
+                stripe_event_created_at = $11,
+                metadata = jsonb_set(COALESCE(metadata, '{}'::jsonb), '{stripe_metered_items}', $12::jsonb),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .bind(subscription.plan_id)
+        .bind(status_column(subscription.status))
+        .bind(subscription.current_period_start)
+        .bind(subscription.current_period_end)
+        .bind(subscription.trial_start)
+        .bind(subscription.trial_end)
+        .bind(subscription.cancel_at_period_end)
+        .bind(subscription.cancelled_at)
+        .bind(&subscription.stripe_customer_id)
+        .bind(subscription.stripe_event_created_at)
+        .bind(metered_items_json(&subscription)?)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(true)
+    }
+
     pub async fn track_usage(&self, user_id: Uuid, resource_type: &str, usage_count: i32) -> Result<(), ServiceError> {
         let current_month_start = chrono::Utc::now()
             .with_day(1)
@@ -440,3 +619,8 @@ impl BillingServiceDb {
         Ok(())
     }
 }
+
+/// `metadata.stripe_metered_items` for a subscription, bound as text and cast to JSONB
+fn metered_items_json(subscription: &SyncedSubscription) -> Result<String, ServiceError> {
+    serde_json::to_string(&subscription.metered_items).map_err(|e| ServiceError::InternalError(e.to_string()))
+}
@@ -1,5 +1,7 @@
 pub mod billing;
+pub mod stripe_sync;
+pub mod usage_flush;
 
 // Temporarily disabled until database tables are created
 // Run migration: database/migrations/009_create_billing_tables.sql
-pub mod billing_db;
\ No newline at end of file
+pub mod billing_db;
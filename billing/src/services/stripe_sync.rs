@@ -0,0 +1,370 @@
+//! Stripe subscription sync
+//!
+//! Keeps `user_subscriptions` in step with Stripe from webhook events, so
+//! entitlement checks see Stripe's state. Stripe may deliver events out of
+//! order, so each row remembers the creation time of the last event applied
+//! and older events are ignored.
+
+use chrono::{DateTime, TimeZone, Utc};
+use conhub_models::billing::SubscriptionStatus;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::errors::ServiceError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionEventKind {
+    /// `customer.subscription.created` or `customer.subscription.updated`
+    Updated,
+    /// `customer.subscription.deleted`
+    Deleted,
+    /// `invoice.paid`
+    InvoicePaid,
+}
+
+/// The subscription fields a Stripe event carries; unset fields are left as they are
+#[derive(Debug, Clone)]
+pub struct SubscriptionEvent {
+    pub event_id: String,
+    pub kind: SubscriptionEventKind,
+    /// When Stripe created the event, used to order deliveries
+    pub occurred_at: DateTime<Utc>,
+    pub stripe_subscription_id: String,
+    pub stripe_customer_id: Option<String>,
+    /// From the subscription's `metadata.user_id`, set at checkout
+    pub user_id: Option<Uuid>,
+    pub status: Option<SubscriptionStatus>,
+    pub stripe_price_id: Option<String>,
+    /// Metered subscription item per usage resource type; `None` when the
+    /// event doesn't list the subscription's items
+    pub metered_items: Option<BTreeMap<String, String>>,
+    pub current_period_start: Option<DateTime<Utc>>,
+    pub current_period_end: Option<DateTime<Utc>>,
+    pub cancel_at_period_end: Option<bool>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub trial_start: Option<DateTime<Utc>>,
+    pub trial_end: Option<DateTime<Utc>>,
+}
+
+/// Local subscription state kept in step with Stripe
+#[derive(Debug, Clone)]
+pub struct SyncedSubscription {
+    pub user_id: Uuid,
+    pub plan_id: Uuid,
+    pub status: SubscriptionStatus,
+    pub current_period_start: DateTime<Utc>,
+    pub current_period_end: DateTime<Utc>,
+    pub trial_start: Option<DateTime<Utc>>,
+    pub trial_end: Option<DateTime<Utc>>,
+    pub cancel_at_period_end: bool,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub stripe_subscription_id: Option<String>,
+    pub stripe_customer_id: Option<String>,
+    /// Stored as `metadata.stripe_metered_items`, where usage reporting looks
+    /// up the subscription item for each resource type
+    pub metered_items: BTreeMap<String, String>,
+    /// Creation time of the last Stripe event applied
+    pub stripe_event_created_at: Option<DateTime<Utc>>,
+}
+
+impl SyncedSubscription {
+    /// State for a subscription first seen through `event`. `None` when the
+    /// event doesn't carry a billing period, as `invoice.paid` doesn't.
+    pub fn from_event(event: &SubscriptionEvent, user_id: Uuid, plan_id: Uuid) -> Option<Self> {
+        if event.kind == SubscriptionEventKind::InvoicePaid {
+            return None;
+        }
+        let mut subscription = Self {
+            user_id,
+            plan_id,
+            status: SubscriptionStatus::Incomplete,
+            current_period_start: event.current_period_start?,
+            current_period_end: event.current_period_end?,
+            trial_start: None,
+            trial_end: None,
+            cancel_at_period_end: false,
+            cancelled_at: None,
+            stripe_subscription_id: Some(event.stripe_subscription_id.clone()),
+            stripe_customer_id: None,
+            metered_items: BTreeMap::new(),
+            stripe_event_created_at: None,
+        };
+        subscription.apply(event, Some(plan_id));
+        Some(subscription)
+    }
+
+    /// Apply `event` unless an event created after it was already applied.
+    /// `plan_id` is the local plan for the event's price, when it has one.
+    /// Returns whether anything was applied.
+    pub fn apply(&mut self, event: &SubscriptionEvent, plan_id: Option<Uuid>) -> bool {
+        if self.stripe_event_created_at.is_some_and(|last| event.occurred_at < last) {
+            return false;
+        }
+        self.stripe_event_created_at = Some(event.occurred_at);
+
+        match event.kind {
+            SubscriptionEventKind::Updated | SubscriptionEventKind::Deleted => {
+                if let Some(status) = event.status {
+                    self.status = status;
+                }
+                if event.kind == SubscriptionEventKind::Deleted {
+                    self.status = SubscriptionStatus::Cancelled;
+                    self.cancelled_at = event.cancelled_at.or(Some(event.occurred_at));
+                } else {
+                    self.cancelled_at = event.cancelled_at;
+                }
+                if let Some(plan_id) = plan_id {
+                    self.plan_id = plan_id;
+                }
+                if let (Some(start), Some(end)) = (event.current_period_start, event.current_period_end) {
+                    self.current_period_start = start;
+                    self.current_period_end = end;
+                }
+                self.cancel_at_period_end = event.cancel_at_period_end.unwrap_or(self.cancel_at_period_end);
+                self.trial_start = event.trial_start.or(self.trial_start);
+                self.trial_end = event.trial_end.or(self.trial_end);
+                if let Some(items) = &event.metered_items {
+                    self.metered_items = items.clone();
+                }
+            }
+            SubscriptionEventKind::InvoicePaid => {
+                // A late invoice doesn't revive a cancelled subscription
+                if !matches!(self.status, SubscriptionStatus::Cancelled) {
+                    self.status = SubscriptionStatus::Active;
+                }
+                if let Some(end) = event.current_period_end.filter(|end| *end > self.current_period_end) {
+                    self.current_period_start = event.current_period_start.unwrap_or(self.current_period_start);
+                    self.current_period_end = end;
+                }
+            }
+        }
+        if event.stripe_customer_id.is_some() {
+            self.stripe_customer_id = event.stripe_customer_id.clone();
+        }
+        true
+    }
+}
+
+/// Parse the subscription change in a Stripe webhook event. `None` for event
+/// types that don't affect subscriptions.
+pub fn parse_subscription_event(payload: &Value) -> Result<Option<SubscriptionEvent>, ServiceError> {
+    let kind = match payload["type"].as_str() {
+        Some("customer.subscription.created") | Some("customer.subscription.updated") => SubscriptionEventKind::Updated,
+        Some("customer.subscription.deleted") => SubscriptionEventKind::Deleted,
+        Some("invoice.paid") => SubscriptionEventKind::InvoicePaid,
+        _ => return Ok(None),
+    };
+    let event_id = payload["id"].as_str().unwrap_or_default().to_string();
+    let occurred_at = timestamp(&payload["created"])
+        .ok_or_else(|| ServiceError::ValidationError(format!("Stripe event {} has no created time", event_id)))?;
+    let object = &payload["data"]["object"];
+
+    let subscription_id = match kind {
+        SubscriptionEventKind::InvoicePaid => object["subscription"].as_str(),
+        _ => object["id"].as_str(),
+    };
+    // One-off invoices have no subscription to update
+    let Some(subscription_id) = subscription_id else {
+        return Ok(None);
+    };
+
+    let mut event = SubscriptionEvent {
+        event_id,
+        kind,
+        occurred_at,
+        stripe_subscription_id: subscription_id.to_string(),
+        stripe_customer_id: object["customer"].as_str().map(str::to_string),
+        user_id: None,
+        status: None,
+        stripe_price_id: None,
+        metered_items: None,
+        current_period_start: None,
+        current_period_end: None,
+        cancel_at_period_end: None,
+        cancelled_at: None,
+        trial_start: None,
+        trial_end: None,
+    };
+
+    if kind == SubscriptionEventKind::InvoicePaid {
+        // The subscription line carries the period the invoice paid for
+        let period = object["lines"]["data"]
+            .as_array()
+            .and_then(|lines| lines.iter().find(|l| l["type"] == "subscription").or(lines.first()))
+            .map(|line| &line["period"]);
+        if let Some(period) = period {
+            event.current_period_start = timestamp(&period["start"]);
+            event.current_period_end = timestamp(&period["end"]);
+        }
+        return Ok(Some(event));
+    }
+
+    event.user_id = object["metadata"]["user_id"].as_str().and_then(|id| Uuid::parse_str(id).ok());
+    event.status = object["status"].as_str().map(subscription_status);
+    event.stripe_price_id = object["items"]["data"][0]["price"]["id"]
+        .as_str()
+        .or_else(|| object["plan"]["id"].as_str())
+        .map(str::to_string);
+    event.metered_items = object["items"]["data"].as_array().map(|items| metered_items(items));
+    event.current_period_start = timestamp(&object["current_period_start"]);
+    event.current_period_end = timestamp(&object["current_period_end"]);
+    event.cancel_at_period_end = object["cancel_at_period_end"].as_bool();
+    event.cancelled_at = timestamp(&object["canceled_at"]);
+    event.trial_start = timestamp(&object["trial_start"]);
+    event.trial_end = timestamp(&object["trial_end"]);
+    Ok(Some(event))
+}
+
+/// Subscription item id per usage resource type, for the items whose price is
+/// metered. The resource type is the price's `metadata.resource_type`, or its
+/// `lookup_key` when that isn't set.
+fn metered_items(items: &[Value]) -> BTreeMap<String, String> {
+    items
+        .iter()
+        .filter(|item| item["price"]["recurring"]["usage_type"] == "metered")
+        .filter_map(|item| {
+            let price = &item["price"];
+            let resource_type = price["metadata"]["resource_type"].as_str().or_else(|| price["lookup_key"].as_str())?;
+            Some((resource_type.to_string(), item["id"].as_str()?.to_string()))
+        })
+        .collect()
+}
+
+/// Local status for a Stripe subscription status
+fn subscription_status(status: &str) -> SubscriptionStatus {
+    match status {
+        "active" => SubscriptionStatus::Active,
+        "trialing" => SubscriptionStatus::Trialing,
+        "past_due" => SubscriptionStatus::PastDue,
+        "unpaid" | "paused" => SubscriptionStatus::Unpaid,
+        "canceled" | "incomplete_expired" => SubscriptionStatus::Cancelled,
+        _ => SubscriptionStatus::Incomplete,
+    }
+}
+
+/// Value stored in `user_subscriptions.status`
+pub fn status_column(status: SubscriptionStatus) -> &'static str {
+    match status {
+        SubscriptionStatus::Active => "active",
+        SubscriptionStatus::Cancelled => "cancelled",
+        SubscriptionStatus::PastDue => "pastdue",
+        SubscriptionStatus::Unpaid => "unpaid",
+        SubscriptionStatus::Trialing => "trialing",
+        SubscriptionStatus::Incomplete => "incomplete",
+    }
+}
+
+pub fn parse_status_column(status: &str) -> SubscriptionStatus {
+    match status {
+        "active" => SubscriptionStatus::Active,
+        "cancelled" => SubscriptionStatus::Cancelled,
+        "pastdue" | "past_due" => SubscriptionStatus::PastDue,
+        "unpaid" => SubscriptionStatus::Unpaid,
+        "trialing" => SubscriptionStatus::Trialing,
+        _ => SubscriptionStatus::Incomplete,
+    }
+}
+
+fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    value.as_i64().and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn subscription_event(event_type: &str, created: i64, status: &str, price: &str) -> Value {
+        json!({
+            "id": format!("evt_{}", created),
+            "type": event_type,
+            "created": created,
+            "data": { "object": {
+                "id": "sub_123",
+                "customer": "cus_456",
+                "status": status,
+                "cancel_at_period_end": false,
+                "current_period_start": 1_700_000_000,
+                "current_period_end": 1_702_592_000,
+                "items": { "data": [{ "price": { "id": price } }] },
+                "metadata": { "user_id": "550e8400-e29b-41d4-a716-446655440000" }
+            }}
+        })
+    }
+
+    #[test]
+    fn test_updated_subscription_event_updates_local_state() {
+        let (starter, pro) = (Uuid::new_v4(), Uuid::new_v4());
+        let created = parse_subscription_event(&subscription_event("customer.subscription.created", 100, "trialing", "price_starter"))
+            .unwrap()
+            .unwrap();
+        let user_id = created.user_id.unwrap();
+        let mut local = SyncedSubscription::from_event(&created, user_id, starter).unwrap();
+        assert!(matches!(local.status, SubscriptionStatus::Trialing));
+
+        // Upgraded and now paying
+        let updated = parse_subscription_event(&subscription_event("customer.subscription.updated", 200, "active", "price_pro"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.stripe_price_id.as_deref(), Some("price_pro"));
+        assert!(local.apply(&updated, Some(pro)));
+        assert!(matches!(local.status, SubscriptionStatus::Active));
+        assert_eq!(local.plan_id, pro);
+        assert_eq!(local.stripe_customer_id.as_deref(), Some("cus_456"));
+        assert_eq!(local.stripe_event_created_at, Utc.timestamp_opt(200, 0).single());
+
+        // A past_due update created earlier but delivered late is ignored
+        let stale = parse_subscription_event(&subscription_event("customer.subscription.updated", 150, "past_due", "price_starter"))
+            .unwrap()
+            .unwrap();
+        assert!(!local.apply(&stale, Some(starter)));
+        assert!(matches!(local.status, SubscriptionStatus::Active));
+        assert_eq!(local.plan_id, pro);
+
+        let deleted = parse_subscription_event(&subscription_event("customer.subscription.deleted", 300, "canceled", "price_pro"))
+            .unwrap()
+            .unwrap();
+        assert!(local.apply(&deleted, None));
+        assert!(matches!(local.status, SubscriptionStatus::Cancelled));
+        assert!(local.cancelled_at.is_some());
+
+        assert!(parse_subscription_event(&json!({ "type": "charge.refunded", "created": 1 })).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_paid_invoice_extends_the_period() {
+        let mut local = SyncedSubscription {
+            user_id: Uuid::new_v4(),
+            plan_id: Uuid::new_v4(),
+            status: SubscriptionStatus::PastDue,
+            current_period_start: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+            current_period_end: Utc.timestamp_opt(1_702_592_000, 0).unwrap(),
+            trial_start: None,
+            trial_end: None,
+            cancel_at_period_end: false,
+            cancelled_at: None,
+            stripe_subscription_id: Some("sub_123".to_string()),
+            stripe_customer_id: Some("cus_456".to_string()),
+            metered_items: BTreeMap::new(),
+            stripe_event_created_at: Some(Utc.timestamp_opt(100, 0).unwrap()),
+        };
+        let paid = parse_subscription_event(&json!({
+            "id": "evt_paid",
+            "type": "invoice.paid",
+            "created": 400,
+            "data": { "object": {
+                "subscription": "sub_123",
+                "customer": "cus_456",
+                "lines": { "data": [{ "type": "subscription", "period": { "start": 1_702_592_000, "end": 1_705_270_400 } }] }
+            }}
+        }))
+        .unwrap()
+        .unwrap();
+
+        assert!(local.apply(&paid, None));
+        assert!(matches!(local.status, SubscriptionStatus::Active));
+        assert_eq!(local.current_period_end.timestamp(), 1_705_270_400);
+        assert!(SyncedSubscription::from_event(&paid, local.user_id, local.plan_id).is_none());
+    }
+}
@@ -0,0 +1,589 @@
+//! Metered usage reporting
+//!
+//! Usage (embeddings, queries) is counted locally in `usage_tracking` and
+//! reported to Stripe's metered subscription items on a timer. Each flush
+//! first moves the unreported part of every counter into a `usage_reports`
+//! row, then posts the unsent rows with the row id as the Stripe idempotency
+//! key. A post that fails, or whose response is lost, is retried with the same
+//! key and quantity, so Stripe counts the usage once.
+//!
+//! Stripe forgets idempotency keys after 24 hours, so a report that was
+//! attempted but not confirmed within `IDEMPOTENCY_WINDOW` is abandoned for
+//! manual reconciliation instead of risking a double count. Reports Stripe
+//! rejects outright, or that keep failing, are abandoned too.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::errors::ServiceError;
+
+pub const STRIPE_API_URL: &str = "https://api.stripe.com";
+
+/// Attempts before a report that keeps failing is abandoned
+pub const MAX_REPORT_ATTEMPTS: i32 = 8;
+
+/// How long a retry may reuse a report's idempotency key; Stripe keeps keys
+/// for 24 hours
+pub fn idempotency_window() -> chrono::Duration {
+    chrono::Duration::hours(23)
+}
+
+/// Usage waiting to be posted to one Stripe subscription item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageReport {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub resource_type: String,
+    pub stripe_subscription_item_id: String,
+    pub quantity: i64,
+    pub created_at: DateTime<Utc>,
+    /// Failed posts so far
+    pub attempts: i32,
+}
+
+impl UsageReport {
+    pub fn idempotency_key(&self) -> String {
+        format!("usage-report-{}", self.id)
+    }
+
+    /// Whether a retry could reach Stripe after it forgot this report's key.
+    /// A report never attempted can still be posted safely.
+    fn idempotency_expired(&self, now: DateTime<Utc>) -> bool {
+        self.attempts > 0 && now - self.created_at > idempotency_window()
+    }
+}
+
+/// Why posting a usage record failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageReportError {
+    /// Network errors, rate limits and server errors; worth retrying
+    Retryable(String),
+    /// Stripe refused the record, e.g. because the item was deleted
+    Rejected(String),
+}
+
+impl std::fmt::Display for UsageReportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UsageReportError::Retryable(msg) | UsageReportError::Rejected(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportState {
+    Pending,
+    Sent,
+    Abandoned,
+}
+
+#[derive(Default)]
+pub struct MemoryBuffer {
+    /// (usage count, reported count) per user and resource
+    usage: HashMap<(Uuid, String), (i64, i64)>,
+    /// Metered subscription item per user and resource
+    items: HashMap<(Uuid, String), String>,
+    reports: Vec<(UsageReport, ReportState)>,
+}
+
+/// Where usage is counted and pending reports are kept
+pub enum UsageBuffer {
+    Postgres(PgPool),
+    /// Process-local buffer; unreported usage is lost on restart
+    Memory(Mutex<MemoryBuffer>),
+}
+
+impl UsageBuffer {
+    pub fn in_memory() -> Self {
+        UsageBuffer::Memory(Mutex::new(MemoryBuffer::default()))
+    }
+
+    /// Count usage in the memory buffer; with Postgres, usage is recorded by
+    /// `BillingServiceDb::track_usage`
+    pub fn record(&self, user_id: Uuid, resource_type: &str, count: i64) {
+        if let UsageBuffer::Memory(buffer) = self {
+            let mut buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+            buffer.usage.entry((user_id, resource_type.to_string())).or_default().0 += count;
+        }
+    }
+
+    /// Set the memory buffer's metered subscription item for a user's resource.
+    /// With Postgres it comes from the subscription's
+    /// `metadata.stripe_metered_items`.
+    pub fn set_subscription_item(&self, user_id: Uuid, resource_type: &str, item_id: &str) {
+        if let UsageBuffer::Memory(buffer) = self {
+            let mut buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+            buffer.items.insert((user_id, resource_type.to_string()), item_id.to_string());
+        }
+    }
+
+    /// Move unreported usage into new reports. Usage with no metered item
+    /// stays unreported.
+    async fn claim_pending(&self, now: DateTime<Utc>) -> Result<(), ServiceError> {
+        match self {
+            UsageBuffer::Postgres(pool) => claim_pending_postgres(pool, now).await,
+            UsageBuffer::Memory(buffer) => {
+                let mut buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+                let MemoryBuffer { usage, items, reports } = &mut *buffer;
+                for ((user_id, resource_type), (count, reported)) in usage.iter_mut() {
+                    let Some(item_id) = items.get(&(*user_id, resource_type.clone())) else { continue };
+                    if *count <= *reported {
+                        continue;
+                    }
+                    reports.push((
+                        UsageReport {
+                            id: Uuid::new_v4(),
+                            user_id: *user_id,
+                            resource_type: resource_type.clone(),
+                            stripe_subscription_item_id: item_id.clone(),
+                            quantity: *count - *reported,
+                            created_at: now,
+                            attempts: 0,
+                        },
+                        ReportState::Pending,
+                    ));
+                    *reported = *count;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn unsent_reports(&self) -> Result<Vec<UsageReport>, ServiceError> {
+        match self {
+            UsageBuffer::Postgres(pool) => {
+                let rows = sqlx::query(
+                    "SELECT id, user_id, resource_type, stripe_subscription_item_id, quantity, created_at, attempts FROM usage_reports WHERE reported_at IS NULL AND abandoned_at IS NULL ORDER BY created_at"
+                )
+                .fetch_all(pool)
+                .await
+                .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+                rows.iter()
+                    .map(|row| {
+                        Ok(UsageReport {
+                            id: row.try_get("id")?,
+                            user_id: row.try_get("user_id")?,
+                            resource_type: row.try_get("resource_type")?,
+                            stripe_subscription_item_id: row.try_get("stripe_subscription_item_id")?,
+                            quantity: row.try_get("quantity")?,
+                            created_at: row.try_get("created_at")?,
+                            attempts: row.try_get("attempts")?,
+                        })
+                    })
+                    .collect::<Result<_, sqlx::Error>>()
+                    .map_err(|e| ServiceError::DatabaseError(e.to_string()))
+            }
+            UsageBuffer::Memory(buffer) => {
+                let buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+                Ok(buffer
+                    .reports
+                    .iter()
+                    .filter(|(_, state)| *state == ReportState::Pending)
+                    .map(|(r, _)| r.clone())
+                    .collect())
+            }
+        }
+    }
+
+    async fn mark_sent(&self, report_id: Uuid) -> Result<(), ServiceError> {
+        match self {
+            UsageBuffer::Postgres(pool) => {
+                sqlx::query("UPDATE usage_reports SET reported_at = CURRENT_TIMESTAMP, last_error = NULL WHERE id = $1")
+                    .bind(report_id)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+            }
+            UsageBuffer::Memory(_) => self.set_memory_state(report_id, ReportState::Sent, false),
+        }
+        Ok(())
+    }
+
+    async fn mark_failed(&self, report_id: Uuid, error: &str) -> Result<(), ServiceError> {
+        match self {
+            UsageBuffer::Postgres(pool) => {
+                sqlx::query("UPDATE usage_reports SET attempts = attempts + 1, last_error = $2 WHERE id = $1")
+                    .bind(report_id)
+                    .bind(error)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+            }
+            UsageBuffer::Memory(_) => self.set_memory_state(report_id, ReportState::Pending, true),
+        }
+        Ok(())
+    }
+
+    /// Stop retrying a report; it stays in `usage_reports` with its last error
+    async fn mark_abandoned(&self, report_id: Uuid, error: &str) -> Result<(), ServiceError> {
+        match self {
+            UsageBuffer::Postgres(pool) => {
+                sqlx::query(
+                    "UPDATE usage_reports SET abandoned_at = CURRENT_TIMESTAMP, last_error = $2 WHERE id = $1"
+                )
+                .bind(report_id)
+                .bind(error)
+                .execute(pool)
+                .await
+                .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+            }
+            UsageBuffer::Memory(_) => self.set_memory_state(report_id, ReportState::Abandoned, false),
+        }
+        Ok(())
+    }
+
+    fn set_memory_state(&self, report_id: Uuid, new_state: ReportState, failed: bool) {
+        if let UsageBuffer::Memory(buffer) = self {
+            let mut buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((report, state)) = buffer.reports.iter_mut().find(|(r, _)| r.id == report_id) {
+                *state = new_state;
+                if failed {
+                    report.attempts += 1;
+                }
+            }
+        }
+    }
+}
+
+async fn claim_pending_postgres(pool: &PgPool, now: DateTime<Utc>) -> Result<(), ServiceError> {
+    let db_error = |e: sqlx::Error| ServiceError::DatabaseError(e.to_string());
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    // Lock the counters so usage tracked meanwhile lands in the next flush
+    let pending = sqlx::query(
+        r#"
+        SELECT
+            u.id,
+            u.user_id,
+            u.resource_type,
+            u.usage_count - u.reported_count AS quantity,
+            u.usage_count,
+            (
+                SELECT s.metadata -> 'stripe_metered_items' ->> u.resource_type
+                FROM user_subscriptions s
+                WHERE s.user_id = u.user_id AND s.status != 'cancelled'
+                ORDER BY s.created_at DESC
+                LIMIT 1
+            ) AS item_id
+        FROM usage_tracking u
+        WHERE u.usage_count > u.reported_count
+        FOR UPDATE OF u
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    for row in pending {
+        let Some(item_id) = row.try_get::<Option<String>, _>("item_id").map_err(db_error)? else { continue };
+        let usage_id: Uuid = row.try_get("id").map_err(db_error)?;
+
+        sqlx::query(
+            "INSERT INTO usage_reports (user_id, resource_type, stripe_subscription_item_id, quantity, created_at) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(row.try_get::<Uuid, _>("user_id").map_err(db_error)?)
+        .bind(row.try_get::<String, _>("resource_type").map_err(db_error)?)
+        .bind(item_id)
+        .bind(row.try_get::<i32, _>("quantity").map_err(db_error)? as i64)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        sqlx::query("UPDATE usage_tracking SET reported_count = $2 WHERE id = $1")
+            .bind(usage_id)
+            .bind(row.try_get::<i32, _>("usage_count").map_err(db_error)?)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    }
+
+    tx.commit().await.map_err(db_error)
+}
+
+/// Posts usage records to Stripe's metered subscription items
+#[derive(Clone)]
+pub struct StripeUsageClient {
+    http: reqwest::Client,
+    api_url: String,
+    secret_key: String,
+}
+
+impl StripeUsageClient {
+    pub fn new(secret_key: String) -> Self {
+        Self { http: reqwest::Client::new(), api_url: STRIPE_API_URL.to_string(), secret_key }
+    }
+
+    /// Add the report's quantity to its subscription item. Stripe replays the
+    /// original response for a repeated idempotency key instead of counting again.
+    pub async fn post_usage_record(&self, report: &UsageReport) -> Result<(), UsageReportError> {
+        let url = format!("{}/v1/subscription_items/{}/usage_records", self.api_url, report.stripe_subscription_item_id);
+        let response = self
+            .http
+            .post(&url)
+            .basic_auth(&self.secret_key, None::<&str>)
+            .header("Idempotency-Key", report.idempotency_key())
+            .form(&[
+                ("quantity", report.quantity.to_string()),
+                ("timestamp", report.created_at.timestamp().to_string()),
+                ("action", "increment".to_string()),
+            ])
+            .send()
+            .await
+            .map_err(|e| UsageReportError::Retryable(format!("Stripe request failed: {}", e)))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let message = format!("Stripe returned {}: {}", status, response.text().await.unwrap_or_default());
+        // 409 is a concurrent request with the same idempotency key
+        Err(match status.as_u16() {
+            409 | 429 => UsageReportError::Retryable(message),
+            400..=499 => UsageReportError::Rejected(message),
+            _ => UsageReportError::Retryable(message),
+        })
+    }
+}
+
+pub struct UsageFlusher {
+    buffer: UsageBuffer,
+    interval: Duration,
+}
+
+impl UsageFlusher {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(300);
+
+    pub fn new(buffer: UsageBuffer, interval: Duration) -> Self {
+        Self { buffer, interval }
+    }
+
+    pub fn buffer(&self) -> &UsageBuffer {
+        &self.buffer
+    }
+
+    /// Report pending usage through `post`. A report that fails with a
+    /// retryable error stays unsent and is retried on the next flush with the
+    /// same idempotency key, up to `MAX_REPORT_ATTEMPTS` and within the key's
+    /// lifetime. Returns how many reports were posted.
+    pub async fn flush<F, Fut>(&self, now: DateTime<Utc>, post: F) -> Result<usize, ServiceError>
+    where
+        F: Fn(UsageReport) -> Fut,
+        Fut: Future<Output = Result<(), UsageReportError>>,
+    {
+        self.buffer.claim_pending(now).await?;
+
+        let mut posted = 0;
+        for report in self.buffer.unsent_reports().await? {
+            if report.idempotency_expired(now) {
+                self.abandon(&report, "idempotency key expired before Stripe confirmed the record").await?;
+                continue;
+            }
+            match post(report.clone()).await {
+                Ok(()) => {
+                    self.buffer.mark_sent(report.id).await?;
+                    posted += 1;
+                }
+                Err(UsageReportError::Retryable(e)) if report.attempts + 1 < MAX_REPORT_ATTEMPTS => {
+                    tracing::warn!(
+                        "Failed to report {} {} usage for user {}, will retry: {}",
+                        report.quantity, report.resource_type, report.user_id, e
+                    );
+                    self.buffer.mark_failed(report.id, &e).await?;
+                }
+                Err(e) => self.abandon(&report, &e.to_string()).await?,
+            }
+        }
+        Ok(posted)
+    }
+
+    async fn abandon(&self, report: &UsageReport, reason: &str) -> Result<(), ServiceError> {
+        tracing::error!(
+            "Giving up on reporting {} {} usage for user {} (report {}), reconcile manually: {}",
+            report.quantity, report.resource_type, report.user_id, report.id, reason
+        );
+        self.buffer.mark_abandoned(report.id, reason).await
+    }
+
+    /// Flush to Stripe every `interval`
+    pub fn spawn(self: Arc<Self>, stripe: StripeUsageClient) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            loop {
+                interval.tick().await;
+                let stripe = &stripe;
+                let result = self
+                    .flush(Utc::now(), |report| async move { stripe.post_usage_record(&report).await })
+                    .await;
+                match result {
+                    Ok(0) => {}
+                    Ok(posted) => tracing::info!("Reported {} usage records to Stripe", posted),
+                    Err(e) => tracing::error!("Usage flush failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Stripe's side: quantity counted per idempotency key
+    #[derive(Default)]
+    struct FakeStripe {
+        counted: Mutex<HashMap<String, i64>>,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl FakeStripe {
+        fn receive(&self, report: &UsageReport) {
+            let key = report.idempotency_key();
+            self.requests.lock().unwrap().push(key.clone());
+            self.counted.lock().unwrap().entry(key).or_insert(report.quantity);
+        }
+
+        fn total(&self) -> i64 {
+            self.counted.lock().unwrap().values().sum()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buffered_usage_is_posted_once_across_a_retry() {
+        let flusher = UsageFlusher::new(UsageBuffer::in_memory(), UsageFlusher::DEFAULT_INTERVAL);
+        let user = Uuid::new_v4();
+        flusher.buffer().set_subscription_item(user, "embeddings", "si_embed");
+        flusher.buffer().record(user, "embeddings", 3);
+        flusher.buffer().record(user, "embeddings", 2);
+        // No metered item for queries, so it stays buffered
+        flusher.buffer().record(user, "queries", 7);
+
+        let stripe = FakeStripe::default();
+        let lose_response = AtomicBool::new(true);
+        let post = |report: UsageReport| {
+            // Stripe records the usage but the first response never arrives
+            stripe.receive(&report);
+            let lost = lose_response.swap(false, Ordering::SeqCst);
+            async move { if lost { Err(UsageReportError::Retryable("connection reset".to_string())) } else { Ok(()) } }
+        };
+
+        assert_eq!(flusher.flush(Utc::now(), &post).await.unwrap(), 0);
+        assert_eq!(flusher.flush(Utc::now(), &post).await.unwrap(), 1);
+        assert_eq!(flusher.flush(Utc::now(), &post).await.unwrap(), 0);
+
+        let requests = stripe.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0], requests[1]);
+        assert_eq!(stripe.total(), 5);
+
+        // Later usage is a new report, not folded into the retried one
+        flusher.buffer().record(user, "embeddings", 4);
+        assert_eq!(flusher.flush(Utc::now(), &post).await.unwrap(), 1);
+        assert_eq!(stripe.total(), 9);
+    }
+
+    #[tokio::test]
+    async fn test_metered_items_from_subscription_webhook_are_claimed() {
+        use crate::services::stripe_sync::{parse_subscription_event, SyncedSubscription};
+
+        let payload = serde_json::json!({
+            "id": "evt_1",
+            "type": "customer.subscription.created",
+            "created": 1_700_000_000,
+            "data": { "object": {
+                "id": "sub_123",
+                "customer": "cus_456",
+                "status": "active",
+                "current_period_start": 1_700_000_000,
+                "current_period_end": 1_702_592_000,
+                "items": { "data": [
+                    { "id": "si_base", "price": { "id": "price_pro", "recurring": { "usage_type": "licensed" } } },
+                    { "id": "si_embed", "price": { "id": "price_embed", "lookup_key": "embeddings", "recurring": { "usage_type": "metered" } } },
+                    { "id": "si_query", "price": { "id": "price_query", "metadata": { "resource_type": "queries" }, "recurring": { "usage_type": "metered" } } }
+                ] },
+                "metadata": { "user_id": "550e8400-e29b-41d4-a716-446655440000" }
+            }}
+        });
+        let event = parse_subscription_event(&payload).unwrap().unwrap();
+        let user = event.user_id.unwrap();
+        let subscription = SyncedSubscription::from_event(&event, user, Uuid::new_v4()).unwrap();
+        assert_eq!(subscription.metered_items.len(), 2);
+
+        let flusher = UsageFlusher::new(UsageBuffer::in_memory(), UsageFlusher::DEFAULT_INTERVAL);
+        for (resource_type, item_id) in &subscription.metered_items {
+            flusher.buffer().set_subscription_item(user, resource_type, item_id);
+        }
+        flusher.buffer().record(user, "embeddings", 2);
+        flusher.buffer().record(user, "queries", 6);
+
+        let posted = Mutex::new(Vec::new());
+        let post = |report: UsageReport| {
+            posted.lock().unwrap().push((report.stripe_subscription_item_id.clone(), report.quantity));
+            async { Ok(()) }
+        };
+        assert_eq!(flusher.flush(Utc::now(), &post).await.unwrap(), 2);
+
+        let mut posted = posted.into_inner().unwrap();
+        posted.sort();
+        assert_eq!(posted, vec![("si_embed".to_string(), 2), ("si_query".to_string(), 6)]);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_and_expired_reports_are_abandoned() {
+        let flusher = UsageFlusher::new(UsageBuffer::in_memory(), UsageFlusher::DEFAULT_INTERVAL);
+        let user = Uuid::new_v4();
+        flusher.buffer().set_subscription_item(user, "embeddings", "si_deleted");
+        flusher.buffer().set_subscription_item(user, "queries", "si_query");
+        flusher.buffer().record(user, "embeddings", 1);
+        flusher.buffer().record(user, "queries", 1);
+
+        let calls = Mutex::new(Vec::new());
+        let post = |report: UsageReport| {
+            calls.lock().unwrap().push(report.stripe_subscription_item_id.clone());
+            let result = match report.stripe_subscription_item_id.as_str() {
+                "si_deleted" => Err(UsageReportError::Rejected("No such subscription item".to_string())),
+                _ => Err(UsageReportError::Retryable("Stripe returned 503".to_string())),
+            };
+            async move { result }
+        };
+
+        let start = Utc::now();
+        assert_eq!(flusher.flush(start, &post).await.unwrap(), 0);
+        assert_eq!(calls.lock().unwrap().len(), 2);
+
+        // The deleted item isn't retried; the outage is, until Stripe may have
+        // forgotten the key
+        assert_eq!(flusher.flush(start, &post).await.unwrap(), 0);
+        assert_eq!(*calls.lock().unwrap(), ["si_deleted", "si_query", "si_query"]);
+
+        let later = start + idempotency_window() + chrono::Duration::minutes(1);
+        assert_eq!(flusher.flush(later, &post).await.unwrap(), 0);
+        assert_eq!(calls.lock().unwrap().len(), 3);
+        assert!(flusher.buffer().unsent_reports().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_after_max_attempts() {
+        let flusher = UsageFlusher::new(UsageBuffer::in_memory(), UsageFlusher::DEFAULT_INTERVAL);
+        let user = Uuid::new_v4();
+        flusher.buffer().set_subscription_item(user, "embeddings", "si_embed");
+        flusher.buffer().record(user, "embeddings", 1);
+
+        let calls = Mutex::new(0);
+        let post = |_report: UsageReport| {
+            *calls.lock().unwrap() += 1;
+            async { Err(UsageReportError::Retryable("connection reset".to_string())) }
+        };
+        for _ in 0..MAX_REPORT_ATTEMPTS + 2 {
+            flusher.flush(Utc::now(), &post).await.unwrap();
+        }
+        assert_eq!(*calls.lock().unwrap(), MAX_REPORT_ATTEMPTS);
+    }
+}
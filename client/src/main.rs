@@ -1,7 +1,7 @@
 use actix_web::{web, App, HttpServer, middleware::Logger};
 use actix_cors::Cors;
-use sqlx::{PgPool, postgres::{PgPoolOptions, PgConnectOptions}};
-use std::str::FromStr;
+use sqlx::PgPool;
+use conhub_database::DatabaseConfig;
 use std::env;
 use tracing::{info, error};
 use tracing_subscriber;
@@ -45,14 +45,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tracing::info!("📊 [AI Service] Connecting to database...");
     }
 
-    // Disable server-side prepared statements for pgbouncer/Neon transaction pooling
-    let connect_options = PgConnectOptions::from_str(&database_url)?
-        .statement_cache_capacity(0);
-
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect_with(connect_options)
-        .await?;
+    let pool = conhub_database::connect(&DatabaseConfig::for_url(database_url)).await?;
     tracing::info!("✅ [AI Service] Database connection established");
     let db_pool_opt: Option<PgPool> = Some(pool);
 
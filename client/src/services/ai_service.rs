@@ -8,7 +8,7 @@ use reqwest::Client;
 use serde_json::{json, Value};
 use std::time::Duration;
 
-use conhub_models::{AgentRecord, AgentContext, AgentInvokeRequest, AgentInvokeResponse, AgentInvokeUsage};
+use conhub_models::{AgentConfig, AgentRecord, AgentContext, AgentInvokeRequest, AgentInvokeResponse, AgentInvokeUsage};
 use conhub_models::mcp::*;
 use crate::services::mcp_server::ConHubMcpServer;
 use crate::services::mcp_client::{McpClient, AuthConfig};
@@ -336,37 +336,6 @@ pub struct OpenAIUsage {
     total_tokens: u32,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct AnthropicRequest {
-    model: String,
-    messages: Vec<AnthropicMessage>,
-    max_tokens: u32,
-    temperature: Option<f32>,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct AnthropicMessage {
-    role: String,
-    content: String,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct AnthropicResponse {
-    content: Vec<AnthropicContent>,
-    usage: AnthropicUsage,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct AnthropicContent {
-    text: String,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct AnthropicUsage {
-    input_tokens: u32,
-    output_tokens: u32,
-}
-
 impl AgentService {
     #[allow(dead_code)]
     pub fn new() -> Self {
@@ -614,9 +583,10 @@ impl AgentService {
         request: &AgentInvokeRequest,
         mcp_context: &McpEnhancedContext,
     ) -> Result<AgentInvokeResponse, Box<dyn std::error::Error>> {
-        let model = agent.config.model.as_ref().unwrap_or(&"gpt-4".to_string()).clone();
-        let temperature = agent.config.temperature.unwrap_or(0.7);
-        let max_tokens = agent.config.max_tokens.unwrap_or(1000);
+        let config = validated_config(agent)?;
+        let model = config.model.as_ref().unwrap_or(&"gpt-4".to_string()).clone();
+        let temperature = config.temperature.unwrap_or(0.7);
+        let max_tokens = config.max_tokens.unwrap_or(1000);
         
         let mut messages = vec![];
         
@@ -633,7 +603,7 @@ impl AgentService {
         }
 
         
-        if let Some(instructions) = &agent.config.custom_instructions {
+        if let Some(instructions) = &config.custom_instructions {
             messages.push(OpenAIMessage {
                 role: "system".to_string(),
                 content: instructions.clone(),
@@ -697,9 +667,7 @@ impl AgentService {
         request: &AgentInvokeRequest,
         mcp_context: &McpEnhancedContext,
     ) -> Result<AgentInvokeResponse, Box<dyn std::error::Error>> {
-        let model = agent.config.model.as_ref().unwrap_or(&"claude-3-sonnet-20240229".to_string()).clone();
-        let temperature = agent.config.temperature.unwrap_or(0.7);
-        let max_tokens = agent.config.max_tokens.unwrap_or(1000);
+        let config = validated_config(agent)?;
 
         
         let context_summary = self.format_mcp_context_for_anthropic(mcp_context);
@@ -713,41 +681,8 @@ impl AgentService {
         }
         content.push_str(&request.message);
 
-        let messages = vec![AnthropicMessage {
-            role: "user".to_string(),
-            content,
-        }];
+        let (response_text, total_tokens) = self.send_chat(agent, &config, &content).await?;
 
-        let payload = AnthropicRequest {
-            model,
-            messages,
-            max_tokens,
-            temperature: Some(temperature),
-        };
-
-        let response = self.client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &agent.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API error: {}", error_text).into());
-        }
-
-        let anthropic_response: AnthropicResponse = response.json().await?;
-
-        let response_text = anthropic_response.content
-            .first()
-            .ok_or("No response from Anthropic")?
-            .text
-            .clone();
-
-        let total_tokens = anthropic_response.usage.input_tokens + anthropic_response.usage.output_tokens;
         let context_used = mcp_context.mcp_contexts.iter()
             .map(|ctx| ctx.name.clone())
             .collect::<Vec<_>>();
@@ -770,6 +705,7 @@ impl AgentService {
         request: &AgentInvokeRequest,
         mcp_context: &McpEnhancedContext,
     ) -> Result<AgentInvokeResponse, Box<dyn std::error::Error>> {
+        let config = validated_config(agent)?;
         let endpoint = agent.endpoint.as_ref()
             .ok_or("Custom agent endpoint not configured")?;
 
@@ -784,7 +720,7 @@ impl AgentService {
                 "metadata": mcp_context.context_metadata,
                 "formatted_summary": context_summary
             },
-            "config": agent.config,
+            "config": config,
             "include_history": request.include_history.unwrap_or(false)
         });
 
@@ -926,9 +862,10 @@ impl AgentService {
         request: &AgentInvokeRequest,
         context: Option<&AgentContext>,
     ) -> Result<AgentInvokeResponse, Box<dyn std::error::Error>> {
-        let model = agent.config.model.as_ref().unwrap_or(&"gpt-4".to_string()).clone();
-        let temperature = agent.config.temperature.unwrap_or(0.7);
-        let max_tokens = agent.config.max_tokens.unwrap_or(1000);
+        let config = validated_config(agent)?;
+        let model = config.model.as_ref().unwrap_or(&"gpt-4".to_string()).clone();
+        let temperature = config.temperature.unwrap_or(0.7);
+        let max_tokens = config.max_tokens.unwrap_or(1000);
         
         
         let _include_history = request.include_history.unwrap_or(false);
@@ -948,7 +885,7 @@ impl AgentService {
         }
         
         
-        if let Some(instructions) = &agent.config.custom_instructions {
+        if let Some(instructions) = &config.custom_instructions {
             messages.push(OpenAIMessage {
                 role: "system".to_string(),
                 content: instructions.clone(),
@@ -1010,10 +947,7 @@ impl AgentService {
         request: &AgentInvokeRequest,
         context: Option<&AgentContext>,
     ) -> Result<AgentInvokeResponse, Box<dyn std::error::Error>> {
-        let model = agent.config.model.as_ref().unwrap_or(&"claude-3-sonnet-20240229".to_string()).clone();
-        let temperature = agent.config.temperature.unwrap_or(0.7);
-        let max_tokens = agent.config.max_tokens.unwrap_or(1000);
-        
+        let config = validated_config(agent)?;
         let mut content = request.message.clone();
         
         
@@ -1023,45 +957,11 @@ impl AgentService {
         }
         
         
-        if let Some(instructions) = &agent.config.custom_instructions {
+        if let Some(instructions) = &config.custom_instructions {
             content = format!("Instructions: {}\n\n{}", instructions, content);
         }
         
-        let messages = vec![AnthropicMessage {
-            role: "user".to_string(),
-            content,
-        }];
-        
-        let payload = AnthropicRequest {
-            model,
-            messages,
-            max_tokens,
-            temperature: Some(temperature),
-        };
-        
-        let response = self.client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &agent.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
-        
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API error: {}", error_text).into());
-        }
-        
-        let anthropic_response: AnthropicResponse = response.json().await?;
-        
-        let response_text = anthropic_response.content
-            .first()
-            .ok_or("No response from Anthropic")?
-            .text
-            .clone();
-        
-        let total_tokens = anthropic_response.usage.input_tokens + anthropic_response.usage.output_tokens;
+        let (response_text, total_tokens) = self.send_chat(agent, &config, &content).await?;
         let context_used = context.map(|_| vec!["repositories".to_string(), "documents".to_string(), "urls".to_string()])
             .unwrap_or_default();
         
@@ -1075,6 +975,47 @@ impl AgentService {
         })
     }
 
+    /// POST a chat request in `config`'s API format, to the agent's endpoint when
+    /// one is set (a gateway) and to the provider's own API otherwise
+    fn chat_request(&self, agent: &AgentRecord, config: &AgentConfig, payload: &Value) -> reqwest::RequestBuilder {
+        let format = config.api_format();
+        let mut builder = self.client
+            .post(format.request_url(agent.endpoint.as_deref()))
+            .header("Content-Type", "application/json");
+        for (name, value) in format.headers(&agent.api_key, config.anthropic_api_version()) {
+            builder = builder.header(name, value);
+        }
+        builder.json(payload)
+    }
+
+    /// Send `content` as a single-turn chat and return the reply with the tokens used
+    async fn send_chat(
+        &self,
+        agent: &AgentRecord,
+        config: &AgentConfig,
+        content: &str,
+    ) -> Result<(String, u32), Box<dyn std::error::Error>> {
+        let format = config.api_format();
+        let model = config.chat_model()?;
+        let temperature = config.temperature.unwrap_or(0.7);
+        let max_tokens = config.max_tokens.unwrap_or(1000);
+
+        let mut payload = format.request_body(&model, None, content, max_tokens, Some(temperature));
+        if let Some(top_p) = config.top_p {
+            payload["top_p"] = json!(top_p);
+        }
+
+        let response = self.chat_request(agent, config, &payload).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            return Err(format!("Chat API error ({}): {}", status, error_text).into());
+        }
+
+        let body: Value = response.json().await?;
+        format.parse_response(&body).ok_or_else(|| "No response from the chat API".into())
+    }
+
     #[allow(dead_code)]
     async fn invoke_custom(
         &self,
@@ -1082,11 +1023,12 @@ impl AgentService {
         request: &AgentInvokeRequest,
         context: Option<&AgentContext>,
     ) -> Result<AgentInvokeResponse, Box<dyn std::error::Error>> {
+        let config = validated_config(agent)?;
         let endpoint = agent.endpoint.as_ref().ok_or("Custom agent requires endpoint")?;
         
         let mut payload = json!({
             "message": request.message,
-            "config": agent.config
+            "config": config
         });
         
         if let Some(ctx) = context {
@@ -1208,27 +1150,11 @@ impl AgentService {
 
     #[allow(dead_code)]
     async fn test_anthropic_connection(&self, agent: &AgentRecord) -> Result<bool, Box<dyn std::error::Error>> {
-        
-        let messages = vec![AnthropicMessage {
-            role: "user".to_string(),
-            content: "Hello".to_string(),
-        }];
-        
-        let payload = AnthropicRequest {
-            model: "claude-3-haiku-20240307".to_string(), 
-            messages,
-            max_tokens: 10,
-            temperature: Some(0.0),
-        };
-        
-        let response = self.client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &agent.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
+        let format = agent.config.api_format();
+        let model = agent.config.chat_model()?;
+        let payload = format.request_body(&model, None, "Hello", 10, Some(0.0));
+
+        let response = self.chat_request(agent, &agent.config, &payload).send().await?;
         
         Ok(response.status().is_success())
     }
@@ -1265,3 +1191,106 @@ impl AgentService {
         }
     }
 }
+
+/// The agent's config with `temperature` and `top_p` checked, or clamped, for the
+/// API it is called with, so no invoke path sends values the provider rejects
+fn validated_config(agent: &AgentRecord) -> Result<AgentConfig, String> {
+    let mut config = agent.config.clone();
+    config.validate_sampling_for(&agent.agent_type)?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+
+    /// OpenAI-compatible gateway that only answers bearer-authenticated chat completions
+    fn openai_gateway() -> String {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = HttpServer::new(|| {
+            App::new().route(
+                "/v1/chat/completions",
+                web::post().to(|req: HttpRequest, body: web::Json<Value>| async move {
+                    if req.headers().get("Authorization").and_then(|v| v.to_str().ok()) != Some("Bearer gw-key") {
+                        return HttpResponse::Unauthorized().finish();
+                    }
+                    HttpResponse::Ok().json(json!({
+                        "choices": [{ "message": { "role": "assistant", "content": format!("echo from {}", body["model"].as_str().unwrap_or_default()) } }],
+                        "usage": { "total_tokens": 7 }
+                    }))
+                }),
+            )
+        })
+        .listen(listener)
+        .unwrap()
+        .run();
+        drop(actix_web::rt::spawn(server));
+        url
+    }
+
+    fn gateway_agent(endpoint: &str, model: Option<&str>) -> AgentRecord {
+        serde_json::from_value(json!({
+            "id": "agent-1",
+            "user_id": "user-1",
+            "name": "Gateway",
+            "agent_type": "anthropic",
+            "endpoint": endpoint,
+            "api_key": "gw-key",
+            "permissions": [],
+            "status": "Connected",
+            "config": {
+                "model": model,
+                "temperature": null,
+                "max_tokens": null,
+                "timeout": null,
+                "custom_instructions": null,
+                "api_format": "openai"
+            },
+            "created_at": "",
+            "updated_at": "",
+            "last_used": null,
+            "usage_stats": { "total_requests": 0, "total_tokens": 0, "avg_response_time": null, "last_error": null }
+        }))
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_openai_format_agent_is_sent_to_its_gateway() {
+        let gateway = openai_gateway();
+        let service = AgentService::new();
+        let agent = gateway_agent(&gateway, Some("gpt-4o"));
+        let request = AgentInvokeRequest { message: "hi".to_string(), context_type: None, include_history: None };
+
+        let response = service.invoke_anthropic(&agent, &request, None).await.unwrap();
+        assert_eq!(response.response, "echo from gpt-4o");
+        assert_eq!(response.usage.tokens_used, 7);
+        assert!(service.test_anthropic_connection(&agent).await.unwrap());
+
+        // No Anthropic model is substituted for a gateway that needs its own
+        let agent = gateway_agent(&gateway, None);
+        let err = service.invoke_anthropic(&agent, &request, None).await.err().unwrap();
+        assert_eq!(err.to_string(), "model is required when api_format is openai");
+        assert!(service.test_anthropic_connection(&agent).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_out_of_range_temperature_is_rejected_on_every_invoke_path() {
+        let service = AgentService::new();
+        let request = AgentInvokeRequest { message: "hi".to_string(), context_type: None, include_history: None };
+        // Nothing listens here, so only validation can produce the expected error
+        let mut agent = gateway_agent("http://127.0.0.1:9", Some("gpt-4o"));
+        agent.config.temperature = Some(5.0);
+
+        for agent_type in ["anthropic", "openai", "custom"] {
+            agent.agent_type = agent_type.to_string();
+            let err = match agent_type {
+                "anthropic" => service.invoke_anthropic(&agent, &request, None).await.err(),
+                "openai" => service.invoke_openai(&agent, &request, None).await.err(),
+                _ => service.invoke_custom(&agent, &request, None).await.err(),
+            };
+            assert!(err.unwrap().to_string().starts_with("temperature 5 is outside the range"), "{}", agent_type);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+-- Migration: Add per-repository webhook secret to github_repo_configs
+-- Lets each connected repository verify webhook signatures with its own secret
+-- instead of the service-wide GITHUB_WEBHOOK_SECRET
+
+ALTER TABLE github_repo_configs
+ADD COLUMN IF NOT EXISTS webhook_secret VARCHAR(255);
+
+-- Add comment for documentation
+COMMENT ON COLUMN github_repo_configs.webhook_secret IS 'HMAC secret for this repository''s webhooks; NULL falls back to the global GITHUB_WEBHOOK_SECRET';
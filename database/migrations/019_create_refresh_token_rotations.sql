@@ -0,0 +1,14 @@
+-- Migration: Track rotated refresh tokens per session
+-- Each session is a refresh-token family. When a refresh token is rotated its
+-- hash is recorded here; presenting a recorded token again means it was replayed,
+-- and the whole session is revoked.
+
+CREATE TABLE IF NOT EXISTS refresh_token_rotations (
+    token_hash VARCHAR(64) PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES user_sessions(id) ON DELETE CASCADE,
+    rotated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_token_rotations_session_id ON refresh_token_rotations(session_id);
+
+COMMENT ON TABLE refresh_token_rotations IS 'SHA-256 hashes of refresh tokens that have been rotated out; reuse revokes the owning session';
@@ -0,0 +1,9 @@
+-- Migration: Add a resume token to github_sync_jobs
+-- Records which files a sync job has already ingested (path -> blob SHA) so a
+-- retried sync skips them instead of starting over
+
+ALTER TABLE github_sync_jobs
+ADD COLUMN IF NOT EXISTS resume_token JSONB NOT NULL DEFAULT '{}';
+
+-- Add comment for documentation
+COMMENT ON COLUMN github_sync_jobs.resume_token IS 'Files already ingested by this job as {"ingested": {path: blob_sha}}; retries skip matching files';
@@ -0,0 +1,13 @@
+-- Migration: Track the last Stripe event applied to each subscription
+-- Stripe webhooks can arrive out of order; the billing service skips events
+-- created before the one recorded here
+
+ALTER TABLE user_subscriptions
+ADD COLUMN IF NOT EXISTS stripe_event_created_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_user_subscriptions_stripe_subscription_id
+ON user_subscriptions(stripe_subscription_id)
+WHERE stripe_subscription_id IS NOT NULL;
+
+-- Add comment for documentation
+COMMENT ON COLUMN user_subscriptions.stripe_event_created_at IS 'Creation time of the last Stripe webhook event applied; older deliveries are ignored';
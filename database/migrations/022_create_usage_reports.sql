@@ -0,0 +1,29 @@
+-- Migration: Buffer metered usage for reporting to Stripe
+-- The billing service periodically moves usage_count - reported_count into a
+-- usage_reports row and posts it with the row id as the idempotency key, so a
+-- retried post is not counted twice
+
+ALTER TABLE usage_tracking
+ADD COLUMN IF NOT EXISTS reported_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS usage_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    resource_type VARCHAR(100) NOT NULL,
+    stripe_subscription_item_id VARCHAR(255) NOT NULL,
+    quantity BIGINT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    reported_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT fk_usage_reports_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_reports_unsent
+ON usage_reports(created_at)
+WHERE reported_at IS NULL;
+
+-- Add comments for documentation
+COMMENT ON COLUMN usage_tracking.reported_count IS 'Part of usage_count already moved into usage_reports';
+COMMENT ON TABLE usage_reports IS 'Usage records for Stripe metered subscription items; id is the Stripe idempotency key';
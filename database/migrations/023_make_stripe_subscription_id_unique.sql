@@ -0,0 +1,16 @@
+-- Migration: One local subscription per Stripe subscription
+-- Concurrent first deliveries for a subscription both inserted a row; with a
+-- unique index the billing service upserts instead
+
+-- Keep the most recently updated row where duplicates already exist
+DELETE FROM user_subscriptions s
+USING user_subscriptions newer
+WHERE s.stripe_subscription_id IS NOT NULL
+  AND s.stripe_subscription_id = newer.stripe_subscription_id
+  AND (s.updated_at, s.id) < (newer.updated_at, newer.id);
+
+DROP INDEX IF EXISTS idx_user_subscriptions_stripe_subscription_id;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_subscriptions_stripe_subscription_id
+ON user_subscriptions(stripe_subscription_id)
+WHERE stripe_subscription_id IS NOT NULL;
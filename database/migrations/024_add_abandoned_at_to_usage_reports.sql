@@ -0,0 +1,15 @@
+-- Migration: Stop retrying usage reports Stripe rejects or may double count
+-- Reports that Stripe rejected, that kept failing, or whose idempotency key
+-- expired before a retry are marked abandoned and left for reconciliation
+
+ALTER TABLE usage_reports
+ADD COLUMN IF NOT EXISTS abandoned_at TIMESTAMPTZ;
+
+DROP INDEX IF EXISTS idx_usage_reports_unsent;
+
+CREATE INDEX IF NOT EXISTS idx_usage_reports_unsent
+ON usage_reports(created_at)
+WHERE reported_at IS NULL AND abandoned_at IS NULL;
+
+-- Add comment for documentation
+COMMENT ON COLUMN usage_reports.abandoned_at IS 'Set when the report will not be retried; see last_error';
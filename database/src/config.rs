@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPoolOptions;
 use std::env;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
@@ -9,14 +11,23 @@ pub struct DatabaseConfig {
     pub min_connections: u32,
     pub connect_timeout_seconds: u64,
     pub idle_timeout_seconds: u64,
+    /// How long a query waits for a free connection before failing with `PoolTimedOut`
+    pub acquire_timeout_seconds: u64,
 }
 
 impl DatabaseConfig {
     pub fn from_env() -> Self {
-        Self {
-            database_url: env::var("DATABASE_URL")
+        Self::for_url(
+            env::var("DATABASE_URL")
                 .or_else(|_| env::var("DATABASE_URL_NEON"))
                 .expect("DATABASE_URL or DATABASE_URL_NEON must be set"),
+        )
+    }
+
+    /// Pool settings from the environment for a URL the caller resolved itself
+    pub fn for_url(database_url: impl Into<String>) -> Self {
+        Self {
+            database_url: database_url.into(),
             redis_url: env::var("REDIS_URL").ok(),
             max_connections: env::var("DB_MAX_CONNECTIONS")
                 .ok()
@@ -34,6 +45,10 @@ impl DatabaseConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(600),
+            acquire_timeout_seconds: env::var("DB_ACQUIRE_TIMEOUT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
         }
     }
 
@@ -45,6 +60,16 @@ impl DatabaseConfig {
             min_connections: 5,
             connect_timeout_seconds: 30,
             idle_timeout_seconds: 600,
+            acquire_timeout_seconds: 5,
         }
     }
+
+    /// Pool options with the configured size and timeouts
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections.max(1))
+            .min_connections(self.min_connections.min(self.max_connections.max(1)))
+            .idle_timeout(Duration::from_secs(self.idle_timeout_seconds))
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_seconds))
+    }
 }
@@ -5,6 +5,10 @@ pub use neo4j::Neo4jGraphDb;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 /// Graph database trait for knowledge graph operations
@@ -31,13 +35,31 @@ pub trait GraphDb: Send + Sync {
     /// Batch insert entities
     async fn batch_insert_entities(&self, entities: &[GraphEntity]) -> Result<usize>;
     
+    /// Upsert a batch of entities in one transaction, keyed by (source, source_id).
+    /// Returns one result per input entity, in input order.
+    async fn batch_upsert_entities(&self, entities: &[GraphEntity]) -> Result<Vec<EntityUpsertResult>>;
+    
     /// Batch insert relationships
     async fn batch_insert_relationships(&self, relationships: &[GraphRelationship]) -> Result<usize>;
     
+    /// Entities one hop away, optionally restricted by relationship type and direction
+    async fn get_neighbors(&self, id: Uuid, query: &NeighborQuery) -> Result<Vec<Neighbor>>;
+    
+    /// Subgraph reachable within `query.max_depth` hops, cut off at the query's limits
+    async fn traverse_graph(&self, id: Uuid, query: &TraversalQuery) -> Result<Traversal>;
+    
+    /// Fold `merged_id` into `kept_id`: its edges are re-pointed to the kept entity
+    /// and it is removed. Returns the provenance record needed to undo the merge.
+    async fn merge_entities(&self, kept_id: Uuid, merged_id: Uuid, merged_by: &str) -> Result<EntityMerge>;
+    
+    /// Undo a merge, restoring the absorbed entity and its original edges.
+    /// `None` when no such merge exists or it was already undone.
+    async fn unmerge_entities(&self, merge_id: Uuid) -> Result<Option<EntityMerge>>;
+    
     /// Find paths between entities
     async fn find_paths(&self, from_id: Uuid, to_id: Uuid, max_hops: usize) -> Result<Vec<EntityPath>>;
     
-    /// Get graph statistics
+    /// Get graph statistics, served from a short-lived cache
     async fn get_statistics(&self) -> Result<GraphStatistics>;
 }
 
@@ -55,6 +77,96 @@ pub struct GraphEntity {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// What happened to one entity of a batch upsert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityUpsertStatus {
+    Created,
+    Updated,
+    /// Repeated (source, source_id) within the batch; merged into a later entry
+    Duplicate,
+}
+
+/// Per-entity result of `batch_upsert_entities`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityUpsertResult {
+    /// Position in the submitted batch
+    pub index: usize,
+    /// Id of the stored entity (the existing id when it was already in the graph)
+    pub id: Uuid,
+    pub source: String,
+    pub source_id: String,
+    pub status: EntityUpsertStatus,
+}
+
+/// A batch of entities with duplicates collapsed, ready to be written once each
+#[derive(Debug, Clone)]
+pub struct EntityBatch {
+    /// Entities to write; for repeated keys the last occurrence wins
+    pub unique: Vec<GraphEntity>,
+    /// Input index of the entry kept for each unique entity
+    kept: Vec<usize>,
+    /// Unique entity each input entry resolves to
+    slots: Vec<usize>,
+}
+
+impl EntityBatch {
+    pub fn new(entities: &[GraphEntity]) -> Self {
+        let mut by_key: HashMap<(&str, &str), usize> = HashMap::new();
+        let mut unique: Vec<GraphEntity> = Vec::new();
+        let mut kept = Vec::new();
+        let mut slots = Vec::with_capacity(entities.len());
+
+        for (index, entity) in entities.iter().enumerate() {
+            let key = (entity.source.as_str(), entity.source_id.as_str());
+            match by_key.get(&key) {
+                Some(&slot) => {
+                    // Keep the first id so earlier references stay valid, take the newer fields
+                    let id = unique[slot].id;
+                    unique[slot] = GraphEntity { id, ..entity.clone() };
+                    kept[slot] = index;
+                    slots.push(slot);
+                }
+                None => {
+                    by_key.insert(key, unique.len());
+                    slots.push(unique.len());
+                    kept.push(index);
+                    unique.push(entity.clone());
+                }
+            }
+        }
+
+        Self { unique, kept, slots }
+    }
+
+    /// Expand write outcomes (stored id, whether it already existed) for each unique
+    /// entity back into one result per input entry
+    pub fn results(&self, written: &[(Uuid, bool)]) -> Vec<EntityUpsertResult> {
+        self.slots
+            .iter()
+            .enumerate()
+            .map(|(index, &slot)| {
+                let entity = &self.unique[slot];
+                let (id, existed) = written[slot];
+                let status = if self.kept[slot] != index {
+                    EntityUpsertStatus::Duplicate
+                } else if existed {
+                    EntityUpsertStatus::Updated
+                } else {
+                    EntityUpsertStatus::Created
+                };
+                EntityUpsertResult {
+                    index,
+                    id,
+                    source: entity.source.clone(),
+                    source_id: entity.source_id.clone(),
+                    status,
+                }
+            })
+            .collect()
+    }
+}
+
 /// Simplified relationship for graph storage
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphRelationship {
@@ -68,6 +180,340 @@ pub struct GraphRelationship {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Which edges to follow from the starting entity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NeighborDirection {
+    Outgoing,
+    Incoming,
+    #[default]
+    Both,
+}
+
+impl NeighborDirection {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "outgoing" | "out" => Some(Self::Outgoing),
+            "incoming" | "in" => Some(Self::Incoming),
+            "both" | "any" | "" => Some(Self::Both),
+            _ => None,
+        }
+    }
+}
+
+/// Raw neighbor query parameters, e.g. `?relation_types=AUTHORED,REVIEWED&direction=outgoing`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NeighborParams {
+    pub relation_types: Option<String>,
+    pub direction: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Validated neighbor filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeighborQuery {
+    /// Relationship labels to follow; empty means any
+    pub relation_types: Vec<String>,
+    pub direction: NeighborDirection,
+    pub limit: usize,
+}
+
+impl Default for NeighborQuery {
+    fn default() -> Self {
+        Self {
+            relation_types: Vec::new(),
+            direction: NeighborDirection::Both,
+            limit: Self::DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl NeighborQuery {
+    pub const DEFAULT_LIMIT: usize = 100;
+    pub const MAX_LIMIT: usize = 1000;
+
+    pub fn from_params(params: &NeighborParams) -> Result<Self> {
+        let direction = match params.direction.as_deref() {
+            Some(value) => NeighborDirection::parse(value)
+                .ok_or_else(|| anyhow::anyhow!("Invalid direction '{}': expected outgoing, incoming or both", value))?,
+            None => NeighborDirection::Both,
+        };
+
+        let mut relation_types: Vec<String> = params
+            .relation_types
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(relationship_label)
+            .filter(|t| !t.is_empty())
+            .collect();
+        relation_types.sort();
+        relation_types.dedup();
+
+        Ok(Self {
+            relation_types,
+            direction,
+            limit: params.limit.unwrap_or(Self::DEFAULT_LIMIT).clamp(1, Self::MAX_LIMIT),
+        })
+    }
+
+    /// Cypher pattern binding the start node `e`, relationship `r` and neighbor `n`
+    pub fn match_pattern(&self) -> String {
+        let types = if self.relation_types.is_empty() {
+            String::new()
+        } else {
+            format!(":{}", self.relation_types.join("|"))
+        };
+
+        match self.direction {
+            NeighborDirection::Outgoing => format!("(e:Entity {{id: $id}})-[r{}]->(n:Entity)", types),
+            NeighborDirection::Incoming => format!("(e:Entity {{id: $id}})<-[r{}]-(n:Entity)", types),
+            NeighborDirection::Both => format!("(e:Entity {{id: $id}})-[r{}]-(n:Entity)", types),
+        }
+    }
+}
+
+/// Cypher-safe relationship label: uppercase, with anything but letters, digits and `_` mapped to `_`
+pub fn relationship_label(relationship_type: &str) -> String {
+    relationship_type
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// An entity adjacent to the queried one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Neighbor {
+    pub entity: GraphEntity,
+    pub relationship_type: String,
+    pub direction: NeighborDirection,
+}
+
+/// Raw traversal parameters; unset limits default to the server maximums
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TraversalParams {
+    pub max_depth: Option<usize>,
+    pub relation_types: Option<String>,
+    pub direction: Option<String>,
+    pub max_nodes: Option<usize>,
+    pub max_edges: Option<usize>,
+    pub timeout_ms: Option<u64>,
+}
+
+/// Validated traversal request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraversalQuery {
+    /// Relationship types and direction to follow at every hop
+    pub filter: NeighborQuery,
+    pub max_depth: usize,
+    pub max_nodes: usize,
+    pub max_edges: usize,
+    pub timeout: Duration,
+}
+
+impl Default for TraversalQuery {
+    fn default() -> Self {
+        Self {
+            filter: NeighborQuery::default(),
+            max_depth: Self::DEFAULT_DEPTH,
+            max_nodes: Self::MAX_NODES,
+            max_edges: Self::MAX_EDGES,
+            timeout: Self::MAX_TIMEOUT,
+        }
+    }
+}
+
+impl TraversalQuery {
+    pub const DEFAULT_DEPTH: usize = 2;
+    pub const MAX_DEPTH: usize = 5;
+    pub const MAX_NODES: usize = 1000;
+    pub const MAX_EDGES: usize = 5000;
+    pub const MAX_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Requested limits are capped at the server maximums
+    pub fn from_params(params: &TraversalParams) -> Result<Self> {
+        let filter = NeighborQuery::from_params(&NeighborParams {
+            relation_types: params.relation_types.clone(),
+            direction: params.direction.clone(),
+            limit: None,
+        })?;
+
+        Ok(Self {
+            filter,
+            max_depth: params.max_depth.unwrap_or(Self::DEFAULT_DEPTH).clamp(1, Self::MAX_DEPTH),
+            max_nodes: params.max_nodes.unwrap_or(Self::MAX_NODES).clamp(1, Self::MAX_NODES),
+            max_edges: params.max_edges.unwrap_or(Self::MAX_EDGES).min(Self::MAX_EDGES),
+            timeout: params
+                .timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(Self::MAX_TIMEOUT)
+                .min(Self::MAX_TIMEOUT),
+        })
+    }
+
+    /// Cypher pattern binding the start node `e` and each variable-length `path`
+    pub fn match_pattern(&self) -> String {
+        let types = if self.filter.relation_types.is_empty() {
+            String::new()
+        } else {
+            format!(":{}", self.filter.relation_types.join("|"))
+        };
+        let hops = format!("[{}*1..{}]", types, self.max_depth);
+
+        match self.filter.direction {
+            NeighborDirection::Outgoing => format!("path = (e:Entity {{id: $id}})-{}->(n:Entity)", hops),
+            NeighborDirection::Incoming => format!("path = (e:Entity {{id: $id}})<-{}-(n:Entity)", hops),
+            NeighborDirection::Both => format!("path = (e:Entity {{id: $id}})-{}-(n:Entity)", hops),
+        }
+    }
+}
+
+/// Limit that cut a traversal short
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraversalLimit {
+    MaxNodes,
+    MaxEdges,
+    Timeout,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraversalEdge {
+    pub from_entity_id: Uuid,
+    pub to_entity_id: Uuid,
+    pub relationship_type: String,
+}
+
+/// Subgraph returned by `traverse_graph`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Traversal {
+    pub nodes: Vec<GraphEntity>,
+    pub edges: Vec<TraversalEdge>,
+    /// Whether a limit was hit before the whole subgraph was collected
+    pub truncated: bool,
+    pub truncated_by: Option<TraversalLimit>,
+}
+
+/// Accumulates a traversal's edges, nearest first, until a limit is reached.
+/// An edge is only kept when both of its endpoints fit within `max_nodes`.
+pub struct TraversalCollector {
+    max_nodes: usize,
+    max_edges: usize,
+    nodes: Vec<GraphEntity>,
+    node_ids: HashSet<Uuid>,
+    edges: Vec<TraversalEdge>,
+    edge_keys: HashSet<(Uuid, Uuid, String)>,
+    truncated_by: Option<TraversalLimit>,
+}
+
+impl TraversalCollector {
+    pub fn new(query: &TraversalQuery, root: GraphEntity) -> Self {
+        Self {
+            max_nodes: query.max_nodes,
+            max_edges: query.max_edges,
+            node_ids: HashSet::from([root.id]),
+            nodes: vec![root],
+            edges: Vec::new(),
+            edge_keys: HashSet::new(),
+            truncated_by: None,
+        }
+    }
+
+    /// Add one edge and any new endpoints. Returns false once a limit is hit and
+    /// collection should stop.
+    pub fn add_edge(&mut self, from: GraphEntity, to: GraphEntity, relationship_type: String) -> bool {
+        if self.truncated_by.is_some() {
+            return false;
+        }
+        let key = (from.id, to.id, relationship_type);
+        if self.edge_keys.contains(&key) {
+            return true;
+        }
+        if self.edges.len() >= self.max_edges {
+            self.truncated_by = Some(TraversalLimit::MaxEdges);
+            return false;
+        }
+        let new_nodes = [&from, &to]
+            .iter()
+            .filter(|n| !self.node_ids.contains(&n.id))
+            .map(|n| n.id)
+            .collect::<HashSet<_>>()
+            .len();
+        if self.nodes.len() + new_nodes > self.max_nodes {
+            self.truncated_by = Some(TraversalLimit::MaxNodes);
+            return false;
+        }
+
+        for node in [from, to] {
+            if self.node_ids.insert(node.id) {
+                self.nodes.push(node);
+            }
+        }
+        self.edges.push(TraversalEdge {
+            from_entity_id: key.0,
+            to_entity_id: key.1,
+            relationship_type: key.2.clone(),
+        });
+        self.edge_keys.insert(key);
+        true
+    }
+
+    pub fn timed_out(&mut self) {
+        self.truncated_by.get_or_insert(TraversalLimit::Timeout);
+    }
+
+    pub fn finish(self) -> Traversal {
+        Traversal {
+            nodes: self.nodes,
+            edges: self.edges,
+            truncated: self.truncated_by.is_some(),
+            truncated_by: self.truncated_by,
+        }
+    }
+}
+
+/// Provenance of a manual entity merge, holding everything needed to undo it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityMerge {
+    pub id: Uuid,
+    pub kept_id: Uuid,
+    /// The absorbed entity as it was before the merge
+    pub merged: GraphEntity,
+    /// The absorbed entity's edges as they were before the merge
+    pub original_edges: Vec<GraphRelationship>,
+    pub merged_by: String,
+    pub merged_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl EntityMerge {
+    pub fn new(kept_id: Uuid, merged: GraphEntity, original_edges: Vec<GraphRelationship>, merged_by: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kept_id,
+            merged,
+            original_edges,
+            merged_by: merged_by.to_string(),
+            merged_at: chrono::Utc::now(),
+        }
+    }
+
+    /// The absorbed entity's edges re-pointed at the kept entity, keeping their ids.
+    /// Edges between the two entities would become self-loops and are dropped.
+    pub fn repointed_edges(&self) -> Vec<GraphRelationship> {
+        let repoint = |id: Uuid| if id == self.merged.id { self.kept_id } else { id };
+        self.original_edges
+            .iter()
+            .map(|edge| GraphRelationship {
+                from_entity_id: repoint(edge.from_entity_id),
+                to_entity_id: repoint(edge.to_entity_id),
+                ..edge.clone()
+            })
+            .filter(|edge| edge.from_entity_id != edge.to_entity_id)
+            .collect()
+    }
+}
+
 /// Canonical entity (resolved across sources)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanonicalEntity {
@@ -93,6 +539,303 @@ pub struct EntityPath {
 pub struct GraphStatistics {
     pub total_entities: usize,
     pub total_relationships: usize,
-    pub entities_by_type: std::collections::HashMap<String, usize>,
-    pub entities_by_source: std::collections::HashMap<String, usize>,
+    pub entities_by_type: HashMap<String, usize>,
+    pub entities_by_source: HashMap<String, usize>,
+    /// Seconds since these figures were computed; 0 when freshly queried
+    #[serde(default)]
+    pub cache_age_secs: u64,
+}
+
+/// Caches `GraphStatistics` for a short TTL. Writes call `invalidate` so the next
+/// read recomputes instead of serving stale counts.
+pub struct StatisticsCache {
+    ttl: Duration,
+    generation: AtomicU64,
+    entry: tokio::sync::Mutex<Option<CachedStatistics>>,
+}
+
+struct CachedStatistics {
+    computed_at: Instant,
+    generation: u64,
+    statistics: GraphStatistics,
+}
+
+impl StatisticsCache {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            generation: AtomicU64::new(0),
+            entry: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// TTL from `GRAPH_STATS_CACHE_TTL_SECS`, defaulting to 30 seconds
+    pub fn from_env() -> Self {
+        let ttl = std::env::var("GRAPH_STATS_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Self::DEFAULT_TTL);
+        Self::new(ttl)
+    }
+
+    /// Drop the cached figures; called after every graph write
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Cached statistics if still fresh, otherwise the result of `load`.
+    /// Concurrent callers wait for a single load rather than each querying the graph.
+    pub async fn get_or_load<F, Fut>(&self, load: F) -> Result<GraphStatistics>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<GraphStatistics>>,
+    {
+        let mut entry = self.entry.lock().await;
+        let generation = self.generation.load(Ordering::SeqCst);
+
+        if let Some(cached) = entry.as_ref() {
+            let age = cached.computed_at.elapsed();
+            if cached.generation == generation && age < self.ttl {
+                return Ok(GraphStatistics {
+                    cache_age_secs: age.as_secs(),
+                    ..cached.statistics.clone()
+                });
+            }
+        }
+
+        let statistics = load().await?;
+        *entry = Some(CachedStatistics {
+            computed_at: Instant::now(),
+            generation,
+            statistics: statistics.clone(),
+        });
+        Ok(GraphStatistics { cache_age_secs: 0, ..statistics })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(source_id: &str, name: &str) -> GraphEntity {
+        let now = chrono::Utc::now();
+        GraphEntity {
+            id: Uuid::new_v4(),
+            entity_type: "person".to_string(),
+            source: "github".to_string(),
+            source_id: source_id.to_string(),
+            name: name.to_string(),
+            content: None,
+            properties: serde_json::json!({}),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Mirrors the MERGE on (source, source_id): returns the stored id and whether it existed
+    fn write(store: &mut HashMap<(String, String), Uuid>, batch: &EntityBatch) -> Vec<(Uuid, bool)> {
+        batch
+            .unique
+            .iter()
+            .map(|e| {
+                let key = (e.source.clone(), e.source_id.clone());
+                let existed = store.contains_key(&key);
+                (*store.entry(key).or_insert(e.id), existed)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_neighbor_query_filters_by_relation_type() {
+        let query = NeighborQuery::from_params(&NeighborParams {
+            relation_types: Some("reviewed, authored,AUTHORED".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(query.relation_types, vec!["AUTHORED", "REVIEWED"]);
+        assert_eq!(query.match_pattern(), "(e:Entity {id: $id})-[r:AUTHORED|REVIEWED]-(n:Entity)");
+
+        // Labels are interpolated into Cypher, so anything else is neutralised
+        let query = NeighborQuery::from_params(&NeighborParams {
+            relation_types: Some("depends-on]->(x) DETACH DELETE x//".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(query.relation_types[0].chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    }
+
+    #[test]
+    fn test_neighbor_query_filters_by_direction() {
+        let params = |direction: &str| NeighborParams {
+            relation_types: Some("depends_on".to_string()),
+            direction: Some(direction.to_string()),
+            limit: Some(50_000),
+        };
+
+        let outgoing = NeighborQuery::from_params(&params("outgoing")).unwrap();
+        assert_eq!(outgoing.match_pattern(), "(e:Entity {id: $id})-[r:DEPENDS_ON]->(n:Entity)");
+        assert_eq!(outgoing.limit, NeighborQuery::MAX_LIMIT);
+
+        let incoming = NeighborQuery::from_params(&params("in")).unwrap();
+        assert_eq!(incoming.match_pattern(), "(e:Entity {id: $id})<-[r:DEPENDS_ON]-(n:Entity)");
+
+        assert!(NeighborQuery::from_params(&params("sideways")).is_err());
+        assert_eq!(NeighborQuery::from_params(&NeighborParams::default()).unwrap(), NeighborQuery::default());
+    }
+
+    #[test]
+    fn test_broad_traversal_is_truncated_at_node_limit() {
+        let query = TraversalQuery::from_params(&TraversalParams {
+            max_nodes: Some(10),
+            max_depth: Some(50),
+            timeout_ms: Some(60_000),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(query.max_depth, TraversalQuery::MAX_DEPTH);
+        assert_eq!(query.max_edges, TraversalQuery::MAX_EDGES);
+        assert_eq!(query.timeout, TraversalQuery::MAX_TIMEOUT);
+
+        // A hub with 50 direct dependents, as a broad traversal would stream them
+        let hub = entity("hub", "billing-service");
+        let mut collector = TraversalCollector::new(&query, hub.clone());
+        let mut accepted = 0;
+        for i in 0..50 {
+            if !collector.add_edge(entity(&format!("u{}", i), "dependent"), hub.clone(), "DEPENDS_ON".to_string()) {
+                break;
+            }
+            accepted += 1;
+        }
+
+        let traversal = collector.finish();
+        assert_eq!(accepted, 9);
+        assert_eq!(traversal.nodes.len(), 10);
+        assert_eq!(traversal.edges.len(), 9);
+        assert!(traversal.truncated);
+        assert_eq!(traversal.truncated_by, Some(TraversalLimit::MaxNodes));
+
+        // Within the limits nothing is flagged
+        let mut collector = TraversalCollector::new(&TraversalQuery::default(), hub.clone());
+        assert!(collector.add_edge(hub.clone(), entity("u1", "Ada"), "OWNS".to_string()));
+        let traversal = collector.finish();
+        assert!(!traversal.truncated);
+        assert_eq!(query.match_pattern(), "path = (e:Entity {id: $id})-[*1..5]-(n:Entity)");
+    }
+
+    fn edge(relationship_type: &str, from: Uuid, to: Uuid) -> GraphRelationship {
+        GraphRelationship {
+            id: Uuid::new_v4(),
+            relationship_type: relationship_type.to_string(),
+            from_entity_id: from,
+            to_entity_id: to,
+            source: "github".to_string(),
+            confidence_score: 1.0,
+            properties: serde_json::json!({}),
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_merge_repoints_edges_and_unmerge_restores_split() {
+        let kept = entity("u1", "Ada Lovelace");
+        let duplicate = entity("ada@example.com", "Ada");
+        let repo = entity("repo-1", "billing");
+        let issue = entity("issue-7", "Fix invoices");
+
+        let edges = [
+            edge("AUTHORED", duplicate.id, repo.id),
+            edge("ASSIGNED", issue.id, duplicate.id),
+            edge("SAME_AS", kept.id, duplicate.id),
+            edge("OWNS", kept.id, repo.id),
+        ];
+        let touching = |e: &GraphRelationship| e.from_entity_id == duplicate.id || e.to_entity_id == duplicate.id;
+
+        // Merge: the duplicate's edges move onto the kept entity
+        let merge = EntityMerge::new(kept.id, duplicate.clone(), edges.iter().filter(|e| touching(e)).cloned().collect(), "admin-1");
+        let mut graph: Vec<GraphRelationship> = edges.iter().filter(|e| !touching(e)).cloned().collect();
+        graph.extend(merge.repointed_edges());
+
+        assert_eq!(graph.len(), 3);
+        assert!(!graph.iter().any(touching));
+        let authored = graph.iter().find(|e| e.relationship_type == "AUTHORED").unwrap();
+        assert_eq!((authored.id, authored.from_entity_id, authored.to_entity_id), (edges[0].id, kept.id, repo.id));
+        let assigned = graph.iter().find(|e| e.relationship_type == "ASSIGNED").unwrap();
+        assert_eq!((assigned.from_entity_id, assigned.to_entity_id), (issue.id, kept.id));
+
+        // The record survives storage and undoes the merge exactly
+        let merge: EntityMerge = serde_json::from_value(serde_json::to_value(&merge).unwrap()).unwrap();
+        let moved: HashSet<Uuid> = merge.repointed_edges().iter().map(|e| e.id).collect();
+        graph.retain(|e| !moved.contains(&e.id));
+        graph.extend(merge.original_edges.clone());
+
+        let key = |e: &GraphRelationship| (e.id, e.from_entity_id, e.to_entity_id);
+        let mut restored: Vec<_> = graph.iter().map(key).collect();
+        let mut original: Vec<_> = edges.iter().map(key).collect();
+        restored.sort();
+        original.sort();
+        assert_eq!(restored, original);
+        assert_eq!(merge.merged.id, duplicate.id);
+        assert_eq!(merge.merged_by, "admin-1");
+    }
+
+    #[tokio::test]
+    async fn test_statistics_cached_until_invalidated() {
+        use std::sync::atomic::AtomicUsize;
+
+        let loads = AtomicUsize::new(0);
+        let load = || async {
+            loads.fetch_add(1, Ordering::SeqCst);
+            Ok(GraphStatistics {
+                total_entities: loads.load(Ordering::SeqCst),
+                total_relationships: 0,
+                entities_by_type: HashMap::new(),
+                entities_by_source: HashMap::new(),
+                cache_age_secs: 0,
+            })
+        };
+
+        let cache = StatisticsCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get_or_load(load).await.unwrap().total_entities, 1);
+        assert_eq!(cache.get_or_load(load).await.unwrap().total_entities, 1);
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        // An ingestion invalidates the cache and the next read recomputes
+        cache.invalidate();
+        assert_eq!(cache.get_or_load(load).await.unwrap().total_entities, 2);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        // An expired entry is recomputed too
+        let cache = StatisticsCache::new(Duration::ZERO);
+        cache.get_or_load(load).await.unwrap();
+        cache.get_or_load(load).await.unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_batch_with_duplicate_is_idempotent() {
+        let entities = vec![entity("u1", "Ada"), entity("u2", "Grace"), entity("u1", "Ada Lovelace")];
+        let mut store = HashMap::new();
+
+        let batch = EntityBatch::new(&entities);
+        assert_eq!(batch.unique.len(), 2);
+        assert_eq!(batch.unique[0].name, "Ada Lovelace");
+
+        let first = batch.results(&write(&mut store, &batch));
+        let statuses: Vec<_> = first.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![EntityUpsertStatus::Duplicate, EntityUpsertStatus::Created, EntityUpsertStatus::Created]
+        );
+        assert_eq!(first[0].id, first[2].id);
+
+        // Replaying the same batch updates in place and resolves to the same ids
+        let second = batch.results(&write(&mut store, &batch));
+        assert_eq!(store.len(), 2);
+        assert_eq!(second[2].status, EntityUpsertStatus::Updated);
+        assert!(first.iter().zip(&second).all(|(a, b)| a.id == b.id));
+    }
 }
@@ -5,11 +5,16 @@ use std::sync::Arc;
 use uuid::Uuid;
 use std::collections::HashMap;
 
-use super::{GraphDb, GraphEntity, GraphRelationship, CanonicalEntity, EntityPath, GraphStatistics};
+use super::{
+    GraphDb, GraphEntity, GraphRelationship, CanonicalEntity, EntityPath, GraphStatistics,
+    EntityBatch, EntityMerge, EntityUpsertResult, Neighbor, NeighborDirection, NeighborQuery, StatisticsCache,
+    Traversal, TraversalCollector, TraversalQuery, relationship_label,
+};
 
 /// Neo4j implementation of GraphDb
 pub struct Neo4jGraphDb {
     graph: Arc<Graph>,
+    stats_cache: StatisticsCache,
 }
 
 impl Neo4jGraphDb {
@@ -21,10 +26,175 @@ impl Neo4jGraphDb {
         
         Ok(Self {
             graph: Arc::new(graph),
+            stats_cache: StatisticsCache::from_env(),
+        })
+    }
+
+    /// Every relationship touching the entity, in storage form
+    async fn relationships_of(&self, id: Uuid) -> Result<Vec<GraphRelationship>> {
+        let q = query(
+            r#"
+            MATCH (e:Entity {id: $id})-[r]-(:Entity)
+            RETURN DISTINCT r.id AS id, type(r) AS rel_type,
+                   startNode(r).id AS from_id, endNode(r).id AS to_id,
+                   r.source AS source, r.confidence_score AS confidence_score,
+                   r.properties AS properties, r.created_at AS created_at
+            "#
+        )
+        .param("id", id.to_string());
+
+        let mut result = self.graph.execute(q).await?;
+        let mut relationships = Vec::new();
+
+        while let Some(row) = result.next().await? {
+            relationships.push(GraphRelationship {
+                id: Uuid::parse_str(row.get::<String>("id")?.as_str())?,
+                relationship_type: row.get("rel_type")?,
+                from_entity_id: Uuid::parse_str(row.get::<String>("from_id")?.as_str())?,
+                to_entity_id: Uuid::parse_str(row.get::<String>("to_id")?.as_str())?,
+                source: row.get("source")?,
+                confidence_score: row.get::<f64>("confidence_score")? as f32,
+                properties: serde_json::from_str(&row.get::<String>("properties")?)?,
+                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String>("created_at")?)?.with_timezone(&chrono::Utc),
+            });
+        }
+
+        Ok(relationships)
+    }
+
+    async fn load_statistics(&self) -> Result<GraphStatistics> {
+        // Count total entities
+        let mut result = self.graph.execute(
+            query("MATCH (e:Entity) RETURN count(e) as count")
+        ).await?;
+        
+        let total_entities = if let Some(row) = result.next().await? {
+            row.get::<i64>("count")? as usize
+        } else {
+            0
+        };
+        
+        // Count total relationships
+        let mut result = self.graph.execute(
+            query("MATCH ()-[r]->() RETURN count(r) as count")
+        ).await?;
+        
+        let total_relationships = if let Some(row) = result.next().await? {
+            row.get::<i64>("count")? as usize
+        } else {
+            0
+        };
+        
+        // Count by type
+        let mut result = self.graph.execute(
+            query("MATCH (e:Entity) RETURN e.entity_type as type, count(e) as count")
+        ).await?;
+        
+        let mut entities_by_type = HashMap::new();
+        while let Some(row) = result.next().await? {
+            let entity_type: String = row.get("type")?;
+            let count: i64 = row.get("count")?;
+            entities_by_type.insert(entity_type, count as usize);
+        }
+        
+        // Count by source
+        let mut result = self.graph.execute(
+            query("MATCH (e:Entity) RETURN e.source as source, count(e) as count")
+        ).await?;
+        
+        let mut entities_by_source = HashMap::new();
+        while let Some(row) = result.next().await? {
+            let source: String = row.get("source")?;
+            let count: i64 = row.get("count")?;
+            entities_by_source.insert(source, count as usize);
+        }
+        
+        Ok(GraphStatistics {
+            total_entities,
+            total_relationships,
+            entities_by_type,
+            entities_by_source,
+            cache_age_secs: 0,
         })
     }
 }
 
+fn entity_from_node(node: &neo4rs::Node) -> Result<GraphEntity> {
+    Ok(GraphEntity {
+        id: Uuid::parse_str(node.get::<String>("id")?.as_str())?,
+        entity_type: node.get("entity_type")?,
+        source: node.get("source")?,
+        source_id: node.get("source_id")?,
+        name: node.get("name")?,
+        content: node.get::<String>("content").ok(),
+        properties: serde_json::from_str(&node.get::<String>("properties")?)?,
+        created_at: chrono::DateTime::parse_from_rfc3339(&node.get::<String>("created_at")?)?.with_timezone(&chrono::Utc),
+        updated_at: chrono::DateTime::parse_from_rfc3339(&node.get::<String>("updated_at")?)?.with_timezone(&chrono::Utc),
+    })
+}
+
+fn create_entity_query(entity: &GraphEntity) -> Result<neo4rs::Query> {
+    let properties_json = serde_json::to_string(&entity.properties)?;
+    
+    let q = query(
+        r#"
+        CREATE (e:Entity {
+            id: $id,
+            entity_type: $entity_type,
+            source: $source,
+            source_id: $source_id,
+            name: $name,
+            content: $content,
+            properties: $properties,
+            created_at: $created_at,
+            updated_at: $updated_at
+        })
+        "#
+    )
+    .param("id", entity.id.to_string())
+    .param("entity_type", entity.entity_type.clone())
+    .param("source", entity.source.clone())
+    .param("source_id", entity.source_id.clone())
+    .param("name", entity.name.clone())
+    .param("content", entity.content.clone().unwrap_or_default())
+    .param("properties", properties_json)
+    .param("created_at", entity.created_at.to_rfc3339())
+    .param("updated_at", entity.updated_at.to_rfc3339());
+
+    Ok(q)
+}
+
+fn create_relationship_query(relationship: &GraphRelationship) -> Result<neo4rs::Query> {
+    let properties_json = serde_json::to_string(&relationship.properties)?;
+    
+    // Sanitize relationship type for Cypher (remove special chars, use uppercase)
+    let rel_type = relationship_label(&relationship.relationship_type);
+    
+    let q = query(&format!(
+        r#"
+        MATCH (from:Entity {{id: $from_id}})
+        MATCH (to:Entity {{id: $to_id}})
+        CREATE (from)-[r:{} {{
+            id: $id,
+            source: $source,
+            confidence_score: $confidence_score,
+            properties: $properties,
+            created_at: $created_at
+        }}]->(to)
+        "#,
+        rel_type
+    ))
+    .param("from_id", relationship.from_entity_id.to_string())
+    .param("to_id", relationship.to_entity_id.to_string())
+    .param("id", relationship.id.to_string())
+    .param("source", relationship.source.clone())
+    .param("confidence_score", relationship.confidence_score as f64)
+    .param("properties", properties_json)
+    .param("created_at", relationship.created_at.to_rfc3339());
+
+    Ok(q)
+}
+
 #[async_trait]
 impl GraphDb for Neo4jGraphDb {
     async fn initialize(&self) -> Result<()> {
@@ -33,8 +203,10 @@ impl GraphDb for Neo4jGraphDb {
             "CREATE INDEX entity_id IF NOT EXISTS FOR (e:Entity) ON (e.id)",
             "CREATE INDEX entity_type IF NOT EXISTS FOR (e:Entity) ON (e.entity_type)",
             "CREATE INDEX entity_source IF NOT EXISTS FOR (e:Entity) ON (e.source)",
+            "CREATE INDEX entity_source_key IF NOT EXISTS FOR (e:Entity) ON (e.source, e.source_id)",
             "CREATE INDEX entity_name IF NOT EXISTS FOR (e:Entity) ON (e.name)",
             "CREATE INDEX canonical_id IF NOT EXISTS FOR (c:CanonicalEntity) ON (c.id)",
+            "CREATE INDEX entity_merge_id IF NOT EXISTS FOR (m:EntityMerge) ON (m.id)",
         ];
         
         for index_query in indexes {
@@ -45,34 +217,8 @@ impl GraphDb for Neo4jGraphDb {
     }
 
     async fn insert_entity(&self, entity: &GraphEntity) -> Result<()> {
-        let properties_json = serde_json::to_string(&entity.properties)?;
-        
-        let q = query(
-            r#"
-            CREATE (e:Entity {
-                id: $id,
-                entity_type: $entity_type,
-                source: $source,
-                source_id: $source_id,
-                name: $name,
-                content: $content,
-                properties: $properties,
-                created_at: $created_at,
-                updated_at: $updated_at
-            })
-            "#
-        )
-        .param("id", entity.id.to_string())
-        .param("entity_type", entity.entity_type.clone())
-        .param("source", entity.source.clone())
-        .param("source_id", entity.source_id.clone())
-        .param("name", entity.name.clone())
-        .param("content", entity.content.clone().unwrap_or_default())
-        .param("properties", properties_json)
-        .param("created_at", entity.created_at.to_rfc3339())
-        .param("updated_at", entity.updated_at.to_rfc3339());
-        
-        self.graph.run(q).await?;
+        self.graph.run(create_entity_query(entity)?).await?;
+        self.stats_cache.invalidate();
         Ok(())
     }
 
@@ -95,6 +241,7 @@ impl GraphDb for Neo4jGraphDb {
         .param("updated_at", entity.updated_at.to_rfc3339());
         
         self.graph.run(q).await?;
+        self.stats_cache.invalidate();
         Ok(())
     }
 
@@ -106,57 +253,15 @@ impl GraphDb for Neo4jGraphDb {
         
         if let Some(row) = result.next().await? {
             let node: neo4rs::Node = row.get("e")?;
-            
-            let entity = GraphEntity {
-                id: Uuid::parse_str(node.get::<String>("id")?.as_str())?,
-                entity_type: node.get("entity_type")?,
-                source: node.get("source")?,
-                source_id: node.get("source_id")?,
-                name: node.get("name")?,
-                content: node.get::<String>("content").ok(),
-                properties: serde_json::from_str(&node.get::<String>("properties")?)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&node.get::<String>("created_at")?)?.with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&node.get::<String>("updated_at")?)?.with_timezone(&chrono::Utc),
-            };
-            
-            Ok(Some(entity))
+            Ok(Some(entity_from_node(&node)?))
         } else {
             Ok(None)
         }
     }
 
     async fn insert_relationship(&self, relationship: &GraphRelationship) -> Result<()> {
-        let properties_json = serde_json::to_string(&relationship.properties)?;
-        
-        // Sanitize relationship type for Cypher (remove special chars, use uppercase)
-        let rel_type = relationship.relationship_type
-            .to_uppercase()
-            .replace("-", "_")
-            .replace(" ", "_");
-        
-        let q = query(&format!(
-            r#"
-            MATCH (from:Entity {{id: $from_id}})
-            MATCH (to:Entity {{id: $to_id}})
-            CREATE (from)-[r:{} {{
-                id: $id,
-                source: $source,
-                confidence_score: $confidence_score,
-                properties: $properties,
-                created_at: $created_at
-            }}]->(to)
-            "#,
-            rel_type
-        ))
-        .param("from_id", relationship.from_entity_id.to_string())
-        .param("to_id", relationship.to_entity_id.to_string())
-        .param("id", relationship.id.to_string())
-        .param("source", relationship.source.clone())
-        .param("confidence_score", relationship.confidence_score as f64)
-        .param("properties", properties_json)
-        .param("created_at", relationship.created_at.to_rfc3339());
-        
-        self.graph.run(q).await?;
+        self.graph.run(create_relationship_query(relationship)?).await?;
+        self.stats_cache.invalidate();
         Ok(())
     }
 
@@ -185,6 +290,7 @@ impl GraphDb for Neo4jGraphDb {
         .param("created_at", canonical.created_at.to_rfc3339());
         
         self.graph.run(q).await?;
+        self.stats_cache.invalidate();
         Ok(())
     }
 
@@ -198,6 +304,50 @@ impl GraphDb for Neo4jGraphDb {
         Ok(count)
     }
 
+    async fn batch_upsert_entities(&self, entities: &[GraphEntity]) -> Result<Vec<EntityUpsertResult>> {
+        let batch = EntityBatch::new(entities);
+        let mut txn = self.graph.start_txn().await?;
+        let mut written = Vec::with_capacity(batch.unique.len());
+
+        for entity in &batch.unique {
+            let properties_json = serde_json::to_string(&entity.properties)?;
+
+            let q = query(
+                r#"
+                OPTIONAL MATCH (existing:Entity {source: $source, source_id: $source_id})
+                WITH existing IS NOT NULL AS existed
+                MERGE (e:Entity {source: $source, source_id: $source_id})
+                ON CREATE SET e.id = $id, e.created_at = $created_at
+                SET e.entity_type = $entity_type,
+                    e.name = $name,
+                    e.content = $content,
+                    e.properties = $properties,
+                    e.updated_at = $updated_at
+                RETURN e.id AS id, existed
+                "#
+            )
+            .param("id", entity.id.to_string())
+            .param("entity_type", entity.entity_type.clone())
+            .param("source", entity.source.clone())
+            .param("source_id", entity.source_id.clone())
+            .param("name", entity.name.clone())
+            .param("content", entity.content.clone().unwrap_or_default())
+            .param("properties", properties_json)
+            .param("created_at", entity.created_at.to_rfc3339())
+            .param("updated_at", entity.updated_at.to_rfc3339());
+
+            let mut stream = txn.execute(q).await?;
+            let row = stream.next(txn.handle()).await?
+                .context("Entity upsert returned no row")?;
+            let id = Uuid::parse_str(row.get::<String>("id")?.as_str())?;
+            written.push((id, row.get::<bool>("existed")?));
+        }
+
+        txn.commit().await.context("Failed to commit entity batch")?;
+        self.stats_cache.invalidate();
+        Ok(batch.results(&written))
+    }
+
     async fn batch_insert_relationships(&self, relationships: &[GraphRelationship]) -> Result<usize> {
         let mut count = 0;
         for relationship in relationships {
@@ -208,6 +358,158 @@ impl GraphDb for Neo4jGraphDb {
         Ok(count)
     }
 
+    async fn get_neighbors(&self, id: Uuid, neighbor_query: &NeighborQuery) -> Result<Vec<Neighbor>> {
+        let q = query(&format!(
+            r#"
+            MATCH {}
+            RETURN n, type(r) AS rel_type, startNode(r) = e AS outgoing
+            LIMIT {}
+            "#,
+            neighbor_query.match_pattern(),
+            neighbor_query.limit
+        ))
+        .param("id", id.to_string());
+
+        let mut result = self.graph.execute(q).await?;
+        let mut neighbors = Vec::new();
+
+        while let Some(row) = result.next().await? {
+            let node: neo4rs::Node = row.get("n")?;
+            let direction = if row.get::<bool>("outgoing")? {
+                NeighborDirection::Outgoing
+            } else {
+                NeighborDirection::Incoming
+            };
+            neighbors.push(Neighbor {
+                entity: entity_from_node(&node)?,
+                relationship_type: row.get("rel_type")?,
+                direction,
+            });
+        }
+
+        Ok(neighbors)
+    }
+
+    async fn traverse_graph(&self, id: Uuid, traversal_query: &TraversalQuery) -> Result<Traversal> {
+        let deadline = tokio::time::Instant::now() + traversal_query.timeout;
+        let Some(root) = self.find_entity(id).await? else {
+            anyhow::bail!("Entity {} not found", id);
+        };
+
+        // Shortest paths first so a truncated result keeps the nearest entities.
+        // One edge past the limit is fetched to tell a cut-off from an exact fit.
+        let q = query(&format!(
+            r#"
+            MATCH {}
+            WITH path ORDER BY length(path)
+            UNWIND relationships(path) AS r
+            WITH DISTINCT r
+            RETURN startNode(r) AS a, type(r) AS rel_type, endNode(r) AS b
+            LIMIT {}
+            "#,
+            traversal_query.match_pattern(),
+            traversal_query.max_edges + 1
+        ))
+        .param("id", id.to_string());
+
+        let mut collector = TraversalCollector::new(traversal_query, root);
+        let mut result = match tokio::time::timeout_at(deadline, self.graph.execute(q)).await {
+            Ok(result) => result?,
+            Err(_) => {
+                collector.timed_out();
+                return Ok(collector.finish());
+            }
+        };
+
+        loop {
+            let row = match tokio::time::timeout_at(deadline, result.next()).await {
+                Ok(row) => row?,
+                Err(_) => {
+                    tracing::warn!("Traversal from {} timed out after {:?}", id, traversal_query.timeout);
+                    collector.timed_out();
+                    break;
+                }
+            };
+            let Some(row) = row else { break };
+
+            let from: neo4rs::Node = row.get("a")?;
+            let to: neo4rs::Node = row.get("b")?;
+            if !collector.add_edge(entity_from_node(&from)?, entity_from_node(&to)?, row.get("rel_type")?) {
+                break;
+            }
+        }
+
+        Ok(collector.finish())
+    }
+
+    async fn merge_entities(&self, kept_id: Uuid, merged_id: Uuid, merged_by: &str) -> Result<EntityMerge> {
+        if kept_id == merged_id {
+            anyhow::bail!("Cannot merge entity {} into itself", kept_id);
+        }
+        if self.find_entity(kept_id).await?.is_none() {
+            anyhow::bail!("Entity {} not found", kept_id);
+        }
+        let merged = self.find_entity(merged_id).await?
+            .with_context(|| format!("Entity {} not found", merged_id))?;
+
+        let merge = EntityMerge::new(kept_id, merged, self.relationships_of(merged_id).await?, merged_by);
+        let mut txn = self.graph.start_txn().await?;
+
+        for relationship in merge.repointed_edges() {
+            txn.run(create_relationship_query(&relationship)?).await?;
+        }
+        txn.run(query("MATCH (m:Entity {id: $id}) DETACH DELETE m").param("id", merged_id.to_string())).await?;
+        txn.run(
+            query(
+                r#"
+                CREATE (:EntityMerge {
+                    id: $id,
+                    kept_id: $kept_id,
+                    merged_id: $merged_id,
+                    merged_by: $merged_by,
+                    merged_at: $merged_at,
+                    record: $record
+                })
+                "#
+            )
+            .param("id", merge.id.to_string())
+            .param("kept_id", kept_id.to_string())
+            .param("merged_id", merged_id.to_string())
+            .param("merged_by", merged_by.to_string())
+            .param("merged_at", merge.merged_at.to_rfc3339())
+            .param("record", serde_json::to_string(&merge)?),
+        ).await?;
+
+        txn.commit().await.context("Failed to commit entity merge")?;
+        self.stats_cache.invalidate();
+        tracing::info!("Merged entity {} into {} ({} edges re-pointed)", merged_id, kept_id, merge.original_edges.len());
+        Ok(merge)
+    }
+
+    async fn unmerge_entities(&self, merge_id: Uuid) -> Result<Option<EntityMerge>> {
+        let mut result = self.graph.execute(
+            query("MATCH (m:EntityMerge {id: $id}) RETURN m.record AS record")
+                .param("id", merge_id.to_string())
+        ).await?;
+        let Some(row) = result.next().await? else { return Ok(None) };
+        let merge: EntityMerge = serde_json::from_str(&row.get::<String>("record")?)?;
+
+        let mut txn = self.graph.start_txn().await?;
+        for relationship in merge.repointed_edges() {
+            txn.run(query("MATCH ()-[r {id: $id}]->() DELETE r").param("id", relationship.id.to_string())).await?;
+        }
+        txn.run(create_entity_query(&merge.merged)?).await?;
+        for relationship in &merge.original_edges {
+            txn.run(create_relationship_query(relationship)?).await?;
+        }
+        txn.run(query("MATCH (m:EntityMerge {id: $id}) DELETE m").param("id", merge_id.to_string())).await?;
+
+        txn.commit().await.context("Failed to commit entity unmerge")?;
+        self.stats_cache.invalidate();
+        tracing::info!("Undid merge {}: restored entity {}", merge_id, merge.merged.id);
+        Ok(Some(merge))
+    }
+
     async fn find_paths(&self, from_id: Uuid, to_id: Uuid, max_hops: usize) -> Result<Vec<EntityPath>> {
         let q = query(&format!(
             r#"
@@ -232,57 +534,6 @@ impl GraphDb for Neo4jGraphDb {
     }
 
     async fn get_statistics(&self) -> Result<GraphStatistics> {
-        // Count total entities
-        let mut result = self.graph.execute(
-            query("MATCH (e:Entity) RETURN count(e) as count")
-        ).await?;
-        
-        let total_entities = if let Some(row) = result.next().await? {
-            row.get::<i64>("count")? as usize
-        } else {
-            0
-        };
-        
-        // Count total relationships
-        let mut result = self.graph.execute(
-            query("MATCH ()-[r]->() RETURN count(r) as count")
-        ).await?;
-        
-        let total_relationships = if let Some(row) = result.next().await? {
-            row.get::<i64>("count")? as usize
-        } else {
-            0
-        };
-        
-        // Count by type
-        let mut result = self.graph.execute(
-            query("MATCH (e:Entity) RETURN e.entity_type as type, count(e) as count")
-        ).await?;
-        
-        let mut entities_by_type = HashMap::new();
-        while let Some(row) = result.next().await? {
-            let entity_type: String = row.get("type")?;
-            let count: i64 = row.get("count")?;
-            entities_by_type.insert(entity_type, count as usize);
-        }
-        
-        // Count by source
-        let mut result = self.graph.execute(
-            query("MATCH (e:Entity) RETURN e.source as source, count(e) as count")
-        ).await?;
-        
-        let mut entities_by_source = HashMap::new();
-        while let Some(row) = result.next().await? {
-            let source: String = row.get("source")?;
-            let count: i64 = row.get("count")?;
-            entities_by_source.insert(source, count as usize);
-        }
-        
-        Ok(GraphStatistics {
-            total_entities,
-            total_relationships,
-            entities_by_type,
-            entities_by_source,
-        })
+        self.stats_cache.get_or_load(|| self.load_statistics()).await
     }
 }
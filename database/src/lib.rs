@@ -7,6 +7,7 @@ pub mod config;
 pub mod cache;
 pub mod utils;
 pub mod graph;
+pub mod pool;
 
 // Re-export commonly used items
 pub use sqlx;
@@ -14,8 +15,9 @@ pub use uuid;
 pub use chrono;
 pub use config::DatabaseConfig;
 pub use cache::RedisCache;
+pub use pool::{connect, connect_options};
 
-use sqlx::{PgPool, postgres::PgPoolOptions};
+use sqlx::PgPool;
 use anyhow::{Result, Context};
 
 /// Database connection manager
@@ -28,11 +30,7 @@ pub struct Database {
 impl Database {
     /// Create a new database instance from configuration
     pub async fn new(config: &DatabaseConfig) -> Result<Self> {
-        let pool = PgPoolOptions::new()
-            .max_connections(config.max_connections)
-            .connect(&config.database_url)
-            .await
-            .context("Failed to connect to database")?;
+        let pool = connect(config).await?;
 
         let cache = if let Some(ref redis_url) = config.redis_url {
             match RedisCache::new(redis_url).await {
@@ -0,0 +1,122 @@
+//! Code Analyzer
+//!
+//! Tokenizer for search text that understands identifiers. `getUserById` and
+//! `get_user_by_id` both yield the whole identifier plus its words (`get`,
+//! `user`, `by`, `id`), so a query for one word of a name finds it. Stopwords
+//! are dropped from the split words and from prose, never from a whole
+//! identifier.
+
+use std::collections::HashSet;
+
+/// English words too common in prose to help a code search
+pub const DEFAULT_STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "for", "from", "in", "is", "it", "of", "on", "or", "that", "the",
+    "this", "to", "with",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeAnalyzer {
+    stopwords: HashSet<String>,
+}
+
+impl Default for CodeAnalyzer {
+    fn default() -> Self {
+        Self::with_stopwords(DEFAULT_STOPWORDS.iter().copied())
+    }
+}
+
+impl CodeAnalyzer {
+    pub fn with_stopwords<I, S>(stopwords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            stopwords: stopwords
+                .into_iter()
+                .map(|w| w.as_ref().trim().to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Read a comma-separated stopword list from `LEXOR_STOPWORDS`; set it empty
+    /// to keep every word. Unset uses `DEFAULT_STOPWORDS`.
+    pub fn from_env() -> Self {
+        match std::env::var("LEXOR_STOPWORDS") {
+            Ok(list) => Self::with_stopwords(list.split(',')),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn is_stopword(&self, word: &str) -> bool {
+        self.stopwords.contains(word)
+    }
+
+    /// Lowercased tokens of `text`, deduplicated in order of appearance. Each
+    /// identifier yields itself followed by its camelCase/snake_case words.
+    pub fn tokens(&self, text: &str) -> Vec<String> {
+        let mut tokens: Vec<String> = Vec::new();
+        let mut push = |token: String| {
+            if !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        };
+
+        for identifier in text.split(|c: char| !(c.is_alphanumeric() || c == '_')).filter(|t| !t.is_empty()) {
+            let words = split_identifier(identifier);
+            let whole = identifier.to_lowercase();
+            if words.len() > 1 || !self.is_stopword(&whole) {
+                push(whole);
+            }
+            for word in words {
+                if !self.is_stopword(&word) {
+                    push(word);
+                }
+            }
+        }
+        tokens
+    }
+}
+
+/// Split an identifier into lowercased words at underscores, lower-to-upper
+/// case changes, and the end of an acronym (`HTTPServer` -> `http`, `server`)
+pub fn split_identifier(identifier: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in identifier.split('_').filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (prev, cur) = (chars[i - 1], chars[i]);
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            let boundary = (prev.is_lowercase() && cur.is_uppercase())
+                || (prev.is_uppercase() && cur.is_uppercase() && next_is_lower)
+                || (prev.is_alphabetic() != cur.is_alphabetic());
+            if boundary {
+                words.push(chars[start..i].iter().collect::<String>().to_lowercase());
+                start = i;
+            }
+        }
+        words.push(chars[start..].iter().collect::<String>().to_lowercase());
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifiers_split_into_words_and_keep_whole_token() {
+        let analyzer = CodeAnalyzer::default();
+        assert_eq!(analyzer.tokens("getUserById"), ["getuserbyid", "get", "user", "by", "id"]);
+        assert_eq!(analyzer.tokens("parse_HTTPResponse2"), ["parse_httpresponse2", "parse", "http", "response", "2"]);
+
+        // Stopwords go from prose, not from identifiers
+        assert_eq!(analyzer.tokens("the user of the system"), ["user", "system"]);
+        assert_eq!(analyzer.tokens("is_active"), ["is_active", "active"]);
+
+        let keep_all = CodeAnalyzer::with_stopwords(Vec::<String>::new());
+        assert_eq!(keep_all.tokens("the user"), ["the", "user"]);
+    }
+}
@@ -0,0 +1,174 @@
+//! Kafka Producer Settings
+//!
+//! Producer configuration for the robot ingestion path. Events accepted over
+//! HTTP are acknowledged to the caller once produced, so the defaults favour
+//! durability: `acks=all` with idempotence enabled, which survives broker
+//! failover without dropping or duplicating messages.
+
+use crate::robot_ingestion::{EventProducer, ProduceError};
+use async_trait::async_trait;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use rdkafka::ClientConfig;
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::warn;
+
+/// How many replicas must acknowledge a write before it counts as sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KafkaAcks {
+    /// Fire and forget
+    None,
+    /// The partition leader only
+    Leader,
+    /// Every in-sync replica
+    All,
+}
+
+impl KafkaAcks {
+    /// Accepts the librdkafka spellings: `all`/`-1`, `1`/`leader`, `0`/`none`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "all" | "-1" => Some(KafkaAcks::All),
+            "1" | "leader" => Some(KafkaAcks::Leader),
+            "0" | "none" => Some(KafkaAcks::None),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KafkaAcks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KafkaAcks::None => write!(f, "0"),
+            KafkaAcks::Leader => write!(f, "1"),
+            KafkaAcks::All => write!(f, "all"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaProducerConfig {
+    pub bootstrap_servers: String,
+    pub acks: KafkaAcks,
+    /// Requires `acks=all`; the broker rejects idempotent producers otherwise
+    pub enable_idempotence: bool,
+}
+
+impl Default for KafkaProducerConfig {
+    fn default() -> Self {
+        Self {
+            bootstrap_servers: "localhost:9092".to_string(),
+            acks: KafkaAcks::All,
+            enable_idempotence: true,
+        }
+    }
+}
+
+impl KafkaProducerConfig {
+    /// Read `KAFKA_BOOTSTRAP_SERVERS`, `KAFKA_PRODUCER_ACKS` and
+    /// `KAFKA_PRODUCER_ENABLE_IDEMPOTENCE`
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let defaults = Self::default();
+
+        let acks = match lookup("KAFKA_PRODUCER_ACKS") {
+            Some(value) => KafkaAcks::parse(&value).unwrap_or_else(|| {
+                warn!("Ignoring invalid KAFKA_PRODUCER_ACKS '{}', using {}", value, defaults.acks);
+                defaults.acks
+            }),
+            None => defaults.acks,
+        };
+
+        // Idempotence follows acks unless set explicitly
+        let mut enable_idempotence = lookup("KAFKA_PRODUCER_ENABLE_IDEMPOTENCE")
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(acks == KafkaAcks::All);
+        if enable_idempotence && acks != KafkaAcks::All {
+            warn!("Idempotence requires acks=all but KAFKA_PRODUCER_ACKS is {}; disabling idempotence", acks);
+            enable_idempotence = false;
+        }
+
+        Self {
+            bootstrap_servers: lookup("KAFKA_BOOTSTRAP_SERVERS").unwrap_or(defaults.bootstrap_servers),
+            acks,
+            enable_idempotence,
+        }
+    }
+
+    /// Client properties to apply to the producer, keyed by librdkafka name
+    pub fn client_properties(&self) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("bootstrap.servers", self.bootstrap_servers.clone()),
+            ("acks", self.acks.to_string()),
+            ("enable.idempotence", self.enable_idempotence.to_string()),
+        ])
+    }
+}
+
+/// `EventProducer` backed by librdkafka; `produce` resolves once the broker has
+/// acknowledged the message under the configured `acks`
+pub struct KafkaEventProducer {
+    producer: FutureProducer,
+}
+
+impl KafkaEventProducer {
+    pub fn new(config: &KafkaProducerConfig) -> Result<Self, KafkaError> {
+        let mut client = ClientConfig::new();
+        for (key, value) in config.client_properties() {
+            client.set(key, value);
+        }
+        Ok(Self { producer: client.create()? })
+    }
+}
+
+#[async_trait]
+impl EventProducer for KafkaEventProducer {
+    async fn produce(&self, topic: &str, key: &str, payload: Vec<u8>) -> Result<(), ProduceError> {
+        let record = FutureRecord::to(topic).key(key).payload(&payload);
+        match self.producer.send(record, Timeout::Never).await {
+            Ok(_) => Ok(()),
+            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), _)) => {
+                Err(ProduceError::Unavailable("producer queue is full".to_string()))
+            }
+            Err((e, _)) => Err(ProduceError::Rejected(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config_from(vars: &[(&str, &str)]) -> KafkaProducerConfig {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        KafkaProducerConfig::from_lookup(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_producer_config_reflects_env_settings() {
+        let durable = config_from(&[("KAFKA_BOOTSTRAP_SERVERS", "kafka-1:9092,kafka-2:9092")]);
+        let properties = durable.client_properties();
+        assert_eq!(properties["bootstrap.servers"], "kafka-1:9092,kafka-2:9092");
+        assert_eq!(properties["acks"], "all");
+        assert_eq!(properties["enable.idempotence"], "true");
+
+        let leader = config_from(&[("KAFKA_PRODUCER_ACKS", "1")]);
+        assert_eq!(leader.client_properties()["acks"], "1");
+        assert_eq!(leader.client_properties()["enable.idempotence"], "false");
+
+        let explicit = config_from(&[("KAFKA_PRODUCER_ACKS", "-1"), ("KAFKA_PRODUCER_ENABLE_IDEMPOTENCE", "false")]);
+        assert_eq!((explicit.acks, explicit.enable_idempotence), (KafkaAcks::All, false));
+
+        // An incompatible combination falls back to what the broker accepts
+        let conflicting = config_from(&[("KAFKA_PRODUCER_ACKS", "0"), ("KAFKA_PRODUCER_ENABLE_IDEMPOTENCE", "true")]);
+        assert_eq!((conflicting.acks, conflicting.enable_idempotence), (KafkaAcks::None, false));
+
+        assert_eq!(config_from(&[("KAFKA_PRODUCER_ACKS", "most")]).acks, KafkaAcks::All);
+    }
+}
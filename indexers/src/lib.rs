@@ -6,10 +6,28 @@
 //! ## Modules
 //! 
 //! - `robot_memory`: Indexes robot episodes and semantic events from Kafka
+//! - `robot_retention`: Per-robot limits on how long episodes are kept
+//! - `kafka_producer`: Durable Kafka producer for the robot ingestion path
+//! - `robot_ingestion`: HTTP endpoints producing robot events to Kafka
 //! - `relation_builder`: Extracts relations and builds knowledge graph from episodes
+//! - `parser`: Language-aware symbol extraction from source files
+//! - `xref`: Per-project symbol definitions and references
+//! - `git_source`: Clones repositories from a Git URL for indexing
+//! - `performance`: LRU + TTL cache for repeated queries
+//! - `search`: Symbol search within or across projects
+//! - `analyzer`: Identifier-aware tokenizer with configurable stopwords
 
 pub mod robot_memory;
+pub mod robot_retention;
+pub mod kafka_producer;
+pub mod robot_ingestion;
 pub mod relation_builder;
+pub mod parser;
+pub mod xref;
+pub mod git_source;
+pub mod performance;
+pub mod search;
+pub mod analyzer;
 
 pub use robot_memory::{
     RobotMemoryIndexer,
@@ -20,6 +38,8 @@ pub use robot_memory::{
     IndexerError,
 };
 
+pub use robot_retention::{IndexedEpisode, RetentionConfig, RetentionPolicy};
+
 pub use relation_builder::{
     RelationBuilder,
     Relation,
@@ -30,6 +50,58 @@ pub use relation_builder::{
     GraphBatch,
 };
 
+pub use kafka_producer::{KafkaAcks, KafkaEventProducer, KafkaProducerConfig};
+
+pub use robot_ingestion::{
+    EventProducer,
+    IngestionAckConfig,
+    ProduceError,
+    RobotIngestion,
+};
+
+pub use parser::{
+    CodeSymbol,
+    SymbolType,
+    ParserError,
+    extract_symbols,
+    extract_rust_symbols,
+};
+
+pub use xref::{
+    XrefIndex,
+    XrefDeletionSink,
+    ReferenceType,
+    SymbolLocation,
+    SymbolReferences,
+    XrefStats,
+};
+
+pub use git_source::{
+    CloneOptions,
+    ClonedRepository,
+    GitSourceError,
+    RepositoryIndexSummary,
+    clone_repository,
+    index_git_repository,
+};
+
+pub use performance::{
+    QueryCache,
+    QueryCacheConfig,
+    CacheStats,
+};
+
+pub use analyzer::CodeAnalyzer;
+
+pub use search::{
+    SymbolHit,
+    FederatedSearch,
+    FieldBoosts,
+    MatchField,
+    search_project,
+    search_federated,
+};
+
 /// Health check for the indexer service
 pub fn health_check() -> bool {
     true
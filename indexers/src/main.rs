@@ -1,10 +1,17 @@
 //! ConHub Unified Indexer
 //! 
 //! This is the main entry point for the indexer service that runs
-//! background jobs for indexing robot memory and other data sources.
+//! background jobs for indexing robot memory and other data sources, and
+//! serves the indexer's HTTP API on `INDEXER_HOST`:`INDEXER_PORT`.
 
-use conhub_indexers::{RobotMemoryIndexer, RobotMemoryIndexerConfig};
-use conhub_observability::{init_tracing, TracingConfig, info, error};
+use actix_web::{web, App, HttpServer};
+use conhub_indexers::{
+    IngestionAckConfig, KafkaEventProducer, KafkaProducerConfig, RobotIngestion, RobotMemoryIndexer,
+    RobotMemoryIndexerConfig, XrefDeletionSink, XrefIndex,
+};
+use conhub_plugins::registry::PluginRegistry;
+use conhub_observability::{init_tracing, observability, TracingConfig, info, error};
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -20,12 +27,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     // Initialize robot memory indexer
-    let robot_indexer = RobotMemoryIndexer::from_env();
+    let robot_indexer = Arc::new(RobotMemoryIndexer::from_env());
     
     // Start the indexer
     match robot_indexer.start().await {
         Ok(_) => {
             info!("✅ Robot memory indexer started successfully");
+            robot_indexer.clone().spawn_retention_pruner();
         }
         Err(e) => {
             error!("❌ Failed to start robot memory indexer: {}", e);
@@ -33,6 +41,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
     
+    // HTTP API
+    let host = std::env::var("INDEXER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+    let port = std::env::var("INDEXER_PORT")
+        .ok()
+        .and_then(|v| v.parse::<u16>().ok())
+        .unwrap_or(3020);
+    let xref_index = web::Data::new(XrefIndex::new());
+    // Files that source syncs find deleted upstream leave the symbol index
+    let plugin_registry = web::Data::new(
+        PluginRegistry::new().with_deletion_sink(Arc::new(XrefDeletionSink::new(xref_index.clone()))),
+    );
+
+    let producer = KafkaEventProducer::new(&KafkaProducerConfig::from_env()).map_err(|e| {
+        error!("❌ Failed to create Kafka producer for robot ingestion: {}", e);
+        e
+    })?;
+    let robot_ingestion = web::Data::new(RobotIngestion::new(Arc::new(producer), IngestionAckConfig::from_env()));
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(xref_index.clone())
+            .app_data(plugin_registry.clone())
+            .app_data(robot_ingestion.clone())
+            .wrap(observability("indexer-service"))
+            .configure(conhub_indexers::xref::configure)
+            .configure(conhub_indexers::git_source::configure)
+            .configure(conhub_indexers::search::configure)
+            .configure(conhub_indexers::robot_ingestion::configure)
+    })
+    .bind((host.as_str(), port))?
+    .disable_signals()
+    .run();
+    let server_handle = server.handle();
+    let server_task = tokio::spawn(server);
+    info!("🌐 Indexer API listening on {}:{}", host, port);
+
     // Keep running
     info!("📡 Indexer running. Press Ctrl+C to stop.");
     
@@ -40,6 +84,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tokio::signal::ctrl_c().await?;
     
     info!("🛑 Shutdown signal received");
+    server_handle.stop(true).await;
+    if let Err(e) = server_task.await? {
+        error!("❌ Indexer API exited with an error: {}", e);
+    }
     robot_indexer.stop().await;
     
     info!("👋 ConHub Unified Indexer stopped");
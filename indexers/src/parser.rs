@@ -0,0 +1,249 @@
+//! Code Parser
+//!
+//! Language-aware symbol extraction for source files. Symbols feed the
+//! `symbol_name` / `symbol_type` / `symbol_line` fields of indexed code so
+//! search results can link straight to a definition.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+use tree_sitter::{Node, Parser, Tree};
+
+// ============================================================================
+// SYMBOL TYPES
+// ============================================================================
+
+/// Kind of definition a symbol names
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolType {
+    Function,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+}
+
+impl SymbolType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolType::Function => "function",
+            SymbolType::Struct => "struct",
+            SymbolType::Enum => "enum",
+            SymbolType::Trait => "trait",
+            SymbolType::Impl => "impl",
+        }
+    }
+}
+
+/// A definition found in a source file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CodeSymbol {
+    /// Name as written, e.g. `parse` or `Display for Config` for trait impls
+    pub symbol_name: String,
+
+    pub symbol_type: SymbolType,
+
+    /// 1-based line of the definition, for jump-to-definition
+    pub symbol_line: usize,
+
+    /// 1-based last line of the definition
+    pub end_line: usize,
+
+    /// Enclosing impl or trait type, for methods
+    pub parent: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum ParserError {
+    #[error("Unsupported language: {0}")]
+    UnsupportedLanguage(String),
+
+    #[error("Parse error: {0}")]
+    Parse(String),
+}
+
+// ============================================================================
+// EXTRACTION
+// ============================================================================
+
+/// Language name for a source path, if symbols can be extracted from it
+pub fn language_for_path(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()? {
+        "rs" => Some("rust"),
+        _ => None,
+    }
+}
+
+/// Extract definitions from `source` written in `language`
+pub fn extract_symbols(language: &str, source: &str) -> Result<Vec<CodeSymbol>, ParserError> {
+    match language.to_ascii_lowercase().as_str() {
+        "rust" | "rs" => extract_rust_symbols(source),
+        other => Err(ParserError::UnsupportedLanguage(other.to_string())),
+    }
+}
+
+/// Functions, structs, enums, traits and impls in a Rust source file, in source order.
+/// Methods are reported as functions with the impl or trait type as `parent`.
+pub fn extract_rust_symbols(source: &str) -> Result<Vec<CodeSymbol>, ParserError> {
+    let tree = parse_rust(source)?;
+    let mut symbols = Vec::new();
+    collect_rust_symbols(tree.root_node(), source.as_bytes(), None, &mut symbols);
+    Ok(symbols)
+}
+
+pub(crate) fn parse_rust(source: &str) -> Result<Tree, ParserError> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .map_err(|e| ParserError::Parse(e.to_string()))?;
+    parser
+        .parse(source, None)
+        .ok_or_else(|| ParserError::Parse("Rust parser returned no tree".to_string()))
+}
+
+fn collect_rust_symbols(node: Node, source: &[u8], parent: Option<&str>, out: &mut Vec<CodeSymbol>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let symbol_type = match child.kind() {
+            "function_item" | "function_signature_item" => SymbolType::Function,
+            "struct_item" => SymbolType::Struct,
+            "enum_item" => SymbolType::Enum,
+            "trait_item" => SymbolType::Trait,
+            "impl_item" => SymbolType::Impl,
+            // Inline modules can hold any of the above
+            "mod_item" => {
+                if let Some(body) = child.child_by_field_name("body") {
+                    collect_rust_symbols(body, source, None, out);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        let (name, scope) = if symbol_type == SymbolType::Impl {
+            let Some(self_type) = child.child_by_field_name("type").map(|t| type_name(t, source)) else { continue };
+            let name = match child.child_by_field_name("trait") {
+                Some(t) => format!("{} for {}", type_name(t, source), self_type),
+                None => self_type.clone(),
+            };
+            (name, Some(self_type))
+        } else {
+            let Some(name) = child.child_by_field_name("name").and_then(|n| n.utf8_text(source).ok()) else { continue };
+            let scope = (symbol_type == SymbolType::Trait).then(|| name.to_string());
+            (name.to_string(), scope)
+        };
+
+        out.push(CodeSymbol {
+            symbol_name: name,
+            symbol_type,
+            symbol_line: child.start_position().row + 1,
+            end_line: child.end_position().row + 1,
+            parent: parent.map(str::to_string),
+        });
+
+        if let (Some(scope), Some(body)) = (scope, child.child_by_field_name("body")) {
+            collect_rust_symbols(body, source, Some(&scope), out);
+        }
+    }
+}
+
+/// Type name without generic arguments, e.g. `Cache` for `Cache<K, V>`
+fn type_name(node: Node, source: &[u8]) -> String {
+    let node = match node.kind() {
+        "generic_type" => node.child_by_field_name("type").unwrap_or(node),
+        _ => node,
+    };
+    node.utf8_text(source).unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"use std::fmt;
+
+pub struct Config {
+    pub name: String,
+}
+
+pub enum Mode {
+    Fast,
+    Safe,
+}
+
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl Config {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string() }
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+struct Cache<K, V>(Vec<(K, V)>);
+
+impl<K, V> Cache<K, V> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+mod helpers {
+    pub fn run() {}
+}
+
+fn main() {}
+"#;
+
+    fn summary(symbols: &[CodeSymbol]) -> Vec<(&str, SymbolType, usize, Option<&str>)> {
+        symbols
+            .iter()
+            .map(|s| (s.symbol_name.as_str(), s.symbol_type, s.symbol_line, s.parent.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_rust_symbols() {
+        let symbols = extract_rust_symbols(FIXTURE).unwrap();
+
+        assert_eq!(
+            summary(&symbols),
+            vec![
+                ("Config", SymbolType::Struct, 3, None),
+                ("Mode", SymbolType::Enum, 7, None),
+                ("Describe", SymbolType::Trait, 12, None),
+                ("describe", SymbolType::Function, 13, Some("Describe")),
+                ("Config", SymbolType::Impl, 16, None),
+                ("new", SymbolType::Function, 17, Some("Config")),
+                ("fmt::Display for Config", SymbolType::Impl, 22, None),
+                ("fmt", SymbolType::Function, 23, Some("Config")),
+                ("Cache", SymbolType::Struct, 28, None),
+                ("Cache", SymbolType::Impl, 30, None),
+                ("len", SymbolType::Function, 31, Some("Cache")),
+                ("run", SymbolType::Function, 37, None),
+                ("main", SymbolType::Function, 40, None),
+            ]
+        );
+        assert_eq!(symbols[4].end_line, 20);
+    }
+
+    #[test]
+    fn test_extract_symbols_dispatches_by_language() {
+        assert_eq!(language_for_path(Path::new("src/lib.rs")), Some("rust"));
+        assert_eq!(language_for_path(Path::new("README.md")), None);
+
+        assert_eq!(extract_symbols("Rust", "fn a() {}").unwrap()[0].symbol_name, "a");
+        assert!(matches!(
+            extract_symbols("cobol", ""),
+            Err(ParserError::UnsupportedLanguage(_))
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+//! Performance
+//!
+//! Bounded LRU + TTL cache for query results, so repeated searches skip the
+//! index walk. Callers invalidate entries when the data behind them changes.
+//! Empty results are cached too, under a shorter TTL, so a misspelled query
+//! repeated in a loop stays cheap without hiding data indexed soon after.
+//! Invalidation bumps a generation counter; a value computed before an
+//! invalidation is discarded at insert time instead of being cached stale.
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Hit/miss counters reported on `/api/stats`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryCacheConfig {
+    pub capacity: usize,
+    pub ttl: Duration,
+    /// Lifetime of entries stored with `insert_negative`
+    pub negative_ttl: Duration,
+}
+
+impl Default for QueryCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: std::env::var("QUERY_CACHE_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1024),
+            ttl: Duration::from_secs(
+                std::env::var("QUERY_CACHE_TTL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(300),
+            ),
+            negative_ttl: Duration::from_secs(
+                std::env::var("QUERY_CACHE_NEGATIVE_TTL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(30),
+            ),
+        }
+    }
+}
+
+struct CacheState<K, V> {
+    /// Least recently used first, with each entry's expiry
+    entries: IndexMap<K, (Instant, V)>,
+    stats: CacheStats,
+    /// Bumped on every invalidation
+    generation: u64,
+}
+
+/// Least-recently-used cache whose entries also expire after a fixed TTL
+pub struct QueryCache<K, V> {
+    config: QueryCacheConfig,
+    state: Mutex<CacheState<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for QueryCache<K, V> {
+    fn default() -> Self {
+        Self::new(QueryCacheConfig::default())
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> QueryCache<K, V> {
+    pub fn new(config: QueryCacheConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(CacheState { entries: IndexMap::new(), stats: CacheStats::default(), generation: 0 }),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let fresh = match state.entries.shift_remove(key) {
+            Some((expires_at, value)) if Instant::now() < expires_at => Some((expires_at, value)),
+            _ => None,
+        };
+
+        match fresh {
+            Some((expires_at, value)) => {
+                // Re-insert at the back to mark it most recently used
+                state.entries.insert(key.clone(), (expires_at, value.clone()));
+                state.stats.hits += 1;
+                Some(value)
+            }
+            None => {
+                state.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.insert_for(key, value, self.config.ttl, None);
+    }
+
+    /// Store an empty result; it expires after `negative_ttl` rather than `ttl`
+    pub fn insert_negative(&self, key: K, value: V) {
+        self.insert_for(key, value, self.config.negative_ttl, None);
+    }
+
+    /// Generation to read before computing a value for `insert_if_current`
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).generation
+    }
+
+    /// Like `insert`, but skipped if the cache was invalidated since `generation` was read
+    pub fn insert_if_current(&self, key: K, value: V, generation: u64) {
+        self.insert_for(key, value, self.config.ttl, Some(generation));
+    }
+
+    /// Like `insert_negative`, but skipped if the cache was invalidated since `generation` was read
+    pub fn insert_negative_if_current(&self, key: K, value: V, generation: u64) {
+        self.insert_for(key, value, self.config.negative_ttl, Some(generation));
+    }
+
+    fn insert_for(&self, key: K, value: V, ttl: Duration, generation: Option<u64>) {
+        if self.config.capacity == 0 || ttl.is_zero() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if generation.is_some_and(|generation| generation != state.generation) {
+            return;
+        }
+        state.entries.shift_remove(&key);
+        while state.entries.len() >= self.config.capacity {
+            state.entries.shift_remove_index(0);
+            state.stats.evictions += 1;
+        }
+        state.entries.insert(key, (Instant::now() + ttl, value));
+    }
+
+    /// Drop every entry whose key matches `predicate`
+    pub fn invalidate_where(&self, predicate: impl Fn(&K) -> bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.generation += 1;
+        state.entries.retain(|key, _| !predicate(key));
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.generation += 1;
+        state.entries.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        CacheStats { entries: state.entries.len(), ..state.stats }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: usize, ttl: Duration) -> QueryCache<String, usize> {
+        QueryCache::new(QueryCacheConfig { capacity, ttl, negative_ttl: ttl })
+    }
+
+    #[test]
+    fn test_least_recently_used_entry_is_evicted() {
+        let cache = cache(2, Duration::from_secs(60));
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        assert_eq!(cache.get(&"a".to_string()), Some(1));
+
+        cache.insert("c".to_string(), 3);
+        assert_eq!(cache.get(&"b".to_string()), None);
+        assert_eq!(cache.get(&"a".to_string()), Some(1));
+        assert_eq!(
+            cache.stats(),
+            CacheStats { hits: 2, misses: 1, evictions: 1, entries: 2 }
+        );
+    }
+
+    #[test]
+    fn test_expired_entries_miss() {
+        let cache = cache(4, Duration::ZERO);
+        cache.insert("a".to_string(), 1);
+        assert_eq!(cache.get(&"a".to_string()), None);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_value_computed_before_an_invalidation_is_not_cached() {
+        let cache = cache(4, Duration::from_secs(60));
+        let generation = cache.generation();
+        // A writer invalidates while the reader is still computing
+        cache.invalidate_where(|key| key == "a");
+        cache.insert_if_current("a".to_string(), 1, generation);
+        assert_eq!(cache.get(&"a".to_string()), None);
+
+        cache.insert_if_current("a".to_string(), 2, cache.generation());
+        assert_eq!(cache.get(&"a".to_string()), Some(2));
+    }
+}
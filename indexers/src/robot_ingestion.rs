@@ -0,0 +1,234 @@
+//! Robot Event Ingestion
+//!
+//! HTTP endpoints that accept robot episodes and semantic events and produce
+//! them onto the Kafka topics `robot_memory` consumes. With sync acks (the
+//! default) a request only succeeds once the broker has acknowledged every
+//! event, so an accepted event can't be lost in the producer's buffer; a failed
+//! or timed out produce answers 503 and the caller retries. Async acks answer
+//! 202 as soon as the events are handed to the producer, trading durability
+//! for throughput.
+
+use actix_web::{web, HttpResponse, Responder};
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum ProduceError {
+    #[error("Broker rejected message: {0}")]
+    Rejected(String),
+
+    #[error("Producer unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// Sends one message and resolves once the broker has acknowledged it
+#[async_trait]
+pub trait EventProducer: Send + Sync {
+    async fn produce(&self, topic: &str, key: &str, payload: Vec<u8>) -> Result<(), ProduceError>;
+}
+
+/// Whether ingestion waits for broker acknowledgement before answering
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IngestionAckConfig {
+    pub sync_ack: bool,
+    /// How long a sync request waits for acknowledgements before answering 503
+    pub ack_timeout: Duration,
+}
+
+impl Default for IngestionAckConfig {
+    fn default() -> Self {
+        Self {
+            sync_ack: true,
+            ack_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl IngestionAckConfig {
+    /// Read `ROBOT_INGEST_SYNC_ACK` and `ROBOT_INGEST_ACK_TIMEOUT_MS`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            sync_ack: std::env::var("ROBOT_INGEST_SYNC_ACK")
+                .map(|v| v.to_lowercase() != "false")
+                .unwrap_or(defaults.sync_ack),
+            ack_timeout: std::env::var("ROBOT_INGEST_ACK_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.ack_timeout),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestEventsRequest {
+    pub tenant_id: Uuid,
+    pub events: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestEventsResponse {
+    pub accepted: usize,
+    /// False when the events were queued without waiting for the broker
+    pub acknowledged: bool,
+}
+
+pub struct RobotIngestion {
+    producer: Arc<dyn EventProducer>,
+    config: IngestionAckConfig,
+}
+
+impl RobotIngestion {
+    pub fn new(producer: Arc<dyn EventProducer>, config: IngestionAckConfig) -> Self {
+        Self { producer, config }
+    }
+
+    /// Produce every event to `topic`, keyed by robot so a robot's events stay ordered
+    async fn produce_all(
+        producer: &dyn EventProducer,
+        topic: &str,
+        robot_id: Uuid,
+        events: &[serde_json::Value],
+    ) -> Result<(), ProduceError> {
+        let key = robot_id.to_string();
+        try_join_all(events.iter().map(|event| {
+            let payload = serde_json::to_vec(event).unwrap_or_default();
+            producer.produce(topic, &key, payload)
+        }))
+        .await
+        .map(|_| ())
+    }
+
+    async fn ingest(&self, topic: String, robot_id: Uuid, request: IngestEventsRequest) -> HttpResponse {
+        let accepted = request.events.len();
+
+        if !self.config.sync_ack {
+            let producer = self.producer.clone();
+            tokio::spawn(async move {
+                if let Err(e) = Self::produce_all(producer.as_ref(), &topic, robot_id, &request.events).await {
+                    error!("Async produce to {} failed, {} events may be lost: {}", topic, accepted, e);
+                }
+            });
+            return HttpResponse::Accepted().json(IngestEventsResponse { accepted, acknowledged: false });
+        }
+
+        let produced = tokio::time::timeout(
+            self.config.ack_timeout,
+            Self::produce_all(self.producer.as_ref(), &topic, robot_id, &request.events),
+        )
+        .await;
+
+        let error = match produced {
+            Ok(Ok(())) => return HttpResponse::Ok().json(IngestEventsResponse { accepted, acknowledged: true }),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("No broker acknowledgement within {}ms", self.config.ack_timeout.as_millis()),
+        };
+        warn!("Rejecting {} events for {}: {}", accepted, topic, error);
+        HttpResponse::ServiceUnavailable().json(json!({
+            "error": "Events were not acknowledged by the broker; retry the request",
+            "details": error
+        }))
+    }
+}
+
+/// POST /api/robots/{robot_id}/episodes
+pub async fn ingest_episodes(
+    robot_id: web::Path<Uuid>,
+    body: web::Json<IngestEventsRequest>,
+    ingestion: web::Data<RobotIngestion>,
+) -> impl Responder {
+    let robot_id = robot_id.into_inner();
+    ingestion.ingest(format!("robot.{}.episodes", robot_id), robot_id, body.into_inner()).await
+}
+
+/// POST /api/robots/{robot_id}/events
+pub async fn ingest_events(
+    robot_id: web::Path<Uuid>,
+    body: web::Json<IngestEventsRequest>,
+    ingestion: web::Data<RobotIngestion>,
+) -> impl Responder {
+    let robot_id = robot_id.into_inner();
+    ingestion.ingest(format!("robot.{}.semantic_events", robot_id), robot_id, body.into_inner()).await
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/robots/{robot_id}/episodes", web::post().to(ingest_episodes))
+        .route("/api/robots/{robot_id}/events", web::post().to(ingest_events));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use std::sync::Mutex;
+
+    /// Records produced messages, failing instead when `fail` is set
+    #[derive(Default)]
+    struct FakeProducer {
+        fail: bool,
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl EventProducer for FakeProducer {
+        async fn produce(&self, topic: &str, key: &str, _payload: Vec<u8>) -> Result<(), ProduceError> {
+            if self.fail {
+                return Err(ProduceError::Unavailable("all brokers down".to_string()));
+            }
+            self.sent.lock().unwrap().push((topic.to_string(), key.to_string()));
+            Ok(())
+        }
+    }
+
+    async fn post_events(producer: Arc<FakeProducer>, config: IngestionAckConfig) -> (StatusCode, serde_json::Value) {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(RobotIngestion::new(producer, config)))
+                .configure(configure),
+        )
+        .await;
+
+        let robot_id = Uuid::new_v4();
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/robots/{}/events", robot_id))
+            .set_json(json!({ "tenant_id": Uuid::new_v4(), "events": [{ "kind": "object_seen" }, { "kind": "door_opened" }] }))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let status = res.status();
+        (status, test::read_body_json(res).await)
+    }
+
+    #[actix_web::test]
+    async fn test_failed_produce_is_not_reported_as_success() {
+        let producer = Arc::new(FakeProducer { fail: true, ..Default::default() });
+        let (status, body) = post_events(producer, IngestionAckConfig::default()).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body["details"].as_str().unwrap().contains("all brokers down"));
+    }
+
+    #[actix_web::test]
+    async fn test_sync_ack_waits_for_broker_and_async_ack_returns_accepted() {
+        let producer = Arc::new(FakeProducer::default());
+        let (status, body) = post_events(producer.clone(), IngestionAckConfig::default()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["accepted"], 2);
+        assert_eq!(body["acknowledged"], true);
+        let sent = producer.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 2);
+        assert!(sent[0].0.starts_with("robot.") && sent[0].0.ends_with(".semantic_events"));
+
+        let config = IngestionAckConfig { sync_ack: false, ..Default::default() };
+        let (status, body) = post_events(Arc::new(FakeProducer { fail: true, ..Default::default() }), config).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["acknowledged"], false);
+    }
+}
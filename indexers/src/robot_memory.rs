@@ -15,6 +15,8 @@ use tokio::sync::RwLock;
 use tracing::{info, warn, error};
 use uuid::Uuid;
 
+use crate::robot_retention::{IndexedEpisode, RetentionConfig, RetentionPolicy};
+
 // ============================================================================
 // KAFKA MESSAGE TYPES (consumed from Flink output topics)
 // ============================================================================
@@ -227,6 +229,8 @@ pub struct RobotMemoryIndexerConfig {
     pub graph_rag_service_url: String,
     pub batch_size: usize,
     pub batch_timeout_ms: u64,
+    /// How long each robot's episodes are kept
+    pub retention: RetentionConfig,
 }
 
 impl Default for RobotMemoryIndexerConfig {
@@ -245,6 +249,7 @@ impl Default for RobotMemoryIndexerConfig {
                 .unwrap_or_else(|_| "http://localhost:8006".to_string()),
             batch_size: 100,
             batch_timeout_ms: 5000,
+            retention: RetentionConfig::from_env(),
         }
     }
 }
@@ -261,6 +266,9 @@ pub struct RobotMemoryIndexer {
     episode_buffer: Arc<RwLock<Vec<EpisodeMessage>>>,
     semantic_buffer: Arc<RwLock<Vec<SemanticEventMessage>>>,
     running: Arc<RwLock<bool>>,
+    retention: RwLock<RetentionConfig>,
+    /// Indexed episodes per robot, for retention pruning
+    indexed_episodes: RwLock<HashMap<Uuid, Vec<IndexedEpisode>>>,
 }
 
 impl RobotMemoryIndexer {
@@ -271,12 +279,14 @@ impl RobotMemoryIndexer {
         );
         
         Self {
+            retention: RwLock::new(config.retention.clone()),
             config,
             http_client: reqwest::Client::new(),
             relation_builder,
             episode_buffer: Arc::new(RwLock::new(Vec::new())),
             semantic_buffer: Arc::new(RwLock::new(Vec::new())),
             running: Arc::new(RwLock::new(false)),
+            indexed_episodes: RwLock::new(HashMap::new()),
         }
     }
     
@@ -344,6 +354,62 @@ impl RobotMemoryIndexer {
         info!("🛑 Robot memory indexer stopped");
     }
     
+    /// Override the retention policy for one robot
+    pub async fn set_retention_policy(&self, robot_id: Uuid, policy: RetentionPolicy) {
+        self.retention.write().await.per_robot.insert(robot_id, policy);
+    }
+    
+    /// Prune episodes every `prune_interval` until the indexer is stopped
+    pub fn spawn_retention_pruner(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.retention.prune_interval);
+            loop {
+                interval.tick().await;
+                if !*self.running.read().await {
+                    break;
+                }
+                match self.prune_episodes(Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(pruned) => info!("🧹 Pruned {} robot episodes past retention", pruned),
+                    Err(e) => error!("Robot episode pruning failed: {}", e),
+                }
+            }
+        })
+    }
+    
+    /// Remove every episode past its robot's retention policy as of `now`,
+    /// with its vector and graph entries. Returns how many were removed; an
+    /// episode whose entries can't be removed is kept for the next run.
+    pub async fn prune_episodes(&self, now: DateTime<Utc>) -> Result<usize, IndexerError> {
+        let retention = self.retention.read().await.clone();
+        let mut indexed = self.indexed_episodes.write().await;
+        let mut pruned = 0;
+        
+        for (robot_id, episodes) in indexed.iter_mut() {
+            let (mut kept, expired) = retention.policy_for(robot_id).split_expired(std::mem::take(episodes), now);
+            for episode in expired {
+                match self.remove_indexed_episode(&episode).await {
+                    Ok(()) => pruned += 1,
+                    Err(e) => {
+                        warn!("Failed to prune episode {} of robot {}: {}", episode.episode_number, robot_id, e);
+                        kept.push(episode);
+                    }
+                }
+            }
+            *episodes = kept;
+        }
+        indexed.retain(|_, episodes| !episodes.is_empty());
+        
+        Ok(pruned)
+    }
+    
+    /// Episodes currently indexed for `robot_id`, oldest first
+    pub async fn indexed_episodes(&self, robot_id: &Uuid) -> Vec<IndexedEpisode> {
+        let mut episodes = self.indexed_episodes.read().await.get(robot_id).cloned().unwrap_or_default();
+        episodes.sort_by_key(|e| (e.ended_at, e.episode_number));
+        episodes
+    }
+    
     /// Process an episode message (can be called directly for testing)
     pub async fn process_episode(&self, episode: EpisodeMessage) -> Result<(), IndexerError> {
         info!("📝 Processing episode {} from robot {}", 
@@ -358,6 +424,14 @@ impl RobotMemoryIndexer {
         // Index into graph store
         self.index_episode_to_graph(&episode).await?;
         
+        // Track what was indexed so retention can remove it later
+        self.indexed_episodes.write().await.entry(episode.robot_id).or_default().push(IndexedEpisode {
+            episode_number: episode.episode_number,
+            ended_at: episode.ended_at,
+            document_id: document.id,
+            graph_node_id: episode_node_id(&episode),
+        });
+        
         // Extract relations and build knowledge graph
         let relations = self.relation_builder.process_episode(&episode).await;
         info!("🔗 Extracted {} relations from episode", relations.len());
@@ -527,7 +601,7 @@ impl RobotMemoryIndexer {
         
         // Create nodes
         let episode_node = GraphNode {
-            id: format!("episode:{}:{}", episode.robot_id, episode.episode_number),
+            id: episode_node_id(episode),
             node_type: GraphNodeType::Episode,
             name: format!("Episode {}", episode.episode_number),
             properties: {
@@ -583,6 +657,34 @@ impl RobotMemoryIndexer {
         Ok(())
     }
     
+    /// Delete a pruned episode's document and graph node. An entry the store
+    /// no longer has counts as deleted, so a retry after a partial failure
+    /// can still finish.
+    async fn remove_indexed_episode(&self, episode: &IndexedEpisode) -> Result<(), IndexerError> {
+        self.delete_indexed_entry(&format!(
+            "{}/api/documents/{}",
+            self.config.vector_rag_service_url, episode.document_id
+        ))
+        .await?;
+        self.delete_indexed_entry(&format!(
+            "{}/api/nodes/{}",
+            self.config.graph_rag_service_url, episode.graph_node_id
+        ))
+        .await?;
+        
+        info!("🗑️ Deleted document {} and graph node {}", episode.document_id, episode.graph_node_id);
+        Ok(())
+    }
+    
+    async fn delete_indexed_entry(&self, url: &str) -> Result<(), IndexerError> {
+        let status = self.http_client.delete(url).send().await?.status();
+        if status.is_success() || status == reqwest::StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            Err(IndexerError::IndexingError(format!("DELETE {} returned {}", url, status)))
+        }
+    }
+    
     /// Index a semantic event to the graph store
     async fn index_semantic_event_to_graph(&self, event: &SemanticEventMessage) -> Result<(), IndexerError> {
         info!("🔗 Would index semantic fact to graph store");
@@ -651,6 +753,11 @@ impl RobotMemoryIndexer {
     }
 }
 
+/// Graph node id of an episode
+fn episode_node_id(episode: &EpisodeMessage) -> String {
+    format!("episode:{}:{}", episode.robot_id, episode.episode_number)
+}
+
 // ============================================================================
 // ERROR TYPES
 // ============================================================================
@@ -714,4 +821,125 @@ mod tests {
         assert!(document.content.contains("pallet"));
         assert_eq!(document.metadata.episode_number, Some(1));
     }
+    
+    fn episode(robot_id: Uuid, episode_number: i64, ended_at: DateTime<Utc>) -> EpisodeMessage {
+        EpisodeMessage {
+            robot_id,
+            tenant_id: Uuid::nil(),
+            episode_number,
+            episode_type: "navigation".to_string(),
+            started_at: ended_at - chrono::Duration::minutes(5),
+            ended_at,
+            duration_ms: 300_000,
+            summary: format!("Episode {}", episode_number),
+            detailed_description: None,
+            location_id: None,
+            location_name: None,
+            location_coordinates: None,
+            objects_seen: vec![],
+            people_involved: vec![],
+            tasks_related: vec![],
+            observations_count: 0,
+            actions_count: 0,
+            outcome: None,
+            confidence_score: None,
+        }
+    }
+    
+    /// Vector and graph store stub accepting deletes and recording their paths.
+    /// Deleting a graph node fails while `graph_down` is set.
+    fn mock_stores(graph_down: Arc<std::sync::atomic::AtomicBool>, deleted: Arc<std::sync::Mutex<Vec<String>>>) -> String {
+        use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+        use std::sync::atomic::Ordering;
+        
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = HttpServer::new(move || {
+            let (graph_down, deleted) = (graph_down.clone(), deleted.clone());
+            App::new().default_service(web::delete().to(move |req: HttpRequest| {
+                let path = req.path().to_string();
+                let fail = path.starts_with("/api/nodes/") && graph_down.load(Ordering::SeqCst);
+                if !fail {
+                    deleted.lock().unwrap().push(path);
+                }
+                async move {
+                    if fail {
+                        HttpResponse::ServiceUnavailable().finish()
+                    } else {
+                        HttpResponse::NoContent().finish()
+                    }
+                }
+            }))
+        })
+        .listen(listener)
+        .unwrap()
+        .run();
+        drop(actix_web::rt::spawn(server));
+        base_url
+    }
+    
+    fn indexer_with_stores(store_url: &str, retention: &str) -> RobotMemoryIndexer {
+        RobotMemoryIndexer::new(RobotMemoryIndexerConfig {
+            vector_rag_service_url: store_url.to_string(),
+            graph_rag_service_url: store_url.to_string(),
+            retention: RetentionConfig::parse(retention),
+            ..RobotMemoryIndexerConfig::default()
+        })
+    }
+    
+    #[actix_web::test]
+    async fn test_episodes_older_than_retention_window_are_pruned() {
+        let now = Utc::now();
+        let (rover, forklift) = (Uuid::new_v4(), Uuid::new_v4());
+        let deleted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let store_url = mock_stores(Arc::default(), deleted.clone());
+        let indexer = indexer_with_stores(&store_url, "default=7:");
+        // The forklift keeps only its latest episode, whatever its age
+        indexer
+            .set_retention_policy(forklift, RetentionPolicy { max_age: None, max_episodes: Some(1) })
+            .await;
+        
+        for (robot, number, days_ago) in [(rover, 1, 30), (rover, 2, 8), (rover, 3, 1), (forklift, 1, 20), (forklift, 2, 10)] {
+            indexer.process_episode(episode(robot, number, now - chrono::Duration::days(days_ago))).await.unwrap();
+        }
+        let rover_first = indexer.indexed_episodes(&rover).await[0].clone();
+        
+        assert_eq!(indexer.prune_episodes(now).await.unwrap(), 3);
+        let remaining = |episodes: Vec<IndexedEpisode>| episodes.iter().map(|e| e.episode_number).collect::<Vec<_>>();
+        assert_eq!(remaining(indexer.indexed_episodes(&rover).await), vec![3]);
+        assert_eq!(remaining(indexer.indexed_episodes(&forklift).await), vec![2]);
+        assert_eq!(
+            indexer.indexed_episodes(&rover).await[0].graph_node_id,
+            format!("episode:{}:3", rover)
+        );
+        
+        // Each pruned episode's document and node were deleted from the stores
+        let deleted = deleted.lock().unwrap().clone();
+        assert_eq!(deleted.len(), 6);
+        assert!(deleted.contains(&format!("/api/documents/{}", rover_first.document_id)));
+        assert!(deleted.contains(&format!("/api/nodes/{}", rover_first.graph_node_id)));
+        
+        // Nothing further to prune
+        assert_eq!(indexer.prune_episodes(now).await.unwrap(), 0);
+    }
+    
+    #[actix_web::test]
+    async fn test_episode_stays_tracked_when_its_graph_node_cannot_be_deleted() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        
+        let now = Utc::now();
+        let rover = Uuid::new_v4();
+        let graph_down = Arc::new(AtomicBool::new(true));
+        let store_url = mock_stores(graph_down.clone(), Arc::default());
+        let indexer = indexer_with_stores(&store_url, "default=7:");
+        indexer.process_episode(episode(rover, 1, now - chrono::Duration::days(30))).await.unwrap();
+        
+        assert_eq!(indexer.prune_episodes(now).await.unwrap(), 0);
+        assert_eq!(indexer.indexed_episodes(&rover).await.len(), 1);
+        
+        // Once the graph store is back the next run finishes the removal
+        graph_down.store(false, Ordering::SeqCst);
+        assert_eq!(indexer.prune_episodes(now).await.unwrap(), 1);
+        assert!(indexer.indexed_episodes(&rover).await.is_empty());
+    }
 }
@@ -0,0 +1,130 @@
+//! Robot Memory Retention
+//!
+//! How long robot episodes are kept. A policy bounds episode age, the number
+//! of episodes kept per robot, or both; robots without their own policy use
+//! the default. `RobotMemoryIndexer` applies it on a timer and removes each
+//! pruned episode's vector and graph entries along with it.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Limits on one robot's episodes; `None` leaves that dimension unbounded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub max_episodes: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// Split `episodes` into those kept and those past the policy. Episodes
+    /// older than `max_age` go first, then the oldest beyond `max_episodes`.
+    pub fn split_expired(
+        &self,
+        mut episodes: Vec<IndexedEpisode>,
+        now: DateTime<Utc>,
+    ) -> (Vec<IndexedEpisode>, Vec<IndexedEpisode>) {
+        episodes.sort_by_key(|e| (e.ended_at, e.episode_number));
+
+        let (mut expired, mut kept): (Vec<_>, Vec<_>) = match self.max_age {
+            Some(max_age) => episodes.into_iter().partition(|e| now - e.ended_at > max_age),
+            None => (Vec::new(), episodes),
+        };
+        if let Some(max_episodes) = self.max_episodes {
+            let excess = kept.len().saturating_sub(max_episodes);
+            expired.extend(kept.drain(..excess));
+        }
+        (kept, expired)
+    }
+}
+
+/// Per-robot retention and how often the pruner runs
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    pub default: RetentionPolicy,
+    pub per_robot: HashMap<Uuid, RetentionPolicy>,
+    pub prune_interval: std::time::Duration,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            default: RetentionPolicy::default(),
+            per_robot: HashMap::new(),
+            prune_interval: std::time::Duration::from_secs(3600),
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Read `ROBOT_MEMORY_RETENTION` and `ROBOT_MEMORY_PRUNE_INTERVAL_SECS`
+    pub fn from_env() -> Self {
+        let mut config = std::env::var("ROBOT_MEMORY_RETENTION")
+            .map(|v| Self::parse(&v))
+            .unwrap_or_default();
+        if let Some(secs) = std::env::var("ROBOT_MEMORY_PRUNE_INTERVAL_SECS").ok().and_then(|v| v.parse().ok()) {
+            config.prune_interval = std::time::Duration::from_secs(secs);
+        }
+        config
+    }
+
+    /// Entries are `robot=max_age_days:max_episodes`, where `robot` is a robot
+    /// id or `default` and either limit may be left empty for no limit, e.g.
+    /// `default=30:10000,<robot-id>=7:`. Malformed entries are skipped.
+    pub fn parse(value: &str) -> Self {
+        let mut config = Self::default();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((robot, spec)) = entry.split_once('=') else { continue };
+            let Some((days, episodes)) = spec.split_once(':') else { continue };
+            let Ok(max_age) = parse_limit::<i64>(days) else { continue };
+            let Ok(max_episodes) = parse_limit::<usize>(episodes) else { continue };
+            let policy = RetentionPolicy { max_age: max_age.map(Duration::days), max_episodes };
+
+            match robot.trim() {
+                "default" | "*" => config.default = policy,
+                robot => {
+                    if let Ok(robot_id) = Uuid::parse_str(robot) {
+                        config.per_robot.insert(robot_id, policy);
+                    }
+                }
+            }
+        }
+        config
+    }
+
+    pub fn policy_for(&self, robot_id: &Uuid) -> RetentionPolicy {
+        self.per_robot.get(robot_id).copied().unwrap_or(self.default)
+    }
+}
+
+fn parse_limit<T: std::str::FromStr>(value: &str) -> Result<Option<T>, T::Err> {
+    match value.trim() {
+        "" => Ok(None),
+        v => v.parse().map(Some),
+    }
+}
+
+/// An indexed episode and the entries derived from it, so pruning can remove them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedEpisode {
+    pub episode_number: i64,
+    pub ended_at: DateTime<Utc>,
+    /// Document id in the vector store
+    pub document_id: Uuid,
+    pub graph_node_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_default_and_per_robot_policies() {
+        let robot = Uuid::new_v4();
+        let config = RetentionConfig::parse(&format!("default=30:10000, {}=7:, bogus=1:1, default", robot));
+
+        assert_eq!(config.default, RetentionPolicy { max_age: Some(Duration::days(30)), max_episodes: Some(10000) });
+        assert_eq!(config.policy_for(&robot), RetentionPolicy { max_age: Some(Duration::days(7)), max_episodes: None });
+        assert_eq!(config.policy_for(&Uuid::new_v4()), config.default);
+    }
+}
@@ -0,0 +1,436 @@
+//! Symbol Search
+//!
+//! Name search over indexed definitions, within one project or federated across
+//! several. Federated results are merged by score with the project on each hit,
+//! and each project's contribution is capped so one large repo can't crowd out
+//! the rest.
+//!
+//! A query matches a definition's name, the name of the file it's in, or a
+//! usage in a file's content. Each field's match score is scaled by its boost,
+//! so by default a symbol or file name match ranks above a content-only one.
+//! Names and queries are tokenized by the index's `CodeAnalyzer`, so a query
+//! for one word of a camelCase or snake_case name finds it.
+//!
+//! Federated results are cached on the index until one of their projects is
+//! re-indexed.
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::analyzer::CodeAnalyzer;
+use crate::parser::SymbolType;
+use crate::xref::XrefIndex;
+
+const DEFAULT_LIMIT: usize = 20;
+const DEFAULT_PER_PROJECT_LIMIT: usize = 10;
+
+/// Where in a document the query matched
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchField {
+    SymbolName,
+    FileName,
+    Content,
+}
+
+/// Weight of a match in each field. A zero boost leaves the field unsearched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldBoosts {
+    pub symbol_name: f32,
+    pub file_name: f32,
+    pub content: f32,
+}
+
+impl Default for FieldBoosts {
+    fn default() -> Self {
+        Self {
+            symbol_name: 1.0,
+            file_name: 0.8,
+            content: 0.5,
+        }
+    }
+}
+
+impl FieldBoosts {
+    fn get(&self, field: MatchField) -> f32 {
+        let boost = match field {
+            MatchField::SymbolName => self.symbol_name,
+            MatchField::FileName => self.file_name,
+            MatchField::Content => self.content,
+        };
+        boost.max(0.0)
+    }
+
+    /// Boosted score of a match of `query` against `text` in `field`
+    fn score(&self, field: MatchField, text: &str, query: &str, analyzer: &CodeAnalyzer) -> Option<f32> {
+        let boost = self.get(field);
+        if boost == 0.0 {
+            return None;
+        }
+        match_score(text, query, analyzer).map(|score| score * boost)
+    }
+}
+
+/// A matching definition, or for a content match the first matching usage in the file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SymbolHit {
+    pub project: String,
+    pub symbol: String,
+    pub symbol_type: Option<SymbolType>,
+    pub file_path: String,
+    pub line: usize,
+    pub score: f32,
+    pub matched_field: MatchField,
+}
+
+#[derive(Debug, Clone)]
+pub struct FederatedSearch {
+    pub projects: Vec<String>,
+    pub query: String,
+    pub limit: usize,
+    /// Most hits any single project may contribute to the merged results
+    pub per_project_limit: usize,
+    pub boosts: FieldBoosts,
+}
+
+/// Cache key for a federated search: its deduplicated projects and everything else that shapes the hits
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct SearchKey {
+    projects: Vec<String>,
+    query: String,
+    limit: usize,
+    per_project_limit: usize,
+    /// Boosts as bit patterns, since `f32` isn't `Hash`
+    boosts: [u32; 3],
+}
+
+impl SearchKey {
+    pub(crate) fn covers(&self, project: &str) -> bool {
+        self.projects.iter().any(|p| p == project)
+    }
+}
+
+/// How well `name` matches `query`: exact beats case-insensitive exact, then
+/// prefix, then every query word being a word of the name, then substring
+fn match_score(name: &str, query: &str, analyzer: &CodeAnalyzer) -> Option<f32> {
+    if name == query {
+        return Some(1.0);
+    }
+    let (lower_name, lower_query) = (name.to_lowercase(), query.to_lowercase());
+    if lower_name == lower_query {
+        Some(0.9)
+    } else if lower_name.starts_with(&lower_query) {
+        Some(0.7)
+    } else if words_match(name, query, analyzer) {
+        Some(0.5)
+    } else if lower_name.contains(&lower_query) {
+        Some(0.4)
+    } else {
+        None
+    }
+}
+
+fn words_match(name: &str, query: &str, analyzer: &CodeAnalyzer) -> bool {
+    let query_tokens = analyzer.tokens(query);
+    if query_tokens.is_empty() {
+        return false;
+    }
+    let name_tokens = analyzer.tokens(name);
+    query_tokens.iter().all(|t| name_tokens.contains(t))
+}
+
+fn rank(a: &SymbolHit, b: &SymbolHit) -> Ordering {
+    b.score
+        .partial_cmp(&a.score)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| a.symbol.len().cmp(&b.symbol.len()))
+        .then_with(|| (&a.project, &a.file_path, a.line).cmp(&(&b.project, &b.file_path, b.line)))
+}
+
+fn file_stem(file_path: &str) -> &str {
+    Path::new(file_path).file_stem().and_then(|s| s.to_str()).unwrap_or(file_path)
+}
+
+/// Definitions in `project` whose name or file name matches `query`, plus one
+/// hit per otherwise unmatched file whose content uses a matching symbol. Best first.
+pub fn search_project(index: &XrefIndex, project: &str, query: &str, limit: usize, boosts: &FieldBoosts) -> Vec<SymbolHit> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hits: Vec<SymbolHit> = index
+        .project_definitions(project)
+        .into_iter()
+        .filter_map(|(symbol, location)| {
+            let by_symbol = boosts.score(MatchField::SymbolName, &symbol, query, index.analyzer()).map(|s| (s, MatchField::SymbolName));
+            let by_file = boosts
+                .score(MatchField::FileName, file_stem(&location.file_path), query, index.analyzer())
+                .map(|s| (s, MatchField::FileName));
+            let (score, matched_field) = match (by_symbol, by_file) {
+                (Some(a), Some(b)) => if b.0 > a.0 { b } else { a },
+                (a, b) => a.or(b)?,
+            };
+            Some(SymbolHit {
+                project: project.to_string(),
+                symbol,
+                symbol_type: location.symbol_type,
+                file_path: location.file_path,
+                line: location.line,
+                score,
+                matched_field,
+            })
+        })
+        .collect();
+
+    // Content only counts for files that didn't already match by name
+    let mut by_file: HashMap<String, SymbolHit> = HashMap::new();
+    for (symbol, location) in index.project_references(project) {
+        if hits.iter().any(|h| h.file_path == location.file_path) {
+            continue;
+        }
+        let Some(score) = boosts.score(MatchField::Content, &symbol, query, index.analyzer()) else { continue };
+        let better = by_file
+            .get(&location.file_path)
+            .is_none_or(|best| score > best.score || (score == best.score && location.line < best.line));
+        if better {
+            by_file.insert(location.file_path.clone(), SymbolHit {
+                project: project.to_string(),
+                symbol,
+                symbol_type: None,
+                file_path: location.file_path,
+                line: location.line,
+                score,
+                matched_field: MatchField::Content,
+            });
+        }
+    }
+    hits.extend(by_file.into_values());
+
+    hits.sort_by(rank);
+    hits.truncate(limit);
+    hits
+}
+
+/// Search every project in `search.projects` and merge the results by score
+pub fn search_federated(index: &XrefIndex, search: &FederatedSearch) -> Vec<SymbolHit> {
+    let per_project = search.per_project_limit.min(search.limit);
+    let mut projects = search.projects.clone();
+    projects.sort();
+    projects.dedup();
+
+    let key = SearchKey {
+        projects,
+        query: search.query.trim().to_string(),
+        limit: search.limit,
+        per_project_limit: per_project,
+        boosts: [search.boosts.symbol_name, search.boosts.file_name, search.boosts.content].map(f32::to_bits),
+    };
+    let cache = index.search_cache();
+    if let Some(hits) = cache.get(&key) {
+        return hits;
+    }
+    let generation = cache.generation();
+
+    let mut hits: Vec<SymbolHit> = key
+        .projects
+        .iter()
+        .flat_map(|project| search_project(index, project, &key.query, per_project, &search.boosts))
+        .collect();
+    hits.sort_by(rank);
+    hits.truncate(search.limit);
+
+    if hits.is_empty() {
+        cache.insert_negative_if_current(key, hits.clone(), generation);
+    } else {
+        cache.insert_if_current(key, hits.clone(), generation);
+    }
+    hits
+}
+
+// ============================================================================
+// HTTP
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    /// Comma-separated project ids
+    pub projects: String,
+    pub limit: Option<usize>,
+    pub per_project_limit: Option<usize>,
+    /// Override the default weight of a match in each field
+    pub symbol_boost: Option<f32>,
+    pub file_name_boost: Option<f32>,
+    pub content_boost: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub projects: Vec<String>,
+    pub hits: Vec<SymbolHit>,
+}
+
+/// GET /api/search?q=...&projects=a,b
+pub async fn search_handler(query: web::Query<SearchQuery>, index: web::Data<XrefIndex>) -> impl Responder {
+    let query = query.into_inner();
+    let defaults = FieldBoosts::default();
+    let boosts = FieldBoosts {
+        symbol_name: query.symbol_boost.unwrap_or(defaults.symbol_name),
+        file_name: query.file_name_boost.unwrap_or(defaults.file_name),
+        content: query.content_boost.unwrap_or(defaults.content),
+    };
+    let search = FederatedSearch {
+        projects: query
+            .projects
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect(),
+        limit: query.limit.unwrap_or(DEFAULT_LIMIT),
+        per_project_limit: query.per_project_limit.unwrap_or(DEFAULT_PER_PROJECT_LIMIT),
+        boosts,
+        query: query.q,
+    };
+
+    let hits = search_federated(&index, &search);
+    HttpResponse::Ok().json(SearchResponse {
+        query: search.query,
+        projects: search.projects,
+        hits,
+    })
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/search", web::get().to(search_handler));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    fn two_projects() -> XrefIndex {
+        let index = XrefIndex::new();
+        index
+            .index_rust_file("billing", "src/lib.rs", "struct Invoice;\nfn invoice_total() {}\nfn invoice_tax() {}\nfn invoice_due() {}\n")
+            .unwrap();
+        index
+            .index_rust_file("crm", "src/lib.rs", "fn invoice() {}\nfn send_invoice() {}\n")
+            .unwrap();
+        index
+    }
+
+    #[actix_web::test]
+    async fn test_federated_search_merges_projects() {
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(two_projects())).configure(configure),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/search?q=invoice&projects=billing,crm&per_project_limit=2")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+
+        let hits: Vec<(&str, &str, f32)> = body
+            .hits
+            .iter()
+            .map(|h| (h.project.as_str(), h.symbol.as_str(), h.score))
+            .collect();
+        assert_eq!(
+            hits,
+            vec![
+                ("crm", "invoice", 1.0),
+                ("billing", "Invoice", 0.9),
+                ("billing", "invoice_tax", 0.7),
+                ("crm", "send_invoice", 0.5),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_search_skips_unknown_projects_and_empty_queries() {
+        let index = two_projects();
+        let search = FederatedSearch {
+            projects: vec!["crm".to_string(), "missing".to_string()],
+            query: "invoice".to_string(),
+            limit: 10,
+            per_project_limit: 10,
+            boosts: FieldBoosts::default(),
+        };
+        assert_eq!(search_federated(&index, &search).len(), 2);
+        assert!(search_project(&index, "crm", "  ", 10, &FieldBoosts::default()).is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_file_name_match_outranks_content_match() {
+        let index = XrefIndex::new();
+        index.index_rust_file("billing", "src/invoice.rs", "fn total() {}\n").unwrap();
+        index
+            .index_rust_file("billing", "src/report.rs", "fn summary() { invoice(); }\n")
+            .unwrap();
+        let app = test::init_service(App::new().app_data(web::Data::new(index)).configure(configure)).await;
+
+        let req = test::TestRequest::get().uri("/api/search?q=invoice&projects=billing").to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        let hits: Vec<(&str, MatchField)> = body.hits.iter().map(|h| (h.file_path.as_str(), h.matched_field)).collect();
+        assert_eq!(hits, vec![("src/invoice.rs", MatchField::FileName), ("src/report.rs", MatchField::Content)]);
+        assert!(body.hits[0].score > body.hits[1].score);
+
+        // Boosts are per request: weighting content up flips the order
+        let req = test::TestRequest::get()
+            .uri("/api/search?q=invoice&projects=billing&content_boost=2.0")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits[0].file_path, "src/report.rs");
+
+        let req = test::TestRequest::get()
+            .uri("/api/search?q=invoice&projects=billing&content_boost=0")
+            .to_request();
+        let body: SearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.hits.len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_identifier_matches_whole_name_and_its_words() {
+        let index = XrefIndex::new();
+        index.index_rust_file("api", "src/handlers.rs", "fn getUserById() {}\nfn reuse() {}\n").unwrap();
+        let search = |query: &str| -> Vec<(String, f32)> {
+            search_project(&index, "api", query, 10, &FieldBoosts::default())
+                .into_iter()
+                .map(|h| (h.symbol, h.score))
+                .collect()
+        };
+
+        assert_eq!(search("getUserById"), vec![("getUserById".to_string(), 1.0)]);
+        assert_eq!(search("user"), vec![("getUserById".to_string(), 0.5)]);
+        assert_eq!(search("user by id"), vec![("getUserById".to_string(), 0.5)]);
+        // Not a word of either name, so only a weaker substring match
+        assert_eq!(search("use"), vec![("reuse".to_string(), 0.4), ("getUserById".to_string(), 0.4)]);
+    }
+
+    #[actix_web::test]
+    async fn test_federated_results_are_cached_until_a_project_is_reindexed() {
+        let index = two_projects();
+        let search = FederatedSearch {
+            projects: vec!["crm".to_string(), "billing".to_string()],
+            query: "refund".to_string(),
+            limit: 10,
+            per_project_limit: 10,
+            boosts: FieldBoosts::default(),
+        };
+        assert!(search_federated(&index, &search).is_empty());
+        assert!(search_federated(&index, &search).is_empty());
+        assert_eq!((index.stats().search_cache.hits, index.stats().search_cache.misses), (1, 1));
+
+        index.index_rust_file("billing", "src/refund.rs", "pub fn refund() {}").unwrap();
+        let hits = search_federated(&index, &search);
+        assert_eq!(hits.iter().map(|h| h.symbol.as_str()).collect::<Vec<_>>(), vec!["refund"]);
+    }
+}
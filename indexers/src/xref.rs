@@ -0,0 +1,502 @@
+//! Cross References
+//!
+//! Per-project index of where each symbol is defined and used, built from the
+//! same syntax trees as `parser`. Serves `GET /api/symbols/{name}/references`.
+
+use actix_web::{web, HttpResponse, Responder};
+use async_trait::async_trait;
+use conhub_plugins::sources::{DeletionSink, DocumentDeletion};
+use conhub_plugins::PluginResult;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tree_sitter::Node;
+
+use crate::analyzer::CodeAnalyzer;
+use crate::parser::{parse_rust, ParserError, SymbolType};
+use crate::performance::{CacheStats, QueryCache, QueryCacheConfig};
+use crate::search::{SearchKey, SymbolHit};
+
+// ============================================================================
+// REFERENCE TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceType {
+    Definition,
+    Reference,
+}
+
+/// One occurrence of a symbol in a file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SymbolLocation {
+    pub file_path: String,
+
+    /// 1-based line
+    pub line: usize,
+
+    /// 1-based column
+    pub column: usize,
+
+    pub reference_type: ReferenceType,
+
+    /// Kind of definition, set for definitions only
+    pub symbol_type: Option<SymbolType>,
+}
+
+/// Response body for the find-references endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolReferences {
+    pub symbol: String,
+    pub project: String,
+    pub definitions: Vec<SymbolLocation>,
+    pub references: Vec<SymbolLocation>,
+}
+
+/// Response body for `/api/stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XrefStats {
+    pub projects: usize,
+    pub symbols: usize,
+    pub query_cache: CacheStats,
+    pub search_cache: CacheStats,
+}
+
+// ============================================================================
+// INDEX
+// ============================================================================
+
+/// Symbol occurrences keyed by project, then symbol name
+#[derive(Default)]
+pub struct XrefIndex {
+    projects: RwLock<HashMap<String, HashMap<String, Vec<SymbolLocation>>>>,
+    /// Lookup results keyed by (project, symbol), dropped when the project is re-indexed
+    cache: QueryCache<(String, String), SymbolReferences>,
+    /// Federated search results, dropped when any of their projects is re-indexed
+    search_cache: QueryCache<SearchKey, Vec<SymbolHit>>,
+    /// Tokenizes symbol names and queries for search
+    analyzer: CodeAnalyzer,
+}
+
+impl XrefIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_cache_config(config: QueryCacheConfig) -> Self {
+        Self {
+            projects: RwLock::default(),
+            cache: QueryCache::new(config.clone()),
+            search_cache: QueryCache::new(config),
+            analyzer: CodeAnalyzer::default(),
+        }
+    }
+
+    pub fn with_analyzer(mut self, analyzer: CodeAnalyzer) -> Self {
+        self.analyzer = analyzer;
+        self
+    }
+
+    pub fn analyzer(&self) -> &CodeAnalyzer {
+        &self.analyzer
+    }
+
+    pub(crate) fn search_cache(&self) -> &QueryCache<SearchKey, Vec<SymbolHit>> {
+        &self.search_cache
+    }
+
+    /// Drop cached results for `project`. Callers hold the projects write lock,
+    /// so no lookup can read the old data after this and still cache it.
+    fn invalidate(&self, project: &str) {
+        self.cache.invalidate_where(|(cached_project, _)| cached_project == project);
+        self.search_cache.invalidate_where(|key| key.covers(project));
+    }
+
+    /// Index a Rust source file, replacing anything previously indexed for that path
+    pub fn index_rust_file(&self, project: &str, file_path: &str, source: &str) -> Result<usize, ParserError> {
+        let tree = parse_rust(source)?;
+        let mut found = Vec::new();
+        collect_occurrences(tree.root_node(), source.as_bytes(), &mut found);
+
+        let mut projects = self.projects.write().unwrap_or_else(|e| e.into_inner());
+        let symbols = projects.entry(project.to_string()).or_default();
+        for locations in symbols.values_mut() {
+            locations.retain(|l| l.file_path != file_path);
+        }
+
+        self.invalidate(project);
+
+        let count = found.len();
+        for (name, line, column, symbol_type) in found {
+            symbols.entry(name).or_default().push(SymbolLocation {
+                file_path: file_path.to_string(),
+                line,
+                column,
+                reference_type: if symbol_type.is_some() { ReferenceType::Definition } else { ReferenceType::Reference },
+                symbol_type,
+            });
+        }
+        Ok(count)
+    }
+
+    /// Drop everything indexed from `file_path`, as when the file is deleted
+    /// upstream. Returns whether anything was removed; removing again is a no-op.
+    pub fn remove_file(&self, project: &str, file_path: &str) -> bool {
+        let mut projects = self.projects.write().unwrap_or_else(|e| e.into_inner());
+        let Some(symbols) = projects.get_mut(project) else { return false };
+
+        let before: usize = symbols.values().map(Vec::len).sum();
+        for locations in symbols.values_mut() {
+            locations.retain(|l| l.file_path != file_path);
+        }
+        symbols.retain(|_, locations| !locations.is_empty());
+        let removed = symbols.values().map(Vec::len).sum::<usize>() != before;
+
+        if removed {
+            self.invalidate(project);
+        }
+        removed
+    }
+
+    pub fn has_project(&self, project: &str) -> bool {
+        self.projects.read().unwrap_or_else(|e| e.into_inner()).contains_key(project)
+    }
+
+    /// Definitions and usages of `name` within `project`, ordered by file and position
+    pub fn find_references(&self, project: &str, name: &str) -> SymbolReferences {
+        let key = (project.to_string(), name.to_string());
+        if let Some(cached) = self.cache.get(&key) {
+            return cached;
+        }
+        // Read before the index so a re-index racing this lookup stops it caching stale data
+        let generation = self.cache.generation();
+
+        let mut locations = self
+            .projects
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(project)
+            .and_then(|symbols| symbols.get(name))
+            .cloned()
+            .unwrap_or_default();
+        locations.sort_by(|a, b| (&a.file_path, a.line, a.column).cmp(&(&b.file_path, b.line, b.column)));
+
+        let (definitions, references) = locations
+            .into_iter()
+            .partition(|l| l.reference_type == ReferenceType::Definition);
+
+        let result = SymbolReferences {
+            symbol: name.to_string(),
+            project: project.to_string(),
+            definitions,
+            references,
+        };
+        if result.definitions.is_empty() && result.references.is_empty() {
+            self.cache.insert_negative_if_current(key, result.clone(), generation);
+        } else {
+            self.cache.insert_if_current(key, result.clone(), generation);
+        }
+        result
+    }
+
+    /// Every definition in `project` as (symbol name, location)
+    pub fn project_definitions(&self, project: &str) -> Vec<(String, SymbolLocation)> {
+        self.project_locations(project, ReferenceType::Definition)
+    }
+
+    /// Every usage in `project` as (symbol name, location)
+    pub fn project_references(&self, project: &str) -> Vec<(String, SymbolLocation)> {
+        self.project_locations(project, ReferenceType::Reference)
+    }
+
+    fn project_locations(&self, project: &str, reference_type: ReferenceType) -> Vec<(String, SymbolLocation)> {
+        let projects = self.projects.read().unwrap_or_else(|e| e.into_inner());
+        let Some(symbols) = projects.get(project) else { return Vec::new() };
+        symbols
+            .iter()
+            .flat_map(|(name, locations)| locations.iter().map(move |l| (name, l)))
+            .filter(|(_, l)| l.reference_type == reference_type)
+            .map(|(name, l)| (name.clone(), l.clone()))
+            .collect()
+    }
+
+    pub fn stats(&self) -> XrefStats {
+        let projects = self.projects.read().unwrap_or_else(|e| e.into_inner());
+        XrefStats {
+            projects: projects.len(),
+            symbols: projects.values().map(HashMap::len).sum(),
+            query_cache: self.cache.stats(),
+            search_cache: self.search_cache.stats(),
+        }
+    }
+}
+
+/// Every identifier in the tree as (name, line, column, definition kind)
+fn collect_occurrences(node: Node, source: &[u8], out: &mut Vec<(String, usize, usize, Option<SymbolType>)>) {
+    if matches!(node.kind(), "identifier" | "type_identifier" | "field_identifier") {
+        if let Ok(name) = node.utf8_text(source) {
+            let position = node.start_position();
+            out.push((name.to_string(), position.row + 1, position.column + 1, definition_kind(node)));
+        }
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_occurrences(child, source, out);
+    }
+}
+
+/// Kind of item `node` names, if it is the name of a definition
+fn definition_kind(node: Node) -> Option<SymbolType> {
+    let parent = node.parent()?;
+    if parent.child_by_field_name("name") != Some(node) {
+        return None;
+    }
+    match parent.kind() {
+        "function_item" | "function_signature_item" => Some(SymbolType::Function),
+        "struct_item" => Some(SymbolType::Struct),
+        "enum_item" => Some(SymbolType::Enum),
+        "trait_item" => Some(SymbolType::Trait),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// DELETION SINK
+// ============================================================================
+
+/// Drops files deleted upstream from the symbol index. A source instance's
+/// files are indexed as the project named after the instance.
+pub struct XrefDeletionSink {
+    index: web::Data<XrefIndex>,
+}
+
+impl XrefDeletionSink {
+    pub fn new(index: web::Data<XrefIndex>) -> Self {
+        Self { index }
+    }
+}
+
+#[async_trait]
+impl DeletionSink for XrefDeletionSink {
+    fn name(&self) -> &str {
+        "xref-index"
+    }
+
+    async fn document_deleted(&self, deletion: &DocumentDeletion) -> PluginResult<()> {
+        if self.index.remove_file(&deletion.instance_id, &deletion.document_id) {
+            tracing::debug!("Removed {} of {} from the symbol index", deletion.document_id, deletion.instance_id);
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// HTTP
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct ReferencesQuery {
+    pub project: String,
+}
+
+/// GET /api/symbols/{name}/references?project=...
+pub async fn find_references_handler(
+    name: web::Path<String>,
+    query: web::Query<ReferencesQuery>,
+    index: web::Data<XrefIndex>,
+) -> impl Responder {
+    if !index.has_project(&query.project) {
+        return HttpResponse::NotFound().json(json!({
+            "error": format!("Project '{}' has not been indexed", query.project)
+        }));
+    }
+
+    HttpResponse::Ok().json(index.find_references(&query.project, &name))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileQuery {
+    pub path: String,
+}
+
+/// DELETE /api/projects/{project}/files?path=...
+/// Remove a file deleted upstream. Succeeds whether or not it was indexed, so a
+/// repeated delete event is harmless.
+pub async fn delete_file_handler(
+    project: web::Path<String>,
+    query: web::Query<FileQuery>,
+    index: web::Data<XrefIndex>,
+) -> impl Responder {
+    let removed = index.remove_file(&project, &query.path);
+    HttpResponse::Ok().json(json!({ "project": project.into_inner(), "path": query.path, "removed": removed }))
+}
+
+/// GET /api/stats
+pub async fn stats_handler(index: web::Data<XrefIndex>) -> impl Responder {
+    HttpResponse::Ok().json(index.stats())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/symbols/{name}/references", web::get().to(find_references_handler))
+        .route("/api/projects/{project}/files", web::delete().to(delete_file_handler))
+        .route("/api/stats", web::get().to(stats_handler));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use std::time::Duration;
+
+    const CONFIG_RS: &str = r#"pub struct Config {
+    pub name: String,
+}
+
+pub fn load_config() -> Config {
+    Config { name: String::new() }
+}
+"#;
+
+    const MAIN_RS: &str = r#"use crate::config::{load_config, Config};
+
+fn main() {
+    let config: Config = load_config();
+    println!("{}", config.name);
+}
+"#;
+
+    fn fixture_index() -> XrefIndex {
+        let index = XrefIndex::new();
+        index.index_rust_file("app", "src/config.rs", CONFIG_RS).unwrap();
+        index.index_rust_file("app", "src/main.rs", MAIN_RS).unwrap();
+        // Same symbol in another project must not leak into "app"
+        index.index_rust_file("other", "src/lib.rs", "struct Config;").unwrap();
+        index
+    }
+
+    fn positions(locations: &[SymbolLocation]) -> Vec<(&str, usize, usize)> {
+        locations.iter().map(|l| (l.file_path.as_str(), l.line, l.column)).collect()
+    }
+
+    #[actix_web::test]
+    async fn test_find_references_across_files() {
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(fixture_index())).configure(configure),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/symbols/Config/references?project=app").to_request();
+        let body: SymbolReferences = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(positions(&body.definitions), vec![("src/config.rs", 1, 12)]);
+        assert_eq!(body.definitions[0].symbol_type, Some(SymbolType::Struct));
+        assert_eq!(
+            positions(&body.references),
+            vec![
+                ("src/config.rs", 5, 25),
+                ("src/config.rs", 6, 5),
+                ("src/main.rs", 1, 34),
+                ("src/main.rs", 4, 17),
+            ]
+        );
+
+        let req = test::TestRequest::get().uri("/api/symbols/Config/references?project=missing").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_repeated_query_hits_cache() {
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(fixture_index())).configure(configure),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = test::TestRequest::get().uri("/api/symbols/load_config/references?project=app").to_request();
+            let body: SymbolReferences = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body.references.len(), 2);
+        }
+
+        let req = test::TestRequest::get().uri("/api/stats").to_request();
+        let stats: XrefStats = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(stats.projects, 2);
+        assert_eq!((stats.query_cache.hits, stats.query_cache.misses), (1, 1));
+    }
+
+    #[actix_web::test]
+    async fn test_repeated_empty_query_hits_negative_cache() {
+        let index = XrefIndex::with_cache_config(QueryCacheConfig {
+            capacity: 16,
+            ttl: Duration::from_secs(300),
+            negative_ttl: Duration::from_secs(60),
+        });
+        index.index_rust_file("app", "src/config.rs", CONFIG_RS).unwrap();
+
+        // Misspelled symbol: the second lookup is served from the cache
+        for _ in 0..2 {
+            let refs = index.find_references("app", "lod_config");
+            assert!(refs.definitions.is_empty() && refs.references.is_empty());
+        }
+        assert_eq!((index.stats().query_cache.hits, index.stats().query_cache.misses), (1, 1));
+
+        // Indexing the project invalidates the empty result
+        index.index_rust_file("app", "src/legacy.rs", "fn lod_config() {}").unwrap();
+        assert_eq!(positions(&index.find_references("app", "lod_config").definitions), vec![("src/legacy.rs", 1, 4)]);
+    }
+
+    #[actix_web::test]
+    async fn test_reindexing_a_file_replaces_its_occurrences() {
+        let index = fixture_index();
+        assert_eq!(index.find_references("app", "load_config").references.len(), 2);
+        index.index_rust_file("app", "src/main.rs", "fn main() {}").unwrap();
+
+        let refs = index.find_references("app", "load_config");
+        assert_eq!(positions(&refs.definitions), vec![("src/config.rs", 5, 8)]);
+        assert!(refs.references.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_deleted_file_is_removed_from_search_results() {
+        use crate::search::{search_project, FieldBoosts};
+
+        let index = web::Data::new(fixture_index());
+        let app = test::init_service(App::new().app_data(index.clone()).configure(configure)).await;
+        let found = |query: &str| -> Vec<String> {
+            search_project(&index, "app", query, 10, &FieldBoosts::default())
+                .into_iter()
+                .map(|h| h.file_path)
+                .collect()
+        };
+        assert_eq!(found("load_config"), vec!["src/config.rs", "src/main.rs"]);
+
+        let delete = || test::TestRequest::delete().uri("/api/projects/app/files?path=src/config.rs").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, delete()).await;
+        assert_eq!(body["removed"], true);
+        // Only main.rs's usage is left
+        assert_eq!(found("load_config"), vec!["src/main.rs"]);
+        assert!(index.find_references("app", "Config").definitions.is_empty());
+
+        // Delivering the same deletion again changes nothing
+        let body: serde_json::Value = test::call_and_read_body_json(&app, delete()).await;
+        assert_eq!(body["removed"], false);
+        assert_eq!(found("load_config"), vec!["src/main.rs"]);
+    }
+
+    #[actix_web::test]
+    async fn test_deletion_sink_removes_the_file_from_the_instance_project() {
+        let index = web::Data::new(fixture_index());
+        let sink = XrefDeletionSink::new(index.clone());
+        let deletion = DocumentDeletion { instance_id: "app".to_string(), document_id: "src/config.rs".to_string() };
+
+        sink.document_deleted(&deletion).await.unwrap();
+        assert!(index.find_references("app", "Config").definitions.is_empty());
+        assert_eq!(index.find_references("other", "Config").definitions.len(), 1);
+
+        // A repeated delivery still succeeds
+        sink.document_deleted(&deletion).await.unwrap();
+    }
+}
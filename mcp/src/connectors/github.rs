@@ -2,16 +2,156 @@
 use super::Connector;
 use crate::{context::*, errors::{McpError, McpResult}, protocol::McpTool, security_client::SecurityClient};
 use async_trait::async_trait;
+use conhub_plugins::retry::RetryPolicy;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
 use std::sync::Arc;
 use conhub_database::cache::RedisCache;
 
+/// Blobs fetched at once during a branch sync, unless GITHUB_FETCH_CONCURRENCY says otherwise
+const DEFAULT_FETCH_CONCURRENCY: usize = 8;
+
 pub struct GitHubConnector {
     api_base: String,
     security: Arc<SecurityClient>,
     cache: Option<RedisCache>,
     client: reqwest::Client,
+    fetch_concurrency: usize,
+    retry: RetryPolicy,
+    default_branches: DefaultBranches,
+}
+
+/// Outcome of fetching a branch's files. Each file succeeds or fails on its
+/// own, so one bad blob doesn't abort the sync.
+#[derive(Debug, Default, Serialize)]
+pub struct BlobFetchReport {
+    pub files: Vec<FetchedBlob>,
+    pub failed: Vec<FailedBlob>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FetchedBlob {
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailedBlob {
+    pub path: String,
+    pub error: String,
+}
+
+/// A failed blob request and whether the retry policy should try it again
+#[derive(Debug)]
+struct BlobError {
+    error: McpError,
+    retryable: bool,
+}
+
+impl fmt::Display for BlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+/// Files that changed between the last synced commit and the branch head
+#[derive(Debug, Default, Serialize)]
+pub struct TreeDiff {
+    pub head_sha: String,
+    /// Added or modified; these are the only files that need fetching
+    pub changed: Vec<String>,
+    /// Deleted or renamed away; their chunks should be dropped from the index
+    pub removed: Vec<String>,
+}
+
+impl TreeDiff {
+    fn from_compare(compare: GitHubCompare, base_sha: &str) -> Self {
+        let mut diff = TreeDiff {
+            head_sha: compare.commits.last().map(|c| c.sha.clone()).unwrap_or_else(|| base_sha.to_string()),
+            ..Default::default()
+        };
+        for file in compare.files {
+            match file.status.as_str() {
+                "removed" => diff.removed.push(file.filename),
+                "renamed" => {
+                    diff.removed.extend(file.previous_filename);
+                    diff.changed.push(file.filename);
+                }
+                // Unchanged entries carry no content change worth refetching
+                "unchanged" => {}
+                _ => diff.changed.push(file.filename),
+            }
+        }
+        diff
+    }
+}
+
+/// Result of an incremental sync. Callers store `head_sha` as the new
+/// last-synced commit once the fetched files are indexed.
+#[derive(Debug, Serialize)]
+pub struct IncrementalSyncReport {
+    pub head_sha: String,
+    pub fetched: BlobFetchReport,
+    pub removed: Vec<String>,
+}
+
+/// Run `fetch` over `paths` with at most `concurrency` requests in flight,
+/// collecting each file's result separately
+async fn fetch_bounded<F, Fut>(paths: Vec<String>, concurrency: usize, fetch: F) -> BlobFetchReport
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = McpResult<String>>,
+{
+    let results: Vec<(String, McpResult<String>)> = stream::iter(paths)
+        .map(|path| {
+            let fetched = fetch(path.clone());
+            async move { (path, fetched.await) }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut report = BlobFetchReport::default();
+    for (path, result) in results {
+        match result {
+            Ok(content) => report.files.push(FetchedBlob { path, content }),
+            Err(e) => {
+                tracing::warn!("Failed to fetch {}: {}", path, e);
+                report.failed.push(FailedBlob { path, error: e.to_string() });
+            }
+        }
+    }
+    report.files.sort_by(|a, b| a.path.cmp(&b.path));
+    report.failed.sort_by(|a, b| a.path.cmp(&b.path));
+    report
+}
+
+/// Each repository's default branch, looked up from GitHub on first use
+#[derive(Debug, Default)]
+struct DefaultBranches(tokio::sync::RwLock<HashMap<String, String>>);
+
+impl DefaultBranches {
+    /// `requested` when given, otherwise the repository's default branch from `lookup`
+    async fn resolve<F, Fut>(&self, repo_path: &str, requested: Option<&str>, lookup: F) -> McpResult<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = McpResult<String>>,
+    {
+        if let Some(branch) = requested.map(str::trim).filter(|b| !b.is_empty()) {
+            return Ok(branch.to_string());
+        }
+        if let Some(branch) = self.0.read().await.get(repo_path) {
+            return Ok(branch.clone());
+        }
+
+        let branch = lookup().await?;
+        self.0.write().await.insert(repo_path.to_string(), branch.clone());
+        Ok(branch)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,6 +184,39 @@ struct GitHubCommit {
     sha: String,
 }
 
+/// GitHub caps compare responses at these sizes; a diff that hits either is incomplete
+const COMPARE_FILE_LIMIT: usize = 300;
+const COMPARE_COMMIT_LIMIT: usize = 250;
+
+#[derive(Debug, Deserialize)]
+struct GitHubCompare {
+    #[serde(default)]
+    commits: Vec<GitHubCommit>,
+    #[serde(default)]
+    files: Vec<GitHubCompareFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubCompareFile {
+    filename: String,
+    status: String,
+    previous_filename: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubTree {
+    tree: Vec<GitHubTreeEntry>,
+    #[serde(default)]
+    truncated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubTreeEntry {
+    path: String,
+    #[serde(rename = "type")]
+    entry_type: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct GitHubContent {
     name: String,
@@ -62,8 +235,24 @@ impl GitHubConnector {
             security,
             cache,
             client: reqwest::Client::new(),
+            fetch_concurrency: std::env::var("GITHUB_FETCH_CONCURRENCY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_FETCH_CONCURRENCY),
+            retry: RetryPolicy::default(),
+            default_branches: DefaultBranches::default(),
         }
     }
+
+    pub fn with_fetch_concurrency(mut self, concurrency: usize) -> Self {
+        self.fetch_concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
     
     async fn get_token(&self, user_id: Option<&uuid::Uuid>) -> McpResult<String> {
         // For now, use env variable or get from security
@@ -144,11 +333,12 @@ impl GitHubConnector {
         let branches: Vec<GitHubBranch> = response.json().await
             .map_err(|e| McpError::ProviderError(e.to_string()))?;
         
+        let default_branch = self.branch_arg(&token, repo_path, &json!({})).await?;
         let descriptors: Vec<BranchDescriptor> = branches.into_iter().map(|b| {
             BranchDescriptor {
+                is_default: b.name == default_branch,
                 name: b.name,
                 commit_id: b.commit.sha,
-                is_default: false, // Would need to check against repo default
                 protected: Some(b.protected),
             }
         }).collect();
@@ -160,10 +350,10 @@ impl GitHubConnector {
         let token = self.get_token(None).await?;
         let repo_id = args.get("repo_id").and_then(|v| v.as_str())
             .ok_or_else(|| McpError::InvalidArguments("Missing repo_id".to_string()))?;
-        let branch = args.get("branch").and_then(|v| v.as_str()).unwrap_or("main");
         let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
         
         let repo_path = repo_id.strip_prefix("gh:").unwrap_or(repo_id);
+        let branch = self.branch_arg(&token, repo_path, &args).await?;
         let url = format!("{}/repos/{}/contents/{}?ref={}", self.api_base, repo_path, path, branch);
         
         let response = self.client
@@ -202,11 +392,11 @@ impl GitHubConnector {
         let token = self.get_token(None).await?;
         let repo_id = args.get("repo_id").and_then(|v| v.as_str())
             .ok_or_else(|| McpError::InvalidArguments("Missing repo_id".to_string()))?;
-        let branch = args.get("branch").and_then(|v| v.as_str()).unwrap_or("main");
         let path = args.get("path").and_then(|v| v.as_str())
             .ok_or_else(|| McpError::InvalidArguments("Missing path".to_string()))?;
         
         let repo_path = repo_id.strip_prefix("gh:").unwrap_or(repo_id);
+        let branch = self.branch_arg(&token, repo_path, &args).await?;
         let url = format!("{}/repos/{}/contents/{}?ref={}", self.api_base, repo_path, path, branch);
         
         let response = self.client
@@ -237,6 +427,165 @@ impl GitHubConnector {
         }))
     }
     
+    /// Branch named in `args`, or the repository's default branch when omitted
+    async fn branch_arg(&self, token: &str, repo_path: &str, args: &Value) -> McpResult<String> {
+        self.default_branches
+            .resolve(repo_path, args.get("branch").and_then(|v| v.as_str()), || async {
+                let repo: GitHubRepo = self.get_json(token, &format!("{}/repos/{}", self.api_base, repo_path)).await?;
+                Ok(repo.default_branch)
+            })
+            .await
+    }
+
+    /// Raw content of one file. Rate limits, server errors and network failures
+    /// are marked retryable.
+    async fn fetch_raw(&self, token: &str, repo_path: &str, branch: &str, path: &str) -> Result<String, BlobError> {
+        let url = format!("{}/repos/{}/contents/{}?ref={}", self.api_base, repo_path, path, branch);
+
+        let response = self.client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("User-Agent", "ConHub-MCP")
+            .header("Accept", "application/vnd.github.v3.raw")
+            .send()
+            .await
+            .map_err(|e| BlobError { error: McpError::ProviderError(e.to_string()), retryable: true })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let rate_limited = status.as_u16() == 429
+                || (status.as_u16() == 403
+                    && response.headers().get("x-ratelimit-remaining").is_some_and(|v| v == "0"));
+            let error = if rate_limited {
+                McpError::RateLimited(format!("GitHub API rate limit hit fetching {}", path))
+            } else {
+                McpError::ProviderError(format!("GitHub API error: {}", status))
+            };
+            return Err(BlobError { error, retryable: rate_limited || self.retry.is_retryable_status(status.as_u16()) });
+        }
+
+        response.text().await
+            .map_err(|e| BlobError { error: McpError::ProviderError(e.to_string()), retryable: true })
+    }
+
+    /// Fetch `paths` from a branch with bounded concurrency, retrying each file
+    /// through the shared retry policy
+    pub async fn sync_repository_branch(&self, repo_id: &str, branch: &str, paths: Vec<String>) -> McpResult<BlobFetchReport> {
+        let token = self.get_token(None).await?;
+        let repo_path = repo_id.strip_prefix("gh:").unwrap_or(repo_id);
+
+        Ok(fetch_bounded(paths, self.fetch_concurrency, |path| {
+            let token = &token;
+            async move {
+                self.retry
+                    .retry(|| self.fetch_raw(token, repo_path, branch, &path), |e: &BlobError| e.retryable)
+                    .await
+                    .map_err(|e| e.error)
+            }
+        })
+        .await)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, token: &str, url: &str) -> McpResult<T> {
+        let response = self.client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("User-Agent", "ConHub-MCP")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(McpError::ProviderError(format!("GitHub API error: {}", response.status())));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Every blob at the branch head, for a first sync or when a compare is incomplete
+    async fn full_tree(&self, token: &str, repo_path: &str, branch: &str) -> McpResult<TreeDiff> {
+        let head: GitHubBranch = self.get_json(token, &format!("{}/repos/{}/branches/{}", self.api_base, repo_path, branch)).await?;
+        let tree: GitHubTree = self.get_json(
+            token,
+            &format!("{}/repos/{}/git/trees/{}?recursive=1", self.api_base, repo_path, head.commit.sha),
+        ).await?;
+        if tree.truncated {
+            tracing::warn!("Tree for {}@{} was truncated by GitHub; some files will not be synced", repo_path, branch);
+        }
+
+        Ok(TreeDiff {
+            head_sha: head.commit.sha,
+            changed: tree.tree.into_iter().filter(|e| e.entry_type == "blob").map(|e| e.path).collect(),
+            removed: Vec::new(),
+        })
+    }
+
+    /// What changed on `branch` since `last_synced_commit`, or the whole tree
+    /// when there is no previous sync
+    pub async fn tree_diff(&self, repo_id: &str, branch: &str, last_synced_commit: Option<&str>) -> McpResult<TreeDiff> {
+        let token = self.get_token(None).await?;
+        let repo_path = repo_id.strip_prefix("gh:").unwrap_or(repo_id);
+
+        let Some(base) = last_synced_commit else {
+            return self.full_tree(&token, repo_path, branch).await;
+        };
+
+        let compare: GitHubCompare = self.get_json(
+            &token,
+            &format!("{}/repos/{}/compare/{}...{}", self.api_base, repo_path, base, branch),
+        ).await?;
+        if compare.files.len() >= COMPARE_FILE_LIMIT || compare.commits.len() >= COMPARE_COMMIT_LIMIT {
+            tracing::info!("Compare from {} in {} is incomplete; falling back to a full sync", base, repo_path);
+            return self.full_tree(&token, repo_path, branch).await;
+        }
+
+        Ok(TreeDiff::from_compare(compare, base))
+    }
+
+    /// Fetch only the files that changed since `last_synced_commit`. Files are
+    /// read at the resolved head commit so they match the returned `head_sha`.
+    pub async fn sync_repository_incremental(
+        &self,
+        repo_id: &str,
+        branch: &str,
+        last_synced_commit: Option<&str>,
+    ) -> McpResult<IncrementalSyncReport> {
+        let diff = self.tree_diff(repo_id, branch, last_synced_commit).await?;
+        let fetched = self.sync_repository_branch(repo_id, &diff.head_sha, diff.changed).await?;
+
+        Ok(IncrementalSyncReport {
+            head_sha: diff.head_sha,
+            fetched,
+            removed: diff.removed,
+        })
+    }
+
+    async fn sync_changes(&self, args: Value) -> McpResult<Value> {
+        let repo_id = args.get("repo_id").and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::InvalidArguments("Missing repo_id".to_string()))?;
+        let since = args.get("since_commit").and_then(|v| v.as_str());
+        let token = self.get_token(None).await?;
+        let branch = self.branch_arg(&token, repo_id.strip_prefix("gh:").unwrap_or(repo_id), &args).await?;
+
+        let report = self.sync_repository_incremental(repo_id, &branch, since).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+
+    async fn sync_branch(&self, args: Value) -> McpResult<Value> {
+        let repo_id = args.get("repo_id").and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::InvalidArguments("Missing repo_id".to_string()))?;
+        let token = self.get_token(None).await?;
+        let branch = self.branch_arg(&token, repo_id.strip_prefix("gh:").unwrap_or(repo_id), &args).await?;
+        let paths: Vec<String> = args.get("paths")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| McpError::InvalidArguments("Missing paths".to_string()))?
+            .iter()
+            .filter_map(|p| p.as_str().map(String::from))
+            .collect();
+
+        let report = self.sync_repository_branch(repo_id, &branch, paths).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+
     fn detect_language(path: &str) -> Option<String> {
         path.rsplit('.').next().and_then(|ext| {
             match ext {
@@ -321,6 +670,32 @@ impl Connector for GitHubConnector {
                     "required": ["repo_id", "path"]
                 })),
             },
+            McpTool {
+                name: "github.sync_branch".to_string(),
+                description: "Fetch many files from a branch concurrently; failed files are reported, not fatal".to_string(),
+                input_schema: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "repo_id": { "type": "string" },
+                        "branch": { "type": "string" },
+                        "paths": { "type": "array", "items": { "type": "string" } }
+                    },
+                    "required": ["repo_id", "paths"]
+                })),
+            },
+            McpTool {
+                name: "github.sync_changes".to_string(),
+                description: "Fetch only files changed on a branch since a previously synced commit".to_string(),
+                input_schema: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "repo_id": { "type": "string" },
+                        "branch": { "type": "string" },
+                        "since_commit": { "type": "string", "description": "Last synced commit sha; omit for a full sync" }
+                    },
+                    "required": ["repo_id"]
+                })),
+            },
         ]
     }
     
@@ -330,7 +705,111 @@ impl Connector for GitHubConnector {
             "list_branches" => self.list_branches(args).await,
             "list_files" => self.list_files(args).await,
             "get_file_content" => self.get_file_content(args).await,
+            "sync_branch" => self.sync_branch(args).await,
+            "sync_changes" => self.sync_changes(args).await,
             _ => Err(McpError::ToolNotFound(format!("Unknown GitHub tool: {}", tool))),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_blob_fetching_is_bounded_and_isolates_failures() {
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let paths: Vec<String> = (0..12).map(|i| format!("src/file_{:02}.rs", i)).collect();
+
+        let report = fetch_bounded(paths, 3, |path| {
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                if path.ends_with("_05.rs") {
+                    Err(McpError::ProviderError("GitHub API error: 404 Not Found".to_string()))
+                } else {
+                    Ok(format!("// {}", path))
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+        assert_eq!(report.files.len(), 11);
+        assert_eq!(report.files[0].content, "// src/file_00.rs");
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, "src/file_05.rs");
+        assert!(report.failed[0].error.contains("404"));
+    }
+
+    #[tokio::test]
+    async fn test_two_file_change_fetches_only_those_files() {
+        let compare: GitHubCompare = serde_json::from_value(json!({
+            "commits": [{ "sha": "b2" }, { "sha": "c3" }],
+            "files": [
+                { "filename": "src/lib.rs", "status": "modified" },
+                { "filename": "src/new.rs", "status": "added" },
+                { "filename": "src/old.rs", "status": "removed" }
+            ]
+        }))
+        .unwrap();
+        let diff = TreeDiff::from_compare(compare, "a1");
+        assert_eq!(diff.head_sha, "c3");
+        assert_eq!(diff.removed, vec!["src/old.rs"]);
+
+        let requested = std::sync::Mutex::new(Vec::new());
+        let report = fetch_bounded(diff.changed, 4, |path| {
+            requested.lock().unwrap().push(path.clone());
+            async move { Ok(format!("// {}", path)) }
+        })
+        .await;
+
+        let mut requested = requested.into_inner().unwrap();
+        requested.sort();
+        assert_eq!(requested, vec!["src/lib.rs", "src/new.rs"]);
+        assert_eq!(report.files.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_omitted_branch_resolves_to_provider_default() {
+        let branches = DefaultBranches::default();
+        let lookups = AtomicUsize::new(0);
+        let lookup = || async {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            let repo: GitHubRepo = serde_json::from_value(json!({
+                "id": 1, "name": "billing", "full_name": "acme/billing",
+                "owner": { "login": "acme" }, "private": true, "description": null,
+                "default_branch": "develop", "html_url": "https://github.com/acme/billing",
+                "updated_at": "2024-01-01T00:00:00Z"
+            }))
+            .unwrap();
+            Ok(repo.default_branch)
+        };
+
+        assert_eq!(branches.resolve("acme/billing", None, lookup).await.unwrap(), "develop");
+        assert_eq!(branches.resolve("acme/billing", Some(" "), lookup).await.unwrap(), "develop");
+        assert_eq!(branches.resolve("acme/billing", Some("release/2.0"), lookup).await.unwrap(), "release/2.0");
+        // Looked up once per repository
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_rename_drops_old_path_and_identical_compare_keeps_base() {
+        let compare: GitHubCompare = serde_json::from_value(json!({
+            "files": [{ "filename": "docs/guide.md", "status": "renamed", "previous_filename": "docs/old.md" }]
+        }))
+        .unwrap();
+        let diff = TreeDiff::from_compare(compare, "a1");
+
+        assert_eq!(diff.head_sha, "a1");
+        assert_eq!(diff.changed, vec!["docs/guide.md"]);
+        assert_eq!(diff.removed, vec!["docs/old.md"]);
+    }
+}
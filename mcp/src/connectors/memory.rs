@@ -13,6 +13,13 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tracing::{info, error, debug};
 
+/// Page size used when the caller does not pass `limit`
+const DEFAULT_PAGE_SIZE: u32 = 20;
+/// Largest page a caller may request in one call
+const MAX_PAGE_SIZE: u32 = 100;
+/// Deepest offset a caller may page to; the backend ranks `offset + limit` blocks
+const MAX_OFFSET: u32 = 10_000;
+
 /// Memory connector for MCP
 /// 
 /// Provides tools for AI agents to:
@@ -153,9 +160,17 @@ impl super::Connector for MemoryConnector {
                             },
                             "description": "Optional: Filter by time range"
                         },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Page size, applied after ranking (default: 20, max: 100)"
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Number of ranked blocks to skip; pass back `next_offset` to get the next page"
+                        },
                         "max_blocks": {
                             "type": "integer",
-                            "description": "Maximum number of context blocks to return (default: 20)"
+                            "description": "Deprecated alias for limit"
                         },
                         "strategy": {
                             "type": "string",
@@ -204,9 +219,17 @@ impl super::Connector for MemoryConnector {
                             "type": "boolean",
                             "description": "Include semantic facts (default: true)"
                         },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Page size, applied after ranking (default: 20, max: 100)"
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Number of ranked blocks to skip; pass back `next_offset` to get the next page"
+                        },
                         "max_blocks": {
                             "type": "integer",
-                            "description": "Maximum blocks to return (default: 20)"
+                            "description": "Deprecated alias for limit"
                         }
                     },
                     "required": ["robot_id", "query"]
@@ -301,10 +324,7 @@ impl super::Connector for MemoryConnector {
                     .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
                     .unwrap_or_default();
                 
-                let max_blocks: u32 = args.get("max_blocks")
-                    .and_then(|v| v.as_u64())
-                    .map(|n| n as u32)
-                    .unwrap_or(20);
+                let (offset, limit) = page_args(&args);
                 
                 let strategy = args.get("strategy")
                     .and_then(|v| v.as_str())
@@ -322,13 +342,15 @@ impl super::Connector for MemoryConnector {
                         map.insert("repos".to_string(), json!(repos));
                         map
                     },
-                    max_blocks,
+                    // Rank everything up to the end of the requested page, then slice
+                    max_blocks: ranked_window(offset, limit),
                     max_tokens: 8000,
                     force_strategy: strategy,
                     include_debug: false,
                 };
                 
-                self.call_memory_search(request).await
+                let result = self.call_memory_search(request).await?;
+                Ok(paginate_blocks(result, offset, limit))
             }
             
             "robot_search" => {
@@ -353,10 +375,7 @@ impl super::Connector for MemoryConnector {
                     .and_then(|v| v.as_bool())
                     .unwrap_or(true);
                 
-                let max_blocks = args.get("max_blocks")
-                    .and_then(|v| v.as_u64())
-                    .map(|n| n as u32)
-                    .unwrap_or(20);
+                let (offset, limit) = page_args(&args);
                 
                 let request = RobotMemorySearchRequest {
                     robot_id: uuid::Uuid::parse_str(robot_id)
@@ -367,10 +386,11 @@ impl super::Connector for MemoryConnector {
                     location,
                     include_episodic,
                     include_semantic,
-                    max_blocks,
+                    max_blocks: ranked_window(offset, limit),
                 };
                 
-                self.call_robot_memory_search(robot_id, request).await
+                let result = self.call_robot_memory_search(robot_id, request).await?;
+                Ok(paginate_blocks(result, offset, limit))
             }
             
             "robot_context" => {
@@ -505,6 +525,65 @@ impl super::Connector for MemoryConnector {
     }
 }
 
+/// Read `offset` and `limit` from tool args, capping the offset to `MAX_OFFSET`
+/// and the limit to `MAX_PAGE_SIZE`. `max_blocks` is still accepted as an alias
+/// for `limit`.
+fn page_args(args: &Value) -> (u32, u32) {
+    let offset = args.get("offset")
+        .and_then(|v| v.as_u64())
+        .map(|n| n.min(MAX_OFFSET as u64) as u32)
+        .unwrap_or(0);
+    
+    let limit = args.get("limit")
+        .or_else(|| args.get("max_blocks"))
+        .and_then(|v| v.as_u64())
+        .map(|n| n.clamp(1, MAX_PAGE_SIZE as u64) as u32)
+        .unwrap_or(DEFAULT_PAGE_SIZE);
+    
+    (offset, limit)
+}
+
+/// Number of blocks the backend has to rank to serve the page
+fn ranked_window(offset: u32, limit: u32) -> u32 {
+    offset.saturating_add(limit).min(MAX_OFFSET + MAX_PAGE_SIZE)
+}
+
+/// Slice the ranked `blocks` of a search response down to one page and report
+/// how many blocks are available in total.
+fn paginate_blocks(mut result: Value, offset: u32, limit: u32) -> Value {
+    let Some(blocks) = result.get_mut("blocks").and_then(|b| b.as_array_mut()) else {
+        return result;
+    };
+    
+    let ranked = std::mem::take(blocks);
+    // The backend stops ranking at `max_blocks`, so a full window may have more behind it
+    let window_full = ranked.len() >= ranked_window(offset, limit) as usize;
+    let total_available = result.get("total_results")
+        .and_then(|t| t.as_u64())
+        .map(|t| t as usize)
+        .unwrap_or(0)
+        .max(ranked.len());
+    
+    let page: Vec<Value> = ranked.into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+    
+    let next = offset as usize + page.len();
+    let more = next < total_available || (window_full && page.len() == limit as usize);
+    let next_offset = if !page.is_empty() && more && next < (MAX_OFFSET as usize) { Some(next) } else { None };
+    
+    if let Some(obj) = result.as_object_mut() {
+        obj.insert("blocks".to_string(), json!(page));
+        obj.insert("offset".to_string(), json!(offset));
+        obj.insert("limit".to_string(), json!(limit));
+        obj.insert("total_available".to_string(), json!(total_available));
+        obj.insert("next_offset".to_string(), json!(next_offset));
+    }
+    
+    result
+}
+
 // Request/response types for API calls
 
 #[derive(Debug, Serialize)]
@@ -537,3 +616,79 @@ struct TimeRange {
     from: String,
     to: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked_response(count: usize) -> Value {
+        let blocks: Vec<Value> = (0..count)
+            .map(|i| json!({ "chunk_id": format!("chunk-{}", i), "score": 1.0 - i as f64 * 0.1 }))
+            .collect();
+        json!({ "blocks": blocks, "total_results": count })
+    }
+
+    #[test]
+    fn test_pages_are_disjoint_and_cover_total() {
+        let mut seen = std::collections::HashSet::new();
+        let mut offset = 0u32;
+        let mut pages = 0;
+
+        loop {
+            let page = paginate_blocks(ranked_response(7), offset, 3);
+            assert_eq!(page["total_available"], 7);
+
+            for block in page["blocks"].as_array().unwrap() {
+                assert!(seen.insert(block["chunk_id"].as_str().unwrap().to_string()));
+            }
+            pages += 1;
+
+            match page["next_offset"].as_u64() {
+                Some(next) => offset = next as u32,
+                None => break,
+            }
+        }
+
+        assert_eq!(pages, 3);
+        assert_eq!(seen.len(), 7);
+    }
+
+    #[test]
+    fn test_limit_is_capped_to_server_max() {
+        let (offset, limit) = page_args(&json!({ "limit": 10_000, "offset": 40 }));
+        assert_eq!(offset, 40);
+        assert_eq!(limit, MAX_PAGE_SIZE);
+
+        let (_, legacy) = page_args(&json!({ "max_blocks": 5 }));
+        assert_eq!(legacy, 5);
+    }
+
+    #[test]
+    fn test_huge_offset_is_clamped() {
+        let (offset, limit) = page_args(&json!({ "offset": u32::MAX as u64 - 1, "limit": 50 }));
+        assert_eq!(offset, MAX_OFFSET);
+        assert_eq!(ranked_window(offset, limit), MAX_OFFSET + 50);
+        assert_eq!(ranked_window(u32::MAX, MAX_PAGE_SIZE), MAX_OFFSET + MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_paging_continues_past_first_window_when_total_is_truncated() {
+        // A backend that ranks only `max_blocks` and reports that as its total
+        let backend = |max_blocks: u32| ranked_response((max_blocks as usize).min(25));
+
+        let mut seen = std::collections::HashSet::new();
+        let mut offset = 0u32;
+        loop {
+            let page = paginate_blocks(backend(ranked_window(offset, 10)), offset, 10);
+            for block in page["blocks"].as_array().unwrap() {
+                assert!(seen.insert(block["chunk_id"].as_str().unwrap().to_string()));
+            }
+            match page["next_offset"].as_u64() {
+                Some(next) => offset = next as u32,
+                None => break,
+            }
+        }
+
+        assert_eq!(seen.len(), 25);
+    }
+}
@@ -1,9 +1,9 @@
 use actix_web::{web, App, HttpServer};
 use conhub_middleware::auth::AuthMiddlewareFactory;
 use actix_cors::Cors;
-use sqlx::{PgPool, postgres::{PgPoolOptions, PgConnectOptions}};
+use sqlx::PgPool;
+use conhub_database::DatabaseConfig;
 use std::env;
-use std::str::FromStr;
 use conhub_observability::{init_tracing, TracingConfig, observability, info, warn, error};
 
 mod services;
@@ -38,14 +38,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("📊 [Security Service] Connecting to database...");
     }
 
-    // Disable server-side prepared statements for pgbouncer/Neon transaction pooling
-    let connect_options = PgConnectOptions::from_str(&database_url)?
-        .statement_cache_capacity(0);
-
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect_with(connect_options)
-        .await?;
+    let pool = conhub_database::connect(&DatabaseConfig::for_url(database_url)).await?;
     info!("✅ [Security Service] Database connection established");
     let db_pool_opt: Option<PgPool> = Some(pool);
 
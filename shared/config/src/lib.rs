@@ -3,11 +3,41 @@ pub mod feature_toggles;
 
 use feature_toggles::FeatureToggles;
 
-use reqwest::Client;
+use reqwest::{Client, Url};
+use thiserror::Error;
+
+/// Service URL env vars that must be set explicitly outside development mode
+const REQUIRED_URL_VARS: [&str; 3] = [
+    "LANGCHAIN_SERVICE_URL",
+    "HAYSTACK_SERVICE_URL",
+    "UNIFIED_INDEXER_URL",
+];
+
+/// Errors raised while loading or validating `AppConfig`
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigError {
+    #[error("required environment variable {0} is not set")]
+    MissingVar(String),
+
+    #[error("{var} is not a valid URL ({value}): {reason}")]
+    InvalidUrl {
+        var: String,
+        value: String,
+        reason: String,
+    },
+
+    #[error("{var} has an invalid value ({value}): {reason}")]
+    InvalidValue {
+        var: String,
+        value: String,
+        reason: String,
+    },
+}
 
 #[derive(Clone)]
 pub struct AppConfig {
     pub http_client: Client,
+    pub env_mode: String,
     pub langchain_url: String,
     pub haystack_url: String,
     pub unified_indexer_url: String,
@@ -16,18 +46,140 @@ pub struct AppConfig {
 
 impl AppConfig {
     pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Load from the environment and validate, failing fast on bad or missing values
+    pub fn try_from_env() -> Result<Self, ConfigError> {
+        Self::try_from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Build config from an arbitrary variable source, applying localhost defaults
+    pub fn from_lookup<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
         // Load feature toggles from default path or env var
         let toggles = FeatureToggles::from_env_path();
 
         Self {
             http_client: Client::new(),
-            langchain_url: std::env::var("LANGCHAIN_SERVICE_URL")
-                .unwrap_or_else(|_| "http://localhost:3002".to_string()),
-            haystack_url: std::env::var("HAYSTACK_SERVICE_URL")
-                .unwrap_or_else(|_| "http://localhost:8001".to_string()),
-            unified_indexer_url: std::env::var("UNIFIED_INDEXER_URL")
-                .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+            env_mode: lookup("ENV_MODE").unwrap_or_else(|| "development".to_string()),
+            langchain_url: lookup("LANGCHAIN_SERVICE_URL")
+                .unwrap_or_else(|| "http://localhost:3002".to_string()),
+            haystack_url: lookup("HAYSTACK_SERVICE_URL")
+                .unwrap_or_else(|| "http://localhost:8001".to_string()),
+            unified_indexer_url: lookup("UNIFIED_INDEXER_URL")
+                .unwrap_or_else(|| "http://localhost:8080".to_string()),
             feature_toggles: toggles,
         }
     }
-}
\ No newline at end of file
+
+    /// Build and validate config from an arbitrary variable source.
+    /// Outside development mode every service URL must be set explicitly.
+    pub fn try_from_lookup<F>(lookup: F) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let config = Self::from_lookup(&lookup);
+
+        if !config.is_development() {
+            for var in REQUIRED_URL_VARS {
+                if lookup(var).filter(|v| !v.trim().is_empty()).is_none() {
+                    return Err(ConfigError::MissingVar(var.to_string()));
+                }
+            }
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn is_development(&self) -> bool {
+        matches!(self.env_mode.as_str(), "development" | "dev" | "local")
+    }
+
+    /// Check that every configured service URL parses as an http(s) URL
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        validate_url("LANGCHAIN_SERVICE_URL", &self.langchain_url)?;
+        validate_url("HAYSTACK_SERVICE_URL", &self.haystack_url)?;
+        validate_url("UNIFIED_INDEXER_URL", &self.unified_indexer_url)?;
+        Ok(())
+    }
+}
+
+/// Check that `value`, read from `var`, is an http(s) URL with a host
+pub fn validate_url(var: &str, value: &str) -> Result<(), ConfigError> {
+    let invalid = |reason: String| ConfigError::InvalidUrl {
+        var: var.to_string(),
+        value: value.to_string(),
+        reason,
+    };
+
+    let url = Url::parse(value).map_err(|e| invalid(e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid(format!("unsupported scheme '{}'", url.scheme())));
+    }
+    if url.host_str().is_none() {
+        return Err(invalid("missing host".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_development_defaults_are_valid() {
+        let config = AppConfig::try_from_lookup(lookup_from(&[])).unwrap();
+        assert!(config.is_development());
+        assert_eq!(config.langchain_url, "http://localhost:3002");
+    }
+
+    #[test]
+    fn test_missing_required_var_in_production() {
+        let result = AppConfig::try_from_lookup(lookup_from(&[
+            ("ENV_MODE", "production"),
+            ("LANGCHAIN_SERVICE_URL", "https://langchain.internal"),
+            ("HAYSTACK_SERVICE_URL", "https://haystack.internal"),
+        ]));
+
+        assert_eq!(
+            result.err(),
+            Some(ConfigError::MissingVar("UNIFIED_INDEXER_URL".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_malformed_url_is_rejected() {
+        let result = AppConfig::try_from_lookup(lookup_from(&[(
+            "HAYSTACK_SERVICE_URL",
+            "htp//haystack:8001",
+        )]));
+
+        match result {
+            Err(ConfigError::InvalidUrl { var, .. }) => assert_eq!(var, "HAYSTACK_SERVICE_URL"),
+            other => panic!("expected InvalidUrl, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_non_http_scheme_is_rejected() {
+        let result = AppConfig::try_from_lookup(lookup_from(&[(
+            "UNIFIED_INDEXER_URL",
+            "ftp://indexer:8080",
+        )]));
+
+        assert!(matches!(result, Err(ConfigError::InvalidUrl { .. })));
+    }
+}
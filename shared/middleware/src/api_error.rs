@@ -0,0 +1,185 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use conhub_models::ErrorEnvelope;
+use conhub_observability::current_trace_id;
+use serde_json::Value;
+use std::fmt;
+
+/// Error returned by handlers, rendered as the shared `ErrorEnvelope` with the
+/// current request's trace id filled in
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    status: StatusCode,
+    envelope: ErrorEnvelope,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { status, envelope: ErrorEnvelope::new(code, message) }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad_request", message)
+    }
+
+    /// Request failed validation; `details` lists the offending fields
+    pub fn validation(details: impl serde::Serialize) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "validation_failed", "Validation failed")
+            .with_details(serde_json::to_value(details).unwrap_or(Value::Null))
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "forbidden", message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, "conflict", message)
+    }
+
+    pub fn too_many_requests(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+    }
+
+    pub fn bad_gateway(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_GATEWAY, "upstream_error", message)
+    }
+
+    pub fn service_unavailable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, "service_unavailable", message)
+    }
+
+    /// Replace the default code for the status with a more specific one
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.envelope.code = code.into();
+        self
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.envelope.details = Some(details);
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn envelope(&self) -> &ErrorEnvelope {
+        &self.envelope
+    }
+
+    /// Render the response directly, for handlers that return `Ok(HttpResponse)`
+    pub fn into_response(self) -> HttpResponse {
+        self.error_response()
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.envelope.code, self.envelope.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Database failures: an exhausted pool is a 503 so callers back off and retry
+/// instead of waiting on a request that never gets a connection
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::PoolTimedOut => {
+                tracing::warn!("Timed out acquiring a database connection");
+                ApiError::service_unavailable("Database is busy, please retry").with_code("database_busy")
+            }
+            sqlx::Error::RowNotFound => ApiError::not_found("Record not found"),
+            e => {
+                tracing::error!("Database error: {}", e);
+                ApiError::internal("Database error")
+            }
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut envelope = self.envelope.clone();
+        if envelope.trace_id.is_none() {
+            envelope.trace_id = current_trace_id();
+        }
+        HttpResponse::build(self.status).json(envelope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+    use conhub_observability::{observability, TRACE_ID_HEADER};
+    use serde_json::json;
+
+    async fn missing_job() -> Result<HttpResponse, ApiError> {
+        Err(ApiError::not_found("Sync job not found").with_details(json!({ "sync_job_id": "job-1" })))
+    }
+
+    #[actix_web::test]
+    async fn test_error_envelope_shape() {
+        let app = test::init_service(
+            App::new()
+                .wrap(observability("test-service"))
+                .route("/api/data/sync/job-1", web::get().to(missing_job))
+                .route("/api/auth/login", web::post().to(|| async {
+                    ApiError::unauthorized("Invalid credentials").into_response()
+                })),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/data/sync/job-1")
+            .insert_header((TRACE_ID_HEADER, "trace-123"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(
+            body,
+            json!({
+                "code": "not_found",
+                "message": "Sync job not found",
+                "details": { "sync_job_id": "job-1" },
+                "trace_id": "trace-123"
+            })
+        );
+
+        let req = test::TestRequest::post().uri("/api/auth/login").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let trace_id = res.headers().get(TRACE_ID_HEADER).unwrap().to_str().unwrap().to_string();
+        let envelope: ErrorEnvelope = test::read_body_json(res).await;
+        assert_eq!(envelope.code, "unauthorized");
+        assert_eq!(envelope.trace_id, Some(trace_id));
+    }
+
+    #[actix_web::test]
+    async fn test_pool_timeout_maps_to_service_unavailable() {
+        let err = ApiError::from(sqlx::Error::PoolTimedOut);
+        assert_eq!(err.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(err.envelope().code, "database_busy");
+
+        assert_eq!(ApiError::from(sqlx::Error::RowNotFound).status(), StatusCode::NOT_FOUND);
+        assert_eq!(ApiError::from(sqlx::Error::PoolClosed).status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -17,7 +17,153 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use reqwest::Client;
 use std::time::{Duration, Instant};
+use std::str::FromStr;
 use uuid::Uuid;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as JWT_B64;
+use base64::Engine as _;
+
+/// Default clock-skew tolerance for `exp`/`nbf`
+const DEFAULT_JWT_LEEWAY_SECS: u64 = 60;
+
+/// Why a bearer token was rejected
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum JwtError {
+    #[error("Malformed token: {0}")]
+    Malformed(String),
+    #[error("Missing kid in JWT header")]
+    MissingKid,
+    #[error("Algorithm '{0}' is not allowed")]
+    AlgorithmNotAllowed(String),
+    #[error("Signing key unavailable: {0}")]
+    KeyUnavailable(String),
+    #[error("Unsupported JWK key type '{0}'")]
+    UnsupportedKeyType(String),
+    #[error("Invalid token signature")]
+    InvalidSignature,
+    #[error("Token expired")]
+    Expired,
+    #[error("Token not yet valid")]
+    NotYetValid,
+    #[error("Invalid issuer")]
+    InvalidIssuer,
+    #[error("Invalid audience")]
+    InvalidAudience,
+    #[error("Missing required scope '{0}'")]
+    MissingScope(String),
+    #[error("Not a ConHub token")]
+    NotConHubToken,
+}
+
+impl JwtError {
+    /// Stable machine-readable code returned to clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            JwtError::Malformed(_) => "token_malformed",
+            JwtError::MissingKid => "token_missing_kid",
+            JwtError::AlgorithmNotAllowed(_) => "token_algorithm_not_allowed",
+            JwtError::KeyUnavailable(_) => "token_key_unavailable",
+            JwtError::UnsupportedKeyType(_) => "token_unsupported_key_type",
+            JwtError::InvalidSignature => "token_invalid_signature",
+            JwtError::Expired => "token_expired",
+            JwtError::NotYetValid => "token_not_yet_valid",
+            JwtError::InvalidIssuer => "token_invalid_issuer",
+            JwtError::InvalidAudience => "token_invalid_audience",
+            JwtError::MissingScope(_) => "token_missing_scope",
+            JwtError::NotConHubToken => "token_not_conhub",
+        }
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for JwtError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+        match err.kind() {
+            ErrorKind::ExpiredSignature => JwtError::Expired,
+            ErrorKind::ImmatureSignature => JwtError::NotYetValid,
+            ErrorKind::InvalidIssuer => JwtError::InvalidIssuer,
+            ErrorKind::InvalidAudience => JwtError::InvalidAudience,
+            ErrorKind::InvalidSignature => JwtError::InvalidSignature,
+            ErrorKind::InvalidAlgorithm => JwtError::AlgorithmNotAllowed("mismatch".to_string()),
+            _ => JwtError::Malformed(err.to_string()),
+        }
+    }
+}
+
+/// Clock-skew leeway and accepted signing algorithms for bearer tokens
+#[derive(Clone, Debug)]
+struct JwtPolicy {
+    leeway_secs: u64,
+    allowed_algorithms: Vec<Algorithm>,
+}
+
+impl JwtPolicy {
+    /// `JWT_LEEWAY_SECS` (default 60) and `JWT_ALLOWED_ALGORITHMS` (comma separated, default RS256)
+    fn from_env() -> Self {
+        let leeway_secs = std::env::var("JWT_LEEWAY_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_JWT_LEEWAY_SECS);
+        let allowed_algorithms = std::env::var("JWT_ALLOWED_ALGORITHMS")
+            .ok()
+            .map(|v| parse_algorithms(&v))
+            .filter(|algs| !algs.is_empty())
+            .unwrap_or_else(|| vec![Algorithm::RS256]);
+
+        Self { leeway_secs, allowed_algorithms }
+    }
+
+    /// Check the header's `alg` before anything else so `none` and unexpected
+    /// algorithms are rejected without touching keys
+    fn check_algorithm(&self, token: &str) -> Result<Algorithm, JwtError> {
+        let alg = raw_header_alg(token)?;
+        Algorithm::from_str(&alg)
+            .ok()
+            .filter(|a| self.allowed_algorithms.contains(a))
+            .ok_or(JwtError::AlgorithmNotAllowed(alg))
+    }
+
+    /// Validation for a token whose header `alg` already passed
+    /// `check_algorithm`. jsonwebtoken requires every listed algorithm to match
+    /// the key's family, so only the header's algorithm is listed; otherwise an
+    /// allow-list mixing RSA and HMAC would reject every token.
+    fn validation(&self, alg: Algorithm) -> Validation {
+        let mut validation = Validation::new(alg);
+        validation.algorithms = vec![alg];
+        validation.leeway = self.leeway_secs;
+        validation.validate_nbf = true;
+        validation
+    }
+}
+
+fn parse_algorithms(value: &str) -> Vec<Algorithm> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| match Algorithm::from_str(s) {
+            Ok(alg) => Some(alg),
+            Err(_) => {
+                tracing::warn!("Ignoring unknown JWT algorithm '{}'", s);
+                None
+            }
+        })
+        .collect()
+}
+
+/// `alg` from the token header, read directly so that `none` is seen as-is
+fn raw_header_alg(token: &str) -> Result<String, JwtError> {
+    let segment = token.split('.').next().unwrap_or_default();
+    let bytes = JWT_B64
+        .decode(segment.trim_end_matches('='))
+        .map_err(|e| JwtError::Malformed(format!("header is not base64url: {}", e)))?;
+    let header: JsonValue = serde_json::from_slice(&bytes)
+        .map_err(|e| JwtError::Malformed(format!("header is not JSON: {}", e)))?;
+    header
+        .get("alg")
+        .and_then(|a| a.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| JwtError::Malformed("header has no alg".to_string()))
+}
 
 #[derive(Clone)]
 struct Auth0Config {
@@ -25,7 +171,7 @@ struct Auth0Config {
     issuer: String,
     audience: String,
     jwks_uri: String,
-    leeway_secs: u64,
+    policy: JwtPolicy,
 }
 
 impl Auth0Config {
@@ -40,7 +186,7 @@ impl Auth0Config {
             issuer,
             audience,
             jwks_uri,
-            leeway_secs: 60,
+            policy: JwtPolicy::from_env(),
         })
     }
 }
@@ -176,7 +322,7 @@ where
                                 let token = &auth_str[7..];
                                 
                                 // Try ConHub token first (issued by auth service after Auth0 exchange)
-                                if let Ok(claims) = verify_conhub_jwt_token(token).await {
+                                if let Ok(claims) = verify_conhub_jwt_token(token, &verifier.config.policy).await {
                                     req.extensions_mut().insert(claims);
                                     let res = service.call(req).await?;
                                     return Ok(res.map_into_left_body());
@@ -195,6 +341,7 @@ where
                                             HttpResponse::Unauthorized()
                                                 .json(json!({
                                                     "error": "Invalid or expired token",
+                                                    "code": e.code(),
                                                     "details": e.to_string()
                                                 }))
                                         ).map_into_right_body());
@@ -275,20 +422,28 @@ where
 async fn verify_auth0_jwt_token(
     token: &str,
     verifier: &Auth0Verifier,
-) -> Result<conhub_models::auth::Claims, Box<dyn std::error::Error>> {
+) -> Result<conhub_models::auth::Claims, JwtError> {
+    let policy = &verifier.config.policy;
+    let alg = policy.check_algorithm(token)?;
     let header = decode_header(token)?;
-    let kid = header.kid.ok_or("Missing kid in JWT header")?;
+    let kid = header.kid.ok_or(JwtError::MissingKid)?;
 
     let mut cache = verifier.jwks_cache.lock().await;
-    let jwk = cache.get_key(&kid).await?;
+    let jwk = cache.get_key(&kid).await.map_err(|e| JwtError::KeyUnavailable(e.to_string()))?;
 
     if jwk.kty != "RSA" {
-        return Err("Unsupported JWK kty".into());
+        return Err(JwtError::UnsupportedKeyType(jwk.kty.clone()));
+    }
+    // A key published for one algorithm must not verify tokens claiming another
+    if let Some(jwk_alg) = &jwk.alg {
+        if Algorithm::from_str(jwk_alg).ok() != Some(alg) {
+            return Err(JwtError::AlgorithmNotAllowed(format!("{:?}", alg)));
+        }
     }
 
     let decoding_key = DecodingKey::from_rsa_components(&jwk.n, &jwk.e)?;
 
-    let mut validation = Validation::new(Algorithm::RS256);
+    let mut validation = policy.validation(alg);
     validation.iss = Some(std::collections::HashSet::from([verifier.config.issuer.clone()]));
     // Audience can be string or array in Auth0; validate manually
     validation.validate_aud = false;
@@ -296,29 +451,23 @@ async fn verify_auth0_jwt_token(
     let token_data = decode::<RawAuth0Claims>(token, &decoding_key, &validation)?;
     let claims = token_data.claims;
 
-    let now = chrono::Utc::now().timestamp() as usize;
-    if claims.exp + (verifier.config.leeway_secs as usize) < now {
-        return Err("Token expired".into());
-    }
-
     let aud_ok = match &claims.aud {
         JsonValue::String(aud) => aud == &verifier.config.audience,
         JsonValue::Array(arr) => arr.iter().any(|v| v == &JsonValue::String(verifier.config.audience.clone())),
         _ => false,
     };
     if !aud_ok {
-        return Err("Invalid audience".into());
+        return Err(JwtError::InvalidAudience);
     }
 
     if let Ok(required_scope) = std::env::var("AUTH0_REQUIRED_SCOPE") {
-        if let Some(scope_str) = &claims.scope {
-            let scopes: std::collections::HashSet<_> =
-                scope_str.split_whitespace().map(|s| s.to_string()).collect();
-            if !scopes.contains(&required_scope) {
-                return Err("Missing required scope".into());
-            }
-        } else {
-            return Err("Missing scope claim".into());
+        let has_scope = claims
+            .scope
+            .as_deref()
+            .map(|s| s.split_whitespace().any(|scope| scope == required_scope))
+            .unwrap_or(false);
+        if !has_scope {
+            return Err(JwtError::MissingScope(required_scope));
         }
     }
 
@@ -358,20 +507,22 @@ async fn verify_auth0_jwt_token(
 
 /// Verify ConHub JWT tokens (issued by auth service after Auth0 exchange)
 /// These tokens have issuer "conhub-auth" and audience "conhub-services"
-async fn verify_conhub_jwt_token(token: &str) -> Result<conhub_models::auth::Claims, Box<dyn std::error::Error>> {
+async fn verify_conhub_jwt_token(token: &str, policy: &JwtPolicy) -> Result<conhub_models::auth::Claims, JwtError> {
+    let alg = policy.check_algorithm(token)?;
+
     // Decode header to check if this is a ConHub token
     let header = decode_header(token)?;
     
     // ConHub tokens have kid "conhub-auth-key"
     let kid = header.kid.as_deref().unwrap_or("");
     if kid != "conhub-auth-key" {
-        return Err("Not a ConHub token".into());
+        return Err(JwtError::NotConHubToken);
     }
     
     // Get the auth service public key from environment
     let public_key_raw = std::env::var("CONHUB_AUTH_PUBLIC_KEY")
         .or_else(|_| std::env::var("JWT_PUBLIC_KEY"))
-        .map_err(|_| "ConHub auth public key not configured")?;
+        .map_err(|_| JwtError::KeyUnavailable("ConHub auth public key not configured".to_string()))?;
     
     // Handle escaped newlines from .env files and remove surrounding quotes
     let public_key_pem = public_key_raw
@@ -380,22 +531,15 @@ async fn verify_conhub_jwt_token(token: &str) -> Result<conhub_models::auth::Cla
     
     // Parse the public key
     let decoding_key = DecodingKey::from_rsa_pem(public_key_pem.as_bytes())
-        .map_err(|e| format!("Failed to parse ConHub public key: {}", e))?;
+        .map_err(|e| JwtError::KeyUnavailable(format!("Failed to parse ConHub public key: {}", e)))?;
     
-    // Set up validation for ConHub tokens
-    let mut validation = Validation::new(Algorithm::RS256);
+    // Set up validation for ConHub tokens; exp/nbf are checked with the policy's leeway
+    let mut validation = policy.validation(alg);
     validation.set_issuer(&["conhub-auth"]);
     validation.set_audience(&["conhub-services"]);
     
     // Decode and validate token
-    let token_data = decode::<conhub_models::auth::Claims>(token, &decoding_key, &validation)
-        .map_err(|e| format!("ConHub token validation failed: {}", e))?;
-    
-    // Check expiry
-    let now = chrono::Utc::now().timestamp() as usize;
-    if token_data.claims.exp < now {
-        return Err("ConHub token expired".into());
-    }
+    let token_data = decode::<conhub_models::auth::Claims>(token, &decoding_key, &validation)?;
     
     tracing::debug!("Successfully verified ConHub token for sub: {}", token_data.claims.sub);
     Ok(token_data.claims)
@@ -611,7 +755,7 @@ mod tests {
             sub: "user-x".to_string(),
             iss: verifier.config.issuer.clone(),
             aud: verifier.config.audience.clone(),
-            exp: now - 10,
+            exp: now - 120,
             iat: now - 20,
         };
 
@@ -663,6 +807,112 @@ mod tests {
         let res = verify_auth0_jwt_token(&token, &verifier).await;
         assert!(res.is_err());
     }
+
+    fn verifier_with_policy(jwks_uri: String, policy: JwtPolicy) -> Auth0Verifier {
+        let config = Auth0Config {
+            domain: "test.local".to_string(),
+            issuer: "https://test.local/".to_string(),
+            audience: "https://api.conhub.dev".to_string(),
+            jwks_uri,
+            policy,
+        };
+        Auth0Verifier {
+            jwks_cache: Arc::new(tokio::sync::Mutex::new(Auth0JwksCache::new(config.clone()))),
+            config,
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_within_leeway_ok() {
+        let mut rng = rand::thread_rng();
+        let private_key = RsaPrivateKey::new(&mut rng, 2048).unwrap();
+        let public_key = RsaPublicKey::from(&private_key);
+        let kid = "kid6";
+        let (jwks_uri, server) = start_jwks_server(Jwks { keys: vec![make_jwk(&public_key, kid)] }).await;
+        drop(tokio::spawn(server));
+
+        let policy = JwtPolicy { leeway_secs: 30, allowed_algorithms: vec![Algorithm::RS256] };
+        let verifier = verifier_with_policy(jwks_uri, policy);
+
+        #[derive(Serialize)]
+        struct ClaimsForSign { sub: String, iss: String, aud: String, exp: usize, iat: usize, scope: String }
+        let now = chrono::Utc::now().timestamp() as usize;
+        let sign = |exp: usize| {
+            let claims = ClaimsForSign {
+                sub: "user-x".to_string(),
+                iss: verifier.config.issuer.clone(),
+                aud: verifier.config.audience.clone(),
+                exp,
+                iat: now - 120,
+                // Other tests set AUTH0_REQUIRED_SCOPE process-wide
+                scope: "read:conhub".to_string(),
+            };
+            let mut header = Header::new(Algorithm::RS256);
+            header.kid = Some(kid.to_string());
+            encode(&header, &claims, &make_encoding_key(&private_key)).unwrap()
+        };
+
+        assert!(verify_auth0_jwt_token(&sign(now - 10), &verifier).await.is_ok());
+        assert_eq!(verify_auth0_jwt_token(&sign(now - 60), &verifier).await.unwrap_err(), JwtError::Expired);
+    }
+
+    #[tokio::test]
+    async fn mixed_family_allow_list_ok() {
+        let mut rng = rand::thread_rng();
+        let private_key = RsaPrivateKey::new(&mut rng, 2048).unwrap();
+        let public_key = RsaPublicKey::from(&private_key);
+        let kid = "kid8";
+        let (jwks_uri, server) = start_jwks_server(Jwks { keys: vec![make_jwk(&public_key, kid)] }).await;
+        drop(tokio::spawn(server));
+
+        let policy = JwtPolicy { leeway_secs: 0, allowed_algorithms: vec![Algorithm::HS256, Algorithm::RS256] };
+        let verifier = verifier_with_policy(jwks_uri, policy.clone());
+
+        #[derive(Serialize)]
+        struct ClaimsForSign { sub: String, iss: String, aud: String, exp: usize, scope: String }
+        let claims = ClaimsForSign {
+            sub: "user-x".to_string(),
+            iss: verifier.config.issuer.clone(),
+            aud: verifier.config.audience.clone(),
+            exp: chrono::Utc::now().timestamp() as usize + 3600,
+            scope: "read:conhub".to_string(),
+        };
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+        let token = encode(&header, &claims, &make_encoding_key(&private_key)).unwrap();
+
+        assert!(verify_auth0_jwt_token(&token, &verifier).await.is_ok());
+        // The ConHub path reports typed errors too
+        assert_eq!(verify_conhub_jwt_token(&token, &policy).await.unwrap_err(), JwtError::NotConHubToken);
+    }
+
+    #[tokio::test]
+    async fn wrong_alg_err() {
+        let verifier = verifier_with_policy(
+            "http://127.0.0.1:9/unused".to_string(),
+            JwtPolicy { leeway_secs: 0, allowed_algorithms: vec![Algorithm::RS256] },
+        );
+
+        #[derive(Serialize)]
+        struct ClaimsForSign { sub: String, exp: usize }
+        let claims = ClaimsForSign { sub: "user-x".to_string(), exp: chrono::Utc::now().timestamp() as usize + 3600 };
+
+        // HS256 signed with a guessable secret, e.g. the RSA public key
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("kid7".to_string());
+        let hs_token = encode(&header, &claims, &EncodingKey::from_secret(b"public-key-bytes")).unwrap();
+        assert_eq!(
+            verify_auth0_jwt_token(&hs_token, &verifier).await.unwrap_err(),
+            JwtError::AlgorithmNotAllowed("HS256".to_string())
+        );
+
+        let none_header = URL_SAFE_NO_PAD.encode(br#"{"alg":"none","typ":"JWT","kid":"kid7"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        let none_token = format!("{}.{}.", none_header, payload);
+        let err = verify_auth0_jwt_token(&none_token, &verifier).await.unwrap_err();
+        assert_eq!(err, JwtError::AlgorithmNotAllowed("none".to_string()));
+        assert_eq!(err.code(), "token_algorithm_not_allowed");
+    }
 }
 
 // Check if the endpoint is public and doesn't require authentication
@@ -675,6 +925,8 @@ fn is_public_endpoint(path: &str) -> bool {
 
     let public_paths = [
         "/health",
+        "/ready",                    // Readiness probes (/ready, /readyz)
+        "/info",                     // Embedding model discovery for downstreams
         "/metrics",
         "/auth/login",
         "/auth/register",
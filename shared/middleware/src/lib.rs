@@ -1,11 +1,15 @@
+pub mod api_error;
 pub mod auth;
 pub mod authorization;
 pub mod cors;
 pub mod logging;
 pub mod rate_limiting;
+pub mod service_auth;
 
+pub use api_error::*;
 pub use auth::*;
 pub use authorization::*;
 pub use cors::*;
 pub use logging::*;
-pub use rate_limiting::*;
\ No newline at end of file
+pub use rate_limiting::*;
+pub use service_auth::*;
\ No newline at end of file
@@ -0,0 +1,213 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error as ActixError, HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+use serde_json::json;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Header carrying the shared secret on service-to-service calls
+pub const SERVICE_TOKEN_HEADER: &str = "X-Service-Token";
+
+/// Env var holding the shared secret. A comma-separated list is accepted so the
+/// secret can be rotated without downtime; callers send the first entry.
+pub const SERVICE_TOKEN_ENV: &str = "INTERNAL_SERVICE_TOKEN";
+
+/// Shared secret for internal endpoints, used by both the guarded service and its callers
+#[derive(Clone, Default)]
+pub struct ServiceTokenConfig {
+    tokens: Vec<String>,
+}
+
+impl ServiceTokenConfig {
+    pub fn new<I, T>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self {
+            tokens: tokens
+                .into_iter()
+                .map(Into::into)
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let raw = std::env::var(SERVICE_TOKEN_ENV).unwrap_or_default();
+        Self::new(raw.split(','))
+    }
+
+    pub fn is_configured(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    /// Token callers attach to outgoing internal requests
+    pub fn outgoing(&self) -> Option<&str> {
+        self.tokens.first().map(String::as_str)
+    }
+
+    /// Whether `presented` matches any accepted token, compared in constant time
+    pub fn accepts(&self, presented: &str) -> bool {
+        self.tokens
+            .iter()
+            .fold(false, |ok, token| constant_time_eq(token.as_bytes(), presented.as_bytes()) | ok)
+    }
+
+    /// Attach the service token to an outgoing internal request, if one is configured
+    pub fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.outgoing() {
+            Some(token) => request.header(SERVICE_TOKEN_HEADER, token),
+            None => request,
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Rejects requests that don't carry a valid `X-Service-Token`.
+/// Fails closed: with no token configured every request is refused.
+pub struct ServiceAuthMiddleware<S> {
+    service: Rc<S>,
+    config: Arc<ServiceTokenConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for ServiceAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = ActixError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let config = Arc::clone(&self.config);
+
+        Box::pin(async move {
+            let presented = req
+                .headers()
+                .get(SERVICE_TOKEN_HEADER)
+                .and_then(|v| v.to_str().ok());
+
+            let rejection = match presented {
+                _ if !config.is_configured() => Some(("service_token_not_configured", "Internal service authentication is not configured")),
+                None => Some(("missing_service_token", "Missing X-Service-Token header")),
+                Some(token) if !config.accepts(token) => Some(("invalid_service_token", "Invalid service token")),
+                Some(_) => None,
+            };
+
+            match rejection {
+                None => {
+                    let res = service.call(req).await?;
+                    Ok(res.map_into_left_body())
+                }
+                Some((code, message)) => {
+                    tracing::warn!("Rejected internal request to {}: {}", req.path(), code);
+                    Ok(req.into_response(
+                        HttpResponse::Unauthorized().json(json!({
+                            "error": message,
+                            "code": code
+                        }))
+                    ).map_into_right_body())
+                }
+            }
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct ServiceAuthMiddlewareFactory {
+    config: Arc<ServiceTokenConfig>,
+}
+
+impl ServiceAuthMiddlewareFactory {
+    pub fn new(config: ServiceTokenConfig) -> Self {
+        Self { config: Arc::new(config) }
+    }
+
+    pub fn from_env() -> Self {
+        let config = ServiceTokenConfig::from_env();
+        if !config.is_configured() {
+            tracing::warn!("{} is not set; internal endpoints will reject all requests", SERVICE_TOKEN_ENV);
+        }
+        Self::new(config)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ServiceAuthMiddlewareFactory
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = ActixError;
+    type Transform = ServiceAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ServiceAuthMiddleware {
+            service: Rc::new(service),
+            config: Arc::clone(&self.config),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test, web, App};
+
+    async fn call_internal(config: ServiceTokenConfig, token: Option<&str>) -> StatusCode {
+        let app = test::init_service(
+            App::new().service(
+                web::scope("/internal")
+                    .wrap(ServiceAuthMiddlewareFactory::new(config))
+                    .route("/oauth/github/token", web::get().to(|| async { HttpResponse::Ok().finish() })),
+            ),
+        )
+        .await;
+
+        let mut req = test::TestRequest::get().uri("/internal/oauth/github/token");
+        if let Some(token) = token {
+            req = req.insert_header((SERVICE_TOKEN_HEADER, token));
+        }
+        test::call_service(&app, req.to_request()).await.status()
+    }
+
+    #[actix_web::test]
+    async fn accepts_valid_service_token() {
+        let config = ServiceTokenConfig::new(["current-secret", "previous-secret"]);
+        assert_eq!(call_internal(config.clone(), Some("current-secret")).await, StatusCode::OK);
+        assert_eq!(call_internal(config, Some("previous-secret")).await, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn rejects_missing_or_wrong_service_token() {
+        let config = ServiceTokenConfig::new(["current-secret"]);
+        assert_eq!(call_internal(config.clone(), None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(call_internal(config, Some("guess")).await, StatusCode::UNAUTHORIZED);
+
+        // Unconfigured means closed, not open
+        assert_eq!(
+            call_internal(ServiceTokenConfig::default(), Some("")).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -239,6 +239,7 @@ pub struct AuthResponse {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RefreshTokenResponse {
     pub token: String,
+    pub refresh_token: String,
     pub expires_at: DateTime<Utc>,
 }
 
@@ -20,6 +20,13 @@ pub struct EmbeddingResult {
     pub dimension: usize,
     pub model: String,
     pub count: usize,
+    /// Whether the vectors were L2-normalized before being returned
+    #[serde(default)]
+    pub normalized: bool,
+    /// Input positions not embedded before the batch deadline; `embeddings` covers
+    /// the inputs before the first of these, so callers can retry just the rest
+    #[serde(default)]
+    pub unprocessed_indices: Vec<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, InputObject)]
@@ -39,6 +39,24 @@ impl OptimizedVector {
         }
     }
     
+    /// Rebuild a vector whose norm and hash are already known, e.g. when hydrating
+    /// an index from storage. The values are trusted; debug builds check them.
+    pub fn from_parts(data: Vec<f32>, norm: f32, hash: u64) -> Self {
+        debug_assert!(
+            (norm - Self::calculate_norm(&data)).abs() <= 1e-4 * norm.max(1.0),
+            "stored norm {} does not match the vector data",
+            norm
+        );
+        debug_assert_eq!(hash, Self::calculate_hash(&data), "stored hash does not match the vector data");
+
+        Self {
+            dimension: data.len(),
+            data: Arc::new(data),
+            norm: Some(norm),
+            hash,
+        }
+    }
+
     fn calculate_norm(data: &[f32]) -> f32 {
         data.iter().map(|x| x * x).sum::<f32>().sqrt()
     }
@@ -52,6 +70,14 @@ impl OptimizedVector {
         hasher.finish()
     }
     
+    /// Whether the vector carries no direction, as produced for empty or
+    /// whitespace-only input. Such vectors score 0 against everything, so
+    /// indexing them only adds noise.
+    pub fn is_degenerate(&self) -> bool {
+        let norm = self.norm.unwrap_or_else(|| Self::calculate_norm(&self.data));
+        !norm.is_finite() || norm < MIN_VECTOR_NORM
+    }
+
     /// Fast cosine similarity using cached norms
     pub fn cosine_similarity(&self, other: &Self) -> f32 {
         if self.dimension != other.dimension {
@@ -74,8 +100,26 @@ impl OptimizedVector {
             _ => 0.0,
         }
     }
+
+    pub fn dot_product(&self, other: &Self) -> f32 {
+        if self.dimension != other.dimension {
+            return 0.0;
+        }
+        self.data.iter().zip(other.data.iter()).map(|(a, b)| a * b).sum()
+    }
+
+    /// L2 distance; vectors of different dimensions are infinitely far apart
+    pub fn euclidean_distance(&self, other: &Self) -> f32 {
+        if self.dimension != other.dimension {
+            return f32::INFINITY;
+        }
+        self.data.iter().zip(other.data.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt()
+    }
 }
 
+/// Vectors with an L2 norm below this are treated as zero vectors
+pub const MIN_VECTOR_NORM: f32 = 1e-6;
+
 /// Spatial index for fast nearest neighbor search
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpatialIndex {
@@ -83,6 +127,427 @@ pub struct SpatialIndex {
     pub metadata: Vec<VectorMetadata>,
     pub dimension: usize,
     pub index_type: IndexType,
+    /// LSH bucket -> positions in `vectors`, used by the approximate index types
+    #[serde(default)]
+    pub buckets: HashMap<u64, Vec<usize>>,
+    /// Positions removed since the last rebuild. They stay in their buckets and
+    /// still use up probe budget until the index is compacted.
+    #[serde(default)]
+    pub deleted: HashSet<usize>,
+    #[serde(default)]
+    pub metric: SimilarityMetric,
+}
+
+/// How `SpatialIndex` compares a query with stored vectors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SimilarityMetric {
+    #[default]
+    Cosine,
+    /// Raw dot product, for embeddings that aren't normalized
+    Dot,
+    /// L2 distance; lower is closer
+    Euclidean,
+}
+
+impl SimilarityMetric {
+    pub fn measure(&self, a: &OptimizedVector, b: &OptimizedVector) -> f32 {
+        match self {
+            SimilarityMetric::Cosine => a.cosine_similarity(b),
+            SimilarityMetric::Dot => a.dot_product(b),
+            SimilarityMetric::Euclidean => a.euclidean_distance(b),
+        }
+    }
+
+    pub fn higher_is_better(&self) -> bool {
+        !matches!(self, SimilarityMetric::Euclidean)
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            SimilarityMetric::Cosine => 0,
+            SimilarityMetric::Dot => 1,
+            SimilarityMetric::Euclidean => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(SimilarityMetric::Cosine),
+            1 => Some(SimilarityMetric::Dot),
+            2 => Some(SimilarityMetric::Euclidean),
+            _ => None,
+        }
+    }
+}
+
+/// Number of random hyperplanes used to hash vectors into LSH buckets
+const LSH_PLANES: usize = 8;
+/// Approximate search inspects at most `k * CANDIDATE_FACTOR` slots (and at least `MIN_CANDIDATES`)
+const CANDIDATE_FACTOR: usize = 4;
+const MIN_CANDIDATES: usize = 32;
+
+/// Quality assumed for vectors whose metadata carries no `quality_score`
+const NEUTRAL_QUALITY: f32 = 0.5;
+
+/// Per-query ranking options for `SpatialIndex::search_with`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Share of the ranking score taken from `VectorMetadata.quality_score` rather than
+    /// similarity, in `[0, 1]`. 0 ranks purely by similarity.
+    #[serde(default)]
+    pub quality_weight: f32,
+    /// Caller's permitted tags; only vectors tagged with at least one of them are
+    /// returned. Empty means no restriction.
+    #[serde(default)]
+    pub allowed_tags: HashSet<String>,
+}
+
+impl SearchOptions {
+    /// Whether the caller's tag scope admits this vector
+    pub fn permits(&self, metadata: &VectorMetadata) -> bool {
+        self.allowed_tags.is_empty() || !self.allowed_tags.is_disjoint(&metadata.tags)
+    }
+
+    /// Ranking score for a candidate: similarity blended with its quality score
+    pub fn score(&self, similarity: f32, metadata: &VectorMetadata) -> f32 {
+        let weight = self.quality_weight.clamp(0.0, 1.0);
+        if weight == 0.0 {
+            return similarity;
+        }
+        let quality = metadata.quality_score.unwrap_or(NEUTRAL_QUALITY).clamp(0.0, 1.0);
+        (1.0 - weight) * similarity + weight * quality
+    }
+}
+
+/// Leading bytes of a binary `SpatialIndex` file
+const INDEX_MAGIC: &[u8; 4] = b"CHSI";
+/// Version 2 added the similarity metric; version 1 files are cosine indexes
+const INDEX_FORMAT_VERSION: u8 = 2;
+
+fn invalid_index(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+fn read_array<const N: usize>(reader: &mut impl std::io::Read) -> std::io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Outcome of `SpatialIndex::rebuild`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactionStats {
+    pub removed: usize,
+    pub live: usize,
+}
+
+impl SpatialIndex {
+    pub fn new(dimension: usize, index_type: IndexType) -> Self {
+        Self {
+            vectors: Vec::new(),
+            metadata: Vec::new(),
+            dimension,
+            index_type,
+            buckets: HashMap::new(),
+            deleted: HashSet::new(),
+            metric: SimilarityMetric::default(),
+        }
+    }
+
+    pub fn with_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn insert(&mut self, vector: OptimizedVector, metadata: VectorMetadata) {
+        let position = self.vectors.len();
+        let signature = self.signature(&vector);
+        self.buckets.entry(signature).or_default().push(position);
+        self.vectors.push(vector);
+        self.metadata.push(metadata);
+    }
+
+    /// Insert a batch, skipping zero/near-zero-norm vectors. Returns the
+    /// positions within `entries` that were skipped so callers can report them.
+    pub fn insert_batch(&mut self, entries: Vec<(OptimizedVector, VectorMetadata)>) -> Vec<usize> {
+        let mut skipped = Vec::new();
+        for (i, (vector, metadata)) in entries.into_iter().enumerate() {
+            if vector.is_degenerate() {
+                skipped.push(i);
+            } else {
+                self.insert(vector, metadata);
+            }
+        }
+        skipped
+    }
+
+    /// Mark the vector with this id as removed. Storage is reclaimed by `rebuild`.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let position = self.metadata
+            .iter()
+            .enumerate()
+            .position(|(i, m)| m.id == id && !self.deleted.contains(&i));
+
+        match position {
+            Some(i) => self.deleted.insert(i),
+            None => false,
+        }
+    }
+
+    /// Mark every vector from `source_id` as removed, as when the source file is
+    /// deleted upstream. Returns how many were removed; repeating it removes none.
+    pub fn remove_source(&mut self, source_id: &str) -> usize {
+        let positions: Vec<usize> = self.metadata
+            .iter()
+            .enumerate()
+            .filter(|(i, m)| m.source_id == source_id && !self.deleted.contains(i))
+            .map(|(i, _)| i)
+            .collect();
+        self.deleted.extend(positions.iter().copied());
+        positions.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.len() - self.deleted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Top-k search. `Flat` scans everything; `LSH`/`HNSW` probe buckets nearest
+    /// the query's hash until the candidate budget is spent. Scores are in the
+    /// index's metric, so for `Euclidean` they are distances, smallest first.
+    pub fn search(&self, query: &OptimizedVector, k: usize) -> Vec<(&VectorMetadata, f32)> {
+        self.search_with(query, k, &SearchOptions::default())
+    }
+
+    /// Top-k search ranked by `options`; returned scores are the blended ranking scores.
+    /// Blending needs a higher-is-better score, so with a quality weight Euclidean
+    /// distances are first mapped to `1 / (1 + distance)`.
+    pub fn search_with(&self, query: &OptimizedVector, k: usize, options: &SearchOptions) -> Vec<(&VectorMetadata, f32)> {
+        match self.index_type {
+            IndexType::Flat => self.search_exact_with(query, k, options),
+            IndexType::LSH | IndexType::HNSW => self.search_approximate(query, k, options),
+        }
+    }
+
+    /// Brute-force search over every live vector
+    pub fn search_exact(&self, query: &OptimizedVector, k: usize) -> Vec<(&VectorMetadata, f32)> {
+        self.search_exact_with(query, k, &SearchOptions::default())
+    }
+
+    fn search_exact_with(&self, query: &OptimizedVector, k: usize, options: &SearchOptions) -> Vec<(&VectorMetadata, f32)> {
+        let candidates = (0..self.vectors.len())
+            .filter(|i| !self.deleted.contains(i) && options.permits(&self.metadata[*i]));
+        self.top_k(query, candidates, k, options)
+    }
+
+    fn search_approximate(&self, query: &OptimizedVector, k: usize, options: &SearchOptions) -> Vec<(&VectorMetadata, f32)> {
+        let budget = (k * CANDIDATE_FACTOR).max(MIN_CANDIDATES);
+        let query_signature = self.signature(query);
+
+        let mut probe_order: Vec<&u64> = self.buckets.keys().collect();
+        probe_order.sort_by_key(|&&sig| ((sig ^ query_signature).count_ones(), sig));
+
+        let candidates = probe_order
+            .into_iter()
+            .flat_map(|sig| self.buckets[sig].iter().copied())
+            // Out-of-scope vectors don't use up the probe budget
+            .filter(|i| options.permits(&self.metadata[*i]))
+            .take(budget)
+            .filter(|i| !self.deleted.contains(i));
+
+        self.top_k(query, candidates, k, options)
+    }
+
+    fn top_k(
+        &self,
+        query: &OptimizedVector,
+        candidates: impl Iterator<Item = usize>,
+        k: usize,
+        options: &SearchOptions,
+    ) -> Vec<(&VectorMetadata, f32)> {
+        let blend_distance = !self.metric.higher_is_better() && options.quality_weight > 0.0;
+        let ascending = !self.metric.higher_is_better() && !blend_distance;
+
+        let mut scored: Vec<(usize, f32)> = candidates
+            .map(|i| {
+                let value = self.metric.measure(&self.vectors[i], query);
+                let score = if blend_distance {
+                    options.score(1.0 / (1.0 + value), &self.metadata[i])
+                } else {
+                    options.score(value, &self.metadata[i])
+                };
+                (i, score)
+            })
+            .collect();
+        // Equal scores (e.g. duplicate vectors) are ordered by id, then position, so
+        // results don't depend on insertion or bucket order
+        scored.sort_by(|a, b| {
+            let (better, worse) = if ascending { (a, b) } else { (b, a) };
+            better.1.partial_cmp(&worse.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| self.metadata[a.0].id.cmp(&self.metadata[b.0].id))
+                .then(a.0.cmp(&b.0))
+        });
+        scored.truncate(k);
+
+        scored
+            .into_iter()
+            .map(|(i, score)| (&self.metadata[i], score))
+            .collect()
+    }
+
+    /// Drop removed vectors and rebuild the buckets from the live set
+    pub fn rebuild(&mut self) -> CompactionStats {
+        let removed = self.deleted.len();
+        let deleted = std::mem::take(&mut self.deleted);
+
+        let entries: Vec<(OptimizedVector, VectorMetadata)> = std::mem::take(&mut self.vectors)
+            .into_iter()
+            .zip(std::mem::take(&mut self.metadata))
+            .enumerate()
+            .filter(|(i, _)| !deleted.contains(i))
+            .map(|(_, entry)| entry)
+            .collect();
+
+        self.buckets.clear();
+        for (vector, metadata) in entries {
+            self.insert(vector, metadata);
+        }
+
+        CompactionStats { removed, live: self.vectors.len() }
+    }
+
+    /// Write the index in its compact binary form: a header, then each vector's
+    /// cached norm and hash followed by its components as little-endian f32,
+    /// then the removed positions and the metadata as JSON. Buckets are not
+    /// stored; signatures are deterministic, so loading recomputes them. Use
+    /// serde (JSON) instead when the file needs to be human readable.
+    pub fn write_binary(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        writer.write_all(INDEX_MAGIC)?;
+        writer.write_all(&[INDEX_FORMAT_VERSION, self.index_type.to_byte(), self.metric.to_byte()])?;
+        writer.write_all(&(self.dimension as u64).to_le_bytes())?;
+        writer.write_all(&(self.vectors.len() as u64).to_le_bytes())?;
+
+        for vector in &self.vectors {
+            writer.write_all(&(vector.data.len() as u64).to_le_bytes())?;
+            // NaN marks a vector without a cached norm
+            writer.write_all(&vector.norm.unwrap_or(f32::NAN).to_le_bytes())?;
+            writer.write_all(&vector.hash.to_le_bytes())?;
+            for value in vector.data.iter() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+
+        let mut deleted: Vec<&usize> = self.deleted.iter().collect();
+        deleted.sort();
+        writer.write_all(&(deleted.len() as u64).to_le_bytes())?;
+        for &position in deleted {
+            writer.write_all(&(position as u64).to_le_bytes())?;
+        }
+
+        let metadata = serde_json::to_vec(&self.metadata)?;
+        writer.write_all(&(metadata.len() as u64).to_le_bytes())?;
+        writer.write_all(&metadata)
+    }
+
+    /// Load an index written by `write_binary`
+    pub fn read_binary(reader: &mut impl std::io::Read) -> std::io::Result<Self> {
+        if &read_array::<4>(reader)? != INDEX_MAGIC {
+            return Err(invalid_index("not a binary spatial index"));
+        }
+        let [version, index_type] = read_array::<2>(reader)?;
+        let metric = match version {
+            1 => SimilarityMetric::Cosine,
+            INDEX_FORMAT_VERSION => {
+                let [metric] = read_array::<1>(reader)?;
+                SimilarityMetric::from_byte(metric)
+                    .ok_or_else(|| invalid_index(format!("unknown similarity metric {}", metric)))?
+            }
+            _ => return Err(invalid_index(format!("unsupported index format version {}", version))),
+        };
+        let index_type = IndexType::from_byte(index_type)
+            .ok_or_else(|| invalid_index(format!("unknown index type {}", index_type)))?;
+        let dimension = u64::from_le_bytes(read_array(reader)?) as usize;
+        let count = u64::from_le_bytes(read_array(reader)?) as usize;
+
+        let mut vectors = Vec::with_capacity(count.min(1 << 20));
+        for _ in 0..count {
+            let len = u64::from_le_bytes(read_array(reader)?) as usize;
+            let norm = f32::from_le_bytes(read_array(reader)?);
+            let hash = u64::from_le_bytes(read_array(reader)?);
+            let mut bytes = vec![0u8; len * 4];
+            reader.read_exact(&mut bytes)?;
+            let data: Vec<f32> = bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+
+            vectors.push(if norm.is_nan() {
+                OptimizedVector::new(data)
+            } else {
+                OptimizedVector::from_parts(data, norm, hash)
+            });
+        }
+
+        let deleted_count = u64::from_le_bytes(read_array(reader)?) as usize;
+        let mut deleted = HashSet::with_capacity(deleted_count.min(count));
+        for _ in 0..deleted_count {
+            deleted.insert(u64::from_le_bytes(read_array(reader)?) as usize);
+        }
+
+        let metadata_len = u64::from_le_bytes(read_array(reader)?) as usize;
+        let mut metadata_bytes = vec![0u8; metadata_len];
+        reader.read_exact(&mut metadata_bytes)?;
+        let metadata: Vec<VectorMetadata> = serde_json::from_slice(&metadata_bytes)?;
+        if metadata.len() != vectors.len() {
+            return Err(invalid_index(format!("{} vectors but {} metadata entries", vectors.len(), metadata.len())));
+        }
+
+        // Inserting in position order reproduces the original buckets exactly
+        let mut index = Self::new(dimension, index_type).with_metric(metric);
+        for (vector, metadata) in vectors.into_iter().zip(metadata) {
+            index.insert(vector, metadata);
+        }
+        index.deleted = deleted;
+        Ok(index)
+    }
+
+    pub fn save_binary(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_binary(&mut writer)?;
+        std::io::Write::flush(&mut writer)
+    }
+
+    pub fn load_binary(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        Self::read_binary(&mut std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    /// Sign pattern of the vector against a fixed set of pseudo-random hyperplanes
+    fn signature(&self, vector: &OptimizedVector) -> u64 {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut signature = 0u64;
+
+        for plane in 0..LSH_PLANES {
+            let mut dot = 0.0f32;
+            for &value in vector.data.iter().take(self.dimension) {
+                // xorshift64: deterministic so signatures are stable across rebuilds and restarts
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let weight = (state >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0;
+                dot += value * weight;
+            }
+            if dot >= 0.0 {
+                signature |= 1 << plane;
+            }
+        }
+
+        signature
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,14 +557,64 @@ pub enum IndexType {
     HNSW, // Hierarchical Navigable Small World
 }
 
+impl IndexType {
+    fn to_byte(&self) -> u8 {
+        match self {
+            IndexType::Flat => 0,
+            IndexType::LSH => 1,
+            IndexType::HNSW => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(IndexType::Flat),
+            1 => Some(IndexType::LSH),
+            2 => Some(IndexType::HNSW),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorMetadata {
     pub id: String,
     pub source_id: String,
+    #[serde(default)]
+    pub source_kind: Option<chunking::SourceKind>,
     pub chunk_index: Option<usize>,
     pub timestamp: DateTime<Utc>,
     pub tags: HashSet<String>,
     pub quality_score: Option<f32>,
+    /// Model the vector was embedded with
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+}
+
+impl VectorMetadata {
+    /// Build index metadata from the metadata the chunker attached to a chunk.
+    /// Returns `None` if the chunk was not stamped with its source.
+    pub fn from_chunk_metadata(id: &str, metadata: &serde_json::Value) -> Option<Self> {
+        let source_id = metadata.get(chunking::SOURCE_ID_KEY)?.as_str()?.to_string();
+        let source_kind = metadata
+            .get(chunking::SOURCE_KIND_KEY)
+            .and_then(|v| v.as_str())
+            .and_then(chunking::SourceKind::from_str);
+
+        Some(Self {
+            id: id.to_string(),
+            source_id,
+            source_kind,
+            chunk_index: metadata.get(chunking::CHUNK_INDEX_KEY).and_then(|v| v.as_u64()).map(|i| i as usize),
+            timestamp: Utc::now(),
+            tags: HashSet::new(),
+            quality_score: None,
+            embedding_model: metadata
+                .get(chunking::EMBEDDING_MODEL_KEY)
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        })
+    }
 }
 
 /// Bloom filter for fast membership testing
@@ -306,6 +821,34 @@ pub enum RepositorySyncStatus {
     PendingAuth,
 }
 
+impl RepositorySyncStatus {
+    /// Status implied by the repository's most recent sync job, if it has one
+    pub fn from_job_status(status: Option<&str>) -> Self {
+        match status {
+            None => RepositorySyncStatus::Connected,
+            Some("pending") | Some("running") => RepositorySyncStatus::Syncing,
+            Some("completed") => RepositorySyncStatus::SyncCompleted,
+            Some(_) => RepositorySyncStatus::SyncFailed,
+        }
+    }
+}
+
+/// A connected repository as listed to the UI, with how fresh its data is
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RepositoryListing {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub full_name: String,
+    pub owner: String,
+    pub url: Option<String>,
+    pub is_private: bool,
+    pub default_branch: String,
+    /// When the last sync completed successfully
+    pub last_synced: Option<DateTime<Utc>>,
+    pub sync_status: RepositorySyncStatus,
+    pub document_count: i64,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ConnectRepositoryRequest {
     pub url: String,
@@ -363,6 +906,40 @@ impl<T> ApiResponse<T> {
     }
 }
 
+/// Error body shared by every service: a stable machine-readable `code`, a
+/// human-readable `message`, optional structured `details`, and the request's
+/// `trace_id` for correlating with logs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ErrorEnvelope {
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub details: Option<serde_json::Value>,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+}
+
+impl ErrorEnvelope {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            details: None,
+            trace_id: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn with_trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct UserSettings {
     pub user_id: String,
@@ -434,6 +1011,231 @@ pub struct AgentConfig {
     pub max_tokens: Option<u32>,
     pub timeout: Option<u32>, 
     pub custom_instructions: Option<String>,
+    /// `anthropic-version` header sent to the Messages API
+    #[serde(default)]
+    pub api_version: Option<String>,
+    /// Wire format of the agent's endpoint, for gateways and proxies that
+    /// speak another provider's API. Defaults to Anthropic.
+    #[serde(default)]
+    pub api_format: Option<ChatApiFormat>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Clamp out-of-range `temperature`/`top_p` into the provider's range
+    /// instead of rejecting the config
+    #[serde(default)]
+    pub clamp_sampling: bool,
+}
+
+/// Request/response shape of a chat completion API
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatApiFormat {
+    /// `POST /v1/messages` with `x-api-key` auth
+    #[default]
+    Anthropic,
+    /// `POST /v1/chat/completions` with bearer auth
+    OpenAI,
+}
+
+impl ChatApiFormat {
+    /// Temperatures the provider accepts
+    pub fn temperature_range(&self) -> std::ops::RangeInclusive<f32> {
+        match self {
+            ChatApiFormat::Anthropic => 0.0..=1.0,
+            ChatApiFormat::OpenAI => 0.0..=2.0,
+        }
+    }
+
+    /// Full request URL on `base_url`, or on the provider's own API when none is given
+    pub fn request_url(&self, base_url: Option<&str>) -> String {
+        let (default_base, path) = match self {
+            ChatApiFormat::Anthropic => ("https://api.anthropic.com", "/v1/messages"),
+            ChatApiFormat::OpenAI => ("https://api.openai.com", "/v1/chat/completions"),
+        };
+        let base = base_url.unwrap_or(default_base).trim_end_matches('/');
+        // Accept gateway URLs given with or without the version prefix
+        match base.strip_suffix("/v1") {
+            Some(root) => format!("{}{}", root, path),
+            None => format!("{}{}", base, path),
+        }
+    }
+
+    /// Auth and version headers for `api_key`
+    pub fn headers(&self, api_key: &str, api_version: &str) -> Vec<(&'static str, String)> {
+        match self {
+            ChatApiFormat::Anthropic => vec![
+                ("x-api-key", api_key.to_string()),
+                ("anthropic-version", api_version.to_string()),
+            ],
+            ChatApiFormat::OpenAI => vec![("Authorization", format!("Bearer {}", api_key))],
+        }
+    }
+
+    /// Request body for a single-turn call. Anthropic takes the system prompt
+    /// as a top-level field; OpenAI takes it as the first message.
+    pub fn request_body(
+        &self,
+        model: &str,
+        system: Option<&str>,
+        user_message: &str,
+        max_tokens: u32,
+        temperature: Option<f32>,
+    ) -> serde_json::Value {
+        match self {
+            ChatApiFormat::Anthropic => {
+                let mut body = serde_json::json!({
+                    "model": model,
+                    "max_tokens": max_tokens,
+                    "messages": [{ "role": "user", "content": user_message }],
+                });
+                if let Some(system) = system {
+                    body["system"] = serde_json::Value::String(system.to_string());
+                }
+                if let Some(temperature) = temperature {
+                    body["temperature"] = serde_json::json!(temperature);
+                }
+                body
+            }
+            ChatApiFormat::OpenAI => {
+                let mut messages = Vec::new();
+                if let Some(system) = system {
+                    messages.push(serde_json::json!({ "role": "system", "content": system }));
+                }
+                messages.push(serde_json::json!({ "role": "user", "content": user_message }));
+                let mut body = serde_json::json!({
+                    "model": model,
+                    "max_tokens": max_tokens,
+                    "messages": messages,
+                });
+                if let Some(temperature) = temperature {
+                    body["temperature"] = serde_json::json!(temperature);
+                }
+                body
+            }
+        }
+    }
+
+    /// Reply text and total tokens used, or `None` if the body isn't this format's response
+    pub fn parse_response(&self, body: &serde_json::Value) -> Option<(String, u32)> {
+        let tokens = |value: &serde_json::Value| value.as_u64().unwrap_or(0) as u32;
+        match self {
+            ChatApiFormat::Anthropic => {
+                let text = body["content"]
+                    .as_array()?
+                    .iter()
+                    .filter(|block| block["type"].as_str().is_none_or(|t| t == "text"))
+                    .filter_map(|block| block["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("");
+                let usage = &body["usage"];
+                Some((text, tokens(&usage["input_tokens"]) + tokens(&usage["output_tokens"])))
+            }
+            ChatApiFormat::OpenAI => {
+                let text = body["choices"].get(0)?["message"]["content"].as_str()?.to_string();
+                Some((text, tokens(&body["usage"]["total_tokens"])))
+            }
+        }
+    }
+}
+
+/// Anthropic Messages API version used unless an agent overrides it
+pub const DEFAULT_ANTHROPIC_API_VERSION: &str = "2023-06-01";
+pub const DEFAULT_ANTHROPIC_MODEL: &str = "claude-3-sonnet-20240229";
+
+/// Models the Anthropic integration has been checked against. Other names are
+/// still sent as configured, since Anthropic ships new models more often than we release.
+pub const KNOWN_ANTHROPIC_MODELS: &[&str] = &[
+    "claude-3-haiku-20240307",
+    "claude-3-sonnet-20240229",
+    "claude-3-opus-20240229",
+    "claude-3-5-sonnet-20240620",
+    "claude-3-5-sonnet-20241022",
+    "claude-3-5-haiku-20241022",
+    "claude-3-7-sonnet-20250219",
+    "claude-sonnet-4-20250514",
+    "claude-opus-4-20250514",
+    "claude-3-5-sonnet-latest",
+    "claude-3-5-haiku-latest",
+    "claude-3-7-sonnet-latest",
+];
+
+impl AgentConfig {
+    /// Model to request from Anthropic. An unrecognized model is logged but
+    /// used as configured, so a newly released or retired model surfaces
+    /// without blocking the call.
+    pub fn anthropic_model(&self) -> String {
+        let model = self.model.as_deref().unwrap_or(DEFAULT_ANTHROPIC_MODEL);
+        if !KNOWN_ANTHROPIC_MODELS.contains(&model) {
+            log::warn!("Anthropic model '{}' is not in the known models list; sending it as configured", model);
+        }
+        model.to_string()
+    }
+
+    pub fn anthropic_api_version(&self) -> &str {
+        self.api_version.as_deref().unwrap_or(DEFAULT_ANTHROPIC_API_VERSION)
+    }
+
+    pub fn api_format(&self) -> ChatApiFormat {
+        self.api_format.unwrap_or_default()
+    }
+
+    /// Model to request in the configured API format. Anthropic falls back to
+    /// `DEFAULT_ANTHROPIC_MODEL`; an OpenAI-format endpoint has no default that
+    /// would work, so its model must be configured.
+    pub fn chat_model(&self) -> Result<String, String> {
+        match self.api_format() {
+            ChatApiFormat::Anthropic => Ok(self.anthropic_model()),
+            ChatApiFormat::OpenAI => self
+                .model
+                .clone()
+                .filter(|model| !model.trim().is_empty())
+                .ok_or_else(|| "model is required when api_format is openai".to_string()),
+        }
+    }
+
+    /// Check `temperature` and `top_p` against the ranges the configured API
+    /// accepts. Out-of-range values are clamped when `clamp_sampling` is set and
+    /// rejected otherwise; non-numeric values (NaN) are always rejected.
+    pub fn validate_sampling(&mut self) -> Result<(), String> {
+        self.validate_sampling_in(self.api_format())
+    }
+
+    /// `validate_sampling` for an agent of `agent_type`. OpenAI agents are always
+    /// called in the OpenAI format, whatever `api_format` says.
+    pub fn validate_sampling_for(&mut self, agent_type: &str) -> Result<(), String> {
+        match agent_type {
+            "openai" => self.validate_sampling_in(ChatApiFormat::OpenAI),
+            _ => self.validate_sampling(),
+        }
+    }
+
+    fn validate_sampling_in(&mut self, format: ChatApiFormat) -> Result<(), String> {
+        let clamp = self.clamp_sampling;
+        let check = |name: &str, value: &mut Option<f32>, range: std::ops::RangeInclusive<f32>| -> Result<(), String> {
+            let Some(v) = *value else {
+                return Ok(());
+            };
+            if v.is_nan() {
+                return Err(format!("{} must be a number", name));
+            }
+            if range.contains(&v) {
+                return Ok(());
+            }
+            if !clamp {
+                return Err(format!(
+                    "{} {} is outside the range {}..={} accepted by {:?}",
+                    name, v, range.start(), range.end(), format
+                ));
+            }
+            let clamped = v.clamp(*range.start(), *range.end());
+            log::warn!("Clamping {} {} to {}", name, v, clamped);
+            *value = Some(clamped);
+            Ok(())
+        };
+
+        check("temperature", &mut self.temperature, format.temperature_range())?;
+        check("top_p", &mut self.top_p, 0.0..=1.0)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -455,6 +1257,13 @@ pub struct CreateAgentRequest {
     pub config: AgentConfig,
 }
 
+impl CreateAgentRequest {
+    /// Check (or clamp) the new agent's sampling settings before it is stored
+    pub fn validate_config(&mut self) -> Result<(), String> {
+        self.config.validate_sampling_for(&self.agent_type)
+    }
+}
+
 #[derive(Deserialize)]
 #[allow(dead_code)]
 pub struct UpdateAgentRequest {
@@ -466,6 +1275,16 @@ pub struct UpdateAgentRequest {
     pub status: Option<AgentStatus>,
 }
 
+impl UpdateAgentRequest {
+    /// Check (or clamp) a replacement config for an agent of `agent_type`
+    pub fn validate_config(&mut self, agent_type: &str) -> Result<(), String> {
+        match &mut self.config {
+            Some(config) => config.validate_sampling_for(agent_type),
+            None => Ok(()),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[allow(dead_code)]
 pub struct AgentInvokeRequest {
@@ -534,3 +1353,438 @@ pub enum DataSourceType {
     Notion,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(id: usize) -> VectorMetadata {
+        VectorMetadata {
+            id: format!("vec-{}", id),
+            source_id: "source".to_string(),
+            source_kind: None,
+            chunk_index: Some(id),
+            timestamp: Utc::now(),
+            tags: HashSet::new(),
+            quality_score: None,
+            embedding_model: None,
+        }
+    }
+
+    fn recall(index: &SpatialIndex, query: &OptimizedVector, k: usize) -> f32 {
+        let expected: HashSet<&str> = index.search_exact(query, k).iter().map(|(m, _)| m.id.as_str()).collect();
+        let found = index.search(query, k).iter().filter(|(m, _)| expected.contains(m.id.as_str())).count();
+        found as f32 / expected.len() as f32
+    }
+
+    #[test]
+    fn test_rebuild_restores_recall_after_removals() {
+        let mut index = SpatialIndex::new(4, IndexType::LSH);
+        for i in 0..200 {
+            let angle = i as f32 * 0.002;
+            index.insert(OptimizedVector::new(vec![angle.cos(), angle.sin(), 0.0, 0.0]), metadata(i));
+        }
+        let query = OptimizedVector::new(vec![1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(recall(&index, &query, 5), 1.0);
+
+        // Remove the closest matches; their slots still fill the probe budget
+        for i in 0..150 {
+            assert!(index.remove(&format!("vec-{}", i)));
+        }
+        assert!(recall(&index, &query, 5) < 1.0);
+
+        let stats = index.rebuild();
+        assert_eq!(stats, CompactionStats { removed: 150, live: 50 });
+        assert_eq!(recall(&index, &query, 5), 1.0);
+        assert_eq!(index.search(&query, 1)[0].0.id, "vec-150");
+    }
+
+    #[test]
+    fn test_binary_round_trip_preserves_search_results() {
+        let mut index = SpatialIndex::new(4, IndexType::LSH);
+        for i in 0..300 {
+            let angle = i as f32 * 0.021;
+            index.insert(OptimizedVector::new(vec![angle.cos(), angle.sin(), (i % 7) as f32 * 0.1, 0.5]), metadata(i));
+        }
+        assert!(index.remove("vec-3"));
+
+        let mut bytes = Vec::new();
+        index.write_binary(&mut bytes).unwrap();
+        let loaded = SpatialIndex::read_binary(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(loaded.len(), index.len());
+        assert_eq!(loaded.buckets, index.buckets);
+        for query in [vec![1.0, 0.0, 0.0, 0.5], vec![-0.3, 0.9, 0.6, 0.5]] {
+            let query = OptimizedVector::new(query);
+            let expected: Vec<(String, f32)> = index.search(&query, 10).into_iter().map(|(m, s)| (m.id.clone(), s)).collect();
+            let actual: Vec<(String, f32)> = loaded.search(&query, 10).into_iter().map(|(m, s)| (m.id.clone(), s)).collect();
+            assert_eq!(actual, expected);
+        }
+        assert!(!loaded.search_exact(&OptimizedVector::new(vec![1.0, 0.0, 0.0, 0.5]), 300).iter().any(|(m, _)| m.id == "vec-3"));
+
+        assert!(SpatialIndex::read_binary(&mut &bytes[..bytes.len() - 1]).is_err());
+        assert!(SpatialIndex::read_binary(&mut &b"JSON{}"[..]).is_err());
+
+        // At embedding dimensions the raw floats dominate and JSON is more than twice the size
+        let mut embeddings = SpatialIndex::new(384, IndexType::Flat);
+        for i in 0..20 {
+            embeddings.insert(OptimizedVector::new((0..384).map(|d| ((i * 384 + d) as f32).sin()).collect()), metadata(i));
+        }
+        let mut bytes = Vec::new();
+        embeddings.write_binary(&mut bytes).unwrap();
+        assert!(bytes.len() * 2 < serde_json::to_vec(&embeddings).unwrap().len());
+    }
+
+    #[test]
+    fn test_search_ranks_by_configured_metric() {
+        let ranking = |metric: SimilarityMetric| {
+            let mut index = SpatialIndex::new(2, IndexType::Flat).with_metric(metric);
+            index.insert(OptimizedVector::new(vec![3.0, 3.0]), metadata(0));
+            index.insert(OptimizedVector::new(vec![0.6, 0.0]), metadata(1));
+            index.insert(OptimizedVector::new(vec![1.2, 0.3]), metadata(2));
+
+            index.search(&OptimizedVector::new(vec![1.0, 0.0]), 3)
+                .into_iter()
+                .map(|(m, score)| (m.id.clone(), score))
+                .collect::<Vec<_>>()
+        };
+        let ids = |results: &[(String, f32)]| results.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+
+        // Same direction wins under cosine, magnitude under dot, proximity under euclidean
+        let cosine = ranking(SimilarityMetric::Cosine);
+        assert_eq!(ids(&cosine), vec!["vec-1", "vec-2", "vec-0"]);
+        let dot = ranking(SimilarityMetric::Dot);
+        assert_eq!(ids(&dot), vec!["vec-0", "vec-2", "vec-1"]);
+        assert!((dot[0].1 - 3.0).abs() < 1e-6);
+        let euclidean = ranking(SimilarityMetric::Euclidean);
+        assert_eq!(ids(&euclidean), vec!["vec-2", "vec-1", "vec-0"]);
+        assert!(euclidean.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!((euclidean[1].1 - 0.4).abs() < 1e-6);
+
+        let mut index = SpatialIndex::new(2, IndexType::Flat).with_metric(SimilarityMetric::Euclidean);
+        index.insert(OptimizedVector::new(vec![1.0, 0.0]), metadata(0));
+        let mut bytes = Vec::new();
+        index.write_binary(&mut bytes).unwrap();
+        assert_eq!(SpatialIndex::read_binary(&mut bytes.as_slice()).unwrap().metric, SimilarityMetric::Euclidean);
+    }
+
+    #[test]
+    fn test_equal_scores_are_ordered_by_id() {
+        let ids = |index: &SpatialIndex| -> Vec<String> {
+            index
+                .search(&OptimizedVector::new(vec![1.0, 0.0]), 3)
+                .iter()
+                .map(|(m, _)| m.id.clone())
+                .collect()
+        };
+
+        let mut forward = SpatialIndex::new(2, IndexType::Flat);
+        let mut reverse = SpatialIndex::new(2, IndexType::LSH);
+        for i in [3, 1, 4, 0, 2] {
+            forward.insert(OptimizedVector::new(vec![2.0, 0.0]), metadata(i));
+        }
+        for i in [2, 0, 4, 1, 3] {
+            reverse.insert(OptimizedVector::new(vec![2.0, 0.0]), metadata(i));
+        }
+
+        assert_eq!(ids(&forward), vec!["vec-0", "vec-1", "vec-2"]);
+        assert_eq!(ids(&forward), ids(&reverse));
+    }
+
+    #[test]
+    fn test_quality_weight_reorders_equally_similar_results() {
+        let mut index = SpatialIndex::new(2, IndexType::Flat);
+        for (i, quality) in [(0, Some(0.2)), (1, Some(0.9)), (2, None)] {
+            index.insert(
+                OptimizedVector::new(vec![1.0, 0.0]),
+                VectorMetadata { quality_score: quality, ..metadata(i) },
+            );
+        }
+        // Slightly more similar, but low quality
+        index.insert(OptimizedVector::new(vec![1.0, 0.05]), VectorMetadata { quality_score: Some(0.1), ..metadata(3) });
+        let query = OptimizedVector::new(vec![1.0, 0.0]);
+        let ids = |results: Vec<(&VectorMetadata, f32)>| -> Vec<String> {
+            results.iter().map(|(m, _)| m.id.clone()).collect()
+        };
+
+        // Default weight 0 keeps pure similarity ordering
+        assert_eq!(ids(index.search(&query, 4)), vec!["vec-0", "vec-1", "vec-2", "vec-3"]);
+
+        let weighted = SearchOptions { quality_weight: 0.3, ..Default::default() };
+        assert_eq!(ids(index.search_with(&query, 4, &weighted)), vec!["vec-1", "vec-2", "vec-0", "vec-3"]);
+    }
+
+    #[test]
+    fn test_results_are_limited_to_callers_tag_scope() {
+        let tagged = |id: usize, tags: &[&str]| VectorMetadata {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..metadata(id)
+        };
+
+        for index_type in [IndexType::Flat, IndexType::LSH] {
+            let mut index = SpatialIndex::new(2, index_type);
+            index.insert(OptimizedVector::new(vec![1.0, 0.0]), tagged(0, &["tenant:a"]));
+            index.insert(OptimizedVector::new(vec![1.0, 0.1]), tagged(1, &["tenant:b"]));
+            index.insert(OptimizedVector::new(vec![1.0, 0.2]), tagged(2, &["tenant:a", "project:x"]));
+            index.insert(OptimizedVector::new(vec![1.0, 0.3]), tagged(3, &[]));
+            let query = OptimizedVector::new(vec![1.0, 0.0]);
+
+            let scoped = |tags: &[&str]| -> Vec<String> {
+                let options = SearchOptions {
+                    allowed_tags: tags.iter().map(|t| t.to_string()).collect(),
+                    ..Default::default()
+                };
+                index.search_with(&query, 10, &options).iter().map(|(m, _)| m.id.clone()).collect()
+            };
+
+            assert_eq!(scoped(&["tenant:a"]), vec!["vec-0", "vec-2"]);
+            assert_eq!(scoped(&["project:x", "tenant:b"]), vec!["vec-1", "vec-2"]);
+            assert_eq!(scoped(&[]).len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_removed_vectors_are_not_returned() {
+        let mut index = SpatialIndex::new(2, IndexType::Flat);
+        index.insert(OptimizedVector::new(vec![1.0, 0.0]), metadata(0));
+        index.insert(OptimizedVector::new(vec![0.0, 1.0]), metadata(1));
+
+        assert!(index.remove("vec-0"));
+        assert!(!index.remove("vec-0"));
+        assert_eq!(index.len(), 1);
+
+        let results = index.search(&OptimizedVector::new(vec![1.0, 0.0]), 2);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, "vec-1");
+    }
+
+    #[test]
+    fn test_from_parts_matches_new_for_stored_values() {
+        let built = OptimizedVector::new(vec![0.3, -1.2, 4.0, 0.0]);
+        let hydrated = OptimizedVector::from_parts(vec![0.3, -1.2, 4.0, 0.0], built.norm.unwrap(), built.hash);
+
+        assert_eq!(hydrated.dimension, built.dimension);
+        assert_eq!(hydrated.norm, built.norm);
+        assert_eq!(hydrated.hash, built.hash);
+        assert_eq!(hydrated.data, built.data);
+        assert!((hydrated.cosine_similarity(&built) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "stored hash does not match")]
+    fn test_from_parts_rejects_stale_hash_in_debug_builds() {
+        let built = OptimizedVector::new(vec![1.0, 2.0]);
+        OptimizedVector::from_parts(vec![1.0, 2.0], built.norm.unwrap(), built.hash ^ 1);
+    }
+
+    /// Collects warnings logged through the `log` facade
+    struct WarningCapture(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for WarningCapture {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static WARNINGS: WarningCapture = WarningCapture(std::sync::Mutex::new(Vec::new()));
+
+    #[test]
+    fn test_unknown_anthropic_model_warns_but_is_still_used() {
+        let _ = log::set_logger(&WARNINGS);
+        log::set_max_level(log::LevelFilter::Warn);
+
+        let config: AgentConfig = serde_json::from_value(serde_json::json!({
+            "model": "claude-next-preview",
+            "temperature": null,
+            "max_tokens": null,
+            "timeout": null,
+            "custom_instructions": null
+        }))
+        .unwrap();
+
+        assert_eq!(config.anthropic_model(), "claude-next-preview");
+        assert_eq!(config.anthropic_api_version(), DEFAULT_ANTHROPIC_API_VERSION);
+        assert!(WARNINGS.0.lock().unwrap().iter().any(|w| w.contains("'claude-next-preview' is not in the known models list")));
+
+        let pinned = AgentConfig { model: None, api_version: Some("2024-10-01".to_string()), ..config };
+        assert_eq!(pinned.anthropic_model(), DEFAULT_ANTHROPIC_MODEL);
+        assert_eq!(pinned.anthropic_api_version(), "2024-10-01");
+    }
+
+    #[test]
+    fn test_anthropic_request_shape() {
+        let format = ChatApiFormat::default();
+        assert_eq!(format, ChatApiFormat::Anthropic);
+        assert_eq!(format.request_url(None), "https://api.anthropic.com/v1/messages");
+        assert_eq!(format.request_url(Some("https://gateway.internal/anthropic/v1/")), "https://gateway.internal/anthropic/v1/messages");
+        assert_eq!(
+            format.headers("sk-ant", "2023-06-01"),
+            vec![("x-api-key", "sk-ant".to_string()), ("anthropic-version", "2023-06-01".to_string())]
+        );
+
+        let body = format.request_body("claude-3-5-sonnet-latest", Some("Be brief."), "Explain the diff", 512, Some(0.2));
+        assert_eq!(body["system"], "Be brief.");
+        assert_eq!(body["max_tokens"], 512);
+        assert_eq!(body["messages"], serde_json::json!([{ "role": "user", "content": "Explain the diff" }]));
+
+        let reply = serde_json::json!({
+            "content": [{ "type": "text", "text": "It renames " }, { "type": "text", "text": "a field." }],
+            "usage": { "input_tokens": 30, "output_tokens": 12 }
+        });
+        assert_eq!(format.parse_response(&reply), Some(("It renames a field.".to_string(), 42)));
+    }
+
+    #[test]
+    fn test_openai_request_shape() {
+        let config: AgentConfig = serde_json::from_value(serde_json::json!({
+            "model": "llama-3-70b",
+            "temperature": null,
+            "max_tokens": null,
+            "timeout": null,
+            "custom_instructions": null,
+            "api_format": "openai"
+        }))
+        .unwrap();
+        let format = config.api_format();
+        assert_eq!(format, ChatApiFormat::OpenAI);
+        assert_eq!(format.request_url(Some("http://localhost:4000")), "http://localhost:4000/v1/chat/completions");
+        assert_eq!(format.headers("sk-proxy", "ignored"), vec![("Authorization", "Bearer sk-proxy".to_string())]);
+
+        let body = format.request_body("llama-3-70b", Some("Be brief."), "Explain the diff", 512, None);
+        assert!(body.get("system").is_none() && body.get("temperature").is_none());
+        assert_eq!(
+            body["messages"],
+            serde_json::json!([
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": "Explain the diff" }
+            ])
+        );
+
+        let reply = serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": "It renames a field." } }],
+            "usage": { "total_tokens": 42 }
+        });
+        assert_eq!(format.parse_response(&reply), Some(("It renames a field.".to_string(), 42)));
+        assert_eq!(ChatApiFormat::Anthropic.parse_response(&reply), None);
+    }
+
+    fn sampling_config(temperature: Option<f32>, top_p: Option<f32>, api_format: &str, clamp: bool) -> AgentConfig {
+        serde_json::from_value(serde_json::json!({
+            "model": null,
+            "temperature": temperature,
+            "max_tokens": null,
+            "timeout": null,
+            "custom_instructions": null,
+            "api_format": api_format,
+            "top_p": top_p,
+            "clamp_sampling": clamp
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_out_of_range_sampling_is_rejected() {
+        let err = sampling_config(Some(5.0), None, "anthropic", false).validate_sampling().unwrap_err();
+        assert!(err.contains("temperature 5 is outside the range 0..=1"));
+
+        let err = sampling_config(Some(0.5), Some(1.5), "openai", false).validate_sampling().unwrap_err();
+        assert!(err.starts_with("top_p 1.5"));
+
+        // OpenAI-format endpoints accept temperatures up to 2
+        assert!(sampling_config(Some(1.5), Some(0.9), "openai", false).validate_sampling().is_ok());
+        assert!(sampling_config(None, None, "anthropic", false).validate_sampling().is_ok());
+        let mut nan = sampling_config(None, None, "anthropic", true);
+        nan.temperature = Some(f32::NAN);
+        assert!(nan.validate_sampling().is_err());
+    }
+
+    #[test]
+    fn test_openai_format_requires_a_model() {
+        let mut config = sampling_config(None, None, "openai", false);
+        assert_eq!(config.chat_model().unwrap_err(), "model is required when api_format is openai");
+        config.model = Some("gpt-4o".to_string());
+        assert_eq!(config.chat_model().unwrap(), "gpt-4o");
+
+        let config = sampling_config(None, None, "anthropic", false);
+        assert_eq!(config.chat_model().unwrap(), DEFAULT_ANTHROPIC_MODEL);
+    }
+
+    #[test]
+    fn test_agent_requests_validate_sampling_for_their_agent_type() {
+        let mut create: CreateAgentRequest = serde_json::from_value(serde_json::json!({
+            "name": "Writer",
+            "agent_type": "anthropic",
+            "endpoint": null,
+            "api_key": "key",
+            "permissions": [],
+            "config": { "model": null, "temperature": 1.5, "max_tokens": null, "timeout": null, "custom_instructions": null }
+        }))
+        .unwrap();
+        assert!(create.validate_config().is_err());
+        // OpenAI agents accept temperatures up to 2 without an explicit api_format
+        create.agent_type = "openai".to_string();
+        assert!(create.validate_config().is_ok());
+
+        let mut update: UpdateAgentRequest = serde_json::from_value(serde_json::json!({
+            "config": { "model": null, "temperature": 3.0, "max_tokens": null, "timeout": null, "custom_instructions": null, "clamp_sampling": true }
+        }))
+        .unwrap();
+        update.validate_config("openai").unwrap();
+        assert_eq!(update.config.unwrap().temperature, Some(2.0));
+
+        let mut no_config: UpdateAgentRequest = serde_json::from_value(serde_json::json!({ "name": "Renamed" })).unwrap();
+        assert!(no_config.validate_config("anthropic").is_ok());
+    }
+
+    #[test]
+    fn test_out_of_range_sampling_is_clamped_when_enabled() {
+        let mut config = sampling_config(Some(5.0), Some(-0.2), "anthropic", true);
+        config.validate_sampling().unwrap();
+        assert_eq!(config.temperature, Some(1.0));
+        assert_eq!(config.top_p, Some(0.0));
+
+        let mut config = sampling_config(Some(5.0), None, "openai", true);
+        config.validate_sampling().unwrap();
+        assert_eq!(config.temperature, Some(2.0));
+    }
+
+    #[test]
+    fn test_empty_input_embedding_is_flagged_not_stored() {
+        let mut index = SpatialIndex::new(3, IndexType::Flat);
+        // What an embedder returns for "" or "   "
+        let empty = OptimizedVector::new(vec![0.0, 0.0, 0.0]);
+        assert!(empty.is_degenerate());
+        assert!(OptimizedVector::new(vec![1e-9, 0.0, 0.0]).is_degenerate());
+        assert!(!OptimizedVector::new(vec![0.0, 0.5, 0.0]).is_degenerate());
+
+        let skipped = index.insert_batch(vec![
+            (OptimizedVector::new(vec![1.0, 0.0, 0.0]), metadata(0)),
+            (empty, metadata(1)),
+            (OptimizedVector::new(vec![0.0, 1.0, 0.0]), metadata(2)),
+        ]);
+
+        assert_eq!(skipped, vec![1]);
+        assert_eq!(index.len(), 2);
+        let ids: Vec<&str> = index.search(&OptimizedVector::new(vec![1.0, 1.0, 0.0]), 3)
+            .iter()
+            .map(|(m, _)| m.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["vec-0", "vec-2"]);
+    }
+
+    #[test]
+    fn test_repository_sync_status_follows_latest_job() {
+        assert_eq!(RepositorySyncStatus::from_job_status(None), RepositorySyncStatus::Connected);
+        assert_eq!(RepositorySyncStatus::from_job_status(Some("running")), RepositorySyncStatus::Syncing);
+        assert_eq!(RepositorySyncStatus::from_job_status(Some("completed")), RepositorySyncStatus::SyncCompleted);
+        assert_eq!(RepositorySyncStatus::from_job_status(Some("cancelled")), RepositorySyncStatus::SyncFailed);
+    }
+}
@@ -0,0 +1,223 @@
+//! Tower layer for axum services, equivalent to the actix `observability(service)`
+//! middleware:
+//! - Extracts or generates trace context from headers
+//! - Logs requests and responses with the same structured fields
+//! - Warns on slow requests
+//! - Makes the trace ID available via `current_trace_id` and echoes it in `X-Trace-Id`
+//!
+//! ```ignore
+//! let app = Router::new()
+//!     .route("/decide", post(decide))
+//!     .layer(conhub_observability::observability_layer("decision-engine"));
+//! ```
+
+use http::{HeaderName, HeaderValue, Request, Response};
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::{debug, error, span, Instrument, Level};
+
+use crate::middleware::{log_response, ObservabilityConfig};
+use crate::trace_context::{with_trace_id, TraceContext, TRACE_ID_HEADER};
+
+/// Observability layer for axum/tower services
+#[derive(Debug, Clone)]
+pub struct ObservabilityLayer {
+    config: ObservabilityConfig,
+}
+
+impl ObservabilityLayer {
+    pub fn new(config: ObservabilityConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn for_service(name: impl Into<String>) -> Self {
+        Self::new(ObservabilityConfig::for_service(name))
+    }
+}
+
+impl<S> Layer<S> for ObservabilityLayer {
+    type Service = ObservabilityService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ObservabilityService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ObservabilityService<S> {
+    inner: S,
+    config: ObservabilityConfig,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for ObservabilityService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: fmt::Display,
+    ReqBody: Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        // Use the instance that was polled ready and leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let path = req.uri().path().to_string();
+            let method = req.method().to_string();
+            let headers = req.headers();
+            let trace_ctx = TraceContext::from_header_lookup(|name| {
+                headers.get(name).and_then(|h| h.to_str().ok()).map(String::from)
+            })
+            .with_service(&config.service_name);
+
+            // Excluded paths still carry the trace ID, they just aren't logged
+            if config.exclude_paths.iter().any(|p| path.starts_with(p)) {
+                let mut res = with_trace_id(trace_ctx.trace_id.clone(), inner.call(req)).await?;
+                echo_trace_id(&mut res, &trace_ctx.trace_id);
+                return Ok(res);
+            }
+
+            debug!(
+                trace_id = %trace_ctx.trace_id,
+                span_id = %trace_ctx.span_id,
+                request_id = %trace_ctx.request_id,
+                service = %config.service_name,
+                method = %method,
+                path = %path,
+                query = req.uri().query().unwrap_or_default(),
+                "→ {} {}", method, path
+            );
+            req.extensions_mut().insert(trace_ctx.clone());
+
+            let request_span = span!(
+                Level::INFO,
+                "http_request",
+                trace_id = %trace_ctx.trace_id,
+                span_id = %trace_ctx.span_id,
+                method = %method,
+                path = %path,
+                service = %config.service_name,
+            );
+
+            let start = Instant::now();
+            let result = with_trace_id(trace_ctx.trace_id.clone(), inner.call(req))
+                .instrument(request_span)
+                .await;
+            let duration_ms = start.elapsed().as_millis() as u64;
+
+            match result {
+                Ok(mut res) => {
+                    echo_trace_id(&mut res, &trace_ctx.trace_id);
+                    log_response(
+                        &trace_ctx.trace_id,
+                        &method,
+                        &path,
+                        res.status().as_u16(),
+                        duration_ms,
+                        config.slow_request_threshold_ms,
+                    );
+                    Ok(res)
+                }
+                Err(e) => {
+                    error!(
+                        trace_id = %trace_ctx.trace_id,
+                        duration_ms = duration_ms,
+                        error = %e,
+                        "← {} {} ERROR {}ms: {}",
+                        method, path, duration_ms, e
+                    );
+                    Err(e)
+                }
+            }
+        })
+    }
+}
+
+fn echo_trace_id<B>(res: &mut Response<B>, trace_id: &str) {
+    if let Ok(value) = HeaderValue::from_str(trace_id) {
+        res.headers_mut().insert(HeaderName::from_static(TRACE_ID_HEADER), value);
+    }
+}
+
+/// Helper to create the observability layer for an axum service
+pub fn observability_layer(service_name: impl Into<String>) -> ObservabilityLayer {
+    ObservabilityLayer::for_service(service_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    /// Collects formatted log output in memory
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_emits_structured_log_with_trace_id() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_max_level(Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route("/decide", get(|| async { "ok" }))
+            .layer(observability_layer("decision-engine"));
+
+        let req = Request::get("/decide")
+            .header(TRACE_ID_HEADER, "trace-abc")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(TRACE_ID_HEADER).unwrap(), "trace-abc");
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let logs: Vec<serde_json::Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        let response_log = logs
+            .iter()
+            .find(|l| l["fields"]["status"] == 200)
+            .expect("response log");
+        assert_eq!(response_log["level"], "INFO");
+        assert_eq!(response_log["fields"]["trace_id"], "trace-abc");
+        assert!(logs
+            .iter()
+            .any(|l| l["level"] == "DEBUG" && l["fields"]["path"] == "/decide" && l["fields"]["service"] == "decision-engine"));
+    }
+}
@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::time::Instant;
 use uuid::Uuid;
 
 /// Result of a domain operation
@@ -119,6 +120,43 @@ impl DomainEvent {
             metadata: None,
         }
     }
+
+    /// Emit the event as a log, at a level matching its result
+    pub fn emit(&self) {
+        let json = serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string());
+        
+        match self.result {
+            OperationResult::Success => tracing::info!(
+                target: "domain_event",
+                category = %self.category,
+                event_type = %self.event_type,
+                result = "success",
+                "DomainEvent: {}", json
+            ),
+            OperationResult::Failure => tracing::error!(
+                target: "domain_event",
+                category = %self.category,
+                event_type = %self.event_type,
+                result = "failure",
+                error = ?self.error,
+                "DomainEvent: {}", json
+            ),
+            OperationResult::Partial => tracing::warn!(
+                target: "domain_event",
+                category = %self.category,
+                event_type = %self.event_type,
+                result = "partial",
+                "DomainEvent: {}", json
+            ),
+            OperationResult::Skipped => tracing::debug!(
+                target: "domain_event",
+                category = %self.category,
+                event_type = %self.event_type,
+                result = "skipped",
+                "DomainEvent: {}", json
+            ),
+        }
+    }
 }
 
 /// Builder for constructing domain events
@@ -195,40 +233,7 @@ impl DomainEventBuilder {
 
     /// Build and emit the event as a log
     pub fn emit(self) {
-        let event = self.build();
-        let json = serde_json::to_string(&event).unwrap_or_else(|_| "{}".to_string());
-        
-        match event.result {
-            OperationResult::Success => tracing::info!(
-                target: "domain_event",
-                category = %event.category,
-                event_type = %event.event_type,
-                result = "success",
-                "DomainEvent: {}", json
-            ),
-            OperationResult::Failure => tracing::error!(
-                target: "domain_event",
-                category = %event.category,
-                event_type = %event.event_type,
-                result = "failure",
-                error = ?event.error,
-                "DomainEvent: {}", json
-            ),
-            OperationResult::Partial => tracing::warn!(
-                target: "domain_event",
-                category = %event.category,
-                event_type = %event.event_type,
-                result = "partial",
-                "DomainEvent: {}", json
-            ),
-            OperationResult::Skipped => tracing::debug!(
-                target: "domain_event",
-                category = %event.category,
-                event_type = %event.event_type,
-                result = "skipped",
-                "DomainEvent: {}", json
-            ),
-        }
+        self.build().emit();
     }
 
     /// Build the event without emitting
@@ -281,12 +286,173 @@ pub fn log_sync_job_completed(service: &str, job_id: Uuid, docs_processed: usize
 }
 
 /// Log sync job failure
-pub fn log_sync_job_failed(service: &str, job_id: Uuid, error: &str, duration_ms: u64) {
-    DomainEvent::new(service, EventCategory::Sync, "job_failed")
+pub fn log_sync_job_failed(service: &str, job_id: Uuid, error: &str, duration_ms: u64, trace_id: Option<&str>) {
+    sync_job_failed_event(service, job_id, error, duration_ms, trace_id).emit();
+}
+
+fn sync_job_failed_event(service: &str, job_id: Uuid, error: &str, duration_ms: u64, trace_id: Option<&str>) -> DomainEvent {
+    let mut builder = DomainEvent::new(service, EventCategory::Sync, "job_failed")
         .entity("sync_job", job_id.to_string())
         .duration_ms(duration_ms)
-        .failure(error)
+        .failure(error);
+    
+    if let Some(tid) = trace_id {
+        builder = builder.trace(tid, "");
+    }
+    
+    builder.build()
+}
+
+/// Tracks a sync job from start to its terminal event, so every failure path
+/// reports the same job id, trace id and elapsed time
+pub struct SyncJobLifecycle {
+    service: String,
+    job_id: Uuid,
+    trace_id: Option<String>,
+    started: Instant,
+}
+
+impl SyncJobLifecycle {
+    /// Emit `job_started` and begin timing the job
+    pub fn start(service: &str, job_id: Uuid, connector: &str, trace_id: Option<&str>) -> Self {
+        log_sync_job_started(service, job_id, connector, trace_id);
+        Self {
+            service: service.to_string(),
+            job_id,
+            trace_id: trace_id.map(String::from),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn job_id(&self) -> Uuid {
+        self.job_id
+    }
+
+    /// Emit `job_completed` and return the emitted event
+    pub fn completed(self, docs_processed: usize) -> DomainEvent {
+        let mut builder = DomainEvent::new(&self.service, EventCategory::Sync, "job_completed")
+            .entity("sync_job", self.job_id.to_string())
+            .duration_ms(self.elapsed_ms())
+            .metadata(serde_json::json!({ "documents_processed": docs_processed }))
+            .success();
+        if let Some(tid) = &self.trace_id {
+            builder = builder.trace(tid, "");
+        }
+
+        let event = builder.build();
+        event.emit();
+        event
+    }
+
+    /// Emit `job_failed` and return the emitted event
+    pub fn failed(self, error: &str) -> DomainEvent {
+        let event = sync_job_failed_event(
+            &self.service,
+            self.job_id,
+            error,
+            self.elapsed_ms(),
+            self.trace_id.as_deref(),
+        );
+        event.emit();
+        event
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+/// Tracks a chunk job through its pipeline stages. Lifecycle events use the same
+/// `job_started`/`job_completed`/`job_failed` types as sync jobs under the
+/// chunking category, and carry the source id so both can be viewed together.
+pub struct ChunkJobLifecycle {
+    service: String,
+    job_id: Uuid,
+    source_id: String,
+    trace_id: Option<String>,
+    started: Instant,
+    stage_timings: Vec<(String, u64)>,
+}
+
+impl ChunkJobLifecycle {
+    /// Emit `job_started` and begin timing the job
+    pub fn start(service: &str, job_id: Uuid, source_id: &str, trace_id: Option<&str>) -> Self {
+        let job = Self {
+            service: service.to_string(),
+            job_id,
+            source_id: source_id.to_string(),
+            trace_id: trace_id.map(String::from),
+            started: Instant::now(),
+            stage_timings: Vec::new(),
+        };
+        job.event("job_started", serde_json::json!({ "source_id": job.source_id }))
+            .success()
+            .emit();
+        job
+    }
+
+    pub fn job_id(&self) -> Uuid {
+        self.job_id
+    }
+
+    /// Emit `stage_completed` for a pipeline stage (e.g. "fetch", "chunk", "embed")
+    pub fn stage(&mut self, stage: &str, duration_ms: u64) {
+        self.stage_timings.push((stage.to_string(), duration_ms));
+        self.event("stage_completed", serde_json::json!({ "source_id": self.source_id, "stage": stage }))
+            .duration_ms(duration_ms)
+            .success()
+            .emit();
+    }
+
+    /// Emit `job_completed` with the per-stage timings
+    pub fn completed(self, chunks_created: usize) {
+        self.event(
+            "job_completed",
+            serde_json::json!({
+                "source_id": self.source_id,
+                "chunks_created": chunks_created,
+                "stage_timings_ms": self.timings(),
+            }),
+        )
+        .duration_ms(self.elapsed_ms())
+        .success()
         .emit();
+    }
+
+    /// Emit `job_failed` and return the emitted event
+    pub fn failed(self, error: &str) -> DomainEvent {
+        let event = self
+            .event(
+                "job_failed",
+                serde_json::json!({ "source_id": self.source_id, "stage_timings_ms": self.timings() }),
+            )
+            .duration_ms(self.elapsed_ms())
+            .failure(error)
+            .build();
+        event.emit();
+        event
+    }
+
+    fn event(&self, event_type: &str, metadata: serde_json::Value) -> DomainEventBuilder {
+        let mut builder = DomainEvent::new(&self.service, EventCategory::Chunking, event_type)
+            .entity("chunk_job", self.job_id.to_string())
+            .metadata(metadata);
+        if let Some(tid) = &self.trace_id {
+            builder = builder.trace(tid, "");
+        }
+        builder
+    }
+
+    fn timings(&self) -> serde_json::Map<String, serde_json::Value> {
+        self.stage_timings
+            .iter()
+            .map(|(stage, ms)| (stage.clone(), serde_json::json!(ms)))
+            .collect()
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
 }
 
 /// Log document chunking
@@ -426,4 +592,76 @@ mod tests {
         assert_eq!(event.entity_id, Some("123".to_string()));
         assert_eq!(event.result, OperationResult::Success);
     }
+
+    #[test]
+    fn test_failed_sync_emits_failure_event() {
+        let job_id = Uuid::new_v4();
+        let job = SyncJobLifecycle::start("data-service", job_id, "github", Some("trace-abc"));
+
+        let event = job.failed("Failed to fetch installation token");
+
+        assert_eq!(event.event_type, "job_failed");
+        assert_eq!(event.result, OperationResult::Failure);
+        assert_eq!(event.entity_id, Some(job_id.to_string()));
+        assert_eq!(event.error.as_deref(), Some("Failed to fetch installation token"));
+        assert_eq!(event.trace_id.as_deref(), Some("trace-abc"));
+        assert!(event.duration_ms.is_some());
+    }
+
+    #[test]
+    fn test_completed_chunk_job_emits_lifecycle_events() {
+        use std::io::Write;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Captured(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let job_id = Uuid::new_v4();
+        tracing::subscriber::with_default(subscriber, || {
+            let mut job = ChunkJobLifecycle::start("chunker", job_id, "source-42", Some("trace-abc"));
+            job.stage("fetch", 120);
+            job.stage("chunk", 30);
+            job.completed(17);
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<DomainEvent> = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter_map(|log| log["fields"]["message"].as_str()?.strip_prefix("DomainEvent: ").map(String::from))
+            .map(|json| serde_json::from_str(&json).unwrap())
+            .collect();
+
+        let types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, vec!["job_started", "stage_completed", "stage_completed", "job_completed"]);
+        for event in &events {
+            assert!(matches!(event.category, EventCategory::Chunking));
+            assert_eq!(event.entity_type.as_deref(), Some("chunk_job"));
+            assert_eq!(event.entity_id, Some(job_id.to_string()));
+            assert_eq!(event.trace_id.as_deref(), Some("trace-abc"));
+            assert_eq!(event.metadata.as_ref().unwrap()["source_id"], "source-42");
+        }
+
+        let completed = &events[3].metadata.as_ref().unwrap();
+        assert_eq!(completed["chunks_created"], 17);
+        assert_eq!(completed["stage_timings_ms"], serde_json::json!({ "fetch": 120, "chunk": 30 }));
+    }
 }
@@ -6,7 +6,7 @@
 //! - Structured JSON logging with consistent schema
 //! - Distributed trace ID propagation across services
 //! - Domain event logging macros
-//! - HTTP middleware for request/response logging
+//! - HTTP middleware for request/response logging (actix-web, and axum behind the `axum` feature)
 //! - Performance tracking and slow request detection
 
 pub mod trace_context;
@@ -14,11 +14,15 @@ pub mod domain_events;
 pub mod middleware;
 pub mod init;
 pub mod macros;
+#[cfg(feature = "axum")]
+pub mod axum_layer;
 
 pub use trace_context::*;
 pub use domain_events::*;
 pub use middleware::*;
 pub use init::*;
+#[cfg(feature = "axum")]
+pub use axum_layer::{observability_layer, ObservabilityLayer};
 
 // Re-export tracing for convenience
 pub use tracing::{debug, error, info, warn, trace, span, Level, Instrument};
@@ -5,10 +5,11 @@
 //! - Logs requests and responses with structured fields
 //! - Tracks request duration
 //! - Propagates trace context to downstream services
+//! - Echoes the trace ID to clients in an `X-Trace-Id` response header
 
 use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    http::header::HeaderMap,
+    http::header::{HeaderMap, HeaderName, HeaderValue},
     Error, HttpMessage,
 };
 use futures_util::future::LocalBoxFuture;
@@ -20,7 +21,7 @@ use std::{
 };
 use tracing::{info, warn, error, debug, span, Level, Instrument};
 
-use crate::trace_context::TraceContext;
+use crate::trace_context::{with_trace_id, TraceContext, TRACE_ID_HEADER};
 
 /// Configuration for observability middleware
 #[derive(Debug, Clone)]
@@ -185,7 +186,10 @@ where
 
             // Check if path is excluded
             if config.exclude_paths.iter().any(|p| path.starts_with(p)) {
-                return service.call(req).await;
+                let trace_id = TraceContext::from_request(req.request()).trace_id;
+                let mut res = with_trace_id(trace_id.clone(), service.call(req)).await?;
+                echo_trace_id(&mut res, &trace_id);
+                return Ok(res);
             }
 
             // Extract or create trace context
@@ -253,12 +257,15 @@ where
             let start = Instant::now();
 
             // Call the service within the span
-            let result = service.call(req).instrument(request_span).await;
+            let result = with_trace_id(trace_ctx.trace_id.clone(), service.call(req))
+                .instrument(request_span)
+                .await;
 
             let duration_ms = start.elapsed().as_millis() as u64;
 
             match result {
-                Ok(res) => {
+                Ok(mut res) => {
+                    echo_trace_id(&mut res, &trace_ctx.trace_id);
                     let status_code = res.status().as_u16();
 
                     let response_log = HttpResponseLog {
@@ -275,40 +282,14 @@ where
                         tenant_id: trace_ctx.tenant_id.map(|t| t.to_string()),
                     };
 
-                    // Log at appropriate level based on status and duration
-                    if status_code >= 500 {
-                        error!(
-                            trace_id = %trace_ctx.trace_id,
-                            status = status_code,
-                            duration_ms = duration_ms,
-                            "← {} {} {} {}ms",
-                            method, path, status_code, duration_ms
-                        );
-                    } else if status_code >= 400 {
-                        warn!(
-                            trace_id = %trace_ctx.trace_id,
-                            status = status_code,
-                            duration_ms = duration_ms,
-                            "← {} {} {} {}ms",
-                            method, path, status_code, duration_ms
-                        );
-                    } else if duration_ms > config.slow_request_threshold_ms {
-                        warn!(
-                            trace_id = %trace_ctx.trace_id,
-                            status = status_code,
-                            duration_ms = duration_ms,
-                            "← SLOW {} {} {} {}ms",
-                            method, path, status_code, duration_ms
-                        );
-                    } else {
-                        info!(
-                            trace_id = %trace_ctx.trace_id,
-                            status = status_code,
-                            duration_ms = duration_ms,
-                            "← {} {} {} {}ms",
-                            method, path, status_code, duration_ms
-                        );
-                    }
+                    log_response(
+                        &trace_ctx.trace_id,
+                        &method,
+                        &path,
+                        status_code,
+                        duration_ms,
+                        config.slow_request_threshold_ms,
+                    );
 
                     Ok(res)
                 }
@@ -327,6 +308,58 @@ where
     }
 }
 
+/// Log a completed request at a level that reflects its status and duration:
+/// error for 5xx, warn for 4xx or slow responses, info otherwise
+pub(crate) fn log_response(
+    trace_id: &str,
+    method: &str,
+    path: &str,
+    status_code: u16,
+    duration_ms: u64,
+    slow_request_threshold_ms: u64,
+) {
+    if status_code >= 500 {
+        error!(
+            trace_id = %trace_id,
+            status = status_code,
+            duration_ms = duration_ms,
+            "← {} {} {} {}ms",
+            method, path, status_code, duration_ms
+        );
+    } else if status_code >= 400 {
+        warn!(
+            trace_id = %trace_id,
+            status = status_code,
+            duration_ms = duration_ms,
+            "← {} {} {} {}ms",
+            method, path, status_code, duration_ms
+        );
+    } else if duration_ms > slow_request_threshold_ms {
+        warn!(
+            trace_id = %trace_id,
+            status = status_code,
+            duration_ms = duration_ms,
+            "← SLOW {} {} {} {}ms",
+            method, path, status_code, duration_ms
+        );
+    } else {
+        info!(
+            trace_id = %trace_id,
+            status = status_code,
+            duration_ms = duration_ms,
+            "← {} {} {} {}ms",
+            method, path, status_code, duration_ms
+        );
+    }
+}
+
+/// Return the trace ID to the caller so it can be quoted when reporting a problem
+fn echo_trace_id<B>(res: &mut ServiceResponse<B>, trace_id: &str) {
+    if let Ok(value) = HeaderValue::from_str(trace_id) {
+        res.headers_mut().insert(HeaderName::from_static(TRACE_ID_HEADER), value);
+    }
+}
+
 /// Helper to create observability middleware for a service
 pub fn observability(service_name: impl Into<String>) -> ObservabilityMiddleware {
     ObservabilityMiddleware::for_service(service_name)
@@ -339,3 +372,35 @@ pub fn get_trace_context(req: &actix_web::HttpRequest) -> TraceContext {
         .cloned()
         .unwrap_or_else(TraceContext::new)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn test_responses_carry_trace_id_header() {
+        let app = test::init_service(
+            App::new()
+                .wrap(observability("test-service"))
+                .route("/api/items", web::get().to(|| async { HttpResponse::NotFound().finish() }))
+                .route("/health", web::get().to(|| async { HttpResponse::Ok().finish() })),
+        )
+        .await;
+
+        // Generated when the caller didn't send one
+        let res = test::call_service(&app, test::TestRequest::get().uri("/api/items").to_request()).await;
+        let generated = res.headers().get(TRACE_ID_HEADER).expect("trace id header");
+        assert!(!generated.is_empty());
+
+        // Propagated from the caller, including on excluded paths
+        for uri in ["/api/items", "/health"] {
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header((TRACE_ID_HEADER, "trace-123"))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.headers().get(TRACE_ID_HEADER).unwrap(), "trace-123");
+        }
+    }
+}
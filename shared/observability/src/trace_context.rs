@@ -14,6 +14,21 @@ pub const PARENT_SPAN_ID_HEADER: &str = "x-parent-span-id";
 pub const REQUEST_ID_HEADER: &str = "x-request-id";
 pub const W3C_TRACEPARENT_HEADER: &str = "traceparent";
 
+tokio::task_local! {
+    static CURRENT_TRACE_ID: String;
+}
+
+/// Trace ID of the request being handled on this task, set by the observability
+/// middleware. Lets code without access to the request (e.g. error mappers) report it.
+pub fn current_trace_id() -> Option<String> {
+    CURRENT_TRACE_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Run `future` with `trace_id` visible to `current_trace_id`
+pub async fn with_trace_id<F: std::future::Future>(trace_id: String, future: F) -> F::Output {
+    CURRENT_TRACE_ID.scope(trace_id, future).await
+}
+
 /// Trace context containing IDs for distributed tracing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraceContext {
@@ -83,33 +98,23 @@ impl TraceContext {
     /// Extract trace context from HTTP request headers
     pub fn from_request(req: &HttpRequest) -> Self {
         let headers = req.headers();
+        Self::from_header_lookup(|name| headers.get(name).and_then(|h| h.to_str().ok()).map(String::from))
+    }
 
+    /// Extract trace context through a header lookup, for frameworks whose
+    /// request type isn't actix's
+    pub fn from_header_lookup(get: impl Fn(&str) -> Option<String>) -> Self {
         // Try W3C traceparent first
-        if let Some(traceparent) = headers.get(W3C_TRACEPARENT_HEADER) {
-            if let Ok(tp) = traceparent.to_str() {
-                if let Some(ctx) = Self::parse_traceparent(tp) {
-                    return ctx;
-                }
+        if let Some(traceparent) = get(W3C_TRACEPARENT_HEADER) {
+            if let Some(ctx) = Self::parse_traceparent(&traceparent) {
+                return ctx;
             }
         }
 
         // Fall back to custom headers
-        let trace_id = headers
-            .get(TRACE_ID_HEADER)
-            .and_then(|h| h.to_str().ok())
-            .map(String::from)
-            .unwrap_or_else(|| Uuid::new_v4().to_string());
-
-        let request_id = headers
-            .get(REQUEST_ID_HEADER)
-            .and_then(|h| h.to_str().ok())
-            .map(String::from)
-            .unwrap_or_else(|| trace_id.clone());
-
-        let parent_span_id = headers
-            .get(SPAN_ID_HEADER)
-            .and_then(|h| h.to_str().ok())
-            .map(String::from);
+        let trace_id = get(TRACE_ID_HEADER).unwrap_or_else(|| Uuid::new_v4().to_string());
+        let request_id = get(REQUEST_ID_HEADER).unwrap_or_else(|| trace_id.clone());
+        let parent_span_id = get(SPAN_ID_HEADER);
 
         Self {
             trace_id,
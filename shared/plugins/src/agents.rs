@@ -1,7 +1,11 @@
-use crate::{Plugin, PluginResult};
+use crate::{error::PluginError, Plugin, PluginResult};
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
 
 /// Agent message
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +58,8 @@ pub struct AgentCapabilities {
 /// Conversation context
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationContext {
+    /// Owner of the conversation; history is scoped to it
+    pub user_id: String,
     pub conversation_id: String,
     pub messages: Vec<AgentMessage>,
     pub workspace_path: Option<String>,
@@ -61,6 +67,142 @@ pub struct ConversationContext {
     pub user_preferences: HashMap<String, serde_json::Value>,
 }
 
+/// Conversation history kept by agent plugins between messages, scoped to
+/// `(user_id, conversation_id)` so users whose conversation ids collide never
+/// see each other's messages
+#[derive(Debug, Default)]
+pub struct ConversationStore {
+    histories: tokio::sync::RwLock<HashMap<(String, String), Vec<AgentMessage>>>,
+}
+
+impl ConversationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Messages exchanged so far in the context's conversation
+    pub async fn history(&self, context: &ConversationContext) -> Vec<AgentMessage> {
+        self.histories
+            .read()
+            .await
+            .get(&Self::key(context))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub async fn append(&self, context: &ConversationContext, message: AgentMessage) {
+        self.histories.write().await.entry(Self::key(context)).or_default().push(message);
+    }
+
+    pub async fn clear(&self, context: &ConversationContext) {
+        self.histories.write().await.remove(&Self::key(context));
+    }
+
+    fn key(context: &ConversationContext) -> (String, String) {
+        (context.user_id.clone(), context.conversation_id.clone())
+    }
+}
+
+/// What to do with the oldest turns once a conversation outgrows its budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryStrategy {
+    /// Drop them
+    Truncate,
+    /// Replace them with a model-written summary message
+    Summarize,
+}
+
+/// How much conversation history an agent sends to its model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPolicy {
+    /// Histories longer than this are compacted
+    pub max_messages: usize,
+    /// Most recent messages always kept verbatim
+    pub keep_recent: usize,
+    pub strategy: HistoryStrategy,
+}
+
+impl Default for HistoryPolicy {
+    fn default() -> Self {
+        Self {
+            max_messages: 40,
+            keep_recent: 20,
+            strategy: HistoryStrategy::Truncate,
+        }
+    }
+}
+
+impl HistoryPolicy {
+    /// Read `history_max_messages`, `history_keep_recent` and `history_strategy`
+    /// (`truncate` or `summarize`) from plugin settings
+    pub fn from_settings(settings: &HashMap<String, serde_json::Value>) -> Self {
+        let defaults = Self::default();
+        let max_messages = settings.get("history_max_messages")
+            .and_then(|v| v.as_u64())
+            .map(|n| n.max(1) as usize)
+            .unwrap_or(defaults.max_messages);
+        Self {
+            max_messages,
+            keep_recent: settings.get("history_keep_recent")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+                .unwrap_or(defaults.keep_recent)
+                .min(max_messages),
+            strategy: settings.get("history_strategy")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(defaults.strategy),
+        }
+    }
+
+    /// Fit `messages` within the policy. With `Summarize`, the turns before the
+    /// most recent `keep_recent` are compressed by `summarizer` into one system
+    /// message placed first; if no summarizer is given or it fails, they are
+    /// dropped as with `Truncate`.
+    pub async fn apply(
+        &self,
+        mut messages: Vec<AgentMessage>,
+        summarizer: Option<&dyn HistorySummarizer>,
+    ) -> Vec<AgentMessage> {
+        if messages.len() <= self.max_messages {
+            return messages;
+        }
+        let recent = messages.split_off(messages.len().saturating_sub(self.keep_recent));
+        let older = messages;
+
+        if self.strategy == HistoryStrategy::Summarize {
+            if let Some(summarizer) = summarizer {
+                match summarizer.summarize(&older).await {
+                    Ok(summary) => {
+                        let mut fitted = Vec::with_capacity(recent.len() + 1);
+                        fitted.push(AgentMessage {
+                            id: uuid::Uuid::new_v4().to_string(),
+                            content: summary,
+                            role: MessageRole::System,
+                            timestamp: older.last().map(|m| m.timestamp).unwrap_or_else(chrono::Utc::now),
+                            metadata: HashMap::from([
+                                ("summary".to_string(), serde_json::Value::Bool(true)),
+                                ("summarized_messages".to_string(), serde_json::json!(older.len())),
+                            ]),
+                        });
+                        fitted.extend(recent);
+                        return fitted;
+                    }
+                    Err(e) => tracing::warn!("History summarization failed, truncating instead: {}", e),
+                }
+            }
+        }
+
+        recent
+    }
+}
+
+/// Compresses older conversation turns, usually by calling the agent's own model
+#[async_trait]
+pub trait HistorySummarizer: Send + Sync {
+    async fn summarize(&self, messages: &[AgentMessage]) -> PluginResult<String>;
+}
+
 /// Agent plugin trait
 #[async_trait]
 pub trait AgentPlugin: Plugin {
@@ -96,6 +238,84 @@ pub struct AgentFunction {
     pub parameters: serde_json::Value, // JSON Schema
 }
 
+impl AgentFunction {
+    /// Check action parameters against this function's JSON schema, so a
+    /// malformed call is rejected before it reaches the plugin. Supports `type`,
+    /// `properties`, `required`, `additionalProperties: false`, `enum` and `items`.
+    pub fn validate_parameters(&self, parameters: &HashMap<String, serde_json::Value>) -> PluginResult<()> {
+        let value = serde_json::Value::Object(parameters.clone().into_iter().collect());
+        let mut errors = Vec::new();
+        check_schema(&self.parameters, &value, "parameters", &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PluginError::ValidationError(format!("Invalid call to '{}': {}", self.name, errors.join("; "))))
+        }
+    }
+}
+
+fn matches_type(expected: &str, value: &serde_json::Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn check_schema(schema: &serde_json::Value, value: &serde_json::Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(serde_json::Value::String(t)) => vec![t.as_str()],
+        Some(serde_json::Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|t| matches_type(t, value)) {
+        errors.push(format!("{} must be of type {}", path, types.join(" or ")));
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            errors.push(format!("{} must be one of {}", path, serde_json::Value::Array(allowed.clone())));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        for required in schema.get("required").and_then(|r| r.as_array()).into_iter().flatten() {
+            if let Some(name) = required.as_str() {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}.{} is required", path, name));
+                }
+            }
+        }
+        for (name, field) in object {
+            match properties.and_then(|p| p.get(name)) {
+                Some(field_schema) => check_schema(field_schema, field, &format!("{}.{}", path, name), errors),
+                None if schema.get("additionalProperties") == Some(&serde_json::Value::Bool(false)) => {
+                    errors.push(format!("{}.{} is not a recognized parameter", path, name));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            check_schema(items, item, &format!("{}[{}]", path, i), errors);
+        }
+    }
+}
+
 /// Streaming response chunk
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentResponseChunk {
@@ -112,8 +332,418 @@ pub enum ChunkType {
     Complete,
 }
 
+impl ChunkType {
+    /// SSE event name for this chunk type
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            ChunkType::Text => "token",
+            ChunkType::Action => "action",
+            ChunkType::Error => "error",
+            ChunkType::Complete => "done",
+        }
+    }
+
+    /// Whether the stream ends after this chunk
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ChunkType::Error | ChunkType::Complete)
+    }
+}
+
+impl AgentResponseChunk {
+    /// Render as a server-sent event frame
+    pub fn to_sse(&self) -> String {
+        let data = serde_json::json!({
+            "content": self.content,
+            "metadata": self.metadata,
+        });
+        format!("event: {}\ndata: {}\n\n", self.chunk_type.event_name(), data)
+    }
+}
+
+/// SSE frames of an agent's streamed response, ready for an HTTP
+/// `text/event-stream` body
+pub type SseStream = Pin<Box<dyn Stream<Item = Result<Bytes, Infallible>> + Send>>;
+
+/// Forward `stream_response` chunks as SSE frames, ending after the `done` or
+/// `error` frame, or when the plugin stops sending. The stream owns the
+/// receiver, so dropping it when the client disconnects makes the plugin's
+/// next send fail and its producer task exit.
+pub fn sse_stream(rx: tokio::sync::mpsc::Receiver<AgentResponseChunk>) -> SseStream {
+    Box::pin(futures_util::stream::unfold(Some(rx), |rx| async move {
+        let mut rx = rx?;
+        let chunk = rx.recv().await?;
+        let frame = Bytes::from(chunk.to_sse());
+        // Drop the receiver once the response is complete
+        let rx = (!chunk.chunk_type.is_terminal()).then_some(rx);
+        Some((Ok(frame), rx))
+    }))
+}
+
+/// Phrases after which a model's bullet points are read as suggestions
+const SUGGESTION_PHRASES: &[&str] = &["i suggest", "you might want to"];
+
+/// A file the model wrote out in full, from a fence like ```` ```rust:src/main.rs ````
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEdit {
+    pub path: String,
+    pub language: Option<String>,
+    pub content: String,
+}
+
+/// Commands, file edits and suggestions found in a coding agent's reply
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParsedAgentOutput {
+    /// One entry per command line of the ```` ```bash ```` / ```` ```sh ```` blocks
+    pub commands: Vec<String>,
+    pub file_edits: Vec<FileEdit>,
+    pub suggestions: Vec<String>,
+}
+
+impl ParsedAgentOutput {
+    /// `run_command`, `modify_file` and `suggestion` actions, in that order
+    pub fn to_actions(&self) -> Vec<AgentAction> {
+        let commands = self.commands.iter().map(|command| AgentAction {
+            action_type: "run_command".to_string(),
+            parameters: HashMap::from([("command".to_string(), serde_json::json!(command))]),
+            description: format!("Run `{}`", command),
+        });
+        let edits = self.file_edits.iter().map(|edit| {
+            let mut parameters = HashMap::from([
+                ("path".to_string(), serde_json::json!(edit.path)),
+                ("content".to_string(), serde_json::json!(edit.content)),
+            ]);
+            if let Some(language) = &edit.language {
+                parameters.insert("language".to_string(), serde_json::json!(language));
+            }
+            AgentAction {
+                action_type: "modify_file".to_string(),
+                parameters,
+                description: format!("Update {}", edit.path),
+            }
+        });
+        let suggestions = self.suggestions.iter().map(|suggestion| AgentAction {
+            action_type: "suggestion".to_string(),
+            parameters: HashMap::from([("text".to_string(), serde_json::json!(suggestion))]),
+            description: suggestion.clone(),
+        });
+        commands.chain(edits).chain(suggestions).collect()
+    }
+}
+
+/// Parse a coding agent's markdown reply. Shell fences become commands, with
+/// `$ ` prompts, comments and `\` continuations handled; fences whose info
+/// string is `language:path` become file edits; bullet points following "I
+/// suggest" or "You might want to" become suggestions. Other fences are
+/// ignored, as is an unterminated fence.
+pub fn parse_agent_output(text: &str) -> ParsedAgentOutput {
+    let mut output = ParsedAgentOutput::default();
+    let mut lines = text.lines();
+    let mut in_suggestions = false;
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            in_suggestions = false;
+            let mut body = Vec::new();
+            let mut closed = false;
+            for line in lines.by_ref() {
+                if line.trim() == "```" {
+                    closed = true;
+                    break;
+                }
+                body.push(line);
+            }
+            if closed {
+                parse_fence(info.trim(), &body, &mut output);
+            }
+            continue;
+        }
+
+        let lowered = trimmed.to_lowercase();
+        if let Some(phrase) = SUGGESTION_PHRASES.iter().find(|p| lowered.contains(*p)) {
+            in_suggestions = true;
+            // Take an inline suggestion when it isn't just introducing a list
+            let start = lowered.find(phrase).unwrap_or(0) + phrase.len();
+            let rest = trimmed.get(start..).unwrap_or("").trim();
+            if !rest.is_empty() && !rest.ends_with(':') {
+                output.suggestions.push(trimmed.to_string());
+            }
+        } else if in_suggestions {
+            match bullet_text(trimmed) {
+                Some(item) => output.suggestions.push(item.to_string()),
+                None if trimmed.is_empty() => {}
+                None => in_suggestions = false,
+            }
+        }
+    }
+    output
+}
+
+fn parse_fence(info: &str, body: &[&str], output: &mut ParsedAgentOutput) {
+    if let Some((language, path)) = info.split_once(':') {
+        let path = path.trim();
+        if !path.is_empty() {
+            let language = language.trim();
+            output.file_edits.push(FileEdit {
+                path: path.to_string(),
+                language: (!language.is_empty()).then(|| language.to_string()),
+                content: body.iter().map(|l| format!("{}\n", l)).collect(),
+            });
+        }
+        return;
+    }
+
+    if !matches!(info, "bash" | "sh" | "shell" | "zsh" | "console") {
+        return;
+    }
+    let mut pending = String::new();
+    for line in body {
+        let line = line.trim();
+        let line = line.strip_prefix("$ ").unwrap_or(line);
+        if pending.is_empty() && (line.is_empty() || line.starts_with('#')) {
+            continue;
+        }
+        match line.strip_suffix('\\') {
+            Some(continued) => {
+                pending.push_str(continued.trim_end());
+                pending.push(' ');
+            }
+            None => {
+                pending.push_str(line);
+                output.commands.push(std::mem::take(&mut pending).trim().to_string());
+            }
+        }
+    }
+    if !pending.trim().is_empty() {
+        output.commands.push(pending.trim().to_string());
+    }
+}
+
+/// Text of a `-`, `*` or numbered list item
+fn bullet_text(line: &str) -> Option<&str> {
+    if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return Some(item.trim());
+    }
+    let (number, item) = line.split_once(". ")?;
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())).then(|| item.trim())
+}
+
 /// Agent plugin factory
 pub trait AgentPluginFactory: Send + Sync {
     fn create(&self) -> Box<dyn AgentPlugin>;
     fn agent_type(&self) -> &str;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(user_id: &str, conversation_id: &str) -> ConversationContext {
+        ConversationContext {
+            user_id: user_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            messages: Vec::new(),
+            workspace_path: None,
+            active_files: Vec::new(),
+            user_preferences: HashMap::new(),
+        }
+    }
+
+    fn message(content: &str) -> AgentMessage {
+        AgentMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            role: MessageRole::User,
+            timestamp: chrono::Utc::now(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Joins the summarized turns so tests can see what was compressed
+    struct JoiningSummarizer;
+
+    #[async_trait]
+    impl HistorySummarizer for JoiningSummarizer {
+        async fn summarize(&self, messages: &[AgentMessage]) -> PluginResult<String> {
+            let turns: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+            Ok(format!("Earlier: {}", turns.join(", ")))
+        }
+    }
+
+    struct FailingSummarizer;
+
+    #[async_trait]
+    impl HistorySummarizer for FailingSummarizer {
+        async fn summarize(&self, _messages: &[AgentMessage]) -> PluginResult<String> {
+            Err(PluginError::NetworkError("model unavailable".to_string()))
+        }
+    }
+
+    fn turns(n: usize) -> Vec<AgentMessage> {
+        (0..n).map(|i| message(&format!("turn {}", i))).collect()
+    }
+
+    #[tokio::test]
+    async fn test_long_history_is_summarized_and_summary_is_kept() {
+        let settings = HashMap::from([
+            ("history_max_messages".to_string(), serde_json::json!(6)),
+            ("history_keep_recent".to_string(), serde_json::json!(3)),
+            ("history_strategy".to_string(), serde_json::json!("summarize")),
+        ]);
+        let policy = HistoryPolicy::from_settings(&settings);
+        assert_eq!(policy.strategy, HistoryStrategy::Summarize);
+
+        let fitted = policy.apply(turns(8), Some(&JoiningSummarizer)).await;
+        assert_eq!(fitted.len(), 4);
+        assert!(matches!(fitted[0].role, MessageRole::System));
+        assert_eq!(fitted[0].content, "Earlier: turn 0, turn 1, turn 2, turn 3, turn 4");
+        assert_eq!(fitted[0].metadata["summarized_messages"], 5);
+        let recent: Vec<&str> = fitted[1..].iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(recent, vec!["turn 5", "turn 6", "turn 7"]);
+
+        // Short histories are left alone
+        assert_eq!(policy.apply(turns(6), Some(&JoiningSummarizer)).await.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_truncation_is_used_when_configured_or_summarizing_fails() {
+        let truncate = HistoryPolicy { max_messages: 6, keep_recent: 3, strategy: HistoryStrategy::Truncate };
+        let fitted = truncate.apply(turns(8), Some(&JoiningSummarizer)).await;
+        assert_eq!(fitted.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), vec!["turn 5", "turn 6", "turn 7"]);
+
+        let summarize = HistoryPolicy { strategy: HistoryStrategy::Summarize, ..truncate };
+        assert_eq!(summarize.apply(turns(8), Some(&FailingSummarizer)).await.len(), 3);
+        assert_eq!(summarize.apply(turns(8), None).await.len(), 3);
+    }
+
+    #[test]
+    fn test_function_parameters_are_checked_against_schema() {
+        let function = AgentFunction {
+            name: "search_code".to_string(),
+            description: "Search indexed code".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "limit": { "type": "integer" },
+                    "languages": { "type": "array", "items": { "type": "string", "enum": ["rust", "go"] } }
+                },
+                "required": ["query"],
+                "additionalProperties": false
+            }),
+        };
+        let params = |value: serde_json::Value| -> HashMap<String, serde_json::Value> {
+            serde_json::from_value(value).unwrap()
+        };
+
+        assert!(function.validate_parameters(&params(serde_json::json!({ "query": "fn main", "languages": ["rust"] }))).is_ok());
+
+        let err = function
+            .validate_parameters(&params(serde_json::json!({ "limit": "ten", "languages": ["cobol"], "scope": "all" })))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("parameters.query is required"));
+        assert!(err.contains("parameters.limit must be of type integer"));
+        assert!(err.contains("parameters.languages[0] must be one of"));
+        assert!(err.contains("parameters.scope is not a recognized parameter"));
+    }
+
+    #[tokio::test]
+    async fn test_same_conversation_id_is_isolated_per_user() {
+        let store = ConversationStore::new();
+        let alice = context("alice", "chat-1");
+        let bob = context("bob", "chat-1");
+
+        store.append(&alice, message("my API key rotation plan")).await;
+        store.append(&bob, message("deploy checklist")).await;
+        store.append(&alice, message("follow up")).await;
+
+        let alice_history: Vec<String> = store.history(&alice).await.into_iter().map(|m| m.content).collect();
+        let bob_history: Vec<String> = store.history(&bob).await.into_iter().map(|m| m.content).collect();
+        assert_eq!(alice_history, vec!["my API key rotation plan", "follow up"]);
+        assert_eq!(bob_history, vec!["deploy checklist"]);
+
+        store.clear(&bob).await;
+        assert!(store.history(&bob).await.is_empty());
+        assert_eq!(store.history(&alice).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sse_stream_ends_on_done_and_disconnect_stops_producer() {
+        use futures_util::StreamExt;
+
+        let chunk = |chunk_type, content: &str| AgentResponseChunk { chunk_type, content: content.to_string(), metadata: None };
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        for c in [chunk(ChunkType::Text, "hi"), chunk(ChunkType::Complete, ""), chunk(ChunkType::Text, "late")] {
+            tx.send(c).await.unwrap();
+        }
+        let frames: Vec<Bytes> = sse_stream(rx).map(|f| f.unwrap()).collect().await;
+        assert_eq!(frames.len(), 2);
+        assert!(frames[1].starts_with(b"event: done\n"));
+        assert!(tx.is_closed());
+
+        // A producer that never finishes exits once the client goes away
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let producer = tokio::spawn(async move {
+            let mut sent = 0;
+            while tx.send(chunk(ChunkType::Text, "token")).await.is_ok() {
+                sent += 1;
+            }
+            sent
+        });
+        let mut stream = sse_stream(rx);
+        assert!(stream.next().await.unwrap().unwrap().starts_with(b"event: token\n"));
+        drop(stream);
+
+        let sent = tokio::time::timeout(std::time::Duration::from_secs(1), producer).await.unwrap().unwrap();
+        assert!(sent >= 1);
+    }
+
+    #[test]
+    fn test_agent_output_is_parsed_into_actions() {
+        let reply = "\
+The handler panics on an empty body. Here's the fix:
+
+```rust:src/main.rs
+fn main() {
+    println!(\"hello\");
+}
+```
+
+Then rebuild and run the tests:
+
+```bash
+# from the repo root
+$ cargo build
+cargo test --workspace \\
+    --all-features
+```
+
+```python
+print(\"not a command\")
+```
+
+You might want to:
+- add a regression test
+- log the request id
+
+That's all.
+- not a suggestion
+";
+        let parsed = parse_agent_output(reply);
+
+        assert_eq!(parsed.commands, ["cargo build", "cargo test --workspace --all-features"]);
+        assert_eq!(parsed.file_edits, [FileEdit {
+            path: "src/main.rs".to_string(),
+            language: Some("rust".to_string()),
+            content: "fn main() {\n    println!(\"hello\");\n}\n".to_string(),
+        }]);
+        assert_eq!(parsed.suggestions, ["add a regression test", "log the request id"]);
+
+        let actions = parsed.to_actions();
+        let types: Vec<&str> = actions.iter().map(|a| a.action_type.as_str()).collect();
+        assert_eq!(types, ["run_command", "run_command", "modify_file", "suggestion", "suggestion"]);
+        assert_eq!(actions[2].parameters["path"], "src/main.rs");
+        assert_eq!(actions[2].parameters["content"], parsed.file_edits[0].content.as_str());
+    }
+}
@@ -106,6 +106,7 @@ impl PluginConfigManager {
                 settings: HashMap::from([
                     ("access_token".to_string(), serde_json::Value::String("".to_string())),
                     ("sync_interval_minutes".to_string(), serde_json::Value::Number(serde_json::Number::from(30))),
+                    ("max_documents".to_string(), serde_json::Value::Number(serde_json::Number::from(crate::dropbox::DEFAULT_MAX_DOCUMENTS))),
                 ]),
             },
             metadata: None,
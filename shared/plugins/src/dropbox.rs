@@ -0,0 +1,378 @@
+use crate::{
+    error::PluginError,
+    oauth::OAuthSession,
+    sources::{Document, DocumentListing},
+    PluginResult,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+pub const DROPBOX_API_URL: &str = "https://api.dropboxapi.com";
+pub const DROPBOX_CONTENT_URL: &str = "https://content.dropboxapi.com";
+pub const DROPBOX_TOKEN_URL: &str = "https://api.dropboxapi.com/oauth2/token";
+/// Most files one listing returns unless `max_documents` says otherwise
+pub const DEFAULT_MAX_DOCUMENTS: usize = 50_000;
+/// Entries requested per `list_folder` page (Dropbox allows up to 2000)
+const LIST_PAGE_SIZE: u32 = 2000;
+
+/// JSON schema of the Dropbox source's settings
+pub fn dropbox_config_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "access_token": { "type": "string" },
+            "refresh_token": { "type": "string" },
+            "client_id": { "type": "string" },
+            "client_secret": { "type": "string" },
+            "sync_interval_minutes": { "type": "integer", "minimum": 1 },
+            "max_documents": {
+                "type": "integer",
+                "minimum": 1,
+                "default": DEFAULT_MAX_DOCUMENTS,
+                "description": "Most files listed from the account; the rest are skipped with a warning"
+            },
+            "include_globs": { "type": "array", "items": { "type": "string" } },
+            "exclude_globs": { "type": "array", "items": { "type": "string" } }
+        },
+        "required": ["access_token"]
+    })
+}
+
+/// `max_documents` from plugin settings, or `DEFAULT_MAX_DOCUMENTS`
+pub fn max_documents_from_settings(settings: &HashMap<String, serde_json::Value>) -> usize {
+    settings
+        .get("max_documents")
+        .and_then(|v| v.as_u64())
+        .map(|n| n.max(1) as usize)
+        .unwrap_or(DEFAULT_MAX_DOCUMENTS)
+}
+
+/// Dropbox file lookups for the Dropbox source: folder listings and metadata
+/// as `Document`s, and raw file content. A path or id that doesn't exist is `PluginError::NotFound`,
+/// so callers can tell a missing file from a failed request.
+#[derive(Debug)]
+pub struct DropboxApi {
+    session: OAuthSession,
+    api_url: String,
+    content_url: String,
+}
+
+impl DropboxApi {
+    pub fn new(session: OAuthSession) -> Self {
+        Self::with_base_urls(session, DROPBOX_API_URL, DROPBOX_CONTENT_URL)
+    }
+
+    pub fn with_base_urls(session: OAuthSession, api_url: &str, content_url: &str) -> Self {
+        Self {
+            session,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            content_url: content_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Metadata for a path (`/Docs/plan.md`) or id (`id:...`) from `/2/files/get_metadata`
+    pub async fn get_metadata(&self, path: &str) -> PluginResult<Document> {
+        let entry = self.post_json("/2/files/get_metadata", json!({ "path": path }), path).await?;
+        parse_dropbox_entry(&entry)
+    }
+
+    /// Every file under `path` (`""` for the whole account), following
+    /// `list_folder/continue` until Dropbox reports no more. Stops after
+    /// `max_documents` files with a warning, so a huge account can't exhaust memory.
+    pub async fn list_folder(&self, path: &str, max_documents: usize) -> PluginResult<DocumentListing> {
+        let first = self
+            .post_json(
+                "/2/files/list_folder",
+                json!({ "path": path, "recursive": true, "limit": LIST_PAGE_SIZE }),
+                path,
+            )
+            .await?;
+        let (results, truncated) = collect_pages(
+            first,
+            |cursor| async move {
+                self.post_json("/2/files/list_folder/continue", json!({ "cursor": cursor }), path).await
+            },
+            max_documents,
+        )
+        .await?;
+
+        if truncated {
+            tracing::warn!(
+                "Dropbox listing of '{}' stopped at max_documents ({}); remaining files are skipped",
+                path,
+                max_documents
+            );
+        }
+        Ok(DocumentListing::collect("dropbox", results))
+    }
+
+    /// Raw bytes of a file from `/2/files/download`
+    pub async fn download(&self, path: &str) -> PluginResult<Vec<u8>> {
+        let url = format!("{}/2/files/download", self.content_url);
+        let arg = api_arg(&json!({ "path": path }));
+        let response = self
+            .session
+            .send(|http| http.post(&url).header("Dropbox-API-Arg", arg.as_str()))
+            .await?;
+        let response = check_response(response, path).await?;
+
+        response
+            .bytes()
+            .await
+            .map(|b| b.to_vec())
+            .map_err(|e| PluginError::NetworkError(format!("Dropbox download failed: {}", e)))
+    }
+
+    async fn post_json(&self, endpoint: &str, body: serde_json::Value, path: &str) -> PluginResult<serde_json::Value> {
+        let url = format!("{}{}", self.api_url, endpoint);
+        let response = self.session.send(|http| http.post(&url).json(&body)).await?;
+        check_response(response, path)
+            .await?
+            .json()
+            .await
+            .map_err(|e| PluginError::NetworkError(format!("Invalid Dropbox response from {}: {}", endpoint, e)))
+    }
+}
+
+/// Convert the file entries of `first` and each page fetched by `next_page`
+/// with the previous page's cursor, until a page has no more or `max_documents`
+/// files are collected. Returns the converted entries and whether the cap was hit.
+async fn collect_pages<F, Fut>(
+    first: serde_json::Value,
+    mut next_page: F,
+    max_documents: usize,
+) -> PluginResult<(Vec<(String, PluginResult<Document>)>, bool)>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = PluginResult<serde_json::Value>>,
+{
+    let mut results = Vec::new();
+    let mut page = first;
+    loop {
+        let entries = page["entries"].as_array().map(Vec::as_slice).unwrap_or_default();
+        for entry in entries.iter().filter(|e| e[".tag"] == "file") {
+            if results.len() >= max_documents {
+                return Ok((results, true));
+            }
+            let id = entry["id"].as_str().or(entry["path_display"].as_str()).unwrap_or_default().to_string();
+            results.push((id, parse_dropbox_entry(entry)));
+        }
+
+        let cursor = page["cursor"].as_str().filter(|_| page["has_more"].as_bool() == Some(true));
+        let Some(cursor) = cursor else {
+            return Ok((results, false));
+        };
+        if results.len() >= max_documents {
+            return Ok((results, true));
+        }
+        page = next_page(cursor.to_string()).await?;
+    }
+}
+
+/// Convert a Dropbox file or folder metadata entry
+pub fn parse_dropbox_entry(entry: &serde_json::Value) -> PluginResult<Document> {
+    let tag = entry[".tag"].as_str().unwrap_or("file");
+    let name = entry["name"]
+        .as_str()
+        .ok_or_else(|| PluginError::ValidationError("Dropbox entry has no name".to_string()))?;
+    if tag == "deleted" {
+        return Err(PluginError::NotFound(format!("Dropbox entry '{}' was deleted", name)));
+    }
+    let id = entry["id"]
+        .as_str()
+        .ok_or_else(|| PluginError::ValidationError(format!("Dropbox entry '{}' has no id", name)))?;
+
+    let timestamp = |key: &str| {
+        entry[key]
+            .as_str()
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&chrono::Utc))
+    };
+    let modified_at = timestamp("server_modified").unwrap_or_else(chrono::Utc::now);
+
+    let mut metadata = HashMap::from([("dropbox_tag".to_string(), json!(tag))]);
+    for key in ["rev", "content_hash", "path_lower"] {
+        if let Some(value) = entry.get(key).filter(|v| !v.is_null()) {
+            metadata.insert(key.to_string(), value.clone());
+        }
+    }
+
+    Ok(Document {
+        id: id.to_string(),
+        title: name.to_string(),
+        content: String::new(),
+        content_type: if tag == "folder" {
+            "application/vnd.dropbox.folder".to_string()
+        } else {
+            content_type_for(name).to_string()
+        },
+        size: entry["size"].as_u64().unwrap_or(0),
+        created_at: timestamp("client_modified").unwrap_or(modified_at),
+        modified_at,
+        path: entry["path_display"].as_str().unwrap_or(name).to_string(),
+        metadata,
+        // Dropbox's content_hash is its own block hash, not a SHA-256 of the bytes
+        content_hash: None,
+    })
+}
+
+async fn check_response(response: reqwest::Response, path: &str) -> PluginResult<reqwest::Response> {
+    let status = response.status().as_u16();
+    if (200..300).contains(&status) {
+        return Ok(response);
+    }
+    let retry_after = response
+        .headers()
+        .get("Retry-After")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok());
+    let body = response.text().await.unwrap_or_default();
+    Err(status_error(status, &body, path, retry_after))
+}
+
+/// Map a failed Dropbox response. Endpoint errors come back as 409 with an
+/// `error_summary` like `path/not_found/..`.
+fn status_error(status: u16, body: &str, path: &str, retry_after_secs: Option<u64>) -> PluginError {
+    match status {
+        409 => {
+            let summary = serde_json::from_str::<serde_json::Value>(body)
+                .ok()
+                .and_then(|v| v["error_summary"].as_str().map(str::to_string))
+                .unwrap_or_else(|| body.to_string());
+            if summary.starts_with("path/not_found") || summary.starts_with("path_lookup/not_found") {
+                PluginError::NotFound(format!("Dropbox path not found: {}", path))
+            } else {
+                PluginError::RuntimeError(format!("Dropbox error for {}: {}", path, summary))
+            }
+        }
+        401 => PluginError::AuthenticationError(format!("Dropbox rejected the access token: {}", body)),
+        403 => PluginError::PermissionError(format!("Dropbox denied access to {}: {}", path, body)),
+        429 => PluginError::RateLimited(Duration::from_secs(retry_after_secs.unwrap_or(1))),
+        _ => PluginError::NetworkError(format!("Dropbox API error {}: {}", status, body)),
+    }
+}
+
+/// JSON for the `Dropbox-API-Arg` header. HTTP headers must be ASCII, so
+/// other characters are sent as `\u` escapes.
+fn api_arg(arg: &serde_json::Value) -> String {
+    let mut escaped = String::new();
+    for c in arg.to_string().chars() {
+        if c.is_ascii() {
+            escaped.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                escaped.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    escaped
+}
+
+fn content_type_for(name: &str) -> &'static str {
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "pdf" => "application/pdf",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_entry_becomes_document() {
+        let entry = json!({
+            ".tag": "file",
+            "id": "id:a4ayc_80_OEAAAAAAAAAXw",
+            "name": "Roadmap.md",
+            "path_display": "/Projects/Roadmap.md",
+            "path_lower": "/projects/roadmap.md",
+            "rev": "a1c10ce0dd78",
+            "size": 7212,
+            "client_modified": "2024-03-01T10:00:00Z",
+            "server_modified": "2024-03-02T12:30:00Z",
+            "content_hash": "e3b0c442"
+        });
+        let doc = parse_dropbox_entry(&entry).unwrap();
+
+        assert_eq!(doc.id, "id:a4ayc_80_OEAAAAAAAAAXw");
+        assert_eq!(doc.path, "/Projects/Roadmap.md");
+        assert_eq!(doc.content_type, "text/markdown");
+        assert_eq!(doc.size, 7212);
+        assert_eq!(doc.modified_at.to_rfc3339(), "2024-03-02T12:30:00+00:00");
+        assert_eq!(doc.metadata["rev"], "a1c10ce0dd78");
+
+        let folder = parse_dropbox_entry(&json!({ ".tag": "folder", "id": "id:f", "name": "Projects" })).unwrap();
+        assert_eq!(folder.content_type, "application/vnd.dropbox.folder");
+        assert!(matches!(
+            parse_dropbox_entry(&json!({ ".tag": "deleted", "name": "old.txt" })),
+            Err(PluginError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_listing_follows_cursor_until_has_more_is_false() {
+        let file = |n: u32| json!({ ".tag": "file", "id": format!("id:{}", n), "name": format!("{}.txt", n), "path_display": format!("/{}.txt", n) });
+        let pages = HashMap::from([
+            ("c1".to_string(), json!({ "entries": [file(3), file(4)], "cursor": "c2", "has_more": true })),
+            ("c2".to_string(), json!({ "entries": [file(5)], "cursor": "c3", "has_more": false })),
+        ]);
+        let first = json!({
+            "entries": [file(1), { ".tag": "folder", "id": "id:dir", "name": "dir" }, file(2)],
+            "cursor": "c1",
+            "has_more": true
+        });
+        let mut requested = Vec::new();
+
+        let (results, truncated) = collect_pages(
+            first.clone(),
+            |cursor| {
+                requested.push(cursor.clone());
+                let page = pages[&cursor].clone();
+                async move { Ok(page) }
+            },
+            100,
+        )
+        .await
+        .unwrap();
+        let ids: Vec<&str> = results.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["id:1", "id:2", "id:3", "id:4", "id:5"]);
+        assert!(!truncated);
+        assert_eq!(requested, ["c1", "c2"]);
+
+        // The cap stops paging early
+        let next_page = |cursor: String| {
+            let page = pages[&cursor].clone();
+            async move { Ok(page) }
+        };
+        let (results, truncated) = collect_pages(first, next_page, 3).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(truncated);
+
+        let settings = HashMap::from([("max_documents".to_string(), json!(10))]);
+        assert_eq!(max_documents_from_settings(&settings), 10);
+        assert_eq!(max_documents_from_settings(&HashMap::new()), DEFAULT_MAX_DOCUMENTS);
+    }
+
+    #[test]
+    fn test_missing_path_is_not_found_not_network_error() {
+        let body = r#"{"error_summary": "path/not_found/..", "error": {".tag": "path", "path": {".tag": "not_found"}}}"#;
+        assert!(matches!(status_error(409, body, "/gone.txt", None), PluginError::NotFound(_)));
+
+        let body = r#"{"error_summary": "path/unsupported_file/.."}"#;
+        assert!(matches!(status_error(409, body, "/paper.gdoc", None), PluginError::RuntimeError(_)));
+        assert!(matches!(status_error(503, "", "/a.txt", None), PluginError::NetworkError(_)));
+        assert_eq!(status_error(429, "", "/a.txt", Some(5)).retry_after_secs(), Some(5));
+
+        assert_eq!(api_arg(&json!({ "path": "/Café.txt" })), r#"{"path":"/Caf\u00e9.txt"}"#);
+    }
+}
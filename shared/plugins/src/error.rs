@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PluginError {
@@ -13,6 +14,9 @@ pub enum PluginError {
     NetworkError(String),
     AuthenticationError(String),
     PermissionError(String),
+    UnsupportedOperation(String),
+    /// Too many requests to one instance; retry after the given delay
+    RateLimited(Duration),
     Unknown(String),
 }
 
@@ -29,6 +33,8 @@ impl fmt::Display for PluginError {
             PluginError::NetworkError(msg) => write!(f, "Network error: {}", msg),
             PluginError::AuthenticationError(msg) => write!(f, "Authentication error: {}", msg),
             PluginError::PermissionError(msg) => write!(f, "Permission error: {}", msg),
+            PluginError::UnsupportedOperation(msg) => write!(f, "Unsupported operation: {}", msg),
+            PluginError::RateLimited(retry_after) => write!(f, "Rate limit exceeded, retry after {}s", retry_after_secs(*retry_after)),
             PluginError::Unknown(msg) => write!(f, "Unknown error: {}", msg),
         }
     }
@@ -36,6 +42,36 @@ impl fmt::Display for PluginError {
 
 impl std::error::Error for PluginError {}
 
+/// Round up so a client waiting the advertised time is never early
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
+impl PluginError {
+    /// HTTP status a handler should answer with for this error
+    pub fn status_code(&self) -> u16 {
+        match self {
+            PluginError::NotFound(_) => 404,
+            PluginError::AlreadyExists(_) => 409,
+            PluginError::ValidationError(_) | PluginError::ConfigurationError(_) => 400,
+            PluginError::AuthenticationError(_) => 401,
+            PluginError::PermissionError(_) => 403,
+            PluginError::UnsupportedOperation(_) => 405,
+            PluginError::RateLimited(_) => 429,
+            PluginError::NetworkError(_) | PluginError::DependencyError(_) => 502,
+            _ => 500,
+        }
+    }
+
+    /// Value for the `Retry-After` header, in whole seconds, when rate limited
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            PluginError::RateLimited(retry_after) => Some(retry_after_secs(*retry_after)),
+            _ => None,
+        }
+    }
+}
+
 impl From<anyhow::Error> for PluginError {
     fn from(err: anyhow::Error) -> Self {
         PluginError::Unknown(err.to_string())
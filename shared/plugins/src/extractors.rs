@@ -0,0 +1,331 @@
+use crate::{error::PluginError, PluginResult};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+
+/// Turns raw document bytes into plain text for indexing
+pub trait ContentExtractor: Send + Sync {
+    /// MIME types this extractor handles
+    fn mime_types(&self) -> Vec<&'static str>;
+
+    /// Extract plain text from the raw document bytes
+    fn extract(&self, bytes: &[u8]) -> PluginResult<String>;
+}
+
+/// Content extractors keyed by MIME type, shared by source plugins
+#[derive(Clone, Default)]
+pub struct ExtractorRegistry {
+    extractors: HashMap<String, Arc<dyn ContentExtractor>>,
+}
+
+impl ExtractorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the built-in plaintext, HTML, PDF and DOCX extractors
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(Arc::new(PlainTextExtractor));
+        registry.register(Arc::new(HtmlExtractor));
+        registry.register(Arc::new(PdfExtractor));
+        registry.register(Arc::new(DocxExtractor));
+        registry
+    }
+
+    /// Register an extractor for every MIME type it declares, replacing existing ones
+    pub fn register(&mut self, extractor: Arc<dyn ContentExtractor>) {
+        for mime_type in extractor.mime_types() {
+            self.extractors.insert(mime_type.to_string(), extractor.clone());
+        }
+    }
+
+    /// Extractor for a content type; parameters such as `charset` are ignored
+    pub fn get(&self, content_type: &str) -> Option<Arc<dyn ContentExtractor>> {
+        self.extractors.get(&normalize_mime(content_type)).cloned()
+    }
+
+    pub fn supports(&self, content_type: &str) -> bool {
+        self.get(content_type).is_some()
+    }
+
+    /// Extract text using the extractor registered for `content_type`
+    pub fn extract(&self, content_type: &str, bytes: &[u8]) -> PluginResult<String> {
+        let extractor = self.get(content_type).ok_or_else(|| {
+            PluginError::ValidationError(format!("No content extractor for '{}'", content_type))
+        })?;
+        extractor.extract(bytes)
+    }
+}
+
+fn normalize_mime(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Plain text formats, decoded as UTF-8
+pub struct PlainTextExtractor;
+
+impl ContentExtractor for PlainTextExtractor {
+    fn mime_types(&self) -> Vec<&'static str> {
+        vec![
+            "text/plain",
+            "text/markdown",
+            "text/csv",
+            "application/json",
+            "application/xml",
+            "application/x-yaml",
+        ]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> PluginResult<String> {
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// HTML with tags, scripts and styles stripped
+pub struct HtmlExtractor;
+
+impl ContentExtractor for HtmlExtractor {
+    fn mime_types(&self) -> Vec<&'static str> {
+        vec!["text/html", "application/xhtml+xml"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> PluginResult<String> {
+        let html = String::from_utf8_lossy(bytes);
+        let without_blocks = remove_blocks(&html, "script");
+        let without_blocks = remove_blocks(&without_blocks, "style");
+        Ok(collapse_whitespace(&decode_entities(&strip_tags(&without_blocks))))
+    }
+}
+
+/// Word documents: the text runs of `word/document.xml`
+pub struct DocxExtractor;
+
+impl ContentExtractor for DocxExtractor {
+    fn mime_types(&self) -> Vec<&'static str> {
+        vec!["application/vnd.openxmlformats-officedocument.wordprocessingml.document"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> PluginResult<String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| PluginError::ValidationError(format!("Invalid DOCX archive: {}", e)))?;
+        let mut xml = String::new();
+        archive
+            .by_name("word/document.xml")
+            .map_err(|e| PluginError::ValidationError(format!("DOCX has no document body: {}", e)))?
+            .read_to_string(&mut xml)
+            .map_err(|e| PluginError::ValidationError(format!("Unreadable DOCX body: {}", e)))?;
+
+        // Keep paragraph breaks before dropping the markup
+        let xml = xml.replace("</w:p>", "\n").replace("<w:tab/>", "\t");
+        let text = decode_entities(&strip_tags(&xml));
+        Ok(text.lines().map(collapse_whitespace).filter(|l| !l.is_empty()).collect::<Vec<_>>().join("\n"))
+    }
+}
+
+/// PDFs: text shown by `Tj`/`TJ` operators in plain or Flate-compressed content streams
+pub struct PdfExtractor;
+
+impl ContentExtractor for PdfExtractor {
+    fn mime_types(&self) -> Vec<&'static str> {
+        vec!["application/pdf"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> PluginResult<String> {
+        if !bytes.starts_with(b"%PDF") {
+            return Err(PluginError::ValidationError("Not a PDF document".to_string()));
+        }
+
+        let mut text = String::new();
+        for stream in pdf_streams(bytes) {
+            let mut decoded = Vec::new();
+            let content = if flate2::read::ZlibDecoder::new(stream).read_to_end(&mut decoded).is_ok() {
+                &decoded[..]
+            } else {
+                stream
+            };
+            pdf_show_text(content, &mut text);
+        }
+
+        Ok(collapse_whitespace(&text))
+    }
+}
+
+fn pdf_streams(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut streams = Vec::new();
+    let mut rest = bytes;
+    while let Some(start) = find(rest, b"stream") {
+        let mut body = &rest[start + b"stream".len()..];
+        body = body.strip_prefix(b"\r").unwrap_or(body);
+        body = body.strip_prefix(b"\n").unwrap_or(body);
+        let Some(end) = find(body, b"endstream") else { break };
+        streams.push(&body[..end]);
+        rest = &body[end + b"endstream".len()..];
+    }
+    streams
+}
+
+/// Collect string operands of text-showing operators, one line per `Tj`/`TJ`
+fn pdf_show_text(content: &[u8], out: &mut String) {
+    let mut pending = String::new();
+    let mut i = 0;
+    while i < content.len() {
+        match content[i] {
+            b'(' => {
+                let (literal, next) = pdf_literal(content, i + 1);
+                pending.push_str(&literal);
+                i = next;
+            }
+            b'T' if matches!(content.get(i + 1), Some(b'j') | Some(b'J')) => {
+                if !pending.is_empty() {
+                    out.push_str(&pending);
+                    out.push('\n');
+                    pending.clear();
+                }
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+fn pdf_literal(content: &[u8], mut i: usize) -> (String, usize) {
+    let mut literal = Vec::new();
+    let mut depth = 1;
+    while i < content.len() {
+        match content[i] {
+            b'\\' if i + 1 < content.len() => {
+                match content[i + 1] {
+                    b'n' => literal.push(b'\n'),
+                    b't' => literal.push(b'\t'),
+                    other => literal.push(other),
+                }
+                i += 2;
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (String::from_utf8_lossy(&literal).into_owned(), i + 1);
+                }
+            }
+            _ => {}
+        }
+        literal.push(content[i]);
+        i += 1;
+    }
+    (String::from_utf8_lossy(&literal).into_owned(), i)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn remove_blocks(html: &str, tag: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut out = String::with_capacity(html.len());
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find(&open).map(|i| i + pos) {
+        out.push_str(&html[pos..start]);
+        match lower[start..].find(&close) {
+            Some(end) => pos = start + end + close.len(),
+            None => return out,
+        }
+    }
+    out.push_str(&html[pos..]);
+    out
+}
+
+fn strip_tags(markup: &str) -> String {
+    let mut out = String::with_capacity(markup.len());
+    let mut in_tag = false;
+    for c in markup.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                out.push(' ');
+            }
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    struct UpperExtractor;
+
+    impl ContentExtractor for UpperExtractor {
+        fn mime_types(&self) -> Vec<&'static str> {
+            vec!["text/x-shout"]
+        }
+
+        fn extract(&self, bytes: &[u8]) -> PluginResult<String> {
+            Ok(String::from_utf8_lossy(bytes).to_uppercase())
+        }
+    }
+
+    #[test]
+    fn test_registry_dispatches_by_mime_type() {
+        let mut registry = ExtractorRegistry::with_defaults();
+        registry.register(Arc::new(UpperExtractor));
+
+        assert_eq!(registry.extract("text/plain; charset=utf-8", b"hello").unwrap(), "hello");
+        assert_eq!(
+            registry.extract("TEXT/HTML", b"<p>Hello <b>world</b></p><script>x()</script>").unwrap(),
+            "Hello world"
+        );
+        assert_eq!(registry.extract("text/x-shout", b"hello").unwrap(), "HELLO");
+        assert!(matches!(
+            registry.extract("image/png", b"\x89PNG"),
+            Err(PluginError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_docx_and_pdf_text() {
+        let mut docx = Vec::new();
+        {
+            let mut writer = zip::ZipWriter::new(Cursor::new(&mut docx));
+            writer.start_file("word/document.xml", zip::write::FileOptions::default()).unwrap();
+            writer
+                .write_all(b"<w:document><w:body><w:p><w:r><w:t>Quarterly &amp; plan</w:t></w:r></w:p><w:p><w:r><w:t>Second</w:t></w:r></w:p></w:body></w:document>")
+                .unwrap();
+            writer.finish().unwrap();
+        }
+
+        let registry = ExtractorRegistry::with_defaults();
+        let docx_type = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+        assert_eq!(registry.extract(docx_type, &docx).unwrap(), "Quarterly & plan\nSecond");
+
+        let pdf = b"%PDF-1.4\n1 0 obj << /Length 44 >>\nstream\nBT /F1 12 Tf (Hello \\(PDF\\)) Tj [(wor) (ld)] TJ ET\nendstream\nendobj\n%%EOF";
+        assert_eq!(registry.extract("application/pdf", pdf).unwrap(), "Hello (PDF) world");
+    }
+}
@@ -3,6 +3,12 @@ pub mod sources;
 pub mod agents;
 pub mod config;
 pub mod error;
+pub mod extractors;
+pub mod retry;
+pub mod rate_limit;
+pub mod path_filter;
+pub mod oauth;
+pub mod dropbox;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
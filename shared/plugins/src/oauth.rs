@@ -0,0 +1,217 @@
+use crate::{error::PluginError, PluginResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// Google's OAuth 2.0 token endpoint
+pub const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+/// An OAuth access token that renews itself. Source plugins send their API
+/// calls through `send`, which refreshes the token and retries once when the
+/// provider answers 401, so an expired token doesn't fail every call.
+#[derive(Debug)]
+pub struct OAuthSession {
+    http: reqwest::Client,
+    token_url: String,
+    access_token: RwLock<String>,
+    refresh_token: Option<String>,
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RefreshResponse {
+    access_token: String,
+}
+
+impl OAuthSession {
+    pub fn new(
+        http: reqwest::Client,
+        token_url: impl Into<String>,
+        access_token: impl Into<String>,
+        refresh_token: Option<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            http,
+            token_url: token_url.into(),
+            access_token: RwLock::new(access_token.into()),
+            refresh_token: refresh_token.filter(|t| !t.is_empty()),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+        }
+    }
+
+    /// Read `access_token`, `refresh_token`, `client_id` and `client_secret`
+    /// from plugin settings; only `access_token` is required
+    pub fn from_settings(
+        http: reqwest::Client,
+        token_url: &str,
+        settings: &HashMap<String, serde_json::Value>,
+    ) -> PluginResult<Self> {
+        let get = |key: &str| settings.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let access_token = get("access_token")
+            .ok_or_else(|| PluginError::ConfigurationError("access_token is required".to_string()))?;
+        Ok(Self::new(
+            http,
+            token_url,
+            access_token,
+            get("refresh_token"),
+            get("client_id").unwrap_or_default(),
+            get("client_secret").unwrap_or_default(),
+        ))
+    }
+
+    pub async fn access_token(&self) -> String {
+        self.access_token.read().await.clone()
+    }
+
+    /// Exchange the refresh token for a new access token and keep it for later calls
+    pub async fn refresh_access_token(&self) -> PluginResult<String> {
+        let refresh_token = self.refresh_token.as_deref().ok_or_else(|| {
+            PluginError::AuthenticationError("Access token expired and no refresh token is available".to_string())
+        })?;
+
+        let response = self
+            .http
+            .post(&self.token_url)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| PluginError::NetworkError(format!("Token refresh failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            // A revoked or expired refresh token means the user has to reconnect
+            return Err(match status.as_u16() {
+                400 | 401 => PluginError::AuthenticationError(format!("Token refresh rejected: {}", body)),
+                _ => PluginError::NetworkError(format!("Token refresh failed with {}: {}", status, body)),
+            });
+        }
+
+        let refreshed: RefreshResponse = response
+            .json()
+            .await
+            .map_err(|e| PluginError::NetworkError(format!("Invalid token refresh response: {}", e)))?;
+        *self.access_token.write().await = refreshed.access_token.clone();
+        tracing::debug!("Refreshed OAuth access token");
+        Ok(refreshed.access_token)
+    }
+
+    /// Send the request built by `build` with the current access token. On a
+    /// 401 the token is refreshed and the request rebuilt and sent once more;
+    /// without a refresh token the 401 is returned as an `AuthenticationError`
+    /// so the caller can ask the user to reconnect.
+    pub async fn send<F>(&self, build: F) -> PluginResult<reqwest::Response>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        let token = self.access_token().await;
+        let response = self.send_with(&build, &token).await?;
+        if response.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        if self.refresh_token.is_none() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(PluginError::AuthenticationError(format!("Access token rejected: {}", body)));
+        }
+        let token = self.refresh_access_token().await?;
+        let response = self.send_with(&build, &token).await?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            let body = response.text().await.unwrap_or_default();
+            return Err(PluginError::AuthenticationError(format!("Refreshed access token rejected: {}", body)));
+        }
+        Ok(response)
+    }
+
+    async fn send_with<F>(&self, build: &F, token: &str) -> PluginResult<reqwest::Response>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        build(&self.http)
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| PluginError::NetworkError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Minimal token/API server: `/token` issues `fresh-token`, `/files`
+    /// accepts only that token. Records the first line of every request.
+    async fn spawn_server() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 8192];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let line = request.lines().next().unwrap_or("").to_string();
+                log.lock().unwrap().push(line.clone());
+
+                let (status, body) = if line.starts_with("POST /token") {
+                    if request.contains("grant_type=refresh_token") && request.contains("refresh_token=refresh-1") {
+                        ("200 OK", r#"{"access_token":"fresh-token","expires_in":3599}"#)
+                    } else {
+                        ("400 Bad Request", r#"{"error":"invalid_grant"}"#)
+                    }
+                } else if request.to_lowercase().contains("authorization: bearer fresh-token") {
+                    ("200 OK", r#"{"files":[]}"#)
+                } else {
+                    ("401 Unauthorized", r#"{"error":"invalid_token"}"#)
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        (base, requests)
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_refreshed_and_request_retried_once() {
+        let (base, requests) = spawn_server().await;
+        let files_url = format!("{}/files", base);
+        let session = OAuthSession::new(
+            reqwest::Client::new(),
+            format!("{}/token", base),
+            "expired-token",
+            Some("refresh-1".to_string()),
+            "client",
+            "secret",
+        );
+
+        let response = session.send(|http| http.get(&files_url)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(session.access_token().await, "fresh-token");
+        assert_eq!(*requests.lock().unwrap(), ["GET /files HTTP/1.1", "POST /token HTTP/1.1", "GET /files HTTP/1.1"]);
+
+        // Without a refresh token the 401 surfaces as an authentication error
+        let no_refresh = OAuthSession::new(reqwest::Client::new(), format!("{}/token", base), "expired-token", None, "", "");
+        let err = no_refresh.send(|http| http.get(&files_url)).await.unwrap_err();
+        assert!(matches!(err, PluginError::AuthenticationError(_)));
+        assert_eq!(requests.lock().unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{error::PluginError, sources::Document, PluginResult};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::HashMap;
+
+/// Include/exclude glob patterns over document paths, read from a source
+/// plugin's `include_globs` and `exclude_globs` settings. With no include
+/// patterns every path is included; an excluded path is dropped even if it is
+/// also included. A pattern ending in `/` covers everything below it, so
+/// `Backups/` excludes the whole folder.
+#[derive(Debug, Clone)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> PluginResult<Self> {
+        Ok(Self {
+            include: build_set(include)?,
+            exclude: build_set(exclude)?,
+        })
+    }
+
+    /// `None` when neither setting is present; invalid patterns are a configuration error
+    pub fn from_settings(settings: &HashMap<String, serde_json::Value>) -> PluginResult<Option<Self>> {
+        let include = string_list(settings, "include_globs")?;
+        let exclude = string_list(settings, "exclude_globs")?;
+        if include.is_empty() && exclude.is_empty() {
+            return Ok(None);
+        }
+        Self::new(&include, &exclude).map(Some)
+    }
+
+    pub fn allows(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        let included = self.include.as_ref().is_none_or(|set| set.is_match(path));
+        let excluded = self.exclude.as_ref().is_some_and(|set| set.is_match(path));
+        included && !excluded
+    }
+
+    pub fn apply(&self, documents: Vec<Document>) -> Vec<Document> {
+        documents.into_iter().filter(|d| self.allows(&d.path)).collect()
+    }
+}
+
+fn build_set(patterns: &[String]) -> PluginResult<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let mut normalized = pattern.trim().trim_start_matches('/').to_string();
+        if normalized.ends_with('/') {
+            normalized.push_str("**");
+        }
+        let glob = Glob::new(&normalized)
+            .map_err(|e| PluginError::ConfigurationError(format!("Invalid path pattern '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| PluginError::ConfigurationError(format!("Invalid path patterns: {}", e)))
+}
+
+fn string_list(settings: &HashMap<String, serde_json::Value>, key: &str) -> PluginResult<Vec<String>> {
+    match settings.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(Vec::new()),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| PluginError::ConfigurationError(format!("{} must contain only strings", key)))
+            })
+            .collect(),
+        Some(_) => Err(PluginError::ConfigurationError(format!("{} must be an array of glob patterns", key))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filter(settings: serde_json::Value) -> PathFilter {
+        let settings: HashMap<String, serde_json::Value> = serde_json::from_value(settings).unwrap();
+        PathFilter::from_settings(&settings).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_include_only_keeps_matching_paths() {
+        let filter = filter(json!({ "include_globs": ["Projects/**/*.md", "*.pdf"] }));
+
+        assert!(filter.allows("/Projects/conhub/README.md"));
+        assert!(filter.allows("/Invoices/2024/march.pdf"));
+        assert!(!filter.allows("/Projects/conhub/logo.png"));
+        assert!(!filter.allows("/notes.txt"));
+    }
+
+    #[test]
+    fn test_exclude_wins_over_include() {
+        let markdown = filter(json!({
+            "include_globs": ["**/*.md"],
+            "exclude_globs": ["Backups/", "**/draft-*"]
+        }));
+
+        assert!(markdown.allows("/Docs/setup.md"));
+        assert!(!markdown.allows("/Backups/Docs/setup.md"));
+        assert!(!markdown.allows("/Docs/draft-roadmap.md"));
+
+        // Exclude-only keeps everything else
+        let no_backups = filter(json!({ "exclude_globs": ["Backups/"] }));
+        assert!(no_backups.allows("/photo.jpg"));
+        assert!(!no_backups.allows("/Backups/photo.jpg"));
+
+        let invalid = HashMap::from([("exclude_globs".to_string(), json!(["a[b"]))]);
+        assert!(matches!(PathFilter::from_settings(&invalid), Err(PluginError::ConfigurationError(_))));
+        assert!(PathFilter::from_settings(&HashMap::new()).unwrap().is_none());
+    }
+}
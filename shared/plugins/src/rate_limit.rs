@@ -0,0 +1,88 @@
+use crate::error::PluginError;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Token bucket allowing `requests_per_minute` calls, refilled continuously so
+/// short bursts up to the full limit are accepted
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    requests_per_minute: u32,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(requests_per_minute: u32) -> Self {
+        Self {
+            requests_per_minute,
+            tokens: requests_per_minute as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Read `rate_limit_per_minute` from plugin settings; absent or 0 means unlimited
+    pub fn from_settings(settings: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        settings.get("rate_limit_per_minute")
+            .and_then(|v| v.as_u64())
+            .filter(|&n| n > 0)
+            .map(|n| Self::new(n.min(u32::MAX as u64) as u32))
+    }
+
+    pub fn requests_per_minute(&self) -> u32 {
+        self.requests_per_minute
+    }
+
+    /// Take a token, or return how long until one is available
+    pub fn try_acquire(&mut self) -> Result<(), Duration> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, now: Instant) -> Result<(), Duration> {
+        let per_second = self.requests_per_minute as f64 / 60.0;
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(self.requests_per_minute as f64);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+        }
+    }
+
+    /// `try_acquire` as a `RateLimited` error for `instance_id`
+    pub fn acquire(&mut self, instance_id: &str) -> Result<(), PluginError> {
+        self.try_acquire().map_err(|retry_after| {
+            tracing::warn!(
+                "Plugin '{}' exceeded {} requests/min; retry after {:?}",
+                instance_id, self.requests_per_minute, retry_after
+            );
+            PluginError::RateLimited(retry_after)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills_at_configured_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(60);
+        bucket.refilled_at = start;
+
+        for _ in 0..60 {
+            assert!(bucket.try_acquire_at(start).is_ok());
+        }
+        let retry_after = bucket.try_acquire_at(start).unwrap_err();
+        assert!(retry_after <= Duration::from_secs(1) && retry_after > Duration::from_millis(900));
+
+        assert!(bucket.try_acquire_at(start + Duration::from_secs(1)).is_ok());
+        assert!(bucket.try_acquire_at(start + Duration::from_secs(1)).is_err());
+
+        let settings = HashMap::from([("rate_limit_per_minute".to_string(), serde_json::json!(0))]);
+        assert!(TokenBucket::from_settings(&settings).is_none());
+    }
+}
@@ -1,13 +1,39 @@
 use crate::{
     Plugin, PluginConfig, PluginFactory, PluginMetadata, PluginStatus, PluginType, PluginResult,
-    agents::{AgentPlugin, AgentPluginFactory},
-    sources::{SourcePlugin, SourcePluginFactory, SyncResult, Document},
+    agents::{AgentAction, AgentFunction, AgentMessage, AgentPlugin, AgentPluginFactory, AgentResponseChunk, ConversationContext},
+    sources::{merge_by_content_hash, ContentStream, DeletionSink, DocumentDeletion, MergedDocument, SourcePlugin, SourcePluginFactory, SourceOperation, SyncResult, Document, DocumentCursor, DocumentListing, DocumentPage},
     error::PluginError,
+    rate_limit::TokenBucket,
+    path_filter::PathFilter,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use tokio::sync::RwLock as AsyncRwLock;
 
+/// Retry policy for bringing a plugin up (initialize, start, passing health check)
+#[derive(Debug, Clone)]
+pub struct StartupRetry {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for StartupRetry {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Page size when the caller doesn't ask for one
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+/// Largest page a caller may request
+pub const MAX_PAGE_SIZE: usize = 1000;
+
 /// Plugin registry for managing all plugins
 pub struct PluginRegistry {
     source_factories: HashMap<String, Box<dyn SourcePluginFactory>>,
@@ -15,6 +41,17 @@ pub struct PluginRegistry {
     active_sources: Arc<AsyncRwLock<HashMap<String, Box<dyn SourcePlugin>>>>,
     active_agents: Arc<AsyncRwLock<HashMap<String, Box<dyn AgentPlugin>>>>,
     plugin_configs: Arc<RwLock<HashMap<String, PluginConfig>>>,
+    startup_retry: StartupRetry,
+    /// Instances that gave up starting, with the last error
+    startup_failures: Arc<RwLock<HashMap<String, String>>>,
+    /// Request budgets for agent instances configured with `rate_limit_per_minute`
+    rate_limits: Arc<RwLock<HashMap<String, TokenBucket>>>,
+    /// Path patterns for source instances configured with `include_globs`/`exclude_globs`
+    path_filters: Arc<RwLock<HashMap<String, PathFilter>>>,
+    /// Told about every document a sync finds deleted or the API deletes
+    deletion_sinks: Vec<Arc<dyn DeletionSink>>,
+    /// Document ids each source listed after its last sync, to spot upstream deletions
+    synced_documents: Arc<RwLock<HashMap<String, HashSet<String>>>>,
 }
 
 impl PluginRegistry {
@@ -25,6 +62,101 @@ impl PluginRegistry {
             active_sources: Arc::new(AsyncRwLock::new(HashMap::new())),
             active_agents: Arc::new(AsyncRwLock::new(HashMap::new())),
             plugin_configs: Arc::new(RwLock::new(HashMap::new())),
+            startup_retry: StartupRetry::default(),
+            startup_failures: Arc::new(RwLock::new(HashMap::new())),
+            rate_limits: Arc::new(RwLock::new(HashMap::new())),
+            path_filters: Arc::new(RwLock::new(HashMap::new())),
+            deletion_sinks: Vec::new(),
+            synced_documents: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn with_deletion_sink(mut self, sink: Arc<dyn DeletionSink>) -> Self {
+        self.deletion_sinks.push(sink);
+        self
+    }
+
+    pub fn with_startup_retry(mut self, startup_retry: StartupRetry) -> Self {
+        self.startup_retry = startup_retry;
+        self
+    }
+
+    /// Initialize, start and health-check a plugin, retrying transient failures with
+    /// exponential backoff. The plugin is stopped before each retry so a partially
+    /// started attempt doesn't leak resources. After the last attempt the instance is
+    /// reported as `Error`.
+    async fn bring_up<P: Plugin + ?Sized>(&self, instance_id: &str, plugin: &mut P, config: &PluginConfig) -> Result<(), PluginError> {
+        let max_attempts = self.startup_retry.max_attempts.max(1);
+        let mut backoff = self.startup_retry.initial_backoff;
+        let mut attempt = 1;
+
+        loop {
+            match Self::try_bring_up(plugin, config).await {
+                Ok(()) => {
+                    self.startup_failures.write().unwrap().remove(instance_id);
+                    return Ok(());
+                }
+                Err(e) if attempt < max_attempts && is_transient(&e) => {
+                    tracing::warn!(
+                        "Plugin '{}' failed to start (attempt {}/{}): {}; retrying in {:?}",
+                        instance_id, attempt, max_attempts, e, backoff
+                    );
+                    if let Err(stop_err) = plugin.stop().await {
+                        tracing::debug!("Plugin '{}' failed to stop before retrying: {}", instance_id, stop_err);
+                    }
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.startup_retry.max_backoff);
+                    attempt += 1;
+                }
+                Err(e) => {
+                    tracing::error!("Plugin '{}' failed to start after {} attempt(s): {}", instance_id, attempt, e);
+                    self.startup_failures.write().unwrap().insert(instance_id.to_string(), e.to_string());
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    async fn try_bring_up<P: Plugin + ?Sized>(plugin: &mut P, config: &PluginConfig) -> Result<(), PluginError> {
+        plugin.initialize(config.clone()).await?;
+        plugin.start().await?;
+        if !plugin.health_check().await? {
+            return Err(PluginError::RuntimeError("health check failed".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Replace the instance's request budget with the one its config asks for
+    fn set_rate_limit(&self, instance_id: &str, config: &PluginConfig) {
+        let mut rate_limits = self.rate_limits.write().unwrap();
+        match TokenBucket::from_settings(&config.settings) {
+            Some(bucket) => rate_limits.insert(instance_id.to_string(), bucket),
+            None => rate_limits.remove(instance_id),
+        };
+    }
+
+    /// Spend one request from the instance's budget, failing with `RateLimited`
+    /// (429, with a retry delay) once it is used up
+    fn set_path_filter(&self, instance_id: &str, filter: Option<PathFilter>) {
+        let mut path_filters = self.path_filters.write().unwrap();
+        match filter {
+            Some(filter) => path_filters.insert(instance_id.to_string(), filter),
+            None => path_filters.remove(instance_id),
+        };
+    }
+
+    /// Drop documents whose paths the instance's include/exclude patterns reject
+    fn filter_paths(&self, instance_id: &str, documents: Vec<Document>) -> Vec<Document> {
+        match self.path_filters.read().unwrap().get(instance_id) {
+            Some(filter) => filter.apply(documents),
+            None => documents,
+        }
+    }
+
+    fn check_rate_limit(&self, instance_id: &str) -> Result<(), PluginError> {
+        match self.rate_limits.write().unwrap().get_mut(instance_id) {
+            Some(bucket) => bucket.acquire(instance_id),
+            None => Ok(()),
         }
     }
 
@@ -45,9 +177,10 @@ impl PluginRegistry {
         let factory = self.source_factories.get(source_type)
             .ok_or_else(|| PluginError::NotFound(format!("Source type '{}' not found", source_type)))?;
 
+        let path_filter = PathFilter::from_settings(&config.settings)?;
         let mut plugin = factory.create();
-        plugin.initialize(config.clone()).await?;
-        plugin.start().await?;
+        self.bring_up(instance_id, plugin.as_mut(), &config).await?;
+        self.set_path_filter(instance_id, path_filter);
 
         // Store config
         {
@@ -70,8 +203,8 @@ impl PluginRegistry {
             .ok_or_else(|| PluginError::NotFound(format!("Agent type '{}' not found", agent_type)))?;
 
         let mut plugin = factory.create();
-        plugin.initialize(config.clone()).await?;
-        plugin.start().await?;
+        self.bring_up(instance_id, plugin.as_mut(), &config).await?;
+        self.set_rate_limit(instance_id, &config);
 
         // Store config
         {
@@ -94,6 +227,8 @@ impl PluginRegistry {
         if let Some(mut plugin) = active_sources.remove(instance_id) {
             plugin.stop().await?;
         }
+        self.set_path_filter(instance_id, None);
+        self.synced_documents.write().unwrap().remove(instance_id);
 
         // Remove config
         {
@@ -110,6 +245,7 @@ impl PluginRegistry {
         if let Some(mut plugin) = active_agents.remove(instance_id) {
             plugin.stop().await?;
         }
+        self.rate_limits.write().unwrap().remove(instance_id);
 
         // Remove config
         {
@@ -120,6 +256,68 @@ impl PluginRegistry {
         Ok(())
     }
 
+    /// Apply a new configuration to a running instance without a stop/start.
+    /// The config is validated first; if validation or re-initialization fails the
+    /// instance is put back on its previous config and the error is returned.
+    pub async fn update_instance_config(&self, instance_id: &str, config: PluginConfig) -> Result<(), PluginError> {
+        let previous = self.plugin_configs.read().unwrap().get(instance_id).cloned();
+
+        {
+            let mut active_sources = self.active_sources.write().await;
+            if let Some(plugin) = active_sources.get_mut(instance_id) {
+                let path_filter = PathFilter::from_settings(&config.settings)?;
+                apply_config(instance_id, plugin.as_mut(), &config, previous.as_ref()).await?;
+                self.set_path_filter(instance_id, path_filter);
+                self.plugin_configs.write().unwrap().insert(instance_id.to_string(), config);
+                return Ok(());
+            }
+        }
+
+        {
+            let mut active_agents = self.active_agents.write().await;
+            if let Some(plugin) = active_agents.get_mut(instance_id) {
+                apply_config(instance_id, plugin.as_mut(), &config, previous.as_ref()).await?;
+                self.set_rate_limit(instance_id, &config);
+                self.plugin_configs.write().unwrap().insert(instance_id.to_string(), config);
+                return Ok(());
+            }
+        }
+
+        Err(PluginError::NotFound(format!("Plugin instance '{}' not found", instance_id)))
+    }
+
+    /// Stop and remove every active plugin, giving each `timeout` to stop.
+    /// Failures and timeouts are logged and returned; they don't stop the others.
+    pub async fn stop_all(&self, timeout: Duration) -> Vec<(String, PluginError)> {
+        let sources: Vec<_> = self.active_sources.write().await.drain().collect();
+        let agents: Vec<_> = self.active_agents.write().await.drain().collect();
+        let mut failures = Vec::new();
+
+        for (instance_id, mut plugin) in sources {
+            if let Err(e) = stop_with_timeout(&instance_id, plugin.as_mut(), timeout).await {
+                failures.push((instance_id, e));
+            }
+        }
+        for (instance_id, mut plugin) in agents {
+            if let Err(e) = stop_with_timeout(&instance_id, plugin.as_mut(), timeout).await {
+                failures.push((instance_id, e));
+            }
+        }
+
+        self.plugin_configs.write().unwrap().clear();
+        failures
+    }
+
+    /// Wait for Ctrl-C or SIGTERM, then stop all active plugins
+    pub async fn stop_all_on_shutdown(&self, timeout: Duration) {
+        shutdown_signal().await;
+        tracing::info!("Shutdown signal received, stopping active plugins");
+        let failures = self.stop_all(timeout).await;
+        if !failures.is_empty() {
+            tracing::warn!("{} plugin(s) did not stop cleanly", failures.len());
+        }
+    }
+
     /// Get a source plugin instance
     pub async fn get_source(&self, instance_id: &str) -> Option<Box<dyn SourcePlugin>> {
         let active_sources = self.active_sources.read().await;
@@ -174,7 +372,9 @@ impl PluginRegistry {
             }
         }
 
-        None
+        self.startup_failures.read().unwrap()
+            .get(instance_id)
+            .map(|error| PluginStatus::Error(error.clone()))
     }
 
     /// Health check all plugins
@@ -226,7 +426,72 @@ impl PluginRegistry {
     pub async fn sync_source_documents(&self, instance_id: &str) -> Result<SyncResult, PluginError> {
         let active_sources = self.active_sources.read().await;
         if let Some(plugin) = active_sources.get(instance_id) {
-            plugin.sync().await.map_err(|e| PluginError::RuntimeError(e.to_string()))
+            plugin.capabilities().require(SourceOperation::Read)?;
+            let mut result = plugin.sync().await.map_err(|e| PluginError::RuntimeError(e.to_string()))?;
+            self.detect_upstream_deletions(instance_id, plugin.as_ref(), &mut result).await;
+            self.propagate_deletions(instance_id, &result.deleted_ids).await;
+            Ok(result)
+        } else {
+            Err(PluginError::NotFound(format!("Source plugin '{}' not found", instance_id)))
+        }
+    }
+
+    /// List documents in a source plugin, including items that failed to convert
+    pub async fn list_source_documents(&self, instance_id: &str) -> Result<DocumentListing, PluginError> {
+        let active_sources = self.active_sources.read().await;
+        if let Some(plugin) = active_sources.get(instance_id) {
+            let mut listing = plugin.list_documents_with_failures().await?;
+            listing.documents = self.filter_paths(instance_id, listing.documents);
+            Ok(listing)
+        } else {
+            Err(PluginError::NotFound(format!("Source plugin '{}' not found", instance_id)))
+        }
+    }
+
+    /// Documents from every active source, with identical content found in
+    /// several sources merged by `content_hash`. A source whose listing fails
+    /// is logged and skipped.
+    pub async fn list_merged_documents(&self) -> Vec<MergedDocument> {
+        let active_sources = self.active_sources.read().await;
+        let mut documents = Vec::new();
+        for (instance_id, plugin) in active_sources.iter() {
+            match plugin.list_documents().await {
+                Ok(listed) => documents.extend(
+                    self.filter_paths(instance_id, listed).into_iter().map(|d| (instance_id.clone(), d)),
+                ),
+                Err(e) => tracing::warn!("Skipping source {} while merging documents: {}", instance_id, e),
+            }
+        }
+        merge_by_content_hash(documents)
+    }
+
+    /// One page of a source plugin's documents, for callers that can't hold the
+    /// whole listing. `limit` is clamped to `MAX_PAGE_SIZE`.
+    pub async fn list_source_documents_page(
+        &self,
+        instance_id: &str,
+        cursor: Option<DocumentCursor>,
+        limit: Option<usize>,
+    ) -> Result<DocumentPage, PluginError> {
+        let active_sources = self.active_sources.read().await;
+        if let Some(plugin) = active_sources.get(instance_id) {
+            plugin.capabilities().require(SourceOperation::Read)?;
+            let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+            let (documents, next_cursor) = plugin.list_documents_page(cursor, limit).await?;
+            // A filtered page may hold fewer than `limit` documents; keep following the cursor
+            Ok(DocumentPage { documents: self.filter_paths(instance_id, documents), next_cursor })
+        } else {
+            Err(PluginError::NotFound(format!("Source plugin '{}' not found", instance_id)))
+        }
+    }
+
+    /// Stream a document's content from a source plugin, for handlers that
+    /// forward it as a chunked response body
+    pub async fn stream_source_content(&self, instance_id: &str, document_id: &str) -> Result<ContentStream, PluginError> {
+        let active_sources = self.active_sources.read().await;
+        if let Some(plugin) = active_sources.get(instance_id) {
+            plugin.capabilities().require(SourceOperation::Read)?;
+            plugin.get_content_stream(document_id).await
         } else {
             Err(PluginError::NotFound(format!("Source plugin '{}' not found", instance_id)))
         }
@@ -236,7 +501,9 @@ impl PluginRegistry {
     pub async fn search_source_documents(&self, instance_id: &str, query: &str, limit: Option<usize>) -> Result<Vec<Document>, PluginError> {
         let active_sources = self.active_sources.read().await;
         if let Some(plugin) = active_sources.get(instance_id) {
-            let mut documents = plugin.search_documents(query).await.map_err(|e| PluginError::RuntimeError(e.to_string()))?;
+            plugin.capabilities().require(SourceOperation::Search)?;
+            let documents = plugin.search_documents(query).await.map_err(|e| PluginError::RuntimeError(e.to_string()))?;
+            let mut documents = self.filter_paths(instance_id, documents);
             if let Some(limit) = limit {
                 documents.truncate(limit);
             }
@@ -245,4 +512,746 @@ impl PluginRegistry {
             Err(PluginError::NotFound(format!("Source plugin '{}' not found", instance_id)))
         }
     }
-}
\ No newline at end of file
+
+    /// Stream a chat response from an agent plugin, chunk by chunk
+    pub async fn stream_agent_response(
+        &self,
+        instance_id: &str,
+        message: AgentMessage,
+        context: ConversationContext,
+    ) -> Result<tokio::sync::mpsc::Receiver<AgentResponseChunk>, PluginError> {
+        let active_agents = self.active_agents.read().await;
+        if let Some(plugin) = active_agents.get(instance_id) {
+            if !plugin.capabilities().supports_chat {
+                return Err(PluginError::UnsupportedOperation("agent does not support chat".to_string()));
+            }
+            self.check_rate_limit(instance_id)?;
+            plugin.stream_response(message, context).await
+        } else {
+            Err(PluginError::NotFound(format!("Agent plugin '{}' not found", instance_id)))
+        }
+    }
+
+    /// Functions an agent plugin advertises for function calling
+    pub async fn get_agent_functions(&self, instance_id: &str) -> Result<Vec<AgentFunction>, PluginError> {
+        let active_agents = self.active_agents.read().await;
+        if let Some(plugin) = active_agents.get(instance_id) {
+            if !plugin.capabilities().supports_function_calling {
+                return Err(PluginError::UnsupportedOperation("agent does not support function calling".to_string()));
+            }
+            plugin.get_available_functions().await
+        } else {
+            Err(PluginError::NotFound(format!("Agent plugin '{}' not found", instance_id)))
+        }
+    }
+
+    /// Execute an agent action after checking its parameters against the schema
+    /// of the function it names, so malformed calls fail with a validation error
+    /// instead of deep inside the plugin
+    pub async fn execute_agent_action(&self, instance_id: &str, action: AgentAction) -> Result<serde_json::Value, PluginError> {
+        let active_agents = self.active_agents.read().await;
+        if let Some(plugin) = active_agents.get(instance_id) {
+            if !plugin.capabilities().supports_function_calling {
+                return Err(PluginError::UnsupportedOperation("agent does not support function calling".to_string()));
+            }
+            let functions = plugin.get_available_functions().await?;
+            let function = functions
+                .iter()
+                .find(|f| f.name == action.action_type)
+                .ok_or_else(|| PluginError::ValidationError(format!("Unknown function '{}'", action.action_type)))?;
+            function.validate_parameters(&action.parameters)?;
+            self.check_rate_limit(instance_id)?;
+            plugin.execute_action(action).await
+        } else {
+            Err(PluginError::NotFound(format!("Agent plugin '{}' not found", instance_id)))
+        }
+    }
+
+    /// Upload a document to a source plugin that accepts writes
+    pub async fn upload_source_document(&self, instance_id: &str, document: Document, content: Vec<u8>) -> Result<String, PluginError> {
+        let active_sources = self.active_sources.read().await;
+        if let Some(plugin) = active_sources.get(instance_id) {
+            plugin.capabilities().require(SourceOperation::Write)?;
+            plugin.upload_document(document, content).await
+        } else {
+            Err(PluginError::NotFound(format!("Source plugin '{}' not found", instance_id)))
+        }
+    }
+
+    /// Delete a document from a source plugin that allows deletes
+    pub async fn delete_source_document(&self, instance_id: &str, document_id: &str) -> Result<(), PluginError> {
+        let active_sources = self.active_sources.read().await;
+        if let Some(plugin) = active_sources.get(instance_id) {
+            plugin.capabilities().require(SourceOperation::Delete)?;
+            plugin.delete_document(document_id).await?;
+            if let Some(synced) = self.synced_documents.write().unwrap().get_mut(instance_id) {
+                synced.remove(document_id);
+            }
+            self.propagate_deletions(instance_id, &[document_id.to_string()]).await;
+            Ok(())
+        } else {
+            Err(PluginError::NotFound(format!("Source plugin '{}' not found", instance_id)))
+        }
+    }
+
+    /// Add the documents listed after the previous sync but gone now to
+    /// `result.deleted_ids`, so sources whose sync doesn't report deletions
+    /// (Drive, Dropbox) still propagate them. The first sync only records the
+    /// listing; a failed listing skips detection until the next sync.
+    async fn detect_upstream_deletions(&self, instance_id: &str, plugin: &dyn SourcePlugin, result: &mut SyncResult) {
+        let listing = match plugin.list_documents_with_failures().await {
+            Ok(listing) => listing,
+            Err(e) => {
+                tracing::warn!("Failed to list {} to detect deleted documents: {}", instance_id, e);
+                return;
+            }
+        };
+        let mut current: HashSet<String> = listing.documents.into_iter().map(|d| d.id).collect();
+        // An item that failed to convert still exists upstream
+        current.extend(listing.failures.into_iter().map(|f| f.id));
+
+        let previous = self.synced_documents.write().unwrap().insert(instance_id.to_string(), current.clone());
+        let Some(previous) = previous else { return };
+        let mut gone: Vec<String> = previous
+            .difference(&current)
+            .filter(|id| !result.deleted_ids.contains(id))
+            .cloned()
+            .collect();
+        gone.sort();
+        result.deleted_documents += gone.len() as u64;
+        result.deleted_ids.extend(gone);
+    }
+
+    /// Tell every sink about deleted documents. A failing sink is logged rather
+    /// than failing the sync; the document is removed again on the next delivery.
+    async fn propagate_deletions(&self, instance_id: &str, document_ids: &[String]) {
+        for document_id in document_ids {
+            let deletion = DocumentDeletion {
+                instance_id: instance_id.to_string(),
+                document_id: document_id.clone(),
+            };
+            for sink in &self.deletion_sinks {
+                if let Err(e) = sink.document_deleted(&deletion).await {
+                    tracing::warn!(
+                        "Failed to remove {} of {} from {}: {}",
+                        document_id, instance_id, sink.name(), e
+                    );
+                }
+            }
+        }
+    }
+}
+
+async fn apply_config<P: Plugin + ?Sized>(
+    instance_id: &str,
+    plugin: &mut P,
+    config: &PluginConfig,
+    previous: Option<&PluginConfig>,
+) -> Result<(), PluginError> {
+    plugin.validate_config(config)?;
+
+    if let Err(e) = plugin.initialize(config.clone()).await {
+        tracing::error!("Failed to apply new config to plugin '{}': {}", instance_id, e);
+        if let Some(previous) = previous {
+            if let Err(rollback) = plugin.initialize(previous.clone()).await {
+                tracing::error!("Failed to restore previous config for plugin '{}': {}", instance_id, rollback);
+            }
+        }
+        return Err(e);
+    }
+
+    tracing::info!("Applied new config to plugin '{}'", instance_id);
+    Ok(())
+}
+
+async fn stop_with_timeout<P: Plugin + ?Sized>(instance_id: &str, plugin: &mut P, timeout: Duration) -> Result<(), PluginError> {
+    let result = match tokio::time::timeout(timeout, plugin.stop()).await {
+        Ok(result) => result,
+        Err(_) => Err(PluginError::RuntimeError(format!("stop timed out after {:?}", timeout))),
+    };
+
+    match &result {
+        Ok(()) => tracing::info!("Stopped plugin '{}'", instance_id),
+        Err(e) => tracing::error!("Failed to stop plugin '{}': {}", instance_id, e),
+    }
+    result
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(signal) => signal,
+            Err(e) => {
+                tracing::warn!("Failed to install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Errors worth retrying during startup; bad configuration won't fix itself
+fn is_transient(error: &PluginError) -> bool {
+    !matches!(
+        error,
+        PluginError::ConfigurationError(_)
+            | PluginError::ValidationError(_)
+            | PluginError::AuthenticationError(_)
+            | PluginError::NotFound(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::{AgentAction, AgentCapabilities, AgentFunction, AgentResponse, ChunkType, MessageRole};
+    use crate::sources::SourceCapabilities;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Shared view into what the fake source was asked to do
+    #[derive(Default)]
+    struct Probe {
+        searched: AtomicBool,
+        health_failures: AtomicUsize,
+        stops: AtomicUsize,
+        stop_fails: AtomicBool,
+        stop_hangs: AtomicBool,
+        readme_deleted: AtomicBool,
+    }
+
+    /// Read-only source without search
+    struct ArchiveSource {
+        metadata: PluginMetadata,
+        probe: Arc<Probe>,
+        status: PluginStatus,
+        greeting: String,
+    }
+
+    #[async_trait]
+    impl Plugin for ArchiveSource {
+        fn metadata(&self) -> &PluginMetadata { &self.metadata }
+        async fn initialize(&mut self, config: PluginConfig) -> Result<(), PluginError> {
+            self.greeting = config.settings.get("greeting")
+                .and_then(|v| v.as_str())
+                .unwrap_or("hello")
+                .to_string();
+            Ok(())
+        }
+        async fn start(&mut self) -> Result<(), PluginError> {
+            self.status = PluginStatus::Active;
+            Ok(())
+        }
+        async fn stop(&mut self) -> Result<(), PluginError> {
+            self.probe.stops.fetch_add(1, Ordering::SeqCst);
+            if self.probe.stop_hangs.load(Ordering::SeqCst) {
+                std::future::pending::<()>().await;
+            }
+            if self.probe.stop_fails.load(Ordering::SeqCst) {
+                return Err(PluginError::NetworkError("webhook deregistration failed".to_string()));
+            }
+            self.status = PluginStatus::Inactive;
+            Ok(())
+        }
+        fn status(&self) -> PluginStatus { self.status.clone() }
+        async fn health_check(&self) -> Result<bool, PluginError> {
+            let failing = self.probe.health_failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                Err(PluginError::NetworkError("upstream unavailable".to_string()))
+            } else {
+                Ok(true)
+            }
+        }
+        fn validate_config(&self, config: &PluginConfig) -> Result<(), PluginError> {
+            match config.settings.get("greeting") {
+                Some(v) if !v.is_string() => Err(PluginError::ValidationError("greeting must be a string".to_string())),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SourcePlugin for ArchiveSource {
+        fn capabilities(&self) -> SourceCapabilities {
+            SourceCapabilities {
+                can_read: true,
+                can_write: false,
+                can_delete: false,
+                supports_real_time: false,
+                supports_search: false,
+                supports_metadata: true,
+                max_file_size: None,
+                supported_formats: Vec::new(),
+            }
+        }
+        async fn list_documents(&self) -> PluginResult<Vec<Document>> {
+            if self.probe.readme_deleted.load(Ordering::SeqCst) {
+                return Ok(Vec::new());
+            }
+            Ok(vec![Document {
+                id: "readme".to_string(),
+                title: self.greeting.clone(),
+                content: String::new(),
+                content_type: "text/plain".to_string(),
+                size: 0,
+                created_at: chrono::Utc::now(),
+                modified_at: chrono::Utc::now(),
+                path: "/readme".to_string(),
+                metadata: HashMap::new(),
+                content_hash: None,
+            }])
+        }
+        async fn get_document(&self, id: &str) -> PluginResult<Document> { Err(PluginError::NotFound(id.to_string())) }
+        async fn search_documents(&self, _query: &str) -> PluginResult<Vec<Document>> {
+            self.probe.searched.store(true, Ordering::SeqCst);
+            Err(PluginError::RuntimeError("search reached the plugin".to_string()))
+        }
+        async fn sync(&self) -> PluginResult<SyncResult> {
+            Ok(SyncResult {
+                total_documents: 0,
+                new_documents: 0,
+                updated_documents: 0,
+                deleted_documents: 1,
+                deleted_ids: vec!["/old-notes".to_string()],
+                errors: Vec::new(),
+                duration_ms: 0,
+            })
+        }
+        async fn get_content(&self, _id: &str) -> PluginResult<Vec<u8>> { Ok(Vec::new()) }
+        async fn upload_document(&self, _document: Document, _content: Vec<u8>) -> PluginResult<String> {
+            Err(PluginError::RuntimeError("upload reached the plugin".to_string()))
+        }
+        async fn delete_document(&self, _id: &str) -> PluginResult<()> {
+            Err(PluginError::RuntimeError("delete reached the plugin".to_string()))
+        }
+        async fn setup_realtime_sync(&self) -> PluginResult<()> { Ok(()) }
+    }
+
+    struct ArchiveSourceFactory {
+        probe: Arc<Probe>,
+        source_type: &'static str,
+    }
+
+    impl SourcePluginFactory for ArchiveSourceFactory {
+        fn create(&self) -> Box<dyn SourcePlugin> {
+            Box::new(ArchiveSource {
+                metadata: PluginMetadata {
+                    id: "archive".to_string(),
+                    name: "Archive".to_string(),
+                    version: "0.1.0".to_string(),
+                    description: String::new(),
+                    author: String::new(),
+                    plugin_type: PluginType::Source,
+                    capabilities: Vec::new(),
+                    config_schema: None,
+                },
+                probe: self.probe.clone(),
+                status: PluginStatus::Inactive,
+                greeting: String::new(),
+            })
+        }
+
+        fn source_type(&self) -> &str {
+            self.source_type
+        }
+    }
+
+    fn registry_with_archive(probe: &Arc<Probe>) -> PluginRegistry {
+        let mut registry = PluginRegistry::new().with_startup_retry(StartupRetry {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        });
+        registry.register_source_factory(Box::new(ArchiveSourceFactory { probe: probe.clone(), source_type: "archive" }));
+        registry
+    }
+
+    fn enabled_config() -> PluginConfig {
+        PluginConfig { enabled: true, settings: HashMap::new() }
+    }
+
+    #[tokio::test]
+    async fn test_plugin_failing_first_health_check_ends_up_active() {
+        let probe = Arc::new(Probe::default());
+        probe.health_failures.store(1, Ordering::SeqCst);
+        let registry = registry_with_archive(&probe);
+
+        registry.load_source("archive", "archive-1", enabled_config()).await.unwrap();
+
+        assert_eq!(registry.get_plugin_status("archive-1").await, Some(PluginStatus::Active));
+        assert_eq!(probe.health_failures.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_marked_error_after_max_attempts() {
+        let probe = Arc::new(Probe::default());
+        probe.health_failures.store(10, Ordering::SeqCst);
+        let registry = registry_with_archive(&probe);
+
+        assert!(registry.load_source("archive", "archive-1", enabled_config()).await.is_err());
+
+        assert!(matches!(registry.get_plugin_status("archive-1").await, Some(PluginStatus::Error(_))));
+        assert_eq!(probe.health_failures.load(Ordering::SeqCst), 7);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_is_stopped_before_each_startup_retry() {
+        let probe = Arc::new(Probe::default());
+        probe.health_failures.store(2, Ordering::SeqCst);
+        probe.stop_fails.store(true, Ordering::SeqCst);
+        let registry = registry_with_archive(&probe);
+
+        registry.load_source("archive", "archive-1", enabled_config()).await.unwrap();
+
+        assert_eq!(registry.get_plugin_status("archive-1").await, Some(PluginStatus::Active));
+        assert_eq!(probe.stops.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_on_non_searchable_source_is_rejected_up_front() {
+        let probe = Arc::new(Probe::default());
+        let registry = registry_with_archive(&probe);
+        registry.load_source("archive", "archive-1", enabled_config()).await.unwrap();
+
+        let err = registry.search_source_documents("archive-1", "roadmap", None).await.unwrap_err();
+        assert!(matches!(err, PluginError::UnsupportedOperation(_)));
+        assert_eq!(err.status_code(), 405);
+        assert!(!probe.searched.load(Ordering::SeqCst));
+
+        let err = registry.delete_source_document("archive-1", "doc").await.unwrap_err();
+        assert!(matches!(err, PluginError::UnsupportedOperation(_)));
+        assert!(registry.sync_source_documents("archive-1").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stop_all_on_shutdown_stops_every_plugin() {
+        let healthy = Arc::new(Probe::default());
+        let failing = Arc::new(Probe::default());
+        failing.stop_fails.store(true, Ordering::SeqCst);
+        let hanging = Arc::new(Probe::default());
+        hanging.stop_hangs.store(true, Ordering::SeqCst);
+
+        let mut registry = registry_with_archive(&healthy);
+        registry.register_source_factory(Box::new(ArchiveSourceFactory { probe: failing.clone(), source_type: "failing" }));
+        registry.register_source_factory(Box::new(ArchiveSourceFactory { probe: hanging.clone(), source_type: "hanging" }));
+        registry.load_source("archive", "archive-1", enabled_config()).await.unwrap();
+        registry.load_source("failing", "failing-1", enabled_config()).await.unwrap();
+        registry.load_source("hanging", "hanging-1", enabled_config()).await.unwrap();
+
+        let mut failures: Vec<String> = registry
+            .stop_all(Duration::from_millis(20))
+            .await
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        failures.sort();
+
+        assert_eq!(failures, vec!["failing-1", "hanging-1"]);
+        for probe in [&healthy, &failing, &hanging] {
+            assert_eq!(probe.stops.load(Ordering::SeqCst), 1);
+        }
+        assert!(registry.list_active_sources().await.is_empty());
+    }
+
+    /// Agent that streams its reply word by word
+    struct EchoAgent {
+        metadata: PluginMetadata,
+    }
+
+    #[async_trait]
+    impl Plugin for EchoAgent {
+        fn metadata(&self) -> &PluginMetadata { &self.metadata }
+        async fn initialize(&mut self, _config: PluginConfig) -> Result<(), PluginError> { Ok(()) }
+        async fn start(&mut self) -> Result<(), PluginError> { Ok(()) }
+        async fn stop(&mut self) -> Result<(), PluginError> { Ok(()) }
+        fn status(&self) -> PluginStatus { PluginStatus::Active }
+        async fn health_check(&self) -> Result<bool, PluginError> { Ok(true) }
+        fn validate_config(&self, _config: &PluginConfig) -> Result<(), PluginError> { Ok(()) }
+    }
+
+    #[async_trait]
+    impl AgentPlugin for EchoAgent {
+        fn capabilities(&self) -> AgentCapabilities {
+            AgentCapabilities {
+                supports_chat: true,
+                supports_code_generation: false,
+                supports_code_analysis: false,
+                supports_file_operations: false,
+                supports_web_search: false,
+                supports_function_calling: true,
+                max_context_length: None,
+                supported_languages: Vec::new(),
+            }
+        }
+        async fn process_message(&self, _message: AgentMessage, _context: ConversationContext) -> PluginResult<AgentResponse> {
+            Err(PluginError::Unknown("unused".to_string()))
+        }
+        async fn execute_action(&self, action: AgentAction) -> PluginResult<serde_json::Value> {
+            // Indexes blindly, as a plugin trusting its input would
+            Ok(serde_json::json!({ "echo": action.parameters["text"] }))
+        }
+        async fn get_available_functions(&self) -> PluginResult<Vec<AgentFunction>> {
+            Ok(vec![AgentFunction {
+                name: "echo".to_string(),
+                description: "Repeat the text back".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": { "text": { "type": "string" } },
+                    "required": ["text"]
+                }),
+            }])
+        }
+        async fn stream_response(
+            &self,
+            message: AgentMessage,
+            _context: ConversationContext,
+        ) -> PluginResult<tokio::sync::mpsc::Receiver<AgentResponseChunk>> {
+            let (tx, rx) = tokio::sync::mpsc::channel(8);
+            tokio::spawn(async move {
+                for word in message.content.split_whitespace() {
+                    let chunk = AgentResponseChunk { chunk_type: ChunkType::Text, content: word.to_string(), metadata: None };
+                    if tx.send(chunk).await.is_err() {
+                        return;
+                    }
+                }
+                let _ = tx.send(AgentResponseChunk { chunk_type: ChunkType::Complete, content: String::new(), metadata: None }).await;
+            });
+            Ok(rx)
+        }
+    }
+
+    struct EchoAgentFactory;
+
+    impl AgentPluginFactory for EchoAgentFactory {
+        fn create(&self) -> Box<dyn AgentPlugin> {
+            Box::new(EchoAgent {
+                metadata: PluginMetadata {
+                    id: "echo".to_string(),
+                    name: "Echo".to_string(),
+                    version: "0.1.0".to_string(),
+                    description: String::new(),
+                    author: String::new(),
+                    plugin_type: PluginType::Agent,
+                    capabilities: Vec::new(),
+                    config_schema: None,
+                },
+            })
+        }
+
+        fn agent_type(&self) -> &str {
+            "echo"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_stream_is_forwarded_as_sse_events() {
+        let mut registry = PluginRegistry::new();
+        registry.register_agent_factory(Box::new(EchoAgentFactory));
+        registry.load_agent("echo", "echo-1", enabled_config()).await.unwrap();
+
+        let message = AgentMessage {
+            id: "m1".to_string(),
+            content: "hello there".to_string(),
+            role: MessageRole::User,
+            timestamp: chrono::Utc::now(),
+            metadata: HashMap::new(),
+        };
+        let context = ConversationContext {
+            user_id: "u1".to_string(),
+            conversation_id: "c1".to_string(),
+            messages: Vec::new(),
+            workspace_path: None,
+            active_files: Vec::new(),
+            user_preferences: HashMap::new(),
+        };
+
+        let mut rx = registry.stream_agent_response("echo-1", message, context).await.unwrap();
+        let mut frames = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            frames.push(chunk.to_sse());
+            if chunk.chunk_type.is_terminal() {
+                break;
+            }
+        }
+
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].starts_with("event: token\ndata: "));
+        assert!(frames[0].contains("\"content\":\"hello\""));
+        assert!(frames[1].contains("\"content\":\"there\""));
+        assert!(frames[2].starts_with("event: done\n"));
+        assert!(frames.iter().all(|f| f.ends_with("\n\n")));
+    }
+
+    #[tokio::test]
+    async fn test_action_missing_required_parameter_is_rejected_up_front() {
+        let mut registry = PluginRegistry::new();
+        registry.register_agent_factory(Box::new(EchoAgentFactory));
+        registry.load_agent("echo", "echo-1", enabled_config()).await.unwrap();
+
+        let functions = registry.get_agent_functions("echo-1").await.unwrap();
+        assert_eq!(functions[0].name, "echo");
+
+        let action = |parameters: serde_json::Value| AgentAction {
+            action_type: "echo".to_string(),
+            parameters: serde_json::from_value(parameters).unwrap(),
+            description: String::new(),
+        };
+        let err = registry.execute_agent_action("echo-1", action(serde_json::json!({}))).await.unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("parameters.text is required"));
+
+        let result = registry.execute_agent_action("echo-1", action(serde_json::json!({ "text": "hi" }))).await.unwrap();
+        assert_eq!(result["echo"], "hi");
+
+        let unknown = AgentAction { action_type: "delete_repo".to_string(), ..action(serde_json::json!({})) };
+        let err = registry.execute_agent_action("echo-1", unknown).await.unwrap_err();
+        assert!(err.to_string().contains("Unknown function 'delete_repo'"));
+    }
+
+    #[tokio::test]
+    async fn test_agent_calls_beyond_rate_limit_are_rejected_with_429() {
+        let mut registry = PluginRegistry::new();
+        registry.register_agent_factory(Box::new(EchoAgentFactory));
+        let config = PluginConfig {
+            enabled: true,
+            settings: HashMap::from([("rate_limit_per_minute".to_string(), serde_json::json!(2))]),
+        };
+        registry.load_agent("echo", "echo-1", config).await.unwrap();
+        registry.load_agent("echo", "echo-2", enabled_config()).await.unwrap();
+
+        let action = || AgentAction {
+            action_type: "echo".to_string(),
+            parameters: HashMap::from([("text".to_string(), serde_json::json!("hi"))]),
+            description: String::new(),
+        };
+        for _ in 0..2 {
+            assert!(registry.execute_agent_action("echo-1", action()).await.is_ok());
+        }
+        let err = registry.execute_agent_action("echo-1", action()).await.unwrap_err();
+        assert_eq!(err.status_code(), 429);
+        let retry_after = err.retry_after_secs().unwrap();
+        assert!((1..=30).contains(&retry_after));
+
+        // Other instances keep their own budget
+        for _ in 0..5 {
+            assert!(registry.execute_agent_action("echo-2", action()).await.is_ok());
+        }
+
+        // Lifting the limit takes effect on the live instance
+        registry.update_instance_config("echo-1", enabled_config()).await.unwrap();
+        assert!(registry.execute_agent_action("echo-1", action()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_config_update_applies_to_live_instance() {
+        let probe = Arc::new(Probe::default());
+        let registry = registry_with_archive(&probe);
+        registry.load_source("archive", "archive-1", enabled_config()).await.unwrap();
+
+        let title = |listing: DocumentListing| listing.documents[0].title.clone();
+        assert_eq!(title(registry.list_source_documents("archive-1").await.unwrap()), "hello");
+
+        let mut config = enabled_config();
+        config.settings.insert("greeting".to_string(), serde_json::json!("bonjour"));
+        registry.update_instance_config("archive-1", config).await.unwrap();
+        assert_eq!(title(registry.list_source_documents("archive-1").await.unwrap()), "bonjour");
+
+        let mut invalid = enabled_config();
+        invalid.settings.insert("greeting".to_string(), serde_json::json!(42));
+        let err = registry.update_instance_config("archive-1", invalid).await.unwrap_err();
+        assert!(matches!(err, PluginError::ValidationError(_)));
+        assert_eq!(title(registry.list_source_documents("archive-1").await.unwrap()), "bonjour");
+        assert_eq!(registry.get_plugin_status("archive-1").await, Some(PluginStatus::Active));
+    }
+
+    #[tokio::test]
+    async fn test_document_page_through_registry() {
+        let probe = Arc::new(Probe::default());
+        let registry = registry_with_archive(&probe);
+        registry.load_source("archive", "archive-1", enabled_config()).await.unwrap();
+
+        let page = registry.list_source_documents_page("archive-1", None, Some(0)).await.unwrap();
+        assert_eq!(page.documents.len(), 1);
+        assert!(page.next_cursor.is_none());
+
+        let err = registry
+            .list_source_documents_page("archive-1", Some(DocumentCursor("not-an-offset".to_string())), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), 400);
+
+        let err = registry.list_source_documents_page("missing", None, None).await.unwrap_err();
+        assert_eq!(err.status_code(), 404);
+    }
+
+    /// Records deletions, or fails every one when `fail` is set
+    struct RecordingSink {
+        fail: bool,
+        deletions: std::sync::Mutex<Vec<DocumentDeletion>>,
+    }
+
+    #[async_trait]
+    impl DeletionSink for RecordingSink {
+        fn name(&self) -> &str { "recording" }
+        async fn document_deleted(&self, deletion: &DocumentDeletion) -> PluginResult<()> {
+            if self.fail {
+                return Err(PluginError::NetworkError("index unavailable".to_string()));
+            }
+            self.deletions.lock().unwrap().push(deletion.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_propagates_upstream_deletions_to_sinks() {
+        let probe = Arc::new(Probe::default());
+        let sink = Arc::new(RecordingSink { fail: false, deletions: Default::default() });
+        let failing = Arc::new(RecordingSink { fail: true, deletions: Default::default() });
+        let registry = registry_with_archive(&probe)
+            .with_deletion_sink(failing)
+            .with_deletion_sink(sink.clone());
+        registry.load_source("archive", "archive-1", enabled_config()).await.unwrap();
+
+        // One sink failing neither fails the sync nor stops the others
+        let result = registry.sync_source_documents("archive-1").await.unwrap();
+        assert_eq!(result.deleted_ids, vec!["/old-notes".to_string()]);
+        assert_eq!(
+            *sink.deletions.lock().unwrap(),
+            vec![DocumentDeletion { instance_id: "archive-1".to_string(), document_id: "/old-notes".to_string() }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_document_missing_from_the_next_sync_is_propagated_as_deleted() {
+        let probe = Arc::new(Probe::default());
+        let sink = Arc::new(RecordingSink { fail: false, deletions: Default::default() });
+        let registry = registry_with_archive(&probe).with_deletion_sink(sink.clone());
+        registry.load_source("archive", "archive-1", enabled_config()).await.unwrap();
+
+        let first = registry.sync_source_documents("archive-1").await.unwrap();
+        assert_eq!(first.deleted_ids, vec!["/old-notes".to_string()]);
+
+        probe.readme_deleted.store(true, Ordering::SeqCst);
+        let second = registry.sync_source_documents("archive-1").await.unwrap();
+        assert_eq!(second.deleted_ids, vec!["/old-notes".to_string(), "readme".to_string()]);
+        assert_eq!(second.deleted_documents, 2);
+
+        // Already reported, so a third sync doesn't deliver it again
+        let third = registry.sync_source_documents("archive-1").await.unwrap();
+        assert_eq!(third.deleted_ids, vec!["/old-notes".to_string()]);
+        let delivered: Vec<String> = sink.deletions.lock().unwrap().iter().map(|d| d.document_id.clone()).collect();
+        assert_eq!(delivered, vec!["/old-notes", "/old-notes", "readme", "/old-notes"]);
+    }
+}
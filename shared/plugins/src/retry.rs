@@ -0,0 +1,199 @@
+use crate::{error::PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// HTTP statuses worth retrying: timeouts, rate limits and transient server errors
+pub const DEFAULT_RETRYABLE_STATUSES: &[u16] = &[408, 425, 429, 500, 502, 503, 504];
+
+/// Retry policy for source API calls. Each plugin instance builds one from its
+/// settings so retry behavior can be tuned per connection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Fraction of each delay that is randomized, from 0.0 (none) to 1.0
+    pub jitter: f64,
+    pub retryable_statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.2,
+            retryable_statuses: DEFAULT_RETRYABLE_STATUSES.to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Read `retry_max_attempts`, `retry_initial_backoff_ms`, `retry_max_backoff_ms`
+    /// and `retry_jitter` from plugin settings
+    pub fn from_settings(settings: &HashMap<String, serde_json::Value>) -> Self {
+        let defaults = Self::default();
+        let millis = |key: &str| settings.get(key).and_then(|v| v.as_u64()).map(Duration::from_millis);
+        Self {
+            max_attempts: settings.get("retry_max_attempts")
+                .and_then(|v| v.as_u64())
+                .map(|n| n.clamp(1, u32::MAX as u64) as u32)
+                .unwrap_or(defaults.max_attempts),
+            initial_backoff: millis("retry_initial_backoff_ms").unwrap_or(defaults.initial_backoff),
+            max_backoff: millis("retry_max_backoff_ms").unwrap_or(defaults.max_backoff),
+            jitter: settings.get("retry_jitter")
+                .and_then(|v| v.as_f64())
+                .map(|j| j.clamp(0.0, 1.0))
+                .unwrap_or(defaults.jitter),
+            retryable_statuses: defaults.retryable_statuses,
+        }
+    }
+
+    pub fn is_retryable_status(&self, status: u16) -> bool {
+        self.retryable_statuses.contains(&status)
+    }
+
+    /// Delay before retry number `retry` (1-based), before jitter: doubles each
+    /// time up to `max_backoff`
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    /// `backoff` with up to `jitter` of it randomly shaved off, so callers that
+    /// failed together don't retry together
+    pub fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return backoff;
+        }
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        backoff.mul_f64(1.0 - jitter * random)
+    }
+
+    /// Run `operation` until it succeeds, fails with an error `is_retryable`
+    /// rejects, or runs out of attempts. The last error is returned.
+    pub async fn retry<T, E, F, Fut>(&self, mut operation: F, is_retryable: impl Fn(&E) -> bool) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: std::fmt::Display,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < max_attempts && is_retryable(&e) => {
+                    let delay = self.delay(attempt);
+                    tracing::debug!("Attempt {}/{} failed: {}; retrying in {:?}", attempt, max_attempts, e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// `retry` for plugin calls, retrying network and upstream failures
+    pub async fn retry_plugin<T, F, Fut>(&self, operation: F) -> PluginResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = PluginResult<T>>,
+    {
+        self.retry(operation, |e| {
+            matches!(e, PluginError::NetworkError(_) | PluginError::DependencyError(_))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            jitter: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backoff_sequence_and_jitter_bounds() {
+        let policy = policy();
+        let delays: Vec<u128> = (1..=4).map(|n| policy.delay(n).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 300, 300]);
+
+        let jittered = RetryPolicy { jitter: 0.5, ..policy };
+        for _ in 0..20 {
+            let delay = jittered.delay(2);
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_retryable_status_classification() {
+        let policy = RetryPolicy::default();
+        for status in [408, 429, 500, 502, 503, 504] {
+            assert!(policy.is_retryable_status(status), "{} should be retried", status);
+        }
+        for status in [200, 400, 401, 403, 404, 409] {
+            assert!(!policy.is_retryable_status(status), "{} should not be retried", status);
+        }
+    }
+
+    #[test]
+    fn test_policy_from_instance_settings() {
+        let settings: HashMap<String, serde_json::Value> = serde_json::from_value(serde_json::json!({
+            "retry_max_attempts": 6,
+            "retry_initial_backoff_ms": 50,
+            "retry_jitter": 3.0
+        }))
+        .unwrap();
+
+        let policy = RetryPolicy::from_settings(&settings);
+        assert_eq!(policy.max_attempts, 6);
+        assert_eq!(policy.initial_backoff, Duration::from_millis(50));
+        assert_eq!(policy.max_backoff, RetryPolicy::default().max_backoff);
+        assert_eq!(policy.jitter, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_permanent_errors() {
+        let policy = RetryPolicy { initial_backoff: Duration::from_millis(1), ..policy() };
+
+        let calls = AtomicU32::new(0);
+        let result: PluginResult<&str> = policy
+            .retry_plugin(|| async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(PluginError::NetworkError("503 Service Unavailable".to_string())),
+                    _ => Ok("listed"),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "listed");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = AtomicU32::new(0);
+        let result: PluginResult<()> = policy
+            .retry_plugin(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(PluginError::AuthenticationError("token revoked".to_string()))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,7 +1,13 @@
-use crate::{Plugin, PluginResult};
+use crate::{error::PluginError, Plugin, PluginResult};
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 
 /// Document metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +21,59 @@ pub struct Document {
     pub modified_at: chrono::DateTime<chrono::Utc>,
     pub path: String,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Hex SHA-256 of the content bytes, so the same file from two sources is
+    /// recognized as one. Unset when the source can't compute it cheaply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+}
+
+/// Hex SHA-256 of `bytes`, the form stored in `Document::content_hash`
+pub fn content_sha256(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+impl Document {
+    /// Set `content_hash` from the inline `content`, for sources that fetch it
+    /// while listing. Leaves it unset when there is no inline content.
+    pub fn with_content_hash(mut self) -> Self {
+        if !self.content.is_empty() {
+            self.content_hash = Some(content_sha256(self.content.as_bytes()));
+        }
+        self
+    }
+}
+
+/// A document found in one or more sources with identical content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergedDocument {
+    /// The first copy seen
+    pub document: Document,
+    /// Every copy as (source instance id, document id), first copy included
+    pub found_in: Vec<(String, String)>,
+}
+
+/// Collapse documents with the same `content_hash` into one entry, keeping
+/// the first copy and recording where each copy came from. Documents
+/// without a hash are never merged.
+pub fn merge_by_content_hash<I>(documents: I) -> Vec<MergedDocument>
+where
+    I: IntoIterator<Item = (String, Document)>,
+{
+    let mut merged: Vec<MergedDocument> = Vec::new();
+    let mut by_hash: HashMap<String, usize> = HashMap::new();
+    for (instance_id, document) in documents {
+        let copy = (instance_id, document.id.clone());
+        match document.content_hash.as_ref().and_then(|hash| by_hash.get(hash)) {
+            Some(&i) => merged[i].found_in.push(copy),
+            None => {
+                if let Some(hash) = &document.content_hash {
+                    by_hash.insert(hash.clone(), merged.len());
+                }
+                merged.push(MergedDocument { document, found_in: vec![copy] });
+            }
+        }
+    }
+    merged
 }
 
 /// Sync operation result
@@ -24,10 +83,210 @@ pub struct SyncResult {
     pub new_documents: u64,
     pub updated_documents: u64,
     pub deleted_documents: u64,
+    /// Ids of the documents found deleted upstream, propagated to the search indexes
+    #[serde(default)]
+    pub deleted_ids: Vec<String>,
     pub errors: Vec<String>,
     pub duration_ms: u64,
 }
 
+/// A document removed from a source, upstream or through the API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentDeletion {
+    pub instance_id: String,
+    pub document_id: String,
+}
+
+/// Removes deleted documents from an index built from source content (Lexor,
+/// the vector index). Deleting a document that isn't indexed must succeed, as
+/// the same deletion may be delivered more than once.
+#[async_trait]
+pub trait DeletionSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn document_deleted(&self, deletion: &DocumentDeletion) -> PluginResult<()>;
+}
+
+/// A source item that could not be converted into a `Document`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentFailure {
+    pub id: String,
+    pub error: String,
+}
+
+/// Documents listed from a source, plus the items that failed to convert
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentListing {
+    pub documents: Vec<Document>,
+    pub failures: Vec<DocumentFailure>,
+}
+
+impl DocumentListing {
+    /// Split per-item conversion results so one bad item doesn't hide the rest.
+    /// Each failure is logged against `source`.
+    pub fn collect<I>(source: &str, results: I) -> Self
+    where
+        I: IntoIterator<Item = (String, PluginResult<Document>)>,
+    {
+        let mut listing = Self::default();
+        for (id, result) in results {
+            match result {
+                Ok(document) => listing.documents.push(document),
+                Err(e) => {
+                    tracing::warn!("{}: failed to convert item {}: {}", source, id, e);
+                    listing.failures.push(DocumentFailure { id, error: e.to_string() });
+                }
+            }
+        }
+
+        if !listing.failures.is_empty() {
+            tracing::warn!(
+                "{}: listed {} documents, {} failed",
+                source,
+                listing.documents.len(),
+                listing.failures.len()
+            );
+        }
+
+        listing
+    }
+}
+
+/// Opaque position in a paginated listing. Sources with native paging keep their
+/// own token here (a Drive `pageToken`, a Dropbox `list_folder` cursor); the
+/// default `list_documents_page` stores an offset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DocumentCursor(pub String);
+
+impl DocumentCursor {
+    pub fn from_offset(offset: usize) -> Self {
+        Self(offset.to_string())
+    }
+
+    /// Offset stored by `from_offset`; anything else is a cursor from another source
+    pub fn offset(&self) -> PluginResult<usize> {
+        self.0.parse().map_err(|_| PluginError::ValidationError(format!("invalid cursor '{}'", self.0)))
+    }
+}
+
+/// One page of a listing, as returned to API callers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentPage {
+    pub documents: Vec<Document>,
+    /// Pass back to fetch the next page; absent on the last page
+    pub next_cursor: Option<DocumentCursor>,
+}
+
+/// Document content delivered chunk by chunk, so large files needn't fit in memory
+pub type ContentStream = Pin<Box<dyn Stream<Item = PluginResult<Bytes>> + Send>>;
+
+/// Chunk size used when streaming content that was already buffered
+pub const CONTENT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Stream buffered content in `chunk_size` pieces without copying it
+pub fn chunked_content(content: Bytes, chunk_size: usize) -> ContentStream {
+    let chunk_size = chunk_size.max(1);
+    let chunks = (0..content.len())
+        .step_by(chunk_size)
+        .map(move |start| Ok(content.slice(start..(start + chunk_size).min(content.len()))))
+        .collect::<Vec<_>>();
+    Box::pin(futures_util::stream::iter(chunks))
+}
+
+/// Opt-in content prefetch during sync, so ingestion has content ready
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefetchConfig {
+    pub enabled: bool,
+    /// Upper bound on concurrent content downloads, to stay within source rate limits
+    pub max_concurrency: usize,
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrency: 4,
+        }
+    }
+}
+
+impl PrefetchConfig {
+    /// Read `prefetch_content` and `prefetch_concurrency` from plugin settings
+    pub fn from_settings(settings: &HashMap<String, serde_json::Value>) -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: settings.get("prefetch_content")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(defaults.enabled),
+            max_concurrency: settings.get("prefetch_concurrency")
+                .and_then(|v| v.as_u64())
+                .map(|n| n.max(1) as usize)
+                .unwrap_or(defaults.max_concurrency),
+        }
+    }
+}
+
+/// Whether a content type is worth downloading as text
+pub fn is_text_like(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || matches!(
+            content_type,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-yaml"
+                | "application/vnd.google-apps.document"
+        )
+}
+
+/// Fetch content for text-like documents that don't have it yet, with at most
+/// `config.max_concurrency` downloads in flight. Failed fetches are returned and
+/// leave the document's content empty. Does nothing unless prefetch is enabled.
+pub async fn prefetch_contents(
+    plugin: Arc<dyn SourcePlugin>,
+    documents: &mut [Document],
+    config: &PrefetchConfig,
+) -> Vec<DocumentFailure> {
+    if !config.enabled {
+        return Vec::new();
+    }
+
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrency.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for (index, document) in documents.iter().enumerate() {
+        if !document.content.is_empty() || !is_text_like(&document.content_type) {
+            continue;
+        }
+
+        let plugin = plugin.clone();
+        let semaphore = semaphore.clone();
+        let id = document.id.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("prefetch semaphore closed");
+            let result = plugin.get_content(&id).await;
+            (index, id, result)
+        });
+    }
+
+    let mut failures = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, _, Ok(bytes))) => {
+                documents[index].content = String::from_utf8_lossy(&bytes).into_owned();
+            }
+            Ok((_, id, Err(e))) => {
+                tracing::warn!("Failed to prefetch content for {}: {}", id, e);
+                failures.push(DocumentFailure { id, error: e.to_string() });
+            }
+            Err(e) => tracing::error!("Prefetch task failed: {}", e),
+        }
+    }
+
+    failures
+}
+
 /// Source capabilities
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceCapabilities {
@@ -41,6 +300,52 @@ pub struct SourceCapabilities {
     pub supported_formats: Vec<String>,
 }
 
+/// Operations a source instance may or may not support
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceOperation {
+    Read,
+    Write,
+    Delete,
+    Search,
+    RealTime,
+}
+
+impl SourceOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceOperation::Read => "read",
+            SourceOperation::Write => "write",
+            SourceOperation::Delete => "delete",
+            SourceOperation::Search => "search",
+            SourceOperation::RealTime => "real-time sync",
+        }
+    }
+}
+
+impl SourceCapabilities {
+    pub fn supports(&self, operation: SourceOperation) -> bool {
+        match operation {
+            SourceOperation::Read => self.can_read,
+            SourceOperation::Write => self.can_write,
+            SourceOperation::Delete => self.can_delete,
+            SourceOperation::Search => self.supports_search,
+            SourceOperation::RealTime => self.supports_real_time,
+        }
+    }
+
+    /// Reject an operation the source doesn't declare, before calling into the plugin
+    pub fn require(&self, operation: SourceOperation) -> PluginResult<()> {
+        if self.supports(operation) {
+            Ok(())
+        } else {
+            Err(PluginError::UnsupportedOperation(format!(
+                "source does not support {}",
+                operation.as_str()
+            )))
+        }
+    }
+}
+
 /// Source plugin trait
 #[async_trait]
 pub trait SourcePlugin: Plugin {
@@ -49,6 +354,38 @@ pub trait SourcePlugin: Plugin {
     
     /// List all documents
     async fn list_documents(&self) -> PluginResult<Vec<Document>>;
+
+    /// List documents, reporting items that failed to convert instead of aborting.
+    /// Sources that convert item by item should override this and build the
+    /// result with `DocumentListing::collect`.
+    async fn list_documents_with_failures(&self) -> PluginResult<DocumentListing> {
+        Ok(DocumentListing {
+            documents: self.list_documents().await?,
+            failures: Vec::new(),
+        })
+    }
+    
+    /// List at most `limit` documents starting at `cursor` (the first page when
+    /// `None`), returning the cursor for the next page. The default pages over
+    /// `list_documents`, so it still loads everything; sources whose API pages
+    /// natively should override this and pass the API's token through.
+    async fn list_documents_page(
+        &self,
+        cursor: Option<DocumentCursor>,
+        limit: usize,
+    ) -> PluginResult<(Vec<Document>, Option<DocumentCursor>)> {
+        let offset = match cursor {
+            Some(cursor) => cursor.offset()?,
+            None => 0,
+        };
+        let documents = self.list_documents().await?;
+        let total = documents.len();
+        let page: Vec<Document> = documents.into_iter().skip(offset).take(limit.max(1)).collect();
+
+        let end = offset + page.len();
+        let next = (end < total).then(|| DocumentCursor::from_offset(end));
+        Ok((page, next))
+    }
     
     /// Get a specific document by ID
     async fn get_document(&self, id: &str) -> PluginResult<Document>;
@@ -61,6 +398,14 @@ pub trait SourcePlugin: Plugin {
     
     /// Get document content
     async fn get_content(&self, id: &str) -> PluginResult<Vec<u8>>;
+
+    /// Get document content as a stream of chunks. The default buffers through
+    /// `get_content`; sources that download over HTTP should override this and
+    /// forward the response body as it arrives.
+    async fn get_content_stream(&self, id: &str) -> PluginResult<ContentStream> {
+        let content = self.get_content(id).await?;
+        Ok(chunked_content(Bytes::from(content), CONTENT_CHUNK_SIZE))
+    }
     
     /// Upload document (if supported)
     async fn upload_document(&self, document: Document, content: Vec<u8>) -> PluginResult<String>;
@@ -76,4 +421,238 @@ pub trait SourcePlugin: Plugin {
 pub trait SourcePluginFactory: Send + Sync {
     fn create(&self) -> Box<dyn SourcePlugin>;
     fn source_type(&self) -> &str;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn convert_file(file: &Value) -> PluginResult<Document> {
+        let id = file["id"].as_str()
+            .ok_or_else(|| PluginError::ValidationError("file has no id".to_string()))?;
+        let title = file["name"].as_str()
+            .ok_or_else(|| PluginError::ValidationError(format!("file {} has no name", id)))?;
+
+        Ok(Document {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: String::new(),
+            content_type: file["mimeType"].as_str().unwrap_or("application/octet-stream").to_string(),
+            size: 0,
+            created_at: chrono::Utc::now(),
+            modified_at: chrono::Utc::now(),
+            path: format!("/{}", title),
+            metadata: HashMap::new(),
+            content_hash: None,
+        })
+    }
+
+    fn document(id: &str, content_type: &str) -> Document {
+        convert_file(&json!({"id": id, "name": id, "mimeType": content_type})).unwrap()
+    }
+
+    /// Source whose content fetches take a while and record how many overlap
+    struct SlowSource {
+        metadata: crate::PluginMetadata,
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+        listed: Vec<Document>,
+    }
+
+    impl SlowSource {
+        fn new() -> Self {
+            Self {
+                metadata: crate::PluginMetadata {
+                    id: "slow".to_string(),
+                    name: "Slow".to_string(),
+                    version: "0.1.0".to_string(),
+                    description: String::new(),
+                    author: String::new(),
+                    plugin_type: crate::PluginType::Source,
+                    capabilities: Vec::new(),
+                    config_schema: None,
+                },
+                in_flight: Default::default(),
+                max_in_flight: Default::default(),
+                listed: Vec::new(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Plugin for SlowSource {
+        fn metadata(&self) -> &crate::PluginMetadata { &self.metadata }
+        async fn initialize(&mut self, _config: crate::PluginConfig) -> Result<(), PluginError> { Ok(()) }
+        async fn start(&mut self) -> Result<(), PluginError> { Ok(()) }
+        async fn stop(&mut self) -> Result<(), PluginError> { Ok(()) }
+        fn status(&self) -> crate::PluginStatus { crate::PluginStatus::Active }
+        async fn health_check(&self) -> Result<bool, PluginError> { Ok(true) }
+        fn validate_config(&self, _config: &crate::PluginConfig) -> Result<(), PluginError> { Ok(()) }
+    }
+
+    #[async_trait]
+    impl SourcePlugin for SlowSource {
+        fn capabilities(&self) -> SourceCapabilities {
+            SourceCapabilities {
+                can_read: true,
+                can_write: false,
+                can_delete: false,
+                supports_real_time: false,
+                supports_search: false,
+                supports_metadata: true,
+                max_file_size: None,
+                supported_formats: Vec::new(),
+            }
+        }
+        async fn list_documents(&self) -> PluginResult<Vec<Document>> { Ok(self.listed.clone()) }
+        async fn get_document(&self, id: &str) -> PluginResult<Document> { Err(PluginError::NotFound(id.to_string())) }
+        async fn search_documents(&self, _query: &str) -> PluginResult<Vec<Document>> { Ok(Vec::new()) }
+        async fn sync(&self) -> PluginResult<SyncResult> { Err(PluginError::Unknown("unused".to_string())) }
+        async fn get_content(&self, id: &str) -> PluginResult<Vec<u8>> {
+            use std::sync::atomic::Ordering;
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(format!("content of {}", id).into_bytes())
+        }
+        async fn upload_document(&self, _document: Document, _content: Vec<u8>) -> PluginResult<String> {
+            Err(PluginError::PermissionError("read-only".to_string()))
+        }
+        async fn delete_document(&self, _id: &str) -> PluginResult<()> {
+            Err(PluginError::PermissionError("read-only".to_string()))
+        }
+        async fn setup_realtime_sync(&self) -> PluginResult<()> { Ok(()) }
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_concurrency_is_bounded() {
+        let source = Arc::new(SlowSource::new());
+        let mut documents: Vec<Document> = (0..12).map(|i| document(&format!("doc-{}", i), "text/plain")).collect();
+        documents.push(document("image", "image/png"));
+
+        let config = PrefetchConfig { enabled: true, max_concurrency: 3 };
+        let failures = prefetch_contents(source.clone(), &mut documents, &config).await;
+
+        assert!(failures.is_empty());
+        assert_eq!(source.max_in_flight.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(documents[0].content, "content of doc-0");
+        assert!(documents[12].content.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_is_opt_in() {
+        let source = Arc::new(SlowSource::new());
+        let mut documents = vec![document("doc", "text/plain")];
+
+        prefetch_contents(source.clone(), &mut documents, &PrefetchConfig::default()).await;
+
+        assert!(documents[0].content.is_empty());
+        assert_eq!(source.max_in_flight.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_malformed_file_does_not_abort_listing() {
+        let files = [
+            json!({"id": "a", "name": "notes.txt", "mimeType": "text/plain"}),
+            json!({"id": "b", "mimeType": "text/plain"}),
+            json!({"id": "c", "name": "plan.md", "mimeType": "text/markdown"}),
+        ];
+
+        let listing = DocumentListing::collect(
+            "google-drive",
+            files.iter().map(|f| (f["id"].as_str().unwrap_or_default().to_string(), convert_file(f))),
+        );
+
+        let ids: Vec<&str> = listing.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+        assert_eq!(listing.failures.len(), 1);
+        assert_eq!(listing.failures[0].id, "b");
+        assert!(listing.failures[0].error.contains("no name"));
+    }
+
+    #[tokio::test]
+    async fn test_default_pagination_cursor_round_trip() {
+        let mut source = SlowSource::new();
+        source.listed = (0..5).map(|i| document(&format!("doc-{}", i), "text/plain")).collect();
+
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let (page, next) = source.list_documents_page(cursor, 2).await.unwrap();
+            ids.extend(page.into_iter().map(|d| d.id));
+            pages += 1;
+            // Cursors survive a trip through the API as plain strings
+            let json = serde_json::to_string(&next).unwrap();
+            cursor = serde_json::from_str(&json).unwrap();
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(pages, 3);
+        assert_eq!(ids, vec!["doc-0", "doc-1", "doc-2", "doc-3", "doc-4"]);
+
+        let err = source.list_documents_page(Some(DocumentCursor("pageToken-xyz".to_string())), 2).await.unwrap_err();
+        assert!(matches!(err, PluginError::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_large_content_streams_in_chunks() {
+        use futures_util::StreamExt;
+
+        let content: Vec<u8> = (0..CONTENT_CHUNK_SIZE * 3 + 100).map(|i| (i % 251) as u8).collect();
+        let mut stream = chunked_content(Bytes::from(content.clone()), CONTENT_CHUNK_SIZE);
+
+        let mut sizes = Vec::new();
+        let mut received = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            sizes.push(chunk.len());
+            received.extend_from_slice(&chunk);
+        }
+
+        assert_eq!(sizes, vec![CONTENT_CHUNK_SIZE, CONTENT_CHUNK_SIZE, CONTENT_CHUNK_SIZE, 100]);
+        assert_eq!(received, content);
+
+        // The default implementation goes through get_content
+        let source = SlowSource::new();
+        let chunks: Vec<_> = source.get_content_stream("doc").await.unwrap().collect().await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].as_ref().unwrap().as_ref(), b"content of doc");
+    }
+
+    #[test]
+    fn test_identical_content_from_two_sources_shares_a_hash() {
+        let with_content = |id: &str, content: &str| Document {
+            content: content.to_string(),
+            ..document(id, "text/markdown")
+        }
+        .with_content_hash();
+
+        let from_repo = with_content("README.md", "# Billing\n\nOwned by payments.");
+        let from_drive = with_content("drive-file-17", "# Billing\n\nOwned by payments.");
+        let other = with_content("CHANGELOG.md", "# Changes");
+        assert_eq!(from_repo.content_hash, from_drive.content_hash);
+        assert_eq!(from_repo.content_hash.as_deref(), Some(content_sha256(b"# Billing\n\nOwned by payments.").as_str()));
+        assert_ne!(from_repo.content_hash, other.content_hash);
+        // Sources that can't hash cheaply leave it unset and are never merged
+        assert_eq!(document("empty", "text/plain").with_content_hash().content_hash, None);
+
+        let merged = merge_by_content_hash(vec![
+            ("github-1".to_string(), from_repo),
+            ("gdrive-1".to_string(), from_drive),
+            ("github-1".to_string(), other),
+            ("gdrive-1".to_string(), document("a", "text/plain")),
+            ("gdrive-1".to_string(), document("b", "text/plain")),
+        ]);
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged[0].document.id, "README.md");
+        assert_eq!(merged[0].found_in, vec![
+            ("github-1".to_string(), "README.md".to_string()),
+            ("gdrive-1".to_string(), "drive-file-17".to_string()),
+        ]);
+    }
+}
@@ -29,11 +29,38 @@ impl Default for PoolConfig {
             min_idle: 5,
             max_lifetime: Duration::from_secs(3600),
             idle_timeout: Duration::from_secs(600),
-            acquire_timeout: Duration::from_secs(30),
+            acquire_timeout: Duration::from_secs(5),
         }
     }
 }
 
+impl PoolConfig {
+    /// Read `DB_MAX_CONNECTIONS`, `DB_MIN_CONNECTIONS`, `DB_ACQUIRE_TIMEOUT` and
+    /// `DB_IDLE_TIMEOUT` (seconds), keeping the default for anything unset
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_connections: var("DB_MAX_CONNECTIONS").map(|n| n.max(1) as u32).unwrap_or(defaults.max_connections),
+            min_idle: var("DB_MIN_CONNECTIONS").map(|n| n as u32).unwrap_or(defaults.min_idle),
+            max_lifetime: defaults.max_lifetime,
+            idle_timeout: var("DB_IDLE_TIMEOUT").map(Duration::from_secs).unwrap_or(defaults.idle_timeout),
+            acquire_timeout: var("DB_ACQUIRE_TIMEOUT").map(Duration::from_secs).unwrap_or(defaults.acquire_timeout),
+        }
+    }
+
+    /// Postgres pool options with these limits. Acquiring a connection fails with
+    /// `sqlx::Error::PoolTimedOut` after `acquire_timeout` instead of waiting indefinitely.
+    pub fn pg_pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_idle.min(self.max_connections))
+            .max_lifetime(self.max_lifetime)
+            .idle_timeout(self.idle_timeout)
+            .acquire_timeout(self.acquire_timeout)
+    }
+}
+
 struct PoolEntry<T> {
     pool: T,
     created_at: Instant,
@@ -62,12 +89,7 @@ impl ConnectionPoolManager {
         let connect_options = PgConnectOptions::from_str(database_url)?
             .statement_cache_capacity(0);
 
-        let pool = PgPoolOptions::new()
-            .max_connections(self.config.max_connections)
-            .min_connections(self.config.min_idle)
-            .max_lifetime(self.config.max_lifetime)
-            .idle_timeout(self.config.idle_timeout)
-            .acquire_timeout(self.config.acquire_timeout)
+        let pool = self.config.pg_pool_options()
             .test_before_acquire(true)
             .connect_with(connect_options)
             .await?;
@@ -132,3 +154,35 @@ lazy_static::lazy_static! {
 pub fn get_pool_manager() -> &'static ConnectionPoolManager {
     &GLOBAL_POOL_MANAGER
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_timeout_returns_promptly() {
+        // Accepts connections but never answers the startup handshake
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let config = PoolConfig {
+            max_connections: 1,
+            min_idle: 0,
+            acquire_timeout: Duration::from_millis(200),
+            ..PoolConfig::default()
+        };
+        let options = PgConnectOptions::from_str(&format!("postgres://conhub@127.0.0.1:{}/conhub", port)).unwrap();
+        let pool = config.pg_pool_options().connect_lazy_with(options);
+
+        let started = Instant::now();
+        let err = pool.acquire().await.unwrap_err();
+        assert!(matches!(err, sqlx::Error::PoolTimedOut), "unexpected error: {}", err);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
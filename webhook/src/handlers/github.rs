@@ -1,24 +1,60 @@
 use actix_web::{web, HttpResponse, HttpRequest, Result};
 use serde_json::Value;
+use sqlx::PgPool;
+
+use super::{dispatch_job, verify_hmac_signature};
+use crate::retry_queue::{RetryQueue, WebhookJob};
 
 /// GitHub webhook handler
 /// POST /api/webhooks/github
 pub async fn handle_github_webhook(
     req: HttpRequest,
-    payload: web::Json<Value>,
+    body: String,
+    pool: Option<web::Data<PgPool>>,
+    queue: web::Data<RetryQueue>,
 ) -> Result<HttpResponse> {
     tracing::info!("Received GitHub webhook");
 
-    // Verify webhook signature
-    if let Some(signature) = req.headers().get("X-Hub-Signature-256") {
-        let signature_str = signature.to_str()
-            .map_err(|_| actix_web::error::ErrorUnauthorized("Invalid signature format"))?;
+    let signature = match req.headers().get("X-Hub-Signature-256") {
+        Some(signature) => signature.to_str()
+            .map_err(|_| actix_web::error::ErrorUnauthorized("Invalid signature format"))?
+            .to_string(),
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Missing X-Hub-Signature-256 header"
+            })));
+        }
+    };
+
+    // Parse payload
+    let payload_value: Value = serde_json::from_str(&body)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid JSON: {}", e)))?;
+
+    // Verify with the repository's own secret, falling back to the global one
+    let repo_full_name = payload_value
+        .get("repository")
+        .and_then(|r| r.get("full_name"))
+        .and_then(|n| n.as_str());
+
+    let repo_secrets = match (pool.as_ref(), repo_full_name) {
+        (Some(pool), Some(full_name)) => lookup_repo_webhook_secrets(pool.get_ref(), full_name).await,
+        _ => Vec::new(),
+    };
+    let global_secret = std::env::var("GITHUB_WEBHOOK_SECRET").ok();
+
+    let candidates = candidate_webhook_secrets(&repo_secrets, global_secret.as_deref());
+    if candidates.is_empty() {
+        tracing::warn!("No webhook secret configured for repository {:?}", repo_full_name);
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "No webhook secret configured for repository"
+        })));
+    }
 
-        // TODO: Verify HMAC-SHA256 signature
-        tracing::debug!("GitHub signature present: {} chars", signature_str.len());
-    } else {
+    let signature_hex = signature.strip_prefix("sha256=").unwrap_or(&signature);
+    if matching_webhook_secret(body.as_bytes(), signature_hex, &candidates).is_none() {
+        tracing::warn!("Invalid GitHub webhook signature for repository {:?}", repo_full_name);
         return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-            "error": "Missing X-Hub-Signature-256 header"
+            "error": "Invalid webhook signature"
         })));
     }
 
@@ -30,15 +66,10 @@ pub async fn handle_github_webhook(
 
     tracing::info!("GitHub event type: {}", event_type);
 
-    let payload_value = payload.into_inner();
-
-    // Process webhook asynchronously
-    let event_type_owned = event_type.to_string();
-    tokio::spawn(async move {
-        if let Err(e) = process_github_webhook(&event_type_owned, payload_value).await {
-            tracing::error!("Failed to process GitHub webhook: {}", e);
-        }
-    });
+    // Process webhook asynchronously, retrying if the triggered work fails
+    let job = WebhookJob::new("github", None, event_type, payload_value);
+    let queue = queue.into_inner();
+    tokio::spawn(async move { queue.run(job, dispatch_job).await });
 
     // Return 200 OK immediately
     Ok(HttpResponse::Ok().json(serde_json::json!({
@@ -46,6 +77,47 @@ pub async fn handle_github_webhook(
     })))
 }
 
+/// Per-repository secrets from `github_repo_configs`. Several active configs can
+/// share a `full_name` (one per connecting user), each with its own secret.
+async fn lookup_repo_webhook_secrets(pool: &PgPool, full_name: &str) -> Vec<String> {
+    let result = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT webhook_secret FROM github_repo_configs WHERE full_name = $1 AND is_active = true"
+    )
+    .bind(full_name)
+    .fetch_all(pool)
+    .await;
+
+    match result {
+        Ok(secrets) => secrets.into_iter().flatten().filter(|s| !s.is_empty()).collect(),
+        Err(e) => {
+            tracing::error!("Failed to load webhook secrets for {}: {}", full_name, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Secrets a webhook may be signed with: the repository's own secrets when any
+/// are set, otherwise the global one
+pub fn candidate_webhook_secrets<'a>(repo_secrets: &'a [String], global_secret: Option<&'a str>) -> Vec<&'a str> {
+    let repo: Vec<&str> = repo_secrets
+        .iter()
+        .map(String::as_str)
+        .filter(|s| !s.is_empty())
+        .collect();
+    if !repo.is_empty() {
+        return repo;
+    }
+    global_secret.filter(|s| !s.is_empty()).into_iter().collect()
+}
+
+/// The candidate secret whose HMAC matches the webhook signature, if any
+pub fn matching_webhook_secret<'a>(payload: &[u8], signature_hex: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .copied()
+        .find(|secret| verify_hmac_signature(payload, signature_hex, secret))
+}
+
 /// Process GitHub webhook events
 pub async fn process_github_webhook(
     event_type: &str,
@@ -123,3 +195,60 @@ async fn handle_release_event(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    fn sign(payload: &[u8], secret: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(payload);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn secrets(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_per_repo_secret_is_preferred() {
+        let repo = secrets(&["repo-secret"]);
+        let empty = secrets(&[""]);
+        assert_eq!(candidate_webhook_secrets(&repo, Some("global")), vec!["repo-secret"]);
+        assert_eq!(candidate_webhook_secrets(&[], Some("global")), vec!["global"]);
+        assert_eq!(candidate_webhook_secrets(&empty, Some("global")), vec!["global"]);
+        assert!(candidate_webhook_secrets(&[], None).is_empty());
+    }
+
+    #[test]
+    fn test_signature_must_match_selected_repo_secret() {
+        let payload = br#"{"repository":{"full_name":"acme/api"}}"#;
+        let repo = secrets(&["acme-api-secret"]);
+        let candidates = candidate_webhook_secrets(&repo, Some("global"));
+
+        assert_eq!(
+            matching_webhook_secret(payload, &sign(payload, "acme-api-secret"), &candidates),
+            Some("acme-api-secret")
+        );
+        assert_eq!(matching_webhook_secret(payload, &sign(payload, "global"), &candidates), None);
+    }
+
+    #[test]
+    fn test_any_active_config_secret_for_repo_verifies() {
+        let payload = br#"{"repository":{"full_name":"acme/api"}}"#;
+        let repo = secrets(&["first-user-secret", "second-user-secret"]);
+        let candidates = candidate_webhook_secrets(&repo, None);
+
+        assert_eq!(
+            matching_webhook_secret(payload, &sign(payload, "second-user-secret"), &candidates),
+            Some("second-user-secret")
+        );
+        assert_eq!(
+            matching_webhook_secret(payload, &sign(payload, "first-user-secret"), &candidates),
+            Some("first-user-secret")
+        );
+        assert_eq!(matching_webhook_secret(payload, &sign(payload, "stranger"), &candidates), None);
+    }
+}
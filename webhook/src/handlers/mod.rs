@@ -2,6 +2,8 @@ use actix_web::{web, HttpResponse, HttpRequest, Result};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use crate::retry_queue::{RetryQueue, WebhookJob};
+
 pub mod gitlab;
 pub mod github;
 pub mod stripe;
@@ -46,12 +48,26 @@ impl std::fmt::Display for WebhookError {
 
 impl std::error::Error for WebhookError {}
 
+/// Run the work a webhook triggers; failures are retried by the `RetryQueue`
+pub async fn dispatch_job(job: WebhookJob) -> std::result::Result<(), String> {
+    let data_source_id = job.data_source_id.as_deref().unwrap_or_default();
+    let result = match job.provider.as_str() {
+        "github" => github::process_github_webhook(&job.event_type, job.payload).await,
+        "gitlab" => gitlab::process_gitlab_webhook(data_source_id, &job.event_type, job.payload).await,
+        "dropbox" => dropbox::process_dropbox_webhook(data_source_id, job.payload).await,
+        "onedrive" => onedrive::process_onedrive_webhook(data_source_id, job.payload).await,
+        other => return Err(format!("Unknown webhook provider: {}", other)),
+    };
+    result.map_err(|e| e.to_string())
+}
+
 /// GitLab webhook handler
 /// POST /api/webhooks/gitlab/:data_source_id
 pub async fn handle_gitlab_webhook(
     path: web::Path<String>,
     req: HttpRequest,
     payload: web::Json<serde_json::Value>,
+    queue: web::Data<RetryQueue>,
 ) -> Result<HttpResponse> {
     let data_source_id = path.into_inner();
     tracing::info!("Received GitLab webhook for data source: {}", data_source_id);
@@ -77,15 +93,11 @@ pub async fn handle_gitlab_webhook(
 
     tracing::info!("GitLab event type: {}", event_type);
 
-    let payload_value = payload.into_inner();
-    let event_type_owned = event_type.to_string();
+    let job = WebhookJob::new("gitlab", Some(data_source_id), event_type, payload.into_inner());
 
     // Process webhook asynchronously
-    tokio::spawn(async move {
-        if let Err(e) = gitlab::process_gitlab_webhook(&data_source_id, &event_type_owned, payload_value).await {
-            tracing::error!("Failed to process GitLab webhook: {}", e);
-        }
-    });
+    let queue = queue.into_inner();
+    tokio::spawn(async move { queue.run(job, dispatch_job).await });
 
     // Return 200 OK immediately
     Ok(HttpResponse::Ok().json(serde_json::json!({
@@ -99,6 +111,7 @@ pub async fn handle_dropbox_webhook(
     path: web::Path<String>,
     req: HttpRequest,
     body: String,
+    queue: web::Data<RetryQueue>,
 ) -> Result<HttpResponse> {
     let data_source_id = path.into_inner();
     tracing::info!("Received Dropbox webhook for data source: {}", data_source_id);
@@ -130,11 +143,9 @@ pub async fn handle_dropbox_webhook(
         .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid JSON: {}", e)))?;
 
     // Process webhook asynchronously
-    tokio::spawn(async move {
-        if let Err(e) = dropbox::process_dropbox_webhook(&data_source_id, payload).await {
-            tracing::error!("Failed to process Dropbox webhook: {}", e);
-        }
-    });
+    let job = WebhookJob::new("dropbox", Some(data_source_id), "notification", payload);
+    let queue = queue.into_inner();
+    tokio::spawn(async move { queue.run(job, dispatch_job).await });
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "accepted"
@@ -147,6 +158,7 @@ pub async fn handle_onedrive_webhook(
     path: web::Path<String>,
     req: HttpRequest,
     payload: web::Json<serde_json::Value>,
+    queue: web::Data<RetryQueue>,
 ) -> Result<HttpResponse> {
     let data_source_id = path.into_inner();
     tracing::info!("Received OneDrive webhook for data source: {}", data_source_id);
@@ -176,11 +188,9 @@ pub async fn handle_onedrive_webhook(
     }
 
     // Process webhook asynchronously
-    tokio::spawn(async move {
-        if let Err(e) = onedrive::process_onedrive_webhook(&data_source_id, payload_value).await {
-            tracing::error!("Failed to process OneDrive webhook: {}", e);
-        }
-    });
+    let job = WebhookJob::new("onedrive", Some(data_source_id), "notification", payload_value);
+    let queue = queue.into_inner();
+    tokio::spawn(async move { queue.run(job, dispatch_job).await });
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "accepted"
@@ -1,11 +1,15 @@
 use actix_web::{web, App, HttpServer};
 use actix_cors::Cors;
-use sqlx::{PgPool, postgres::{PgPoolOptions, PgConnectOptions}};
-use std::str::FromStr;
+use sqlx::PgPool;
+use conhub_database::DatabaseConfig;
 use std::env;
+use std::sync::Arc;
 use conhub_observability::{init_tracing, TracingConfig, observability, info, warn, error};
 
 mod handlers;
+mod retry_queue;
+
+use retry_queue::{RetryPolicy, RetryQueue};
 
 #[actix_web::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -34,13 +38,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             info!("📊 [Webhook Service] Connecting to database...");
         }
 
-        let connect_options = PgConnectOptions::from_str(&database_url)?
-            .statement_cache_capacity(0);
-
-        match PgPoolOptions::new()
-            .max_connections(10)
-            .connect_with(connect_options)
-            .await {
+        match conhub_database::connect(&DatabaseConfig::for_url(database_url)).await {
             Ok(p) => {
                 info!("✅ [Webhook Service] Database connection established");
                 Some(p)
@@ -52,6 +50,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    // Failed webhook-triggered work is retried from Redis when available
+    let retry_policy = RetryPolicy::from_env();
+    let retry_queue = match env::var("REDIS_URL").ok().filter(|v| !v.trim().is_empty()) {
+        Some(url) => match redis::Client::open(url) {
+            Ok(client) => RetryQueue::redis(client, retry_policy),
+            Err(e) => {
+                warn!("⚠️  [Webhook Service] Invalid REDIS_URL, retrying webhooks in memory: {}", e);
+                RetryQueue::in_memory(retry_policy)
+            }
+        },
+        None => {
+            warn!("⚠️  [Webhook Service] REDIS_URL not set, retrying webhooks in memory");
+            RetryQueue::in_memory(retry_policy)
+        }
+    };
+    let retry_queue = Arc::new(retry_queue);
+    retry_queue.clone().spawn_worker(handlers::dispatch_job);
+
     info!("🚀 [Webhook Service] Starting on port {}", port);
 
     HttpServer::new(move || {
@@ -63,7 +79,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         let mut app = App::new()
             .wrap(cors)
-            .wrap(observability("webhook-service"));
+            .wrap(observability("webhook-service"))
+            .app_data(web::Data::from(retry_queue.clone()));
 
         if let Some(p) = pool.clone() {
             app = app.app_data(web::Data::new(p));
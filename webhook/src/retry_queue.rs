@@ -0,0 +1,368 @@
+//! Retry queue for webhook-triggered work
+//!
+//! A webhook is acknowledged before the work it triggers (a re-sync, a cache
+//! purge) runs, so a failure there would otherwise drop the event. Failed jobs
+//! are rescheduled with exponential backoff and, once they run out of
+//! attempts, moved to a dead-letter list for inspection. Backed by Redis when
+//! available so retries survive a restart and are shared across instances.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Sorted set of pending retries, scored by when each is due (ms since epoch)
+const RETRY_KEY: &str = "webhook:retry";
+/// List of jobs that ran out of attempts, newest first
+const DEAD_LETTER_KEY: &str = "webhook:dlq";
+/// Most dead letters kept
+const MAX_DEAD_LETTERS: usize = 1000;
+/// Most due jobs claimed per poll
+const CLAIM_BATCH: usize = 50;
+
+/// Work triggered by one verified webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookJob {
+    pub id: Uuid,
+    pub provider: String,
+    pub data_source_id: Option<String>,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    /// Attempts made so far
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+impl WebhookJob {
+    pub fn new(
+        provider: &str,
+        data_source_id: Option<String>,
+        event_type: &str,
+        payload: serde_json::Value,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            provider: provider.to_string(),
+            data_source_id,
+            event_type: event_type.to_string(),
+            payload,
+            attempts: 0,
+            last_error: None,
+            received_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// How often the worker looks for due retries
+    pub poll_interval: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(30 * 60),
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Read `WEBHOOK_RETRY_MAX_ATTEMPTS`, `WEBHOOK_RETRY_INITIAL_BACKOFF_SECS`,
+    /// `WEBHOOK_RETRY_MAX_BACKOFF_SECS` and `WEBHOOK_RETRY_POLL_INTERVAL_SECS`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let env = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_attempts: env("WEBHOOK_RETRY_MAX_ATTEMPTS")
+                .map(|n| n.clamp(1, u32::MAX as u64) as u32)
+                .unwrap_or(defaults.max_attempts),
+            initial_backoff: env("WEBHOOK_RETRY_INITIAL_BACKOFF_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.initial_backoff),
+            max_backoff: env("WEBHOOK_RETRY_MAX_BACKOFF_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.max_backoff),
+            poll_interval: env("WEBHOOK_RETRY_POLL_INTERVAL_SECS")
+                .map(|s| Duration::from_secs(s.max(1)))
+                .unwrap_or(defaults.poll_interval),
+        }
+    }
+
+    /// Delay after failed attempt number `attempt` (1-based): doubles each
+    /// time up to `max_backoff`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+#[derive(Default)]
+struct MemoryQueue {
+    /// Jobs with the time they are due
+    pending: Vec<(DateTime<Utc>, WebhookJob)>,
+    dead: Vec<WebhookJob>,
+}
+
+enum QueueStore {
+    Redis(redis::Client),
+    Memory(Mutex<MemoryQueue>),
+}
+
+pub struct RetryQueue {
+    store: QueueStore,
+    policy: RetryPolicy,
+}
+
+impl RetryQueue {
+    pub fn redis(client: redis::Client, policy: RetryPolicy) -> Self {
+        Self { store: QueueStore::Redis(client), policy }
+    }
+
+    /// Process-local queue, used when Redis is disabled; retries are lost on restart
+    pub fn in_memory(policy: RetryPolicy) -> Self {
+        Self { store: QueueStore::Memory(Mutex::new(MemoryQueue::default())), policy }
+    }
+
+    /// Run `job` once now; if it fails, queue it for retry
+    pub async fn run<F, Fut>(&self, job: WebhookJob, trigger: F)
+    where
+        F: Fn(WebhookJob) -> Fut,
+        Fut: Future<Output = Result<(), String>>,
+    {
+        self.attempt(job, &trigger, Utc::now()).await;
+    }
+
+    /// Run every retry due by `now`. Returns how many were attempted.
+    pub async fn process_due<F, Fut>(&self, now: DateTime<Utc>, trigger: F) -> usize
+    where
+        F: Fn(WebhookJob) -> Fut,
+        Fut: Future<Output = Result<(), String>>,
+    {
+        let due = match self.claim_due(now).await {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::error!("Failed to read webhook retry queue: {}", e);
+                return 0;
+            }
+        };
+        let count = due.len();
+        for job in due {
+            self.attempt(job, &trigger, now).await;
+        }
+        count
+    }
+
+    /// Poll for due retries every `poll_interval`
+    pub fn spawn_worker<F, Fut>(self: Arc<Self>, trigger: F) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(WebhookJob) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send,
+    {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.policy.poll_interval);
+            loop {
+                interval.tick().await;
+                self.process_due(Utc::now(), &trigger).await;
+            }
+        })
+    }
+
+    /// Jobs that ran out of attempts, newest first
+    pub async fn dead_letters(&self) -> Result<Vec<WebhookJob>, redis::RedisError> {
+        match &self.store {
+            QueueStore::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                let raw: Vec<String> = redis::cmd("LRANGE")
+                    .arg(DEAD_LETTER_KEY)
+                    .arg(0)
+                    .arg(MAX_DEAD_LETTERS as i64 - 1)
+                    .query_async(&mut conn)
+                    .await?;
+                Ok(raw.iter().filter_map(|j| serde_json::from_str(j).ok()).collect())
+            }
+            QueueStore::Memory(queue) => {
+                let queue = queue.lock().unwrap_or_else(|e| e.into_inner());
+                Ok(queue.dead.iter().rev().cloned().collect())
+            }
+        }
+    }
+
+    async fn attempt<F, Fut>(&self, mut job: WebhookJob, trigger: &F, now: DateTime<Utc>)
+    where
+        F: Fn(WebhookJob) -> Fut,
+        Fut: Future<Output = Result<(), String>>,
+    {
+        job.attempts += 1;
+        let error = match trigger(job.clone()).await {
+            Ok(()) => return,
+            Err(e) => e,
+        };
+        job.last_error = Some(error.clone());
+
+        let result = if job.attempts >= self.policy.max_attempts {
+            tracing::error!(
+                "Webhook job {} ({} {}) failed after {} attempts, moving to dead-letter queue: {}",
+                job.id, job.provider, job.event_type, job.attempts, error
+            );
+            self.dead_letter(job).await
+        } else {
+            let delay = self.policy.backoff(job.attempts);
+            tracing::warn!(
+                "Webhook job {} ({} {}) failed on attempt {}, retrying in {:?}: {}",
+                job.id, job.provider, job.event_type, job.attempts, delay, error
+            );
+            let due = now + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+            self.schedule(job, due).await
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to queue webhook job for retry, dropping it: {}", e);
+        }
+    }
+
+    async fn schedule(&self, job: WebhookJob, due: DateTime<Utc>) -> Result<(), redis::RedisError> {
+        match &self.store {
+            QueueStore::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                redis::cmd("ZADD")
+                    .arg(RETRY_KEY)
+                    .arg(due.timestamp_millis())
+                    .arg(serde_json::to_string(&job).unwrap_or_default())
+                    .query_async::<_, i64>(&mut conn)
+                    .await?;
+            }
+            QueueStore::Memory(queue) => {
+                queue.lock().unwrap_or_else(|e| e.into_inner()).pending.push((due, job));
+            }
+        }
+        Ok(())
+    }
+
+    async fn dead_letter(&self, job: WebhookJob) -> Result<(), redis::RedisError> {
+        match &self.store {
+            QueueStore::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                redis::pipe()
+                    .cmd("LPUSH").arg(DEAD_LETTER_KEY).arg(serde_json::to_string(&job).unwrap_or_default()).ignore()
+                    .cmd("LTRIM").arg(DEAD_LETTER_KEY).arg(0).arg(MAX_DEAD_LETTERS as i64 - 1).ignore()
+                    .query_async::<_, ()>(&mut conn)
+                    .await?;
+            }
+            QueueStore::Memory(queue) => {
+                let mut queue = queue.lock().unwrap_or_else(|e| e.into_inner());
+                queue.dead.push(job);
+                let excess = queue.dead.len().saturating_sub(MAX_DEAD_LETTERS);
+                queue.dead.drain(..excess);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove and return the jobs due by `now`. With Redis a job is only
+    /// returned to the instance whose `ZREM` removed it, so two workers never
+    /// run the same retry.
+    async fn claim_due(&self, now: DateTime<Utc>) -> Result<Vec<WebhookJob>, redis::RedisError> {
+        match &self.store {
+            QueueStore::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                let raw: Vec<String> = redis::cmd("ZRANGEBYSCORE")
+                    .arg(RETRY_KEY)
+                    .arg("-inf")
+                    .arg(now.timestamp_millis())
+                    .arg("LIMIT")
+                    .arg(0)
+                    .arg(CLAIM_BATCH)
+                    .query_async(&mut conn)
+                    .await?;
+
+                let mut claimed = Vec::with_capacity(raw.len());
+                for member in raw {
+                    let removed: i64 = redis::cmd("ZREM").arg(RETRY_KEY).arg(&member).query_async(&mut conn).await?;
+                    if removed == 0 {
+                        continue;
+                    }
+                    match serde_json::from_str(&member) {
+                        Ok(job) => claimed.push(job),
+                        Err(e) => tracing::error!("Dropping unreadable webhook retry entry: {}", e),
+                    }
+                }
+                Ok(claimed)
+            }
+            QueueStore::Memory(queue) => {
+                let mut queue = queue.lock().unwrap_or_else(|e| e.into_inner());
+                let (due, pending): (Vec<_>, Vec<_>) = queue.pending.drain(..).partition(|(at, _)| *at <= now);
+                queue.pending = pending;
+                Ok(due.into_iter().map(|(_, job)| job).take(CLAIM_BATCH).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_trigger_is_retried_then_dead_lettered() {
+        let queue = RetryQueue::in_memory(policy(3));
+        let calls = AtomicU32::new(0);
+        let trigger = |_job: WebhookJob| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err::<(), _>("sync service unavailable".to_string()) }
+        };
+        let start = Utc::now();
+        queue.run(WebhookJob::new("github", None, "push", serde_json::json!({})), &trigger).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Not due until the backoff has passed: 10s after the first failure, then 20s
+        assert_eq!(queue.process_due(start + chrono::Duration::seconds(5), &trigger).await, 0);
+        assert_eq!(queue.process_due(start + chrono::Duration::seconds(11), &trigger).await, 1);
+        assert_eq!(queue.process_due(start + chrono::Duration::seconds(25), &trigger).await, 0);
+        assert!(queue.dead_letters().await.unwrap().is_empty());
+        assert_eq!(queue.process_due(start + chrono::Duration::seconds(32), &trigger).await, 1);
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        let dead = queue.dead_letters().await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].attempts, 3);
+        assert_eq!(dead[0].last_error.as_deref(), Some("sync service unavailable"));
+        assert_eq!(queue.process_due(start + chrono::Duration::hours(1), &trigger).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_trigger_that_recovers_is_not_retried_again() {
+        let queue = RetryQueue::in_memory(policy(3));
+        let calls = AtomicU32::new(0);
+        let trigger = |_job: WebhookJob| {
+            let attempt = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            async move { if attempt == 1 { Err("timeout".to_string()) } else { Ok(()) } }
+        };
+        let start = Utc::now();
+        queue.run(WebhookJob::new("gitlab", Some("ds-1".to_string()), "Push Hook", serde_json::json!({})), &trigger).await;
+
+        assert_eq!(queue.process_due(start + chrono::Duration::minutes(1), &trigger).await, 1);
+        assert_eq!(queue.process_due(start + chrono::Duration::hours(1), &trigger).await, 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(queue.dead_letters().await.unwrap().is_empty());
+    }
+}